@@ -11,6 +11,8 @@ pub enum AdapterError {
     InvalidTranslation,
     /// An error occurred while operating the device
     DeviceError(DeviceError),
+    /// The device is in non-blocking mode and the operation has not completed yet
+    WouldBlock,
 }
 
 impl From<DeviceError> for AdapterError {
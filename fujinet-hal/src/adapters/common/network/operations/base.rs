@@ -1,35 +1,43 @@
 use crate::device::DeviceError;
 use crate::adapters::common::error::AdapterError;
-use super::{context::OperationsContext, types::DeviceOpenRequest};
+use super::{context::OperationsContext, types::{DeviceOpenRequest, ReadRequest, WriteRequest}};
 use crate::device::network::manager::NetworkManager;
 
-impl<M: NetworkManager> OperationsContext<M> {
+impl<M: NetworkManager + Send + 'static> OperationsContext<M> {
     /// Open a network device
     pub fn open_device(&self, request: DeviceOpenRequest) -> Result<usize, AdapterError> {
         println!("OperationsContext::open_device() called with spec: {}", request.device_spec);
-        let mut manager = self.manager.lock().unwrap();
-        
-        // Parse and validate the device specification
-        let parse_result = manager.parse_device_spec(&request.device_spec);
-        println!("parse_device_spec result: {:?}", parse_result);
-        
-        let (device_id, _url) = parse_result.map_err(|_| AdapterError::InvalidDeviceSpec)?;
+        let (device_id, pending, task) = {
+            let mut manager = self.manager.lock().unwrap();
+
+            // Parse and validate the device specification
+            let parse_result = manager.parse_device_spec(&request.device_spec);
+            println!("parse_device_spec result: {:?}", parse_result);
+
+            let (device_id, _url) = parse_result.map_err(|_| AdapterError::InvalidDeviceSpec)?;
+            let (pending, task) = manager.begin_open(&request.device_spec, request.mode, request.translation)
+                .map_err(AdapterError::from)?;
+            (device_id, pending, task)
+        };
 
-        // Execute open_device using stored runtime
-        let open_result = self.runtime.block_on(manager.open_device(&request.device_spec, request.mode, request.translation));
+        // Connect using stored runtime, with only the unit's own slot locked
+        let open_result = self.runtime.block_on(task);
         println!("open_device result: {:?}", open_result);
         
         open_result.map_err(AdapterError::from)?;
+        self.manager.lock().unwrap().finish_open(pending);
+        self.background.reset(device_id);
         Ok(device_id)
     }
 
     /// Close a network device
     pub fn close_device(&self, device_id: usize) -> Result<(), AdapterError> {
-        let mut manager = self.manager.lock().unwrap();
-        
-        // Execute close_device using stored runtime
-        let closed = self.runtime.block_on(manager.close_device(device_id))
+        let task = self.manager.lock().unwrap().begin_close(device_id);
+
+        // Disconnect using stored runtime, with only the unit's own slot locked
+        let closed = self.runtime.block_on(task)
             .map_err(AdapterError::from)?;
+        self.background.reset(device_id);
 
         if !closed {
             return Err(AdapterError::DeviceError(DeviceError::IoError("Failed to close device".into())));
@@ -38,6 +46,43 @@ impl<M: NetworkManager> OperationsContext<M> {
         Ok(())
     }
 
+    /// Close every network device, e.g. before shutting down
+    pub fn close_all_devices(&self) -> Result<(), AdapterError> {
+        let task = self.manager.lock().unwrap().begin_close_all();
+        let result = self.runtime.block_on(task);
+        self.background.reset_all();
+        result.map_err(AdapterError::from)
+    }
+
+    /// Read bytes from an open network device into the request buffer
+    /// In non-blocking mode this fails with WouldBlock until a background read has data
+    pub fn read(&self, request: &mut ReadRequest) -> Result<usize, AdapterError> {
+        if let Some(result) = self.try_read_nonblocking(request) {
+            return result;
+        }
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            device.read_bytes(&mut request.buffer).await.map_err(AdapterError::from)
+        })
+    }
+
+    /// Write the request data to an open network device
+    /// Waits whenever the device's send buffer is full, so none of the data is dropped
+    /// In non-blocking mode the data is queued for a background write
+    pub fn write(&self, mut request: WriteRequest) -> Result<usize, AdapterError> {
+        if let Some(result) = self.try_write_nonblocking(&mut request) {
+            return result;
+        }
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            device.write_all(&request.data).await.map_err(AdapterError::from)
+        })
+    }
+
     /// Validate that a device spec matches what was used in open_device
     pub fn validate_device_spec(&self, spec: &str) -> Result<usize, AdapterError> {
         let mut manager = self.manager.lock().unwrap();
@@ -66,7 +111,7 @@ impl<M: NetworkManager> OperationsContext<M> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::adapters::common::network::test_mocks::TestNetworkManager;
+    use crate::adapters::common::network::test_mocks::{TestNetworkManager, MockStreamProtocol};
     use crate::device::network::url::NetworkUrl;
 
     #[test]
@@ -202,4 +247,51 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), AdapterError::DeviceError(DeviceError::NotReady)));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_read_success() {
+        let protocol = MockStreamProtocol::with_read_data(b"Hello");
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///test.txt")
+            .with_protocol_device(Box::new(protocol));
+
+        let context = OperationsContext::new(manager);
+        let mut request = ReadRequest::new("N1:file:///test.txt".to_string(), vec![0; 16]);
+        let result = context.read(&mut request);
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(request.device_id, Some(1));
+        assert_eq!(&request.buffer[..5], b"Hello");
+    }
+
+    #[test]
+    fn test_read_device_not_found() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///test.txt");
+
+        let context = OperationsContext::new(manager);
+        let mut request = ReadRequest::new("N1:file:///test.txt".to_string(), vec![0; 16]);
+        let result = context.read(&mut request);
+        assert!(matches!(result.unwrap_err(), AdapterError::DeviceError(DeviceError::InvalidUrl)));
+    }
+
+    #[test]
+    fn test_read_invalid_spec() {
+        let context = OperationsContext::new(TestNetworkManager::new());
+        let mut request = ReadRequest::new("invalid".to_string(), vec![0; 16]);
+        assert!(matches!(context.read(&mut request).unwrap_err(), AdapterError::InvalidDeviceSpec));
+    }
+
+    #[test]
+    fn test_write_success() {
+        let protocol = MockStreamProtocol::default();
+        let written = protocol.written.clone();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///test.txt")
+            .with_protocol_device(Box::new(protocol));
+
+        let context = OperationsContext::new(manager);
+        let request = WriteRequest::new("N1:file:///test.txt".to_string(), b"Atari".to_vec());
+        assert_eq!(context.write(request).unwrap(), 5);
+        assert_eq!(written.lock().unwrap().as_slice(), b"Atari");
+    }
+}
\ No newline at end of file
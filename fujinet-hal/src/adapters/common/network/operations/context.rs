@@ -1,11 +1,16 @@
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
+use crate::adapters::common::error::AdapterError;
+use crate::device::DeviceError;
+use crate::device::network::{DeviceGuard, DeviceSlot};
 use crate::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use super::nonblocking::BackgroundIo;
 
 /// Context for network operations that manages dependencies
 pub struct OperationsContext<M: NetworkManager> {
     pub(crate) manager: Arc<Mutex<M>>,
     pub(crate) runtime: Arc<Runtime>,
+    pub(crate) background: Arc<BackgroundIo>,
 }
 
 impl<M: NetworkManager> OperationsContext<M> {
@@ -21,8 +26,37 @@ impl<M: NetworkManager> OperationsContext<M> {
         Self {
             manager: Arc::new(Mutex::new(manager)),
             runtime: Arc::new(runtime),
+            background: Arc::new(BackgroundIo::default()),
         }
     }
+
+    /// Get the device ID for a request, parsing the spec only if it is not already known
+    pub(crate) fn resolve_device_id(&self, device_spec: &str, device_id: &mut Option<usize>) -> Result<usize, AdapterError> {
+        if let Some(id) = *device_id {
+            return Ok(id);
+        }
+        let (id, _) = self.manager.lock().unwrap().parse_device_spec(device_spec)
+            .map_err(|_| AdapterError::InvalidDeviceSpec)?;
+        *device_id = Some(id);
+        Ok(id)
+    }
+
+    /// Lock a unit's network device for I/O
+    /// The manager is only locked for the lookup, so other units can be used from other
+    /// threads while this one is busy
+    pub(crate) async fn lock_device(&self, device_id: usize) -> Result<DeviceGuard, AdapterError> {
+        let slot = self.manager.lock().unwrap().device_slot(device_id);
+        lock_slot(slot).await
+    }
+}
+
+/// Lock the device in a slot looked up earlier, e.g. by a background task
+pub(crate) async fn lock_slot(slot: Option<DeviceSlot>) -> Result<DeviceGuard, AdapterError> {
+    let device = match slot {
+        Some(slot) => DeviceGuard::lock(slot).await,
+        None => None,
+    };
+    device.ok_or(AdapterError::DeviceError(DeviceError::InvalidUrl))
 }
 
 impl OperationsContext<NetworkManagerImpl> {
@@ -0,0 +1,115 @@
+use crate::device::DeviceError;
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::{DirectoryOpenRequest, DirectoryEntryRequest, ReadRequest}};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::protocols::{DirectoryEntry, DirectoryFormat};
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Open a filtered directory listing on a network device
+    pub fn open_directory(&self, request: DirectoryOpenRequest) -> Result<usize, AdapterError> {
+        let format = if request.long { DirectoryFormat::Long } else { DirectoryFormat::Short };
+        let (device_id, task) = {
+            let mut manager = self.manager.lock().unwrap();
+            manager.parse_device_spec(&request.device_spec)
+                .map_err(|_| AdapterError::InvalidDeviceSpec)?;
+            manager.begin_directory(&request.device_spec, request.filter.as_deref(), format)
+                .map_err(AdapterError::from)?
+        };
+
+        // The listing is fetched with only the unit's own slot locked
+        self.runtime.block_on(task).map_err(AdapterError::from)?;
+        Ok(device_id)
+    }
+
+    /// Read the next chunk of the formatted listing into the request buffer
+    pub fn read_directory(&self, request: &mut ReadRequest) -> Result<usize, AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let listing = device.directory()
+                .ok_or(AdapterError::DeviceError(DeviceError::NotReady))?;
+            Ok(listing.read(&mut request.buffer))
+        })
+    }
+
+    /// Take the next entry from an open listing, or None once all entries have been returned
+    pub fn read_directory_entry(&self, request: &mut DirectoryEntryRequest) -> Result<Option<DirectoryEntry>, AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let listing = device.directory()
+                .ok_or(AdapterError::DeviceError(DeviceError::NotReady))?;
+            Ok(listing.next_entry())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::adapters::common::network::test_mocks::{TestNetworkManager, MockStreamProtocol};
+    use crate::device::network::protocols::FileProtocol;
+
+    const SPEC: &str = "N1:file:///";
+
+    fn temp_root() -> TempDir {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("game.atr"), [0u8; 256]).unwrap();
+        std::fs::write(root.path().join("tool.xex"), [0u8; 100]).unwrap();
+        root
+    }
+
+    fn file_context(root: &TempDir) -> OperationsContext<TestNetworkManager> {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, SPEC)
+            .with_protocol_device(Box::new(FileProtocol::new(root.path())));
+        OperationsContext::new(manager)
+    }
+
+    #[test]
+    fn test_directory_entries_are_filtered() {
+        let root = temp_root();
+        let context = file_context(&root);
+
+        let device_id = context.open_directory(DirectoryOpenRequest::new(SPEC.to_string(), Some("*.ATR".to_string()), false)).unwrap();
+        assert_eq!(device_id, 1);
+
+        let mut request = DirectoryEntryRequest::new(SPEC.to_string());
+        let entry = context.read_directory_entry(&mut request).unwrap().unwrap();
+        assert_eq!(entry.name, "game.atr");
+        assert_eq!(entry.size, 256);
+        assert_eq!(context.read_directory_entry(&mut request).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_formatted_directory() {
+        let root = temp_root();
+        let context = file_context(&root);
+
+        context.open_directory(DirectoryOpenRequest::new(SPEC.to_string(), None, false)).unwrap();
+        let mut request = ReadRequest::new(SPEC.to_string(), vec![0; 128]);
+        let len = context.read_directory(&mut request).unwrap();
+        assert_eq!(&request.buffer[..len], b"  GAME     ATR 002\n  TOOL     XEX 001\n");
+        assert_eq!(context.read_directory(&mut request).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_directory_requires_capable_protocol() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::default()));
+        let context = OperationsContext::new(manager);
+
+        let result = context.open_directory(DirectoryOpenRequest::new("N1:tcp://host:23".to_string(), None, true));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::NotSupported))));
+
+        let mut request = DirectoryEntryRequest::new("N1:tcp://host:23".to_string());
+        assert!(matches!(
+            context.read_directory_entry(&mut request),
+            Err(AdapterError::DeviceError(DeviceError::NotReady))
+        ));
+    }
+}
@@ -0,0 +1,43 @@
+use super::context::OperationsContext;
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::events::{EventCallback, NetworkEvent};
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Deliver network events to `callback` as they happen, replacing any earlier callback
+    /// None returns to queueing events for poll_event
+    pub fn set_event_callback(&self, callback: Option<EventCallback>) {
+        let manager = self.manager.lock().unwrap();
+        manager.events().set_callback(callback);
+    }
+
+    /// Take the oldest queued network event, if any
+    pub fn poll_event(&self) -> Option<NetworkEvent> {
+        let manager = self.manager.lock().unwrap();
+        manager.events().poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::adapters::common::network::test_mocks::TestNetworkManager;
+    use crate::device::network::events::NetworkEventKind;
+
+    #[test]
+    fn test_poll_and_callback() {
+        let context = OperationsContext::new(TestNetworkManager::new());
+        let notifier = context.manager.lock().unwrap().events().notifier(0);
+
+        assert_eq!(context.poll_event(), None);
+        notifier.notify(NetworkEventKind::DataAvailable);
+        assert_eq!(context.poll_event(), Some(NetworkEvent { device_id: 0, kind: NetworkEventKind::DataAvailable }));
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        context.set_event_callback(Some(Box::new(move |event| sink.lock().unwrap().push(event.kind))));
+        notifier.notify(NetworkEventKind::ConnectionClosed);
+        assert_eq!(*received.lock().unwrap(), vec![NetworkEventKind::ConnectionClosed]);
+        assert_eq!(context.poll_event(), None);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::FileSystemRequest};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::protocols::FileSystemCommand;
+use crate::device::network::protocols::filesystem::{split_rename_target, XIO_RENAME};
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Dispatch an XIO filesystem command to the protocol named in the device spec
+    pub fn filesystem_command(&self, request: FileSystemRequest) -> Result<(), AdapterError> {
+        // Only rename carries a second name, so commas are part of the path otherwise
+        let (device_spec, new_name) = if request.command == XIO_RENAME {
+            split_rename_target(&request.device_spec)
+        } else {
+            (request.device_spec.as_str(), None)
+        };
+        let command = FileSystemCommand::from_xio(request.command, new_name).map_err(AdapterError::from)?;
+
+        let task = {
+            let mut manager = self.manager.lock().unwrap();
+            manager.parse_device_spec(device_spec)
+                .map_err(|_| AdapterError::InvalidDeviceSpec)?;
+            manager.begin_filesystem_command(device_spec, command).map_err(AdapterError::from)?
+        };
+
+        // Run the command without holding the manager
+        self.runtime.block_on(task).map_err(AdapterError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::common::network::test_mocks::{TestNetworkManager, MockStreamProtocol};
+    use crate::device::DeviceError;
+    use crate::device::network::protocols::FileProtocol;
+    use crate::device::network::protocols::filesystem::{XIO_DELETE, XIO_MKDIR};
+
+    #[test]
+    fn test_rename_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("foo.txt"), b"data").unwrap();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///foo.txt")
+            .with_protocol_device(Box::new(FileProtocol::new(root)));
+        let context = OperationsContext::new(manager);
+
+        context.filesystem_command(FileSystemRequest::new("N1:file:///foo.txt,bar.txt".to_string(), XIO_RENAME)).unwrap();
+        assert!(root.join("bar.txt").exists());
+
+        let result = context.filesystem_command(FileSystemRequest::new("N1:file:///foo.txt".to_string(), XIO_RENAME));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::InvalidUrl))));
+
+        std::fs::write(root.join("foo.txt"), b"again").unwrap();
+        context.filesystem_command(FileSystemRequest::new("N1:file:///foo.txt".to_string(), XIO_DELETE)).unwrap();
+        assert!(!root.join("foo.txt").exists());
+    }
+
+    #[test]
+    fn test_unsupported_protocol_and_command() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::default()));
+        let context = OperationsContext::new(manager);
+
+        let result = context.filesystem_command(FileSystemRequest::new("N1:tcp://host:23".to_string(), XIO_MKDIR));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::NotSupported))));
+
+        let result = context.filesystem_command(FileSystemRequest::new("N1:tcp://host:23".to_string(), 99));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::InvalidOperation))));
+
+        let result = context.filesystem_command(FileSystemRequest::new("bogus".to_string(), XIO_MKDIR));
+        assert!(matches!(result, Err(AdapterError::InvalidDeviceSpec)));
+    }
+}
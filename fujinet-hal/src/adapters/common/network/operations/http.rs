@@ -7,21 +7,11 @@ use crate::device::network::protocols::http::HttpProtocol;
 impl<M: NetworkManager + Send + Sync + 'static> OperationsContext<M> {
     /// Perform an HTTP POST operation
     pub fn http_post(&self, mut request: HttpPostRequest) -> Result<(), AdapterError> {
-        let mut manager = self.manager.lock().unwrap();
-        
-        // Parse device spec only if device_id not set
-        let device_id = if let Some(id) = request.device_id {
-            id
-        } else {
-            let (id, _) = manager.parse_device_spec(&request.device_spec)
-                .map_err(|_| AdapterError::InvalidDeviceSpec)?;
-            request.device_id = Some(id);
-            id
-        };
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
 
         // Execute HTTP POST using stored runtime
         self.runtime.block_on(async {
-            if let Some(device) = manager.get_network_device(device_id) {
+            if let Ok(mut device) = self.lock_device(device_id).await {
                 let protocol = device.protocol_handler();
                 
                 // Try to downcast to HttpProtocol
@@ -62,10 +52,12 @@ impl<M: NetworkManager + Send + Sync + 'static> OperationsContext<M> {
         if !url.has_same_base_url(stored_url) {
             return Err(AdapterError::DeviceError(DeviceError::InvalidUrl));
         }
+        // A slow request must not hold up the other units
+        drop(manager);
 
         // Execute HTTP GET using stored runtime
         self.runtime.block_on(async {
-            if let Some(device) = manager.get_network_device(device_id) {
+            if let Ok(mut device) = self.lock_device(device_id).await {
                 let protocol = device.protocol_handler();
                 
                 // Try to downcast to HttpProtocol
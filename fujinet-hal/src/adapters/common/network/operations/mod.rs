@@ -1,7 +1,16 @@
 pub(crate) mod base;
 pub(crate) mod context;
+pub(crate) mod directory;
+pub(crate) mod events;
+pub(crate) mod filesystem;
 pub(crate) mod http;
+pub(crate) mod mqtt;
+pub(crate) mod nonblocking;
+pub(crate) mod prefix;
+pub(crate) mod seek;
+pub(crate) mod status;
 pub(crate) mod types;
+pub(crate) mod websocket;
 
 pub use context::OperationsContext;
 pub use types::*; 
\ No newline at end of file
@@ -0,0 +1,101 @@
+use crate::device::DeviceError;
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::{MqttSubscribeRequest, MqttPublishRequest}};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::protocols::{MqttProtocol, MqttQos};
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Subscribe an open MQTT device to a topic filter
+    pub fn mqtt_subscribe(&self, mut request: MqttSubscribeRequest) -> Result<(), AdapterError> {
+        let qos = MqttQos::from_u8(request.qos).map_err(AdapterError::from)?;
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let mqtt_protocol = device.protocol_handler().as_any_mut()
+                .downcast_mut::<MqttProtocol>()
+                .ok_or(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))?;
+
+            mqtt_protocol.subscribe(&request.topic, qos).await.map_err(AdapterError::from)
+        })
+    }
+
+    /// Publish a message from an open MQTT device
+    pub fn mqtt_publish(&self, mut request: MqttPublishRequest) -> Result<(), AdapterError> {
+        let qos = MqttQos::from_u8(request.qos).map_err(AdapterError::from)?;
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let mqtt_protocol = device.protocol_handler().as_any_mut()
+                .downcast_mut::<MqttProtocol>()
+                .ok_or(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))?;
+
+            mqtt_protocol.publish(&request.topic, &request.payload, qos, request.retain)
+                .await
+                .map_err(AdapterError::from)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::adapters::common::network::test_mocks::{
+        TestNetworkManager, MockMqttClient, MockMqttClientProvider, MockStreamProtocol,
+    };
+    use crate::device::network::protocols::ProtocolHandler;
+
+    const SPEC: &str = "N1:mqtt://broker.local";
+
+    fn mqtt_context(client: MockMqttClient) -> OperationsContext<TestNetworkManager> {
+        let mut protocol = MqttProtocol::new(Arc::new(MockMqttClientProvider::new(client)));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(protocol.open("mqtt://broker.local")).unwrap();
+
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, SPEC)
+            .with_protocol_device(Box::new(protocol));
+        OperationsContext::new_with_runtime(manager, runtime)
+    }
+
+    #[test]
+    fn test_mqtt_subscribe() {
+        let client = MockMqttClient::default();
+        let context = mqtt_context(client.clone());
+
+        context.mqtt_subscribe(MqttSubscribeRequest::new(SPEC.to_string(), "home/#".to_string(), 1)).unwrap();
+        assert_eq!(client.subscriptions.lock().unwrap().as_slice(), &[("home/#".to_string(), MqttQos::AtLeastOnce)]);
+
+        let result = context.mqtt_subscribe(MqttSubscribeRequest::new(SPEC.to_string(), "home/#".to_string(), 2));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::NotSupported))));
+    }
+
+    #[test]
+    fn test_mqtt_publish() {
+        let client = MockMqttClient::default();
+        let context = mqtt_context(client.clone());
+
+        let request = MqttPublishRequest::new(SPEC.to_string(), "home/lamp".to_string(), b"on".to_vec(), 0, true);
+        context.mqtt_publish(request).unwrap();
+        assert_eq!(
+            client.published.lock().unwrap().as_slice(),
+            &[("home/lamp".to_string(), b"on".to_vec(), MqttQos::AtMostOnce, true)]
+        );
+    }
+
+    #[test]
+    fn test_mqtt_operations_require_mqtt_device() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::default()));
+        let context = OperationsContext::new(manager);
+
+        let request = MqttSubscribeRequest::new("N1:tcp://host:23".to_string(), "a".to_string(), 0);
+        assert!(matches!(
+            context.mqtt_subscribe(request),
+            Err(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::adapters::common::error::AdapterError;
+use super::{context::{lock_slot, OperationsContext}, types::{ReadRequest, WriteRequest}};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::{DeviceSlot, EventBus, NetworkEventKind, NetworkUrl};
+
+/// How often a background read checks whether the peer has sent anything
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Non-blocking state for one unit
+///
+/// At most one read and one write run in the background at a time. Their outcomes
+/// are held here until the host's next call collects them.
+#[derive(Debug, Default)]
+pub(crate) struct UnitIo {
+    nonblocking: bool,
+    reading: bool,
+    writing: bool,
+    /// Data (or the error) from the last background read, not yet returned to the host
+    read_result: Option<Result<Vec<u8>, AdapterError>>,
+    /// Error from the last background write, reported by the next read or write
+    write_error: Option<AdapterError>,
+    /// Bumped when the unit is opened or closed so stale results are dropped
+    generation: u64,
+}
+
+/// Per-unit non-blocking I/O state, each unit behind its own lock
+/// so host calls on one unit never wait for I/O on another
+#[derive(Debug, Default)]
+pub(crate) struct BackgroundIo {
+    units: Mutex<HashMap<usize, Arc<Mutex<UnitIo>>>>,
+}
+
+impl BackgroundIo {
+    fn unit(&self, device_id: usize) -> Arc<Mutex<UnitIo>> {
+        self.units.lock().unwrap().entry(device_id).or_default().clone()
+    }
+
+    /// The unit's state, if it is in non-blocking mode
+    fn nonblocking_unit(&self, device_id: usize) -> Option<Arc<Mutex<UnitIo>>> {
+        let unit = self.units.lock().unwrap().get(&device_id)?.clone();
+        let nonblocking = unit.lock().unwrap().nonblocking;
+        nonblocking.then_some(unit)
+    }
+
+    /// Forget any outstanding results, e.g. when the unit is opened or closed
+    pub(crate) fn reset(&self, device_id: usize) {
+        if let Some(unit) = self.units.lock().unwrap().get(&device_id) {
+            let mut io = unit.lock().unwrap();
+            io.generation += 1;
+            io.reading = false;
+            io.writing = false;
+            io.read_result = None;
+            io.write_error = None;
+        }
+    }
+
+    /// Abandon the background I/O of every unit
+    pub(crate) fn reset_all(&self) {
+        let units: Vec<usize> = self.units.lock().unwrap().keys().copied().collect();
+        for device_id in units {
+            self.reset(device_id);
+        }
+    }
+}
+
+impl<M: NetworkManager + Send + 'static> OperationsContext<M> {
+    /// Switch a unit between blocking and non-blocking reads and writes
+    ///
+    /// In non-blocking mode `read` and `write` return at once. A read starts a background
+    /// read and fails with WouldBlock until the data is ready; a write is queued and
+    /// accepted, failing with WouldBlock while an earlier write is still being sent.
+    pub fn set_nonblocking(&self, device_spec: &str, enabled: bool) -> Result<usize, AdapterError> {
+        let (device_id, _) = self.manager.lock().unwrap().split_unit(device_spec)
+            .map_err(|_| AdapterError::InvalidDeviceSpec)?;
+        self.background.unit(device_id).lock().unwrap().nonblocking = enabled;
+        Ok(device_id)
+    }
+
+    /// Whether a unit is in non-blocking mode
+    pub fn is_nonblocking(&self, device_id: usize) -> bool {
+        self.background.nonblocking_unit(device_id).is_some()
+    }
+
+    /// Find the unit for a request if it is in non-blocking mode, without taking the manager lock
+    fn nonblocking_target(&self, device_spec: &str, device_id: Option<usize>) -> Option<(usize, Arc<Mutex<UnitIo>>)> {
+        let device_id = match device_id {
+            Some(id) => id,
+            None => (NetworkUrl::split_unit(device_spec).ok()?.0 - 1) as usize,
+        };
+        Some((device_id, self.background.nonblocking_unit(device_id)?))
+    }
+
+    /// The unit's device slot and the manager's events, taken out of the manager so
+    /// background I/O never needs the manager lock
+    /// Called before locking the unit, since opening and closing lock the manager first
+    fn device_handle(&self, device_id: usize) -> (Option<DeviceSlot>, EventBus) {
+        let manager = self.manager.lock().unwrap();
+        (manager.device_slot(device_id), manager.events().clone())
+    }
+
+    /// Non-blocking read; returns None if the unit is in blocking mode
+    pub(crate) fn try_read_nonblocking(&self, request: &mut ReadRequest) -> Option<Result<usize, AdapterError>> {
+        let (device_id, unit) = self.nonblocking_target(&request.device_spec, request.device_id)?;
+        request.device_id = Some(device_id);
+        let (slot, events) = self.device_handle(device_id);
+
+        let mut io = unit.lock().unwrap();
+        if let Some(result) = io.read_result.take() {
+            return Some(result.map(|mut data| {
+                let len = std::cmp::min(request.buffer.len(), data.len());
+                request.buffer[..len].copy_from_slice(&data[..len]);
+                // Keep anything the host had no room for until its next read
+                if len < data.len() {
+                    io.read_result = Some(Ok(data.split_off(len)));
+                }
+                len
+            }));
+        }
+        if let Some(err) = io.write_error.take() {
+            return Some(Err(err));
+        }
+        if !io.reading {
+            self.spawn_read(device_id, slot, events, unit.clone(), &mut io, request.buffer.len());
+        }
+        Some(Err(AdapterError::WouldBlock))
+    }
+
+    /// Non-blocking write; returns None if the unit is in blocking mode
+    pub(crate) fn try_write_nonblocking(&self, request: &mut WriteRequest) -> Option<Result<usize, AdapterError>> {
+        let (device_id, unit) = self.nonblocking_target(&request.device_spec, request.device_id)?;
+        request.device_id = Some(device_id);
+        let (slot, _) = self.device_handle(device_id);
+
+        let mut io = unit.lock().unwrap();
+        if let Some(err) = io.write_error.take() {
+            return Some(Err(err));
+        }
+        if io.writing {
+            return Some(Err(AdapterError::WouldBlock));
+        }
+        let len = request.data.len();
+        self.spawn_write(slot, unit.clone(), &mut io, std::mem::take(&mut request.data));
+        Some(Ok(len))
+    }
+
+    /// Read in the background; only the unit's own slot is locked while the read runs,
+    /// and not even that while the peer has yet to send anything
+    fn spawn_read(&self, device_id: usize, slot: Option<DeviceSlot>, events: EventBus, unit: Arc<Mutex<UnitIo>>, io: &mut UnitIo, len: usize) {
+        io.reading = true;
+        let generation = io.generation;
+
+        self.runtime.spawn(async move {
+            let mut buf = vec![0u8; len];
+            let result = async {
+                loop {
+                    let mut device = lock_slot(slot.clone()).await?;
+                    if !device.protocol_handler().read_would_wait().await? {
+                        return device.read_bytes(&mut buf).await.map_err(AdapterError::from);
+                    }
+                    drop(device);
+                    tokio::time::sleep(READ_POLL_INTERVAL).await;
+                }
+            }.await;
+            if matches!(result, Ok(read) if read > 0) {
+                events.notifier(device_id).notify(NetworkEventKind::DataAvailable);
+            }
+            let result = result.map(|read| {
+                buf.truncate(read);
+                buf
+            });
+
+            let mut io = unit.lock().unwrap();
+            if io.generation == generation {
+                io.reading = false;
+                io.read_result = Some(result);
+            }
+        });
+    }
+
+    /// Write in the background; only the unit's own slot is locked while the write runs
+    fn spawn_write(&self, slot: Option<DeviceSlot>, unit: Arc<Mutex<UnitIo>>, io: &mut UnitIo, data: Vec<u8>) {
+        io.writing = true;
+        let generation = io.generation;
+
+        self.runtime.spawn(async move {
+            let result = async {
+                let mut device = lock_slot(slot).await?;
+                device.write_all(&data).await.map_err(AdapterError::from)
+            }.await;
+
+            let mut io = unit.lock().unwrap();
+            if io.generation == generation {
+                io.writing = false;
+                io.write_error = result.err();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+    use crate::device::DeviceError;
+    use crate::adapters::common::network::test_mocks::{TestNetworkManager, MockStreamProtocol};
+
+    const SPEC: &str = "N1:tcp://host:23";
+
+    fn slow_context(delay: Duration) -> (OperationsContext<TestNetworkManager>, Arc<Mutex<Vec<u8>>>) {
+        let protocol = MockStreamProtocol::with_read_data(b"HELLO").with_delay(delay);
+        let written = protocol.written.clone();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, SPEC)
+            .with_protocol_device(Box::new(protocol));
+        (OperationsContext::new(manager), written)
+    }
+
+    /// Retry a non-blocking read until it stops reporting WouldBlock
+    fn read_until_ready(context: &OperationsContext<TestNetworkManager>, request: &mut ReadRequest) -> Result<usize, AdapterError> {
+        for _ in 0..200 {
+            match context.read(request) {
+                Err(AdapterError::WouldBlock) => std::thread::sleep(Duration::from_millis(5)),
+                result => return result,
+            }
+        }
+        panic!("background read never completed");
+    }
+
+    #[test]
+    fn test_nonblocking_read_returns_immediately() {
+        let (context, _) = slow_context(Duration::from_millis(200));
+        assert_eq!(context.set_nonblocking(SPEC, true).unwrap(), 0);
+        assert!(context.is_nonblocking(0));
+
+        let mut request = ReadRequest::new(SPEC.to_string(), vec![0; 8]);
+        let start = Instant::now();
+        assert!(matches!(context.read(&mut request), Err(AdapterError::WouldBlock)));
+        // Still running: calls keep returning at once rather than queueing behind the read
+        assert!(matches!(context.read(&mut request), Err(AdapterError::WouldBlock)));
+        assert!(start.elapsed() < Duration::from_millis(150));
+        // The background read only holds the unit's own slot, never the manager
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(context.manager.try_lock().is_ok());
+
+        // Data beyond the caller's buffer is kept for the next read
+        let mut request = ReadRequest::new(SPEC.to_string(), vec![0; 3]);
+        assert_eq!(read_until_ready(&context, &mut request).unwrap(), 3);
+        assert_eq!(&request.buffer, b"HEL");
+        assert_eq!(context.read(&mut request).unwrap(), 2);
+        assert_eq!(&request.buffer[..2], b"LO");
+    }
+
+    /// Wait for the mock to have been sent `expected`
+    fn wait_for_written(written: &Arc<Mutex<Vec<u8>>>, expected: &[u8]) {
+        for _ in 0..200 {
+            if *written.lock().unwrap() == expected {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*written.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_waiting_read_leaves_unit_unlocked() {
+        let protocol = MockStreamProtocol::with_read_data(b"HELLO");
+        let (written, idle) = (protocol.written.clone(), protocol.idle.clone());
+        idle.store(true, Ordering::SeqCst);
+        let context = OperationsContext::new(TestNetworkManager::new()
+            .with_parse_result(1, SPEC)
+            .with_protocol_device(Box::new(protocol)));
+        context.set_nonblocking(SPEC, true).unwrap();
+
+        let mut request = ReadRequest::new(SPEC.to_string(), vec![0; 8]);
+        assert!(matches!(context.read(&mut request), Err(AdapterError::WouldBlock)));
+
+        // Reads and writes run side by side, and the write isn't stuck behind the idle read
+        assert_eq!(context.write(WriteRequest::new(SPEC.to_string(), b"ABC".to_vec())).unwrap(), 3);
+        wait_for_written(&written, b"ABC");
+        assert!(matches!(context.read(&mut request), Err(AdapterError::WouldBlock)));
+
+        idle.store(false, Ordering::SeqCst);
+        assert_eq!(read_until_ready(&context, &mut request).unwrap(), 5);
+        assert_eq!(&request.buffer[..5], b"HELLO");
+    }
+
+    #[test]
+    fn test_nonblocking_write_is_queued() {
+        let (context, written) = slow_context(Duration::from_millis(20));
+        context.set_nonblocking(SPEC, true).unwrap();
+
+        assert_eq!(context.write(WriteRequest::new(SPEC.to_string(), b"ABC".to_vec())).unwrap(), 3);
+        assert!(matches!(context.write(WriteRequest::new(SPEC.to_string(), b"DEF".to_vec())), Err(AdapterError::WouldBlock)));
+        wait_for_written(&written, b"ABC");
+
+        // Back in blocking mode the write goes straight through
+        context.set_nonblocking(SPEC, false).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(context.write(WriteRequest::new(SPEC.to_string(), b"DEF".to_vec())).unwrap(), 3);
+        assert_eq!(*written.lock().unwrap(), b"ABCDEF");
+    }
+
+    #[test]
+    fn test_background_errors_are_reported() {
+        let context = OperationsContext::new(TestNetworkManager::new());
+        context.set_nonblocking("N2:tcp://host:23", true).unwrap();
+
+        let mut request = ReadRequest::new("N2:tcp://host:23".to_string(), vec![0; 8]);
+        assert!(matches!(context.read(&mut request), Err(AdapterError::WouldBlock)));
+        assert!(matches!(
+            read_until_ready(&context, &mut request),
+            Err(AdapterError::DeviceError(DeviceError::InvalidUrl))
+        ));
+        assert!(matches!(context.set_nonblocking("bogus", true), Err(AdapterError::InvalidDeviceSpec)));
+    }
+}
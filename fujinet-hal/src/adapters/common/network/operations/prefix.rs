@@ -0,0 +1,56 @@
+use crate::adapters::common::error::AdapterError;
+use super::context::OperationsContext;
+use crate::device::network::manager::NetworkManager;
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Change a unit's prefix, e.g. "N1:TNFS://host/dir/", "N1:games" or "N1:.."
+    /// Fails with InvalidDeviceSpec if the spec doesn't name one of the manager's units
+    pub fn set_prefix(&self, spec: &str) -> Result<usize, AdapterError> {
+        let mut manager = self.manager.lock().unwrap();
+        manager.split_unit(spec).map_err(|_| AdapterError::InvalidDeviceSpec)?;
+        manager.set_prefix(spec).map_err(AdapterError::from)
+    }
+
+    /// Get a unit's prefix, or an empty string if none is set
+    pub fn get_prefix(&self, spec: &str) -> Result<String, AdapterError> {
+        let mut manager = self.manager.lock().unwrap();
+        let (device_id, _) = manager.split_unit(spec)
+            .map_err(|_| AdapterError::InvalidDeviceSpec)?;
+        Ok(manager.get_prefix(device_id).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::common::network::test_mocks::TestNetworkManager;
+    use crate::device::DeviceError;
+
+    #[test]
+    fn test_set_and_navigate_prefix() {
+        let context = OperationsContext::new(TestNetworkManager::new());
+
+        assert_eq!(context.get_prefix("N2:").unwrap(), "");
+        assert_eq!(context.set_prefix("N2:http://host/games/").unwrap(), 1);
+        context.set_prefix("N2:arcade").unwrap();
+        assert_eq!(context.get_prefix("N2:").unwrap(), "http://host/games/arcade/");
+        context.set_prefix("N2:..").unwrap();
+        assert_eq!(context.get_prefix("N2:").unwrap(), "http://host/games/");
+
+        // Other units are unaffected
+        assert_eq!(context.get_prefix("N1:").unwrap(), "");
+        context.set_prefix("N2:").unwrap();
+        assert_eq!(context.get_prefix("N2:").unwrap(), "");
+    }
+
+    #[test]
+    fn test_prefix_errors() {
+        let context = OperationsContext::new(TestNetworkManager::new());
+        assert!(matches!(context.set_prefix("N1:.."), Err(AdapterError::DeviceError(DeviceError::InvalidUrl))));
+        assert!(matches!(context.get_prefix("bogus"), Err(AdapterError::InvalidDeviceSpec)));
+
+        // Units past the manager's count are refused before they are used as an index
+        assert!(matches!(context.get_prefix("N9:"), Err(AdapterError::InvalidDeviceSpec)));
+        assert!(matches!(context.set_prefix("N200:http://host/"), Err(AdapterError::InvalidDeviceSpec)));
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::SeekFrom;
+use crate::device::DeviceError;
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::{SeekRequest, TellRequest}};
+use crate::device::network::manager::NetworkManager;
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Move the position of an open random access device (POINT)
+    pub fn seek(&self, mut request: SeekRequest) -> Result<u64, AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let seekable = device.protocol_handler().as_seekable()
+                .ok_or(AdapterError::DeviceError(DeviceError::NotSupported))?;
+            seekable.seek(SeekFrom::Start(request.position)).await.map_err(AdapterError::from)
+        })
+    }
+
+    /// Get the position of an open random access device (NOTE)
+    pub fn tell(&self, request: &mut TellRequest) -> Result<u64, AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        let mut device = self.runtime.block_on(self.lock_device(device_id))?;
+        let seekable = device.protocol_handler().as_seekable()
+            .ok_or(AdapterError::DeviceError(DeviceError::NotSupported))?;
+        Ok(seekable.tell())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::common::network::test_mocks::{TestNetworkManager, MockStreamProtocol};
+    use crate::adapters::common::network::operations::types::ReadRequest;
+    use crate::device::network::protocols::{FileProtocol, ProtocolHandler, OPEN_MODE_READ};
+
+    #[test]
+    fn test_seek_tell_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("data.bin"), b"ABCDEFGH").unwrap();
+
+        let mut protocol = FileProtocol::new(root);
+        protocol.set_mode(OPEN_MODE_READ);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(protocol.open("file:///data.bin")).unwrap();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///data.bin")
+            .with_protocol_device(Box::new(protocol));
+        let context = OperationsContext::new_with_runtime(manager, runtime);
+
+        assert_eq!(context.seek(SeekRequest::new("N1:file:///data.bin".to_string(), 5)).unwrap(), 5);
+        let mut request = ReadRequest::new("N1:file:///data.bin".to_string(), vec![0; 2]);
+        context.read(&mut request).unwrap();
+        assert_eq!(request.buffer, b"FG");
+        assert_eq!(context.tell(&mut TellRequest::new("N1:file:///data.bin".to_string())).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_seek_requires_random_access() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::default()));
+        let context = OperationsContext::new(manager);
+
+        let result = context.seek(SeekRequest::new("N1:tcp://host:23".to_string(), 0));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::NotSupported))));
+        let result = context.tell(&mut TellRequest::new("N1:tcp://host:23".to_string()));
+        assert!(matches!(result, Err(AdapterError::DeviceError(DeviceError::NotSupported))));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::{FlushRequest, StatusRequest, NetworkStatus}};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::protocols::ConnectionStatus;
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Send everything written to a device that is still sitting in its send buffer
+    pub fn flush(&self, mut request: FlushRequest) -> Result<(), AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            device.protocol_handler().flush().await.map_err(AdapterError::from)
+        })
+    }
+
+    /// Get the bytes waiting to be read, the bytes still to be sent and whether the device is connected
+    pub fn status(&self, request: &mut StatusRequest) -> Result<NetworkStatus, AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        self.runtime.block_on(async {
+            let mut device = self.lock_device(device_id).await?;
+            let handler = device.protocol_handler();
+            Ok(NetworkStatus {
+                bytes_waiting: handler.available().await?,
+                bytes_pending: handler.pending().await?,
+                connected: handler.status().await? == ConnectionStatus::Connected,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::any::Any;
+    use std::sync::{Arc, Mutex};
+    use crate::adapters::common::network::test_mocks::TestNetworkManager;
+    use crate::adapters::common::network::operations::types::WriteRequest;
+    use crate::device::DeviceResult;
+    use crate::device::network::protocols::{ProtocolHandler, SendBuffer};
+
+    const SPEC: &str = "N1:tcp://host:23";
+
+    // Buffers writes and only sends them when flushed, like a connection that never drains on its own
+    struct StalledStream {
+        buffer: SendBuffer,
+        sent: Arc<Mutex<Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl ProtocolHandler for StalledStream {
+        fn as_any(&self) -> &dyn Any { self }
+        fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+        async fn open(&mut self, _: &str) -> DeviceResult<()> { Ok(()) }
+        async fn close(&mut self) -> DeviceResult<()> { Ok(()) }
+        async fn read(&mut self, _: &mut [u8]) -> DeviceResult<usize> { Ok(0) }
+
+        async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            Ok(self.buffer.push(buf))
+        }
+
+        async fn status(&self) -> DeviceResult<ConnectionStatus> { Ok(ConnectionStatus::Connected) }
+        async fn available(&self) -> DeviceResult<usize> { Ok(3) }
+
+        async fn pending(&self) -> DeviceResult<usize> {
+            Ok(self.buffer.len())
+        }
+
+        async fn flush(&mut self) -> DeviceResult<()> {
+            self.sent.lock().unwrap().extend_from_slice(self.buffer.as_slice());
+            self.buffer.clear();
+            Ok(())
+        }
+    }
+
+    fn stalled_context() -> (OperationsContext<TestNetworkManager>, Arc<Mutex<Vec<u8>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let protocol = StalledStream { buffer: SendBuffer::new(4), sent: sent.clone() };
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, SPEC)
+            .with_protocol_device(Box::new(protocol));
+        (OperationsContext::new(manager), sent)
+    }
+
+    #[test]
+    fn test_write_waits_for_full_send_buffer() {
+        let (context, sent) = stalled_context();
+
+        // Ten bytes through a four byte buffer: nothing is dropped, the tail is left pending
+        assert_eq!(context.write(WriteRequest::new(SPEC.to_string(), b"0123456789".to_vec())).unwrap(), 10);
+        assert_eq!(*sent.lock().unwrap(), b"01234567");
+
+        let status = context.status(&mut StatusRequest::new(SPEC.to_string())).unwrap();
+        assert_eq!(status, NetworkStatus { bytes_waiting: 3, bytes_pending: 2, connected: true });
+
+        context.flush(FlushRequest::new(SPEC.to_string())).unwrap();
+        assert_eq!(*sent.lock().unwrap(), b"0123456789");
+        assert_eq!(context.status(&mut StatusRequest::new(SPEC.to_string())).unwrap().bytes_pending, 0);
+    }
+
+    #[test]
+    fn test_flush_without_device() {
+        let context = OperationsContext::new(TestNetworkManager::new().with_parse_result(1, SPEC));
+        assert!(matches!(context.flush(FlushRequest::new(SPEC.to_string())), Err(AdapterError::DeviceError(_))));
+        assert!(matches!(context.status(&mut StatusRequest::new("bogus".to_string())), Err(AdapterError::InvalidDeviceSpec)));
+    }
+}
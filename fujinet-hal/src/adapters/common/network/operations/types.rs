@@ -31,6 +31,157 @@ pub struct HttpGetRequest {
     pub buffer: Vec<u8>,
 }
 
+/// Common request structure for reading from a network device
+#[derive(Debug)]
+pub struct ReadRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// The buffer to read into
+    pub buffer: Vec<u8>,
+}
+
+/// Common request structure for writing to a network device
+#[derive(Debug)]
+pub struct WriteRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// The data to write
+    pub data: Vec<u8>,
+}
+
+/// Common request structure for adding a WebSocket handshake header
+#[derive(Debug)]
+pub struct WebSocketHeaderRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// The header in "Name: value" form
+    pub header: String,
+}
+
+/// Common request structure for choosing WebSocket text or binary frames
+#[derive(Debug)]
+pub struct WebSocketMessageTypeRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// True to send binary frames, false for text frames
+    pub binary: bool,
+}
+
+/// Common request structure for subscribing to an MQTT topic
+#[derive(Debug)]
+pub struct MqttSubscribeRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// The topic filter, which may contain + and # wildcards
+    pub topic: String,
+    /// Requested QoS level (0 or 1)
+    pub qos: u8,
+}
+
+/// Common request structure for publishing an MQTT message
+#[derive(Debug)]
+pub struct MqttPublishRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// The topic to publish to
+    pub topic: String,
+    /// The message payload
+    pub payload: Vec<u8>,
+    /// QoS level (0 or 1)
+    pub qos: u8,
+    /// Ask the broker to keep this as the topic's retained message
+    pub retain: bool,
+}
+
+/// Common request structure for opening a directory listing
+#[derive(Debug)]
+pub struct DirectoryOpenRequest {
+    /// The device specification string (e.g. "N1:file:///games/*.ATR")
+    pub device_spec: String,
+    /// Wildcard filter for entry names; None uses any wildcard in the spec
+    pub filter: Option<String>,
+    /// True for the long listing format, false for the short one
+    pub long: bool,
+}
+
+/// Common request structure for taking the next entry from a directory listing
+#[derive(Debug)]
+pub struct DirectoryEntryRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+}
+
+/// Common request structure for filesystem commands (XIO 32-43)
+#[derive(Debug)]
+pub struct FileSystemRequest {
+    /// The device spec string; a rename carries the new name after a comma
+    pub device_spec: String,
+    /// The XIO command number
+    pub command: u8,
+}
+
+/// Common request structure for moving the position of an open device (POINT)
+#[derive(Debug)]
+pub struct SeekRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+    /// Offset from the start of the resource
+    pub position: u64,
+}
+
+/// Common request structure for reading the position of an open device (NOTE)
+#[derive(Debug)]
+pub struct TellRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+}
+
+/// Common request structure for sending everything buffered on a device
+#[derive(Debug)]
+pub struct FlushRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+}
+
+/// Common request structure for reading the status of an open device
+#[derive(Debug)]
+pub struct StatusRequest {
+    /// The device spec string (only used at adapter layer)
+    pub device_spec: String,
+    /// The device ID for internal operations
+    pub device_id: Option<usize>,
+}
+
+/// Status of an open network device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NetworkStatus {
+    /// Bytes received and ready to read
+    pub bytes_waiting: usize,
+    /// Bytes written by the host that have not been sent yet
+    pub bytes_pending: usize,
+    pub connected: bool,
+}
+
 impl HttpGetRequest {
     pub fn new(device_spec: String, buffer: Vec<u8>) -> Self {
         Self {
@@ -49,4 +200,133 @@ impl HttpPostRequest {
             data,
         }
     }
-} 
\ No newline at end of file
+}
+
+impl ReadRequest {
+    pub fn new(device_spec: String, buffer: Vec<u8>) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            buffer,
+        }
+    }
+}
+
+impl WriteRequest {
+    pub fn new(device_spec: String, data: Vec<u8>) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            data,
+        }
+    }
+}
+
+impl WebSocketHeaderRequest {
+    pub fn new(device_spec: String, header: String) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            header,
+        }
+    }
+}
+
+impl WebSocketMessageTypeRequest {
+    pub fn new(device_spec: String, binary: bool) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            binary,
+        }
+    }
+}
+
+impl MqttSubscribeRequest {
+    pub fn new(device_spec: String, topic: String, qos: u8) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            topic,
+            qos,
+        }
+    }
+}
+
+impl MqttPublishRequest {
+    pub fn new(device_spec: String, topic: String, payload: Vec<u8>, qos: u8, retain: bool) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            topic,
+            payload,
+            qos,
+            retain,
+        }
+    }
+}
+
+impl DirectoryOpenRequest {
+    pub fn new(device_spec: String, filter: Option<String>, long: bool) -> Self {
+        Self {
+            device_spec,
+            filter,
+            long,
+        }
+    }
+}
+
+impl DirectoryEntryRequest {
+    pub fn new(device_spec: String) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+        }
+    }
+}
+
+impl FileSystemRequest {
+    pub fn new(device_spec: String, command: u8) -> Self {
+        Self {
+            device_spec,
+            command,
+        }
+    }
+}
+
+impl SeekRequest {
+    pub fn new(device_spec: String, position: u64) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+            position,
+        }
+    }
+}
+
+impl TellRequest {
+    pub fn new(device_spec: String) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+        }
+    }
+}
+
+impl FlushRequest {
+    pub fn new(device_spec: String) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+        }
+    }
+}
+
+impl StatusRequest {
+    pub fn new(device_spec: String) -> Self {
+        Self {
+            device_spec,
+            device_id: None,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::device::DeviceError;
+use crate::adapters::common::error::AdapterError;
+use super::{context::OperationsContext, types::{WebSocketHeaderRequest, WebSocketMessageTypeRequest}};
+use crate::device::network::manager::NetworkManager;
+use crate::device::network::protocols::{WebSocketProtocol, WebSocketMessageType};
+
+impl<M: NetworkManager> OperationsContext<M> {
+    /// Add a header to the WebSocket opening handshake
+    /// Must be called after open and before the first read or write
+    pub fn ws_add_header(&self, mut request: WebSocketHeaderRequest) -> Result<(), AdapterError> {
+        let (key, value) = request.header.split_once(':')
+            .ok_or(AdapterError::DeviceError(DeviceError::InvalidOperation))?;
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() {
+            return Err(AdapterError::DeviceError(DeviceError::InvalidOperation));
+        }
+
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        let mut device = self.runtime.block_on(self.lock_device(device_id))?;
+        let ws_protocol = device.protocol_handler().as_any_mut()
+            .downcast_mut::<WebSocketProtocol>()
+            .ok_or(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))?;
+
+        ws_protocol.set_header(key, value).map_err(AdapterError::from)
+    }
+
+    /// Choose whether writes to a WebSocket device are sent as text or binary frames
+    pub fn ws_set_message_type(&self, mut request: WebSocketMessageTypeRequest) -> Result<(), AdapterError> {
+        let device_id = self.resolve_device_id(&request.device_spec, &mut request.device_id)?;
+
+        let mut device = self.runtime.block_on(self.lock_device(device_id))?;
+        let ws_protocol = device.protocol_handler().as_any_mut()
+            .downcast_mut::<WebSocketProtocol>()
+            .ok_or(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))?;
+
+        ws_protocol.set_message_type(if request.binary {
+            WebSocketMessageType::Binary
+        } else {
+            WebSocketMessageType::Text
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::adapters::common::network::test_mocks::{
+        TestNetworkManager, MockStreamProtocol, MockWebSocketClientProvider,
+    };
+
+    fn websocket_context() -> OperationsContext<TestNetworkManager> {
+        let protocol = WebSocketProtocol::new(Arc::new(MockWebSocketClientProvider));
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:ws://game.example/")
+            .with_protocol_device(Box::new(protocol));
+        OperationsContext::new(manager)
+    }
+
+    fn with_websocket<R>(context: &OperationsContext<TestNetworkManager>, f: impl FnOnce(&WebSocketProtocol) -> R) -> R {
+        let mut device = context.runtime.block_on(context.lock_device(1)).unwrap();
+        f(device.protocol_handler().as_any().downcast_ref::<WebSocketProtocol>().unwrap())
+    }
+
+    #[test]
+    fn test_ws_add_header() {
+        let context = websocket_context();
+        let request = WebSocketHeaderRequest::new("N1:ws://game.example/".to_string(), "X-Player: 7 ".to_string());
+        context.ws_add_header(request).unwrap();
+
+        with_websocket(&context, |protocol| {
+            assert_eq!(protocol.headers(), &[("X-Player".to_string(), "7".to_string())]);
+        });
+
+        let request = WebSocketHeaderRequest::new("N1:ws://game.example/".to_string(), "no colon".to_string());
+        assert!(matches!(
+            context.ws_add_header(request),
+            Err(AdapterError::DeviceError(DeviceError::InvalidOperation))
+        ));
+    }
+
+    #[test]
+    fn test_ws_set_message_type() {
+        let context = websocket_context();
+        context.ws_set_message_type(WebSocketMessageTypeRequest::new("N1:ws://game.example/".to_string(), true)).unwrap();
+        with_websocket(&context, |protocol| {
+            assert_eq!(protocol.message_type(), WebSocketMessageType::Binary);
+        });
+    }
+
+    #[test]
+    fn test_ws_operations_require_websocket_device() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::default()));
+        let context = OperationsContext::new(manager);
+
+        let request = WebSocketMessageTypeRequest::new("N1:tcp://host:23".to_string(), true);
+        assert!(matches!(
+            context.ws_set_message_type(request),
+            Err(AdapterError::DeviceError(DeviceError::UnsupportedProtocol))
+        ));
+    }
+}
@@ -1,15 +1,24 @@
 use crate::device::DeviceResult;
 use crate::device::DeviceError;
-use crate::device::network::NetworkUrl;
-use crate::device::manager::DeviceState;
-use crate::device::network::NetworkDevice;
-use crate::device::network::protocols::{ProtocolHandler, HttpClient, HttpProtocol, HttpClientProvider};
-use crate::device::network::manager::NetworkManager;
+use crate::device::network::{NetworkUrl, EventBus};
+use crate::device::manager::{DeviceState, DEFAULT_NETWORK_UNITS};
+use crate::device::network::{DeviceSlot, NetworkDevice};
+use crate::device::network::protocols::{
+    ProtocolHandler, ConnectionStatus, HttpClient, HttpProtocol, HttpClientProvider,
+    WebSocketClient, WebSocketClientProvider, WebSocketMessage,
+    MqttClient, MqttClientProvider, MqttConnectOptions, MqttMessage, MqttQos,
+    DirectoryFormat, DirectoryListing, FileSystemCommand, filesystem,
+};
+use crate::device::network::manager::{NetworkManager, PendingOpen, SlotTask};
+use crate::device::network::DeviceGuard;
 use crate::device::{Device, DeviceStatus};
 use async_trait::async_trait;
+use futures_util::future::FutureExt;
 use std::collections::HashMap;
 use std::any::Any;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 // Mock HTTP client for testing
 #[derive(Clone)]
@@ -67,6 +76,10 @@ impl HttpClient for MockHttpClient {
         Ok(vec![])
     }
 
+    async fn request(&mut self, _method: &str, _url: &str, _headers: &[(&str, &str)], _body: &[u8]) -> DeviceResult<Vec<u8>> {
+        Ok(vec![])
+    }
+
     fn status_code(&self) -> u16 {
         200
     }
@@ -80,8 +93,9 @@ pub struct TestNetworkManager {
     parse_result: Option<(usize, NetworkUrl)>,
     open_result: bool,
     close_result: bool,
-    device: Option<Box<dyn NetworkDevice>>,
+    device: DeviceSlot,
     device_states: HashMap<usize, DeviceState>,
+    events: EventBus,
 }
 
 #[async_trait]
@@ -100,12 +114,25 @@ impl NetworkManager for TestNetworkManager {
         Err(DeviceError::InvalidUrl)
     }
 
-    async fn open_device(&mut self, _spec: &str, _mode: u8, _trans: u8) -> DeviceResult<()> {
-        if self.open_result {
-            Ok(())
-        } else {
-            Err(DeviceError::InvalidUrl)
+    fn unit_count(&self) -> usize {
+        DEFAULT_NETWORK_UNITS
+    }
+
+    fn begin_open(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<(PendingOpen, SlotTask<()>)> {
+        if !self.open_result {
+            return Err(DeviceError::InvalidUrl);
         }
+        let (device_id, url) = self.parse_device_spec(spec)?;
+        let pending = PendingOpen { device_id, generation: 0, mode, trans, url };
+        Ok((pending, async { Ok(()) }.boxed()))
+    }
+
+    fn finish_open(&mut self, pending: PendingOpen) -> bool {
+        let state = self.device_states.entry(pending.device_id).or_default();
+        state.mode = pending.mode;
+        state.trans = pending.trans;
+        state.url = Some(pending.url);
+        true
     }
 
     async fn find_device(&mut self, _spec: &str) -> DeviceResult<Option<(usize, &mut DeviceState)>> {
@@ -116,12 +143,58 @@ impl NetworkManager for TestNetworkManager {
         self.device_states.get_mut(&device_id)
     }
 
-    async fn close_device(&mut self, _device_id: usize) -> DeviceResult<bool> {
-        Ok(self.close_result)
+    fn begin_close(&mut self, _device_id: usize) -> SlotTask<bool> {
+        let closed = self.close_result;
+        async move { Ok(closed) }.boxed()
+    }
+
+    fn begin_close_all(&mut self) -> SlotTask<()> {
+        let slot = self.device.clone();
+        async move {
+            if let Some(mut device) = slot.lock().await.take() {
+                device.disconnect().await?;
+            }
+            Ok(())
+        }.boxed()
+    }
+
+    fn device_slot(&self, _device_id: usize) -> Option<DeviceSlot> {
+        Some(self.device.clone())
+    }
+
+    fn begin_directory(&mut self, spec: &str, filter: Option<&str>, format: DirectoryFormat) -> DeviceResult<(usize, SlotTask<()>)> {
+        let (device_id, url) = self.parse_device_spec(spec)?;
+        let slot = self.device.clone();
+        let filter = filter.map(str::to_string);
+        Ok((device_id, async move {
+            let mut device = DeviceGuard::lock(slot).await.ok_or(DeviceError::NotReady)?;
+            let handler = device.protocol_handler().as_directory().ok_or(DeviceError::NotSupported)?;
+            let entries = handler.list_directory(&url.url).await?;
+            let free_space = handler.free_space(&url.url).await?;
+            device.set_directory(Some(DirectoryListing::new(entries, filter.as_deref(), free_space, format)));
+            Ok(())
+        }.boxed()))
+    }
+
+    fn begin_filesystem_command(&mut self, spec: &str, command: FileSystemCommand) -> DeviceResult<SlotTask<()>> {
+        let (_, url) = self.parse_device_spec(spec)?;
+        let slot = self.device.clone();
+        Ok(async move {
+            let mut device = DeviceGuard::lock(slot).await.ok_or(DeviceError::NotReady)?;
+            let fs = device.protocol_handler().as_filesystem().ok_or(DeviceError::NotSupported)?;
+            filesystem::execute(fs, &url.url, &command).await
+        }.boxed())
+    }
+
+    fn set_prefix(&mut self, spec: &str) -> DeviceResult<usize> {
+        let (device_id, path) = self.split_unit(spec)?;
+        let state = self.device_states.entry(device_id).or_default();
+        state.prefix = NetworkUrl::change_prefix(state.prefix.as_deref(), path)?;
+        Ok(device_id)
     }
 
-    fn get_network_device(&mut self, _device_id: usize) -> Option<&mut Box<dyn NetworkDevice>> {
-        self.device.as_mut()
+    fn events(&self) -> &EventBus {
+        &self.events
     }
 }
 
@@ -131,8 +204,9 @@ impl TestNetworkManager {
             parse_result: None,
             open_result: false,
             close_result: false,
-            device: None,
+            device: DeviceSlot::default(),
             device_states: HashMap::new(),
+            events: EventBus::new(),
         }
     }
 
@@ -154,31 +228,38 @@ impl TestNetworkManager {
         self
     }
 
-    pub fn with_http_device(mut self, post_result: Result<(), DeviceError>) -> Self {
+    pub fn with_http_device(self, post_result: Result<(), DeviceError>) -> Self {
         let client = MockHttpClient {
             post_result,
             ..Default::default()
         };
         let provider = Arc::new(MockHttpClientProvider::new(client));
         let protocol = HttpProtocol::new(provider);
-        let device = Box::new(MockNetworkDevice {
+        self.with_device(MockNetworkDevice {
             protocol: Box::new(protocol),
-        });
-        self.device = Some(device);
-        self
+            directory: None,
+        })
     }
 
-    pub fn with_http_device_get(mut self, get_result: Result<Vec<u8>, DeviceError>) -> Self {
+    pub fn with_http_device_get(self, get_result: Result<Vec<u8>, DeviceError>) -> Self {
         let client = MockHttpClient {
             get_result,
             ..Default::default()
         };
         let provider = Arc::new(MockHttpClientProvider::new(client));
         let protocol = HttpProtocol::new(provider);
-        let device = Box::new(MockNetworkDevice {
+        self.with_device(MockNetworkDevice {
             protocol: Box::new(protocol),
-        });
-        self.device = Some(device);
+            directory: None,
+        })
+    }
+
+    pub fn with_protocol_device(self, protocol: Box<dyn ProtocolHandler>) -> Self {
+        self.with_device(MockNetworkDevice { protocol, directory: None })
+    }
+
+    fn with_device(self, device: MockNetworkDevice) -> Self {
+        *self.device.try_lock().unwrap() = Some(Box::new(device));
         self
     }
 
@@ -196,6 +277,7 @@ impl TestNetworkManager {
 // Mock network device
 pub struct MockNetworkDevice {
     protocol: Box<dyn ProtocolHandler>,
+    directory: Option<DirectoryListing>,
 }
 
 #[async_trait]
@@ -220,12 +302,12 @@ impl Device for MockNetworkDevice {
         Ok(())
     }
 
-    async fn read_bytes(&mut self, _buf: &mut [u8]) -> DeviceResult<usize> {
-        Ok(0)
+    async fn read_bytes(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        self.protocol.read(buf).await
     }
 
-    async fn write_bytes(&mut self, _buf: &[u8]) -> DeviceResult<usize> {
-        Ok(0)
+    async fn write_bytes(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        self.protocol.write(buf).await
     }
 
     async fn read_block(&mut self, _block: u32, _buf: &mut [u8]) -> DeviceResult<usize> {
@@ -258,6 +340,14 @@ impl NetworkDevice for MockNetworkDevice {
     fn protocol_handler(&mut self) -> &mut dyn ProtocolHandler {
         self.protocol.as_mut()
     }
+
+    fn directory(&mut self) -> Option<&mut DirectoryListing> {
+        self.directory.as_mut()
+    }
+
+    fn set_directory(&mut self, listing: Option<DirectoryListing>) {
+        self.directory = listing;
+    }
 }
 
 // Mock HTTP client provider for testing
@@ -275,4 +365,176 @@ impl HttpClientProvider for MockHttpClientProvider {
     fn create_http_client(&self) -> Box<dyn HttpClient> {
         Box::new(self.client.clone())
     }
-} 
\ No newline at end of file
+}
+
+// Mock stream protocol serving fixed read data and recording writes
+#[derive(Default)]
+pub struct MockStreamProtocol {
+    read_data: Vec<u8>,
+    read_pos: usize,
+    pub written: Arc<Mutex<Vec<u8>>>,
+    /// While set, reads would wait for the peer to send something
+    pub idle: Arc<AtomicBool>,
+    delay: Duration,
+}
+
+impl MockStreamProtocol {
+    pub fn with_read_data(data: &[u8]) -> Self {
+        Self {
+            read_data: data.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Make every read and write take `delay`, like a slow network
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[async_trait]
+impl ProtocolHandler for MockStreamProtocol {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+    async fn open(&mut self, _endpoint: &str) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        tokio::time::sleep(self.delay).await;
+        let remaining = &self.read_data[self.read_pos..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.read_pos += len;
+        Ok(len)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        tokio::time::sleep(self.delay).await;
+        self.written.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        Ok(ConnectionStatus::Connected)
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        Ok(self.read_data.len() - self.read_pos)
+    }
+
+    async fn read_would_wait(&mut self) -> DeviceResult<bool> {
+        Ok(self.idle.load(Ordering::SeqCst))
+    }
+}
+
+// Mock WebSocket client that accepts every handshake and never receives anything
+#[derive(Default)]
+pub struct MockWebSocketClient;
+
+#[async_trait]
+impl WebSocketClient for MockWebSocketClient {
+    async fn connect(&mut self, _url: &str, _headers: &[(String, String)]) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn send(&mut self, _message: WebSocketMessage) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> DeviceResult<Option<WebSocketMessage>> {
+        Ok(None)
+    }
+
+    async fn close(&mut self, _code: u16) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    fn next_message_len(&self) -> usize {
+        0
+    }
+
+    fn close_code(&self) -> Option<u16> {
+        None
+    }
+}
+
+// Mock WebSocket client provider for testing
+pub struct MockWebSocketClientProvider;
+
+impl WebSocketClientProvider for MockWebSocketClientProvider {
+    fn create_websocket_client(&self) -> Box<dyn WebSocketClient> {
+        Box::new(MockWebSocketClient)
+    }
+}
+
+/// A publish recorded by MockMqttClient: topic, payload, QoS and retain flag
+pub type MockPublish = (String, Vec<u8>, MqttQos, bool);
+
+// Mock MQTT client recording subscriptions and publishes
+#[derive(Clone, Default)]
+pub struct MockMqttClient {
+    pub subscriptions: Arc<Mutex<Vec<(String, MqttQos)>>>,
+    pub published: Arc<Mutex<Vec<MockPublish>>>,
+}
+
+#[async_trait]
+impl MqttClient for MockMqttClient {
+    async fn connect(&mut self, _options: &MqttConnectOptions) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, topic: &str, qos: MqttQos) -> DeviceResult<()> {
+        self.subscriptions.lock().unwrap().push((topic.to_string(), qos));
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, topic: &str) -> DeviceResult<()> {
+        self.subscriptions.lock().unwrap().retain(|(t, _)| t != topic);
+        Ok(())
+    }
+
+    async fn publish(&mut self, topic: &str, payload: &[u8], qos: MqttQos, retain: bool) -> DeviceResult<()> {
+        self.published.lock().unwrap().push((topic.to_string(), payload.to_vec(), qos, retain));
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> DeviceResult<Option<MqttMessage>> {
+        Ok(None)
+    }
+
+    async fn disconnect(&mut self) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<MqttMessage> {
+        None
+    }
+
+    fn error(&self) -> Option<DeviceError> {
+        None
+    }
+}
+
+// Mock MQTT client provider handing out clones of one recording client
+pub struct MockMqttClientProvider {
+    client: MockMqttClient,
+}
+
+impl MockMqttClientProvider {
+    pub fn new(client: MockMqttClient) -> Self {
+        Self { client }
+    }
+}
+
+impl MqttClientProvider for MockMqttClientProvider {
+    fn create_mqtt_client(&self) -> Box<dyn MqttClient> {
+        Box::new(self.client.clone())
+    }
+}
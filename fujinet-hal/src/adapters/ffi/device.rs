@@ -1,9 +1,14 @@
 use libc::size_t;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::Arc;
 use tokio::runtime::Runtime;
 use crate::device::Device;
 use crate::device::DeviceError;
+use crate::device::DeviceStatus;
+use crate::platform::Platform;
 use crate::adapters::ffi::{FN_ERR_OK, FN_ERR_IO_ERROR, FN_ERR_OFFLINE, FN_ERR_NO_DEVICE, FN_ERR_BAD_CMD, FN_ERR_WARNING};
-use super::FujiDevice;
+use super::{FujiDevice, FujiPlatform, PlatformContext};
 
 // Error codes for C
 #[repr(C)]
@@ -29,17 +34,83 @@ impl From<DeviceError> for FujiError {
             DeviceError::UnsupportedProtocol => FujiError::InvalidParameter,
             DeviceError::InvalidUrl => FujiError::InvalidParameter,
             DeviceError::InvalidDeviceId => FujiError::InvalidParameter,
+            DeviceError::ConnectionClosed(_) => FujiError::NetworkError,
         }
     }
 }
 
 
+/// Device status for C
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FujiDeviceStatus {
+    Ready = 0,
+    Error = 1,
+    Disconnected = 2,
+}
+
+impl From<DeviceStatus> for FujiDeviceStatus {
+    fn from(status: DeviceStatus) -> Self {
+        match status {
+            DeviceStatus::Ready => FujiDeviceStatus::Ready,
+            DeviceStatus::Error => FujiDeviceStatus::Error,
+            DeviceStatus::Disconnected => FujiDeviceStatus::Disconnected,
+        }
+    }
+}
+
+/// A device handed to C as a FujiDevice, with the platform runtime it runs on
+pub struct DeviceHandle {
+    device: Box<dyn Device>,
+    runtime: Arc<Runtime>,
+}
+
+/// Create a device of the given type (see X86Platform) on a platform from fuji_platform_create
+/// Returns null if the type is unknown or the platform is not up; free it with fuji_device_destroy
+///
+/// # Safety
+/// `platform` must be null or a platform from fuji_platform_create that has not been destroyed
+/// `device_type` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_create(platform: *mut FujiPlatform, device_type: *const c_char) -> *mut FujiDevice {
+    if platform.is_null() || device_type.is_null() {
+        return std::ptr::null_mut();
+    }
+    let context = unsafe { &*platform.cast::<PlatformContext>() };
+    let Ok(device_type) = unsafe { CStr::from_ptr(device_type) }.to_str() else {
+        return std::ptr::null_mut();
+    };
+
+    match context.runtime.block_on(context.platform.create_device(device_type)) {
+        Ok(device) => {
+            let handle = DeviceHandle { device, runtime: context.runtime.clone() };
+            Box::into_raw(Box::new(handle)).cast()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Close and free a device from fuji_device_create
+///
+/// # Safety
+/// `device` must be null or a device from fuji_device_create that has not been destroyed; it is freed here
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_destroy(device: *mut FujiDevice) -> FujiError {
+    if device.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    let mut handle = unsafe { Box::from_raw(device.cast::<DeviceHandle>()) };
+    match handle.runtime.block_on(handle.device.close()) {
+        Ok(_) => FujiError::Ok,
+        Err(e) => e.into(),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn fuji_device_open(device: *mut FujiDevice) -> FujiError {
     unsafe {
-        if let Some(device) = device.cast::<Box<dyn Device>>().as_mut() {
-            let rt = Runtime::new().unwrap();
-            match rt.block_on(device.open()) {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
+            match device.runtime.block_on(device.device.open()) {
                 Ok(_) => FujiError::Ok,
                 Err(e) => e.into(),
             }
@@ -52,9 +123,8 @@ pub extern "C" fn fuji_device_open(device: *mut FujiDevice) -> FujiError {
 #[no_mangle]
 pub extern "C" fn fuji_device_close(device: *mut FujiDevice) -> FujiError {
     unsafe {
-        if let Some(device) = device.cast::<Box<dyn Device>>().as_mut() {
-            let rt = Runtime::new().unwrap();
-            match rt.block_on(device.close()) {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
+            match device.runtime.block_on(device.device.close()) {
                 Ok(_) => FujiError::Ok,
                 Err(e) => e.into(),
             }
@@ -72,10 +142,9 @@ pub extern "C" fn fuji_device_read_bytes(
     bytes_read: *mut size_t,
 ) -> FujiError {
     unsafe {
-        if let Some(device) = device.cast::<Box<dyn Device>>().as_mut() {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
             let buffer = std::slice::from_raw_parts_mut(buffer, len);
-            let rt = Runtime::new().unwrap();
-            match rt.block_on(device.read_bytes(buffer)) {
+            match device.runtime.block_on(device.device.read_bytes(buffer)) {
                 Ok(n) => {
                     *bytes_read = n as size_t;
                     FujiError::Ok
@@ -96,10 +165,73 @@ pub extern "C" fn fuji_device_write_bytes(
     bytes_written: *mut size_t,
 ) -> FujiError {
     unsafe {
-        if let Some(device) = device.cast::<Box<dyn Device>>().as_mut() {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
+            let buffer = std::slice::from_raw_parts(buffer, len);
+            match device.runtime.block_on(device.device.write_bytes(buffer)) {
+                Ok(n) => {
+                    *bytes_written = n as size_t;
+                    FujiError::Ok
+                }
+                Err(e) => e.into(),
+            }
+        } else {
+            FujiError::InvalidParameter
+        }
+    }
+}
+
+/// Read block `block` into `buffer`; the block size is up to the device (a sector for disks)
+///
+/// # Safety
+/// `device` must be null or a device from fuji_device_create that has not been destroyed
+/// `buffer` must be null or valid for writes of `len` bytes, and `bytes_read` null or valid for writing
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_read_block(
+    device: *mut FujiDevice,
+    block: u32,
+    buffer: *mut u8,
+    len: size_t,
+    bytes_read: *mut size_t,
+) -> FujiError {
+    if buffer.is_null() || bytes_read.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    unsafe {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
+            let buffer = std::slice::from_raw_parts_mut(buffer, len);
+            match device.runtime.block_on(device.device.read_block(block, buffer)) {
+                Ok(n) => {
+                    *bytes_read = n as size_t;
+                    FujiError::Ok
+                }
+                Err(e) => e.into(),
+            }
+        } else {
+            FujiError::InvalidParameter
+        }
+    }
+}
+
+/// Write `buffer` to block `block`
+///
+/// # Safety
+/// `device` must be null or a device from fuji_device_create that has not been destroyed
+/// `buffer` must be null or valid for reads of `len` bytes, and `bytes_written` null or valid for writing
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_write_block(
+    device: *mut FujiDevice,
+    block: u32,
+    buffer: *const u8,
+    len: size_t,
+    bytes_written: *mut size_t,
+) -> FujiError {
+    if buffer.is_null() || bytes_written.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    unsafe {
+        if let Some(device) = device.cast::<DeviceHandle>().as_mut() {
             let buffer = std::slice::from_raw_parts(buffer, len);
-            let rt = Runtime::new().unwrap();
-            match rt.block_on(device.write_bytes(buffer)) {
+            match device.runtime.block_on(device.device.write_block(block, buffer)) {
                 Ok(n) => {
                     *bytes_written = n as size_t;
                     FujiError::Ok
@@ -111,3 +243,133 @@ pub extern "C" fn fuji_device_write_bytes(
         }
     }
 }
+
+/// # Safety
+/// `device` must be null or a device from fuji_device_create that has not been destroyed
+/// `status` must be null or valid for writing
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_get_status(device: *mut FujiDevice, status: *mut FujiDeviceStatus) -> FujiError {
+    if status.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    unsafe {
+        if let Some(device) = device.cast::<DeviceHandle>().as_ref() {
+            match device.runtime.block_on(device.device.get_status()) {
+                Ok(s) => {
+                    *status = s.into();
+                    FujiError::Ok
+                }
+                Err(e) => e.into(),
+            }
+        } else {
+            FujiError::InvalidParameter
+        }
+    }
+}
+
+/// Copy the device's name into `buffer` as a NUL-terminated string
+/// Fails with InvalidParameter if the buffer can't hold the name and its terminator
+///
+/// # Safety
+/// `device` must be null or a device from fuji_device_create that has not been destroyed
+/// `buffer` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_device_name(device: *mut FujiDevice, buffer: *mut c_char, len: size_t) -> FujiError {
+    if buffer.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    unsafe {
+        if let Some(device) = device.cast::<DeviceHandle>().as_ref() {
+            let name = device.device.name().as_bytes();
+            if name.len() >= len {
+                return FujiError::InvalidParameter;
+            }
+            let buffer = std::slice::from_raw_parts_mut(buffer.cast::<u8>(), len);
+            buffer[..name.len()].copy_from_slice(name);
+            buffer[name.len()] = 0;
+            FujiError::Ok
+        } else {
+            FujiError::InvalidParameter
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use crate::adapters::ffi::{fuji_platform_create, fuji_platform_destroy};
+    use crate::device::{DiskDevice, PrinterDevice};
+
+    fn create(platform: *mut FujiPlatform, device_type: &str) -> *mut FujiDevice {
+        let device_type = CString::new(device_type).unwrap();
+        unsafe { fuji_device_create(platform, device_type.as_ptr()) }
+    }
+
+    #[test]
+    fn test_device_lifecycle() {
+        let platform = fuji_platform_create();
+        assert!(!platform.is_null());
+        assert!(create(platform, "cassette").is_null());
+        assert!(create(std::ptr::null_mut(), "printer").is_null());
+
+        let printer = create(platform, "printer");
+        let clock = create(platform, "clock");
+        assert!(!printer.is_null() && !clock.is_null());
+
+        // Both devices run on the platform's runtime, which outlives the platform itself
+        assert!(matches!(unsafe { fuji_platform_destroy(platform) }, FujiError::Ok));
+
+        assert!(matches!(fuji_device_open(printer), FujiError::Ok));
+        let mut written: size_t = 0;
+        assert!(matches!(fuji_device_write_bytes(printer, b"HELLO".as_ptr(), 5, &mut written), FujiError::Ok));
+        assert_eq!(written, 5);
+        let handle = unsafe { &*printer.cast::<DeviceHandle>() };
+        assert_eq!(handle.device.as_any().downcast_ref::<PrinterDevice>().unwrap().output(), b"HELLO");
+
+        let mut time = [0u8; 6];
+        let mut read: size_t = 0;
+        assert!(matches!(fuji_device_read_bytes(clock, time.as_mut_ptr(), 6, &mut read), FujiError::NotReady));
+        assert!(matches!(fuji_device_open(clock), FujiError::Ok));
+        assert!(matches!(fuji_device_read_bytes(clock, time.as_mut_ptr(), 6, &mut read), FujiError::Ok));
+        assert_eq!(read, 6);
+
+        assert!(matches!(unsafe { fuji_device_destroy(printer) }, FujiError::Ok));
+        assert!(matches!(unsafe { fuji_device_destroy(clock) }, FujiError::Ok));
+        assert!(matches!(unsafe { fuji_device_destroy(std::ptr::null_mut()) }, FujiError::InvalidParameter));
+    }
+
+    #[test]
+    fn test_disk_block_io() {
+        let platform = fuji_platform_create();
+        let disk = create(platform, "disk");
+        unsafe { fuji_platform_destroy(platform) };
+
+        let mut name = [0 as c_char; 8];
+        assert!(matches!(unsafe { fuji_device_name(disk, name.as_mut_ptr(), 8) }, FujiError::Ok));
+        assert_eq!(unsafe { CStr::from_ptr(name.as_ptr()) }.to_str().unwrap(), "disk");
+        assert!(matches!(unsafe { fuji_device_name(disk, name.as_mut_ptr(), 4) }, FujiError::InvalidParameter));
+
+        let mut status = FujiDeviceStatus::Error;
+        assert!(matches!(unsafe { fuji_device_get_status(disk, &mut status) }, FujiError::Ok));
+        assert_eq!(status, FujiDeviceStatus::Disconnected);
+
+        let handle = unsafe { &mut *disk.cast::<DeviceHandle>() };
+        handle.device.as_any_mut().downcast_mut::<DiskDevice>().unwrap().mount(vec![0; 128 * 3], false);
+        fuji_device_open(disk);
+        assert!(matches!(unsafe { fuji_device_get_status(disk, &mut status) }, FujiError::Ok));
+        assert_eq!(status, FujiDeviceStatus::Ready);
+
+        let sector = [0x5Au8; 128];
+        let mut count: size_t = 0;
+        assert!(matches!(unsafe { fuji_device_write_block(disk, 1, sector.as_ptr(), 128, &mut count) }, FujiError::Ok));
+        assert_eq!(count, 128);
+        let mut buf = [0u8; 128];
+        assert!(matches!(unsafe { fuji_device_read_block(disk, 1, buf.as_mut_ptr(), 128, &mut count) }, FujiError::Ok));
+        assert_eq!(buf, sector);
+        assert!(matches!(unsafe { fuji_device_read_block(disk, 3, buf.as_mut_ptr(), 128, &mut count) }, FujiError::InvalidParameter));
+        assert!(matches!(unsafe { fuji_device_read_block(disk, 0, std::ptr::null_mut(), 128, &mut count) }, FujiError::InvalidParameter));
+
+        unsafe { fuji_device_destroy(disk) };
+    }
+}
@@ -5,6 +5,7 @@ use crate::adapters::common::error::AdapterError;
 // Opaque types for C
 pub type FujiDevice = c_void;
 pub type FujiPlatform = c_void;
+pub type FujiConfigDevice = c_void;
 pub type FujiHostTranslator = c_void;
 
 // FujiNet error codes
@@ -14,6 +15,7 @@ pub const FN_ERR_BAD_CMD: u8 = 2;
 pub const FN_ERR_OFFLINE: u8 = 3;  /* The device is offline */
 pub const FN_ERR_WARNING: u8 = 4;  /* Device specific non-fatal warning issued */
 pub const FN_ERR_NO_DEVICE: u8 = 5; /* There is no network device */
+pub const FN_ERR_WOULD_BLOCK: u8 = 6; /* Non-blocking operation still in progress, try again */
 pub const FN_ERR_UNKNOWN: u8 = 0xff;   /* Device specific error we didn't handle */
 pub const FN_ERR_NOT_INITIALIZED: u8 = 128;  // Using 128 as it's likely not used by other error codes
 
@@ -32,11 +34,13 @@ pub fn adapter_error_to_ffi(error: AdapterError) -> u8 {
         AdapterError::InvalidDeviceSpec => FN_ERR_BAD_CMD,
         AdapterError::InvalidMode => FN_ERR_BAD_CMD,
         AdapterError::InvalidTranslation => FN_ERR_BAD_CMD,
+        AdapterError::WouldBlock => FN_ERR_WOULD_BLOCK,
         AdapterError::DeviceError(device_error) => match device_error {
             // Map specific device errors to appropriate FFI codes
             crate::device::DeviceError::InvalidUrl => FN_ERR_NO_DEVICE,
             crate::device::DeviceError::InvalidDeviceId => FN_ERR_NO_DEVICE,
             crate::device::DeviceError::UnsupportedProtocol => FN_ERR_BAD_CMD,
+            crate::device::DeviceError::ConnectionClosed(_) => FN_ERR_OFFLINE,
             _ => FN_ERR_IO_ERROR,
         },
     }
@@ -0,0 +1,434 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use crate::config::FnConfig;
+use crate::device::{DeviceError, DeviceResult, FujiConfig};
+use crate::device::fuji::{MAX_HOSTS, HOST_NAME_LEN, MAX_DISK_DEVICES, DEVICE_SLOT_LEN};
+use crate::platform::x86::X86AdapterInfo;
+use super::{FujiConfigDevice, FN_ERR_OK, FN_ERR_IO_ERROR, FN_ERR_BAD_CMD, FN_ERR_OFFLINE};
+
+/// The host and device slots the CONFIG program works on, handed to C as a FujiConfigDevice
+pub struct FujiHandle {
+    fuji: FujiConfig,
+    // The settings last loaded, kept so saving leaves the rest of the file alone
+    config: FnConfig,
+}
+
+impl FujiHandle {
+    fn new(fuji: FujiConfig) -> Self {
+        Self { fuji, config: FnConfig::new() }
+    }
+}
+
+/// The handle behind a FujiConfigDevice, or None for null
+fn handle<'a>(fuji: *mut FujiConfigDevice) -> Option<&'a mut FujiHandle> {
+    unsafe { fuji.cast::<FujiHandle>().as_mut() }
+}
+
+/// Create a config device with empty slots, reporting the host's network adapter
+/// Free it with fuji_config_destroy
+#[no_mangle]
+pub extern "C" fn fuji_config_create() -> *mut FujiConfigDevice {
+    let fuji = FujiConfig::with_adapter(Box::new(X86AdapterInfo::new()));
+    Box::into_raw(Box::new(FujiHandle::new(fuji))).cast()
+}
+
+/// Free a config device from fuji_config_create
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed; it is freed here
+#[no_mangle]
+pub unsafe extern "C" fn fuji_config_destroy(fuji: *mut FujiConfigDevice) -> u8 {
+    if fuji.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+    drop(unsafe { Box::from_raw(fuji.cast::<FujiHandle>()) });
+    FN_ERR_OK
+}
+
+/// Maps a Fuji device result to an FFI error code
+fn fuji_result_to_ffi<T>(result: DeviceResult<T>) -> u8 {
+    match result {
+        Ok(_) => FN_ERR_OK,
+        Err(e) => fuji_error_to_ffi(e),
+    }
+}
+
+fn fuji_error_to_ffi(error: DeviceError) -> u8 {
+    match error {
+        DeviceError::InvalidDeviceId | DeviceError::InvalidOperation | DeviceError::NotSupported => FN_ERR_BAD_CMD,
+        DeviceError::NotReady => FN_ERR_OFFLINE,
+        _ => FN_ERR_IO_ERROR,
+    }
+}
+
+/// Copy a slot table into a C buffer, which must be big enough for all of it
+fn copy_out(data: &[u8], buf: *mut u8, len: usize) -> u8 {
+    if buf.is_null() || len < data.len() {
+        return FN_ERR_BAD_CMD;
+    }
+    unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len()) };
+    FN_ERR_OK
+}
+
+/// Read all 8 host slots, 32 NUL-padded bytes each, into `buf` (at least 256 bytes)
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_get_host_slots(fuji: *mut FujiConfigDevice, buf: *mut u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    copy_out(&handle.fuji.read_host_slots(), buf, len)
+}
+
+/// Replace all host slots from 256 bytes in the fuji_get_host_slots layout
+/// Device slots on a host whose name changes are emptied
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for reads of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_put_host_slots(fuji: *mut FujiConfigDevice, buf: *const u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    if buf.is_null() || len != MAX_HOSTS * HOST_NAME_LEN {
+        return FN_ERR_BAD_CMD;
+    }
+    let data = unsafe { std::slice::from_raw_parts(buf, len) };
+    fuji_result_to_ffi(handle.fuji.write_host_slots(data))
+}
+
+/// Read all 8 device slots, 38 bytes each (host slot, mode, 36 byte filename), into `buf`
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_get_device_slots(fuji: *mut FujiConfigDevice, buf: *mut u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    copy_out(&handle.fuji.read_device_slots(), buf, len)
+}
+
+/// Replace all device slots from 304 bytes in the fuji_get_device_slots layout
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for reads of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_put_device_slots(fuji: *mut FujiConfigDevice, buf: *const u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    if buf.is_null() || len != MAX_DISK_DEVICES * DEVICE_SLOT_LEN {
+        return FN_ERR_BAD_CMD;
+    }
+    let data = unsafe { std::slice::from_raw_parts(buf, len) };
+    fuji_result_to_ffi(handle.fuji.write_device_slots(data))
+}
+
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+#[no_mangle]
+pub unsafe extern "C" fn fuji_mount_host_slot(fuji: *mut FujiConfigDevice, host_slot: u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    fuji_result_to_ffi(handle.fuji.mount_host(host_slot as usize))
+}
+
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+#[no_mangle]
+pub unsafe extern "C" fn fuji_unmount_host_slot(fuji: *mut FujiConfigDevice, host_slot: u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    fuji_result_to_ffi(handle.fuji.unmount_host(host_slot as usize))
+}
+
+/// Assign `filename` on host slot `host_slot` to device slot `device_slot`
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `filename` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn fuji_set_device_filename(
+    fuji: *mut FujiConfigDevice,
+    mode: u8,
+    host_slot: u8,
+    device_slot: u8,
+    filename: *const c_char,
+) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    if filename.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+    let Ok(filename) = unsafe { CStr::from_ptr(filename) }.to_str() else {
+        return FN_ERR_BAD_CMD;
+    };
+    fuji_result_to_ffi(handle.fuji.set_device_filename(device_slot as usize, host_slot, mode, filename))
+}
+
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+#[no_mangle]
+pub unsafe extern "C" fn fuji_mount_disk_image(fuji: *mut FujiConfigDevice, device_slot: u8, mode: u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    fuji_result_to_ffi(handle.fuji.mount_disk_image(device_slot as usize, mode))
+}
+
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+#[no_mangle]
+pub unsafe extern "C" fn fuji_unmount_disk_image(fuji: *mut FujiConfigDevice, device_slot: u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    fuji_result_to_ffi(handle.fuji.unmount_disk_image(device_slot as usize))
+}
+
+/// Read the network adapter details, in the 140 byte layout of FUJICMD_GET_ADAPTERCONFIG
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_get_adapter_config(fuji: *mut FujiConfigDevice, buf: *mut u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    match handle.fuji.read_adapter_config() {
+        Ok(config) => copy_out(&config, buf, len),
+        Err(e) => fuji_error_to_ffi(e),
+    }
+}
+
+/// Scan for networks, storing how many were found in `count`
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `count` must be null or valid for writing a byte
+#[no_mangle]
+pub unsafe extern "C" fn fuji_scan_networks(fuji: *mut FujiConfigDevice, count: *mut u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    if count.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+    match handle.fuji.scan_networks() {
+        Ok(found) => {
+            unsafe { *count = found };
+            FN_ERR_OK
+        }
+        Err(e) => fuji_error_to_ffi(e),
+    }
+}
+
+/// Read result `index` of the last scan: a 33 byte SSID and the RSSI as a signed byte
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn fuji_get_scan_result(fuji: *mut FujiConfigDevice, index: u8, buf: *mut u8, len: usize) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    match handle.fuji.read_scan_result(index as usize) {
+        Ok(result) => copy_out(&result, buf, len),
+        Err(e) => fuji_error_to_ffi(e),
+    }
+}
+
+/// Store WIFI_STATUS_CONNECTED (3) or WIFI_STATUS_DISCONNECTED (6) in `status`
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `status` must be null or valid for writing a byte
+#[no_mangle]
+pub unsafe extern "C" fn fuji_get_wifi_status(fuji: *mut FujiConfigDevice, status: *mut u8) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    if status.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+    match handle.fuji.wifi_status() {
+        Ok(value) => {
+            unsafe { *status = value };
+            FN_ERR_OK
+        }
+        Err(e) => fuji_error_to_ffi(e),
+    }
+}
+
+/// The path a config function was given, or the platform default for NULL
+fn config_path(path: *const c_char) -> Option<PathBuf> {
+    if path.is_null() {
+        return Some(crate::platform::x86::default_config_path());
+    }
+    unsafe { CStr::from_ptr(path) }.to_str().ok().map(PathBuf::from)
+}
+
+/// Load fnconfig.ini from `path` (NULL for the default) into the host and device slots
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `path` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn fuji_config_load(fuji: *mut FujiConfigDevice, path: *const c_char) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    let Some(path) = config_path(path) else {
+        return FN_ERR_BAD_CMD;
+    };
+    let result = FnConfig::load(path).and_then(|config| {
+        config.apply_to(&mut handle.fuji)?;
+        handle.config = config;
+        Ok(())
+    });
+    fuji_result_to_ffi(result)
+}
+
+/// Save the host and device slots to fnconfig.ini at `path` (NULL for the file last loaded)
+///
+/// # Safety
+/// `fuji` must be null or a handle from fuji_config_create that has not been destroyed
+/// `path` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn fuji_config_save(fuji: *mut FujiConfigDevice, path: *const c_char) -> u8 {
+    let Some(handle) = handle(fuji) else {
+        return FN_ERR_BAD_CMD;
+    };
+    let path = match (path.is_null(), handle.config.path()) {
+        (true, Some(loaded)) => loaded.to_path_buf(),
+        _ => match config_path(path) {
+            Some(path) => path,
+            None => return FN_ERR_BAD_CMD,
+        },
+    };
+    let result = handle.config
+        .update_from(&handle.fuji)
+        .and_then(|_| handle.config.save_to(&path));
+    fuji_result_to_ffi(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use crate::device::fuji::{MOUNT_MODE_READ, HOST_SLOT_EMPTY, WIFI_STATUS_CONNECTED, WIFI_STATUS_DISCONNECTED};
+    use crate::device::adapter::{ADAPTER_CONFIG_LEN, SCAN_RESULT_LEN, SSID_LEN};
+
+    #[test]
+    fn test_config_session() {
+        let fuji = fuji_config_create();
+
+        // CONFIG reads the host slots, edits one and writes them all back
+        let mut hosts = [0u8; MAX_HOSTS * HOST_NAME_LEN];
+        assert_eq!(unsafe { fuji_get_host_slots(fuji, hosts.as_mut_ptr(), hosts.len()) }, FN_ERR_OK);
+        hosts[HOST_NAME_LEN..HOST_NAME_LEN + 16].copy_from_slice(b"tnfs.example.com");
+        assert_eq!(unsafe { fuji_put_host_slots(fuji, hosts.as_ptr(), hosts.len()) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_get_host_slots(fuji, hosts.as_mut_ptr(), 10) }, FN_ERR_BAD_CMD);
+
+        let filename = CString::new("/games/jumpman.atr").unwrap();
+        assert_eq!(unsafe { fuji_set_device_filename(fuji, MOUNT_MODE_READ, 1, 0, filename.as_ptr()) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_set_device_filename(fuji, MOUNT_MODE_READ, 9, 0, filename.as_ptr()) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { fuji_mount_disk_image(fuji, 0, MOUNT_MODE_READ) }, FN_ERR_OFFLINE);
+        assert_eq!(unsafe { fuji_mount_host_slot(fuji, 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_mount_disk_image(fuji, 0, MOUNT_MODE_READ) }, FN_ERR_OK);
+        assert!(handle(fuji).unwrap().fuji.disk_slot(0).unwrap().mounted);
+
+        let mut devices = [0u8; MAX_DISK_DEVICES * DEVICE_SLOT_LEN];
+        assert_eq!(unsafe { fuji_get_device_slots(fuji, devices.as_mut_ptr(), devices.len()) }, FN_ERR_OK);
+        assert_eq!(&devices[..2], &[1, MOUNT_MODE_READ]);
+        assert_eq!(&devices[2..20], b"/games/jumpman.atr");
+        assert_eq!(devices[DEVICE_SLOT_LEN], HOST_SLOT_EMPTY);
+
+        assert_eq!(unsafe { fuji_unmount_disk_image(fuji, 0) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_unmount_host_slot(fuji, 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_put_device_slots(fuji, devices.as_ptr(), devices.len()) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_put_device_slots(fuji, devices.as_ptr(), 8) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { fuji_mount_disk_image(fuji, 8, MOUNT_MODE_READ) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { fuji_config_destroy(fuji) }, FN_ERR_OK);
+    }
+
+    #[test]
+    fn test_handles_are_separate() {
+        let first = fuji_config_create();
+        let second = fuji_config_create();
+        let filename = CString::new("/dos.atr").unwrap();
+        assert_eq!(unsafe { fuji_set_device_filename(first, MOUNT_MODE_READ, 0, 0, filename.as_ptr()) }, FN_ERR_OK);
+        assert_eq!(handle(first).unwrap().fuji.disk_slot(0).unwrap().filename, "/dos.atr");
+        assert!(handle(second).unwrap().fuji.disk_slot(0).unwrap().is_empty());
+
+        let mut hosts = [0u8; MAX_HOSTS * HOST_NAME_LEN];
+        assert_eq!(unsafe { fuji_get_host_slots(std::ptr::null_mut(), hosts.as_mut_ptr(), hosts.len()) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { fuji_config_destroy(std::ptr::null_mut()) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { fuji_config_destroy(first) }, FN_ERR_OK);
+        assert_eq!(unsafe { fuji_config_destroy(second) }, FN_ERR_OK);
+    }
+
+    #[test]
+    fn test_config_load_and_save() {
+        let fuji = fuji_config_create();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fnconfig.ini");
+        std::fs::write(&path, "[Host1]\ntype=SD\nname=SD\n\n[Mount1]\nhostslot=0\nmode=r\npath=/dos.atr\n\n[Custom]\nkeep=me\n").unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(unsafe { fuji_config_load(fuji, c_path.as_ptr()) }, FN_ERR_OK);
+        assert_eq!(handle(fuji).unwrap().fuji.host_slot(0).unwrap().name, "SD");
+        assert_eq!(handle(fuji).unwrap().fuji.disk_slot(0).unwrap().filename, "/dos.atr");
+
+        handle(fuji).unwrap().fuji.set_host_name(1, "tnfs.example.com").unwrap();
+        assert_eq!(unsafe { fuji_config_save(fuji, std::ptr::null()) }, FN_ERR_OK);
+        let saved = FnConfig::load(&path).unwrap();
+        assert_eq!(saved.host(1).unwrap().name, "tnfs.example.com");
+        assert_eq!(saved.ini().get("Custom", "keep"), Some("me"));
+
+        let missing = CString::new(dir.path().join("none").join("fnconfig.ini").to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { fuji_config_save(fuji, missing.as_ptr()) }, FN_ERR_IO_ERROR);
+        assert_eq!(unsafe { fuji_config_destroy(fuji) }, FN_ERR_OK);
+    }
+
+    #[test]
+    fn test_adapter_status() {
+        let fuji = fuji_config_create();
+        let mut config = [0u8; ADAPTER_CONFIG_LEN];
+        assert_eq!(unsafe { fuji_get_adapter_config(fuji, config.as_mut_ptr(), config.len()) }, FN_ERR_OK);
+        assert_ne!(config[0], 0, "the adapter always has an SSID");
+        assert_eq!(unsafe { fuji_get_adapter_config(fuji, config.as_mut_ptr(), 10) }, FN_ERR_BAD_CMD);
+
+        let mut count = 0;
+        assert_eq!(unsafe { fuji_scan_networks(fuji, &mut count) }, FN_ERR_OK);
+        assert_eq!(count, 1);
+        let mut result = [0u8; SCAN_RESULT_LEN];
+        assert_eq!(unsafe { fuji_get_scan_result(fuji, 0, result.as_mut_ptr(), result.len()) }, FN_ERR_OK);
+        assert_eq!(&result[..SSID_LEN], &config[..SSID_LEN]);
+        assert_eq!(unsafe { fuji_get_scan_result(fuji, 1, result.as_mut_ptr(), result.len()) }, FN_ERR_BAD_CMD);
+
+        let mut status = 0;
+        assert_eq!(unsafe { fuji_get_wifi_status(fuji, &mut status) }, FN_ERR_OK);
+        assert!(status == WIFI_STATUS_CONNECTED || status == WIFI_STATUS_DISCONNECTED);
+        assert_eq!(unsafe { fuji_get_wifi_status(fuji, std::ptr::null_mut()) }, FN_ERR_BAD_CMD);
+
+        // Without an adapter there is nothing to report
+        assert_eq!(unsafe { fuji_config_destroy(fuji) }, FN_ERR_OK);
+        let fuji = Box::into_raw(Box::new(FujiHandle::new(FujiConfig::new()))).cast();
+        assert_eq!(unsafe { fuji_get_adapter_config(fuji, config.as_mut_ptr(), config.len()) }, FN_ERR_OFFLINE);
+        assert_eq!(unsafe { fuji_config_destroy(fuji) }, FN_ERR_OK);
+    }
+}
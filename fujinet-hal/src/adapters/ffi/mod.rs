@@ -1,9 +1,13 @@
 // Declare modules first
 pub mod device;
 pub mod network;
+pub mod platform;
+pub mod fuji;
 pub mod error;
 
 // Then re-export what we want to be public
 pub use device::*;
 pub use network::*;
+pub use platform::*;
+pub use fuji::*;
 pub use error::*;
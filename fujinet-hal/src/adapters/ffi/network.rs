@@ -1,28 +1,35 @@
-use std::ffi::CStr;
+use std::ffi::{c_void, CStr};
 use std::os::raw::c_char;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
-#[cfg(not(test))]
-use crate::platform::create_network_manager;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-#[cfg(not(test))]
-use std::sync::OnceLock;
-
-#[cfg(test)]
-use std::sync::atomic::{AtomicPtr, Ordering};
+use crate::device::manager::DEFAULT_NETWORK_UNITS;
+use crate::platform::{Platform, X86Platform};
 
 use crate::adapters::common::network::operations::OperationsContext;
-use crate::adapters::common::network::operations::types::{DeviceOpenRequest, HttpPostRequest, HttpGetRequest};
+use crate::adapters::common::network::operations::types::{
+    DeviceOpenRequest, HttpPostRequest, HttpGetRequest, ReadRequest, WriteRequest,
+    WebSocketHeaderRequest, WebSocketMessageTypeRequest, MqttSubscribeRequest, MqttPublishRequest,
+    DirectoryOpenRequest, DirectoryEntryRequest, FileSystemRequest, SeekRequest, TellRequest,
+    FlushRequest, StatusRequest, NetworkStatus,
+};
 use crate::adapters::common::error::AdapterError;
 use crate::adapters::ffi::error::{
     device_result_to_error,
     adapter_result_to_ffi,
+    adapter_error_to_ffi,
     FN_ERR_BAD_CMD,
+    FN_ERR_IO_ERROR,
     FN_ERR_NOT_INITIALIZED,
     FN_ERR_OK,
 };
 use crate::device::network::manager::NetworkManager;
+use crate::device::network::events::{EventCallback, NetworkEvent};
+use crate::device::network::protocols::DirectoryEntry;
+use crate::device::network::protocols::filesystem::{XIO_RENAME, XIO_DELETE, XIO_LOCK, XIO_UNLOCK, XIO_MKDIR, XIO_RMDIR};
 
 // Trait to abstract over different OperationsContext types
 trait NetworkOperations: Send + Sync {
@@ -30,6 +37,26 @@ trait NetworkOperations: Send + Sync {
     fn close_device(&self, device_id: usize) -> Result<(), AdapterError>;
     fn http_post(&self, request: HttpPostRequest) -> Result<(), AdapterError>;
     fn http_get(&self, request: &mut HttpGetRequest) -> Result<usize, AdapterError>;
+    fn read(&self, request: &mut ReadRequest) -> Result<usize, AdapterError>;
+    fn write(&self, request: WriteRequest) -> Result<usize, AdapterError>;
+    fn ws_add_header(&self, request: WebSocketHeaderRequest) -> Result<(), AdapterError>;
+    fn ws_set_message_type(&self, request: WebSocketMessageTypeRequest) -> Result<(), AdapterError>;
+    fn mqtt_subscribe(&self, request: MqttSubscribeRequest) -> Result<(), AdapterError>;
+    fn mqtt_publish(&self, request: MqttPublishRequest) -> Result<(), AdapterError>;
+    fn open_directory(&self, request: DirectoryOpenRequest) -> Result<usize, AdapterError>;
+    fn read_directory(&self, request: &mut ReadRequest) -> Result<usize, AdapterError>;
+    fn read_directory_entry(&self, request: &mut DirectoryEntryRequest) -> Result<Option<DirectoryEntry>, AdapterError>;
+    fn filesystem_command(&self, request: FileSystemRequest) -> Result<(), AdapterError>;
+    fn set_prefix(&self, spec: &str) -> Result<usize, AdapterError>;
+    fn seek(&self, request: SeekRequest) -> Result<u64, AdapterError>;
+    fn tell(&self, request: &mut TellRequest) -> Result<u64, AdapterError>;
+    fn get_prefix(&self, spec: &str) -> Result<String, AdapterError>;
+    fn set_event_callback(&self, callback: Option<EventCallback>);
+    fn close_all_devices(&self) -> Result<(), AdapterError>;
+    fn set_nonblocking(&self, spec: &str, enabled: bool) -> Result<usize, AdapterError>;
+    fn flush(&self, request: FlushRequest) -> Result<(), AdapterError>;
+    fn status(&self, request: &mut StatusRequest) -> Result<NetworkStatus, AdapterError>;
+    fn poll_event(&self) -> Option<NetworkEvent>;
     fn parse_device_spec(&self, spec: &str) -> Result<usize, AdapterError>;
     fn validate_device_spec(&self, spec: &str) -> Result<usize, AdapterError>;
 }
@@ -52,6 +79,86 @@ impl<M: NetworkManager + Send + Sync + 'static> NetworkOperations for Operations
         OperationsContext::http_get(self, request)
     }
 
+    fn read(&self, request: &mut ReadRequest) -> Result<usize, AdapterError> {
+        OperationsContext::read(self, request)
+    }
+
+    fn write(&self, request: WriteRequest) -> Result<usize, AdapterError> {
+        OperationsContext::write(self, request)
+    }
+
+    fn ws_add_header(&self, request: WebSocketHeaderRequest) -> Result<(), AdapterError> {
+        OperationsContext::ws_add_header(self, request)
+    }
+
+    fn ws_set_message_type(&self, request: WebSocketMessageTypeRequest) -> Result<(), AdapterError> {
+        OperationsContext::ws_set_message_type(self, request)
+    }
+
+    fn mqtt_subscribe(&self, request: MqttSubscribeRequest) -> Result<(), AdapterError> {
+        OperationsContext::mqtt_subscribe(self, request)
+    }
+
+    fn mqtt_publish(&self, request: MqttPublishRequest) -> Result<(), AdapterError> {
+        OperationsContext::mqtt_publish(self, request)
+    }
+
+    fn open_directory(&self, request: DirectoryOpenRequest) -> Result<usize, AdapterError> {
+        OperationsContext::open_directory(self, request)
+    }
+
+    fn read_directory(&self, request: &mut ReadRequest) -> Result<usize, AdapterError> {
+        OperationsContext::read_directory(self, request)
+    }
+
+    fn read_directory_entry(&self, request: &mut DirectoryEntryRequest) -> Result<Option<DirectoryEntry>, AdapterError> {
+        OperationsContext::read_directory_entry(self, request)
+    }
+
+    fn filesystem_command(&self, request: FileSystemRequest) -> Result<(), AdapterError> {
+        OperationsContext::filesystem_command(self, request)
+    }
+
+    fn set_prefix(&self, spec: &str) -> Result<usize, AdapterError> {
+        OperationsContext::set_prefix(self, spec)
+    }
+
+    fn seek(&self, request: SeekRequest) -> Result<u64, AdapterError> {
+        OperationsContext::seek(self, request)
+    }
+
+    fn tell(&self, request: &mut TellRequest) -> Result<u64, AdapterError> {
+        OperationsContext::tell(self, request)
+    }
+
+    fn get_prefix(&self, spec: &str) -> Result<String, AdapterError> {
+        OperationsContext::get_prefix(self, spec)
+    }
+
+    fn set_event_callback(&self, callback: Option<EventCallback>) {
+        OperationsContext::set_event_callback(self, callback)
+    }
+
+    fn poll_event(&self) -> Option<NetworkEvent> {
+        OperationsContext::poll_event(self)
+    }
+
+    fn close_all_devices(&self) -> Result<(), AdapterError> {
+        OperationsContext::close_all_devices(self)
+    }
+
+    fn set_nonblocking(&self, spec: &str, enabled: bool) -> Result<usize, AdapterError> {
+        OperationsContext::set_nonblocking(self, spec, enabled)
+    }
+
+    fn flush(&self, request: FlushRequest) -> Result<(), AdapterError> {
+        OperationsContext::flush(self, request)
+    }
+
+    fn status(&self, request: &mut StatusRequest) -> Result<NetworkStatus, AdapterError> {
+        OperationsContext::status(self, request)
+    }
+
     fn parse_device_spec(&self, spec: &str) -> Result<usize, AdapterError> {
         let manager = self.manager.lock().unwrap();
         manager.parse_device_spec(spec)
@@ -64,67 +171,133 @@ impl<M: NetworkManager + Send + Sync + 'static> NetworkOperations for Operations
     }
 }
 
-#[cfg(not(test))]
-// Production global state
-static OPERATIONS: OnceLock<Arc<dyn NetworkOperations>> = OnceLock::new();
+/// Everything network_init brings up and network_shutdown tears down again
+struct FfiState {
+    operations: Arc<dyn NetworkOperations>,
+    runtime: Arc<Runtime>,
+    /// The platform the network manager came from; test contexts run without one
+    platform: Option<X86Platform>,
+    units: u8,
+}
+
+static STATE: Mutex<Option<FfiState>> = Mutex::new(None);
 
-// Test-specific global state
-#[cfg(test)]
-static TEST_OPERATIONS: AtomicPtr<Arc<dyn NetworkOperations>> = AtomicPtr::new(std::ptr::null_mut());
+/// How long shutdown waits for tasks still running on the runtime
+const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long shutdown waits for calls still running on other threads to return
+const CALL_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// Initialize the FFI layer with the default platform-specific NetworkManager
 #[no_mangle]
 pub extern "C" fn network_init() -> u8 {
-    println!("network_init() called");
-    
-    #[cfg(not(test))]
-    {
-        // Check if already initialized
-        if OPERATIONS.get().is_some() {
-            return device_result_to_error(Ok(()));
-        }
+    network_init_units(DEFAULT_NETWORK_UNITS as u8)
+}
 
-        // Create a runtime for async operations
-        let rt = match Runtime::new() {
-            Ok(rt) => rt,
-            Err(_) => return FN_ERR_BAD_CMD,
-        };
-        
-        // Create the platform-specific network manager
-        let manager = create_network_manager();
-        
-        // Create operations context with runtime
-        let context = OperationsContext::new_with_runtime(manager, rt);
-        let ops = Arc::new(context);
+/// Initialize the FFI layer with `units` network units (N1: to N<units>:) instead of the usual 8
+/// Does nothing if the layer is already initialized; call network_shutdown first to change units
+#[no_mangle]
+pub extern "C" fn network_init_units(units: u8) -> u8 {
+    log::debug!("network_init_units() called with units={}", units);
 
-        if OPERATIONS.set(ops).is_err() {
-            println!("network_init() failed: already initialized");
-            return FN_ERR_BAD_CMD;
-        }
+    if units == 0 {
+        return FN_ERR_BAD_CMD;
+    }
+
+    let mut state = STATE.lock().unwrap();
+    if state.is_some() {
+        return device_result_to_error(Ok(()));
     }
 
+    // Create a runtime for async operations
+    let rt = match Runtime::new() {
+        Ok(rt) => rt,
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    // Bring up the platform and get the network manager from it
+    let mut platform = X86Platform::with_units(units as usize);
+    if rt.block_on(platform.initialize()).is_err() {
+        return FN_ERR_IO_ERROR;
+    }
+    let manager = match platform.create_network_manager() {
+        Ok(manager) => manager,
+        Err(_) => return FN_ERR_IO_ERROR,
+    };
+
+    // Create operations context with runtime
+    let context = OperationsContext::new_with_runtime(manager, rt);
+    *state = Some(FfiState {
+        runtime: context.runtime.clone(),
+        operations: Arc::new(context),
+        platform: Some(platform),
+        units,
+    });
+
     device_result_to_error(Ok(()))
 }
 
+/// Close every unit, shut the platform down and stop the runtime
+/// network_init can be called again afterwards. Fails with FN_ERR_WOULD_BLOCK if calls on
+/// other threads are still running after CALL_DRAIN_TIMEOUT; the runtime then stops as
+/// soon as the last of them returns
+#[no_mangle]
+pub extern "C" fn network_shutdown() -> u8 {
+    log::debug!("network_shutdown() called");
+
+    let Some(state) = STATE.lock().unwrap().take() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+    let FfiState { operations, runtime, platform, .. } = state;
+
+    // Tear everything down even if a unit fails to close, then report the failure
+    let closed = operations.close_all_devices();
+    let shut_down = match platform {
+        Some(mut platform) => runtime.block_on(platform.shutdown()).map_err(AdapterError::from),
+        None => Ok(()),
+    };
+    drop(operations);
+
+    // Calls still running on other threads share the runtime, so give them time to return
+    let deadline = Instant::now() + CALL_DRAIN_TIMEOUT;
+    let mut runtime = runtime;
+    let stopped = loop {
+        match Arc::try_unwrap(runtime) {
+            Ok(runtime) => {
+                runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+                break Ok(());
+            }
+            Err(shared) if Instant::now() < deadline => {
+                runtime = shared;
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => {
+                log::warn!("network_shutdown: calls still running, the runtime stops once they return");
+                break Err(AdapterError::WouldBlock);
+            }
+        }
+    };
+
+    adapter_result_to_ffi(closed.and(shut_down).and(stopped))
+}
+
+/// Shut down and initialize again with the same number of units, as on a machine reset
+#[no_mangle]
+pub extern "C" fn network_reset() -> u8 {
+    log::debug!("network_reset() called");
+
+    let Some(units) = STATE.lock().unwrap().as_ref().map(|state| state.units) else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+    // Come back up even if something failed to close, so the host isn't left without a network
+    let result = network_shutdown();
+    let init = network_init_units(units);
+    if result != FN_ERR_OK { result } else { init }
+}
+
 // Get the appropriate operations context
 fn get_operations() -> Option<Arc<dyn NetworkOperations>> {
-    #[cfg(test)]
-    {
-        let ptr = TEST_OPERATIONS.load(Ordering::SeqCst);
-        if ptr.is_null() {
-            None
-        } else {
-            // Safety: We know this pointer is valid because:
-            // 1. Tests are serialized with #[serial]
-            // 2. We always set it before use in setup_test_context
-            // 3. The Arc ensures the data lives long enough
-            Some(unsafe { (*ptr).clone() })
-        }
-    }
-    #[cfg(not(test))]
-    {
-        OPERATIONS.get().map(|ops| ops.clone())
-    }
+    STATE.lock().unwrap().as_ref().map(|state| state.operations.clone())
 }
 
 #[no_mangle]
@@ -143,109 +316,828 @@ pub extern "C" fn network_open(devicespec: *const c_char, mode: u8, trans: u8) -
         return FN_ERR_BAD_CMD;
     }
 
-    // Convert C string to Rust string
-    let device_spec = unsafe {
-        match CStr::from_ptr(devicespec).to_str() {
-            Ok(s) => {
-                println!("network_open() devicespec: {}", s);
-                s.to_string()
-            },
-            Err(_) => {
-                println!("network_open() failed: invalid UTF-8 in devicespec");
-                return FN_ERR_BAD_CMD
-            }
-        }
+    // Convert C string to Rust string
+    let device_spec = unsafe {
+        match CStr::from_ptr(devicespec).to_str() {
+            Ok(s) => {
+                println!("network_open() devicespec: {}", s);
+                s.to_string()
+            },
+            Err(_) => {
+                println!("network_open() failed: invalid UTF-8 in devicespec");
+                return FN_ERR_BAD_CMD
+            }
+        }
+    };
+
+    // Create the request
+    let request = DeviceOpenRequest {
+        device_spec,
+        mode,
+        translation: trans,
+    };
+
+    // Open device
+    let result = ops.open_device(request);
+    println!("network_open() result: {:?}", result);
+    adapter_result_to_ffi(result)
+}
+
+#[no_mangle]
+pub extern "C" fn network_http_post(devicespec: *const c_char, data: *const c_char) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || data.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_BAD_CMD;
+    };
+
+    // Convert C strings to Rust strings
+    let (device_spec, data) = unsafe {
+        match (CStr::from_ptr(devicespec).to_str(), CStr::from_ptr(data).to_str()) {
+            (Ok(d), Ok(p)) => (d.to_string(), p.as_bytes().to_vec()),
+            _ => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    // Parse device spec once at the FFI layer
+    let device_id = match ops.parse_device_spec(&device_spec) {
+        Ok(id) => id,
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    // Create the request
+    let mut request = HttpPostRequest::new(device_spec, data);
+    request.device_id = Some(device_id);
+
+    // Perform HTTP POST
+    match ops.http_post(request) {
+        Ok(_) => FN_ERR_OK,
+        Err(e) => adapter_result_to_ffi::<()>(Err(e)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn network_http_get(device_spec: *const c_char, buf: *mut u8, len: u16) -> i16 {
+    // Validate pointers
+    if device_spec.is_null() || buf.is_null() {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_BAD_CMD as i16);
+    };
+
+    // Convert C string to Rust string
+    let device_spec_str = match unsafe { CStr::from_ptr(device_spec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    // Validate device spec matches what was used in open
+    let device_id = match ops.validate_device_spec(&device_spec_str) {
+        Ok(id) => id,
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    // Create buffer for response
+    let buffer = vec![0u8; len as usize];
+    let mut request = HttpGetRequest::new(device_spec_str, buffer);
+    request.device_id = Some(device_id);
+
+    // Perform HTTP GET
+    match ops.http_get(&mut request) {
+        Ok(bytes_read) => {
+            // Copy response to output buffer
+            unsafe {
+                std::ptr::copy_nonoverlapping(request.buffer.as_ptr(), buf, bytes_read);
+            }
+            bytes_read as i16
+        }
+        Err(_) => -(FN_ERR_BAD_CMD as i16),
+    }
+}
+
+/// Read up to `len` bytes from an open device into `buf`, at most 32767 per call
+/// Returns the number of bytes read, or the negative FN_ERR_* code on error
+/// In non-blocking mode -FN_ERR_WOULD_BLOCK means the read is still in progress; call again later
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn network_read(devicespec: *const c_char, buf: *mut u8, len: u16) -> i16 {
+    // Validate pointers
+    if devicespec.is_null() || buf.is_null() {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_NOT_INITIALIZED as i16);
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    // Larger counts would come back negative and look like an error
+    let len = len.min(i16::MAX as u16);
+    let mut request = ReadRequest::new(device_spec, vec![0u8; len as usize]);
+    match ops.read(&mut request) {
+        Ok(bytes_read) => {
+            unsafe {
+                std::ptr::copy_nonoverlapping(request.buffer.as_ptr(), buf, bytes_read);
+            }
+            bytes_read as i16
+        }
+        Err(e) => -(adapter_error_to_ffi(e) as i16),
+    }
+}
+
+/// Write `len` bytes from `buf` to an open device
+/// In non-blocking mode the data is queued, or FN_ERR_WOULD_BLOCK returned while earlier I/O is in progress
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `buf` must be null or valid for reads of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn network_write(devicespec: *const c_char, buf: *const u8, len: u16) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || buf.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string and copy the data
+    let (device_spec, data) = unsafe {
+        match CStr::from_ptr(devicespec).to_str() {
+            Ok(s) => (s.to_string(), std::slice::from_raw_parts(buf, len as usize).to_vec()),
+            Err(_) => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    adapter_result_to_ffi(ops.write(WriteRequest::new(device_spec, data)))
+}
+
+/// Add a "Name: value" header to the handshake of an opened ws:// or wss:// device
+/// Must be called before the first read or write on the device
+///
+/// # Safety
+/// `devicespec` and `header` must each be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_ws_add_header(devicespec: *const c_char, header: *const c_char) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || header.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C strings to Rust strings
+    let (device_spec, header) = unsafe {
+        match (CStr::from_ptr(devicespec).to_str(), CStr::from_ptr(header).to_str()) {
+            (Ok(d), Ok(h)) => (d.to_string(), h.to_string()),
+            _ => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    adapter_result_to_ffi(ops.ws_add_header(WebSocketHeaderRequest::new(device_spec, header)))
+}
+
+/// Select binary (non-zero) or text (zero) frames for writes to a WebSocket device
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_ws_set_message_type(devicespec: *const c_char, binary: u8) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    adapter_result_to_ffi(ops.ws_set_message_type(WebSocketMessageTypeRequest::new(device_spec, binary != 0)))
+}
+
+/// Subscribe an open mqtt:// device to a topic filter at QoS 0 or 1
+/// Received messages are returned by network_read as the topic, a newline and the payload
+///
+/// # Safety
+/// `devicespec` and `topic` must each be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_mqtt_subscribe(devicespec: *const c_char, topic: *const c_char, qos: u8) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || topic.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C strings to Rust strings
+    let (device_spec, topic) = unsafe {
+        match (CStr::from_ptr(devicespec).to_str(), CStr::from_ptr(topic).to_str()) {
+            (Ok(d), Ok(t)) => (d.to_string(), t.to_string()),
+            _ => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    adapter_result_to_ffi(ops.mqtt_subscribe(MqttSubscribeRequest::new(device_spec, topic, qos)))
+}
+
+/// Publish `len` bytes from `data` to a topic from an open mqtt:// device
+/// A non-zero `retain` asks the broker to keep the message for future subscribers
+///
+/// # Safety
+/// `devicespec` and `topic` must each be null or point to a NUL-terminated string
+/// `data` must be null or valid for reads of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn network_mqtt_publish(
+    devicespec: *const c_char,
+    topic: *const c_char,
+    data: *const u8,
+    len: u16,
+    qos: u8,
+    retain: u8,
+) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || topic.is_null() || (data.is_null() && len > 0) {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C strings to Rust strings and copy the payload
+    let (device_spec, topic, payload) = unsafe {
+        match (CStr::from_ptr(devicespec).to_str(), CStr::from_ptr(topic).to_str()) {
+            (Ok(d), Ok(t)) => {
+                let payload = if len > 0 { std::slice::from_raw_parts(data, len as usize).to_vec() } else { Vec::new() };
+                (d.to_string(), t.to_string(), payload)
+            }
+            _ => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    adapter_result_to_ffi(ops.mqtt_publish(MqttPublishRequest::new(device_spec, topic, payload, qos, retain != 0)))
+}
+
+/// Maximum name length (including the NUL terminator) in a NetworkDirectoryEntry
+pub const DIRECTORY_NAME_LEN: usize = 256;
+
+/// Directory entry as returned to C by network_read_directory_entry
+#[repr(C)]
+pub struct NetworkDirectoryEntry {
+    /// NUL terminated name, truncated to fit
+    pub name: [u8; DIRECTORY_NAME_LEN],
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch, or 0 if unknown
+    pub mtime: i64,
+    /// Non-zero for directories
+    pub is_dir: u8,
+}
+
+impl NetworkDirectoryEntry {
+    fn fill(&mut self, entry: &DirectoryEntry) {
+        let name = entry.name.as_bytes();
+        let len = std::cmp::min(name.len(), DIRECTORY_NAME_LEN - 1);
+        self.name[..len].copy_from_slice(&name[..len]);
+        self.name[len] = 0;
+        self.size = entry.size;
+        self.mtime = entry.modified
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        self.is_dir = entry.is_dir as u8;
+    }
+}
+
+/// Open a directory listing, e.g. "N1:file:///games/*.ATR"
+/// `filter` may be NULL to use any wildcard in the devicespec; a non-zero `long` selects the long format
+/// The listing is released by network_close
+///
+/// # Safety
+/// `devicespec` and `filter` must each be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_open_directory(devicespec: *const c_char, filter: *const c_char, long: u8) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C strings to Rust strings
+    let (device_spec, filter) = unsafe {
+        let filter = if filter.is_null() { Ok(None) } else { CStr::from_ptr(filter).to_str().map(|f| Some(f.to_string())) };
+        match (CStr::from_ptr(devicespec).to_str(), filter) {
+            (Ok(d), Ok(f)) => (d.to_string(), f),
+            _ => return FN_ERR_BAD_CMD,
+        }
+    };
+
+    adapter_result_to_ffi(ops.open_directory(DirectoryOpenRequest::new(device_spec, filter, long != 0)))
+}
+
+/// Read up to `len` bytes of the formatted directory listing into `buf`, at most 32767 per call
+/// Returns the number of bytes read (0 at the end), or the negative FN_ERR_* code on error
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn network_read_directory(devicespec: *const c_char, buf: *mut u8, len: u16) -> i16 {
+    // Validate pointers
+    if devicespec.is_null() || buf.is_null() {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_NOT_INITIALIZED as i16);
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    // Larger counts would come back negative and look like an error
+    let len = len.min(i16::MAX as u16);
+    let mut request = ReadRequest::new(device_spec, vec![0u8; len as usize]);
+    match ops.read_directory(&mut request) {
+        Ok(bytes_read) => {
+            unsafe {
+                std::ptr::copy_nonoverlapping(request.buffer.as_ptr(), buf, bytes_read);
+            }
+            bytes_read as i16
+        }
+        Err(e) => -(adapter_error_to_ffi(e) as i16),
+    }
+}
+
+/// Fetch the next entry of an open directory listing into `entry`
+/// Returns 1 when an entry was stored, 0 at the end of the listing, or the negative FN_ERR_* code on error
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `entry` must be null or valid for writing a NetworkDirectoryEntry
+#[no_mangle]
+pub unsafe extern "C" fn network_read_directory_entry(devicespec: *const c_char, entry: *mut NetworkDirectoryEntry) -> i16 {
+    // Validate pointers
+    if devicespec.is_null() || entry.is_null() {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_NOT_INITIALIZED as i16);
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    match ops.read_directory_entry(&mut DirectoryEntryRequest::new(device_spec)) {
+        Ok(Some(next)) => {
+            unsafe { (*entry).fill(&next) };
+            1
+        }
+        Ok(None) => 0,
+        Err(e) => -(adapter_error_to_ffi(e) as i16),
+    }
+}
+
+// Shared body of the network_fs_* functions
+fn fs_command(devicespec: *const c_char, command: u8) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    adapter_result_to_ffi(ops.filesystem_command(FileSystemRequest::new(device_spec, command)))
+}
+
+/// Rename a file, with the new name after a comma, e.g. "N1:TNFS://host/foo.txt,bar.txt" (XIO 32)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_rename(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_RENAME)
+}
+
+/// Delete a file (XIO 33)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_delete(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_DELETE)
+}
+
+/// Make a file read only (XIO 35)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_lock(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_LOCK)
+}
+
+/// Make a file writable (XIO 36)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_unlock(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_UNLOCK)
+}
+
+/// Create a directory (XIO 42)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_mkdir(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_MKDIR)
+}
+
+/// Remove an empty directory (XIO 43)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_rmdir(devicespec: *const c_char) -> u8 {
+    fs_command(devicespec, XIO_RMDIR)
+}
+
+/// Change the unit's prefix: "N1:TNFS://host/dir/" sets it, "N1:sub" and "N1:.." navigate, "N1:" clears it
+/// Later relative specs such as "N1:FOO.TXT" resolve against the prefix
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_cd(devicespec: *const c_char) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    adapter_result_to_ffi(ops.set_prefix(&device_spec))
+}
+
+/// Copy the unit's prefix into `buf` as a NUL terminated string, truncated to fit `len`
+/// Returns the prefix length, or the negative FN_ERR_* code on error
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn network_fs_get_prefix(devicespec: *const c_char, buf: *mut c_char, len: u16) -> i16 {
+    // Validate pointers
+    if devicespec.is_null() || buf.is_null() || len == 0 {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_NOT_INITIALIZED as i16);
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    };
+
+    match ops.get_prefix(&device_spec) {
+        Ok(prefix) => {
+            // Capped so the returned length can't turn negative
+            let copy_len = prefix.len().min(len as usize - 1).min(i16::MAX as usize);
+            unsafe {
+                std::ptr::copy_nonoverlapping(prefix.as_ptr(), buf as *mut u8, copy_len);
+                *buf.add(copy_len) = 0;
+            }
+            copy_len as i16
+        }
+        Err(e) => -(adapter_error_to_ffi(e) as i16),
+    }
+}
+
+/// Store the current position of an open device in `position` (NOTE)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `position` must be null or valid for writing a u32
+#[no_mangle]
+pub unsafe extern "C" fn network_note(devicespec: *const c_char, position: *mut u32) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() || position.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    match ops.tell(&mut TellRequest::new(device_spec)) {
+        Ok(offset) => {
+            unsafe { *position = offset.min(u32::MAX as u64) as u32 };
+            FN_ERR_OK
+        }
+        Err(e) => adapter_error_to_ffi(e),
+    }
+}
+
+/// Move an open device to `position` bytes from the start (POINT)
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_point(devicespec: *const c_char, position: u32) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    adapter_result_to_ffi(ops.seek(SeekRequest::new(device_spec, position as u64)))
+}
+
+/// Switch a unit between blocking (0, the default) and non-blocking (non-zero) reads and writes
+/// In non-blocking mode network_read and network_write return immediately and the I/O runs in the background
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_set_nonblocking(devicespec: *const c_char, enabled: u8) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
+    };
+
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
+    };
+
+    adapter_result_to_ffi(ops.set_nonblocking(&device_spec, enabled != 0))
+}
+
+/// Send everything written to a unit that is still waiting in its send buffer
+/// network_write already waits for room in the buffer, so this is only needed to be sure data has gone out
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_flush(devicespec: *const c_char) -> u8 {
+    // Validate pointers
+    if devicespec.is_null() {
+        return FN_ERR_BAD_CMD;
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
     };
 
-    // Create the request
-    let request = DeviceOpenRequest {
-        device_spec,
-        mode,
-        translation: trans,
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return FN_ERR_BAD_CMD,
     };
 
-    // Open device
-    let result = ops.open_device(request);
-    println!("network_open() result: {:?}", result);
-    adapter_result_to_ffi(result)
+    adapter_result_to_ffi(ops.flush(FlushRequest::new(device_spec)))
 }
 
+/// Network error byte set by network_status: 1 while there is more to read
+pub const NETWORK_STATUS_OK: u8 = 1;
+/// Network error byte set by network_status once the unit has nothing left to read
+pub const NETWORK_STATUS_EOF: u8 = 136;
+
+/// Read a unit's status: bytes waiting (capped at 65535) into `bw`, 1 while connected
+/// into `c` and the network error byte (NETWORK_STATUS_OK or NETWORK_STATUS_EOF) into `err`
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `bw`, `c` and `err` must each be null or valid for writing
 #[no_mangle]
-pub extern "C" fn network_http_post(devicespec: *const c_char, data: *const c_char) -> u8 {
+pub unsafe extern "C" fn network_status(devicespec: *const c_char, bw: *mut u16, c: *mut u8, err: *mut u8) -> u8 {
     // Validate pointers
-    if devicespec.is_null() || data.is_null() {
+    if devicespec.is_null() || bw.is_null() || c.is_null() || err.is_null() {
         return FN_ERR_BAD_CMD;
     }
 
     // Get operations context
     let Some(ops) = get_operations() else {
-        return FN_ERR_BAD_CMD;
-    };
-
-    // Convert C strings to Rust strings
-    let (device_spec, data) = unsafe {
-        match (CStr::from_ptr(devicespec).to_str(), CStr::from_ptr(data).to_str()) {
-            (Ok(d), Ok(p)) => (d.to_string(), p.as_bytes().to_vec()),
-            _ => return FN_ERR_BAD_CMD,
-        }
+        return FN_ERR_NOT_INITIALIZED;
     };
 
-    // Parse device spec once at the FFI layer
-    let device_id = match ops.parse_device_spec(&device_spec) {
-        Ok(id) => id,
+    // Convert C string to Rust string
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
+        Ok(s) => s.to_string(),
         Err(_) => return FN_ERR_BAD_CMD,
     };
 
-    // Create the request
-    let mut request = HttpPostRequest::new(device_spec, data);
-    request.device_id = Some(device_id);
-
-    // Perform HTTP POST
-    match ops.http_post(request) {
-        Ok(_) => FN_ERR_OK,
-        Err(e) => adapter_result_to_ffi::<()>(Err(e)),
+    match ops.status(&mut StatusRequest::new(device_spec)) {
+        Ok(info) => {
+            let at_end = info.bytes_waiting == 0 && !info.connected;
+            unsafe {
+                *bw = info.bytes_waiting.min(u16::MAX as usize) as u16;
+                *c = info.connected as u8;
+                *err = if at_end { NETWORK_STATUS_EOF } else { NETWORK_STATUS_OK };
+            }
+            FN_ERR_OK
+        }
+        Err(e) => adapter_error_to_ffi(e),
     }
 }
 
+/// Read how many bytes written to a unit have not been sent yet (capped at 65535) into `pending`
+///
+/// # Safety
+/// `devicespec` must be null or point to a NUL-terminated string
+/// `pending` must be null or valid for writing a u16
 #[no_mangle]
-pub extern "C" fn network_http_get(device_spec: *const c_char, buf: *mut u8, len: u16) -> i16 {
+pub unsafe extern "C" fn network_pending(devicespec: *const c_char, pending: *mut u16) -> u8 {
     // Validate pointers
-    if device_spec.is_null() || buf.is_null() {
-        return -(FN_ERR_BAD_CMD as i16);
+    if devicespec.is_null() || pending.is_null() {
+        return FN_ERR_BAD_CMD;
     }
 
     // Get operations context
     let Some(ops) = get_operations() else {
-        return -(FN_ERR_BAD_CMD as i16);
+        return FN_ERR_NOT_INITIALIZED;
     };
 
     // Convert C string to Rust string
-    let device_spec_str = match unsafe { CStr::from_ptr(device_spec) }.to_str() {
+    let device_spec = match unsafe { CStr::from_ptr(devicespec) }.to_str() {
         Ok(s) => s.to_string(),
-        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+        Err(_) => return FN_ERR_BAD_CMD,
     };
 
-    // Validate device spec matches what was used in open
-    let device_id = match ops.validate_device_spec(&device_spec_str) {
-        Ok(id) => id,
-        Err(_) => return -(FN_ERR_BAD_CMD as i16),
+    match ops.status(&mut StatusRequest::new(device_spec)) {
+        Ok(info) => {
+            unsafe { *pending = info.bytes_pending.min(u16::MAX as usize) as u16 };
+            FN_ERR_OK
+        }
+        Err(e) => adapter_error_to_ffi(e),
+    }
+}
+
+/// Called when a unit raises an event, with the unit number (1 for N1), the event kind
+/// (1 = data available, 2 = connection closed, 3 = client waiting) and the registered user data
+/// May be called from a background thread while other network_* calls are in progress
+pub type NetworkEventCallback = extern "C" fn(unit: u8, event: u8, user_data: *mut c_void);
+
+// User data registered with the callback, handed back to C untouched
+struct CallbackUserData(*mut c_void);
+
+// Safety: the pointer is never dereferenced here; what it points to is owned by the C caller
+unsafe impl Send for CallbackUserData {}
+unsafe impl Sync for CallbackUserData {}
+
+impl CallbackUserData {
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Register a callback for network events, in place of the PROCEED/interrupt line
+/// Pass NULL to unregister and queue events for network_poll_event instead
+#[no_mangle]
+pub extern "C" fn network_set_event_callback(callback: Option<NetworkEventCallback>, user_data: *mut c_void) -> u8 {
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return FN_ERR_NOT_INITIALIZED;
     };
 
-    // Create buffer for response
-    let buffer = vec![0u8; len as usize];
-    let mut request = HttpGetRequest::new(device_spec_str, buffer);
-    request.device_id = Some(device_id);
+    let callback = callback.map(|callback| {
+        let user_data = CallbackUserData(user_data);
+        Box::new(move |event: NetworkEvent| {
+            callback((event.device_id + 1) as u8, event.kind as u8, user_data.get())
+        }) as EventCallback
+    });
+    ops.set_event_callback(callback);
+    FN_ERR_OK
+}
 
-    // Perform HTTP GET
-    match ops.http_get(&mut request) {
-        Ok(bytes_read) => {
-            // Copy response to output buffer
+/// Event as returned to C by network_poll_event
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct NetworkEventInfo {
+    /// Unit number, 1 for N1
+    pub unit: u8,
+    /// 1 = data available, 2 = connection closed, 3 = client waiting
+    pub event: u8,
+}
+
+/// Take the oldest queued event into `event`, for hosts that poll rather than register a callback
+/// Returns 1 when an event was stored, 0 if none is waiting, or the negative FN_ERR_* code on error
+///
+/// # Safety
+/// `event` must be null or valid for writing a NetworkEventInfo
+#[no_mangle]
+pub unsafe extern "C" fn network_poll_event(event: *mut NetworkEventInfo) -> i16 {
+    // Validate pointers
+    if event.is_null() {
+        return -(FN_ERR_BAD_CMD as i16);
+    }
+
+    // Get operations context
+    let Some(ops) = get_operations() else {
+        return -(FN_ERR_NOT_INITIALIZED as i16);
+    };
+
+    match ops.poll_event() {
+        Some(next) => {
             unsafe {
-                std::ptr::copy_nonoverlapping(request.buffer.as_ptr(), buf, bytes_read);
+                (*event).unit = (next.device_id + 1) as u8;
+                (*event).event = next.kind as u8;
             }
-            bytes_read as i16
+            1
         }
-        Err(_) => -(FN_ERR_BAD_CMD as i16),
+        None => 0,
     }
 }
 
@@ -271,9 +1163,12 @@ mod tests {
     use super::*;
     use std::ffi::CString;
     use serial_test::serial;
-    use crate::adapters::{common::network::test_mocks::TestNetworkManager, ffi::{FN_ERR_OK, FN_ERR_IO_ERROR}};
+    use crate::adapters::{common::network::test_mocks::{TestNetworkManager, MockStreamProtocol, MockWebSocketClientProvider, MockMqttClient, MockMqttClientProvider}, ffi::{FN_ERR_OK, FN_ERR_IO_ERROR, FN_ERR_NO_DEVICE, FN_ERR_WOULD_BLOCK}};
+    use crate::device::network::protocols::{WebSocketProtocol, MqttProtocol, MqttQos, ProtocolHandler, FileProtocol};
     use crate::device::DeviceError;
     use crate::device::network::NetworkUrl;
+    use crate::device::network::NetworkEventKind;
+    use crate::platform::x86::network::FILE_ROOT_ENV;
 
     fn setup_test_context(manager: TestNetworkManager) {
         // Create a runtime for async operations
@@ -281,19 +1176,16 @@ mod tests {
         
         // Create operations context with runtime
         let context = OperationsContext::new_with_runtime(manager, rt);
-        let ops: Arc<dyn NetworkOperations> = Arc::new(context);
-        let ops = Box::new(ops);
-        let ptr = Box::into_raw(ops);
-        TEST_OPERATIONS.store(ptr, Ordering::SeqCst);
+        *STATE.lock().unwrap() = Some(FfiState {
+            runtime: context.runtime.clone(),
+            operations: Arc::new(context),
+            platform: None,
+            units: DEFAULT_NETWORK_UNITS as u8,
+        });
     }
 
     fn cleanup_test_context() {
-        let ptr = TEST_OPERATIONS.swap(std::ptr::null_mut(), Ordering::SeqCst);
-        if !ptr.is_null() {
-            unsafe {
-                drop(Box::from_raw(ptr));
-            }
-        }
+        STATE.lock().unwrap().take();
     }
 
     #[test]
@@ -308,7 +1200,76 @@ mod tests {
         assert_eq!(network_init(), FN_ERR_OK);
         
         // Verify operations context was initialized
-        assert!(!TEST_OPERATIONS.load(Ordering::SeqCst).is_null());
+        assert!(get_operations().is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_shutdown_closes_devices() {
+        cleanup_test_context();
+        assert_eq!(network_shutdown(), FN_ERR_NOT_INITIALIZED);
+        assert_eq!(network_reset(), FN_ERR_NOT_INITIALIZED);
+
+        let manager = TestNetworkManager::new()
+            .with_protocol_device(Box::new(MockStreamProtocol::with_read_data(b"READY")));
+        let slot = manager.device_slot(0).unwrap();
+        setup_test_context(manager);
+
+        assert_eq!(network_shutdown(), FN_ERR_OK);
+        assert!(slot.try_lock().unwrap().is_none());
+        assert!(get_operations().is_none());
+        assert_eq!(network_close(1), FN_ERR_NOT_INITIALIZED);
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_init_shutdown_cycles() {
+        cleanup_test_context();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), b"[package]").unwrap();
+        std::env::set_var(FILE_ROOT_ENV, dir.path());
+        let spec = CString::new("N12:file:///Cargo.toml").unwrap();
+        let mut buf = [0u8; 9];
+
+        for _ in 0..3 {
+            assert_eq!(network_init_units(12), FN_ERR_OK);
+            assert_eq!(network_open(spec.as_ptr(), 4, 0), FN_ERR_OK);
+            assert_eq!(unsafe { network_read(spec.as_ptr(), buf.as_mut_ptr(), 9) }, 9);
+            assert_eq!(&buf, b"[package]");
+
+            // A reset keeps the unit count but starts over with every unit closed
+            assert_eq!(network_reset(), FN_ERR_OK);
+            assert!(unsafe { network_read(spec.as_ptr(), buf.as_mut_ptr(), 9) } < 0);
+            assert_eq!(network_open(spec.as_ptr(), 4, 0), FN_ERR_OK);
+
+            assert_eq!(network_shutdown(), FN_ERR_OK);
+            assert_eq!(unsafe { network_read(spec.as_ptr(), buf.as_mut_ptr(), 9) }, -(FN_ERR_NOT_INITIALIZED as i16));
+        }
+        std::env::remove_var(FILE_ROOT_ENV);
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_shutdown_waits_for_running_calls() {
+        cleanup_test_context();
+
+        // A call that returns within the drain timeout lets shutdown finish normally
+        assert_eq!(network_init(), FN_ERR_OK);
+        let running = get_operations().unwrap();
+        let call = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            drop(running);
+        });
+        assert_eq!(network_shutdown(), FN_ERR_OK);
+        call.join().unwrap();
+
+        // One that doesn't is reported rather than skipped silently
+        assert_eq!(network_init(), FN_ERR_OK);
+        let running = get_operations().unwrap();
+        assert_eq!(network_shutdown(), FN_ERR_WOULD_BLOCK);
+        assert_eq!(network_init(), FN_ERR_OK);
+        drop(running);
+        assert_eq!(network_shutdown(), FN_ERR_OK);
     }
 
     #[test]
@@ -459,6 +1420,351 @@ mod tests {
         assert!(result < 0);
     }
 
+    #[test]
+    #[serial]
+    fn test_network_read_write() {
+        let protocol = MockStreamProtocol::with_read_data(b"READY");
+        let written = protocol.written.clone();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///test.txt")
+            .with_protocol_device(Box::new(protocol));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///test.txt").unwrap();
+        let mut buffer = [0u8; 3];
+        assert_eq!(unsafe { network_read(url.as_ptr(), buffer.as_mut_ptr(), 3) }, 3);
+        assert_eq!(&buffer, b"REA");
+        assert_eq!(unsafe { network_read(url.as_ptr(), buffer.as_mut_ptr(), 3) }, 2);
+        assert_eq!(&buffer[..2], b"DY");
+
+        let data = b"RUN";
+        assert_eq!(unsafe { network_write(url.as_ptr(), data.as_ptr(), data.len() as u16) }, FN_ERR_OK);
+        assert_eq!(written.lock().unwrap().as_slice(), b"RUN");
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_read_write_errors() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///test.txt");
+        setup_test_context(manager);
+
+        let mut buffer = [0u8; 4];
+        assert_eq!(unsafe { network_read(std::ptr::null(), buffer.as_mut_ptr(), 4) }, -(FN_ERR_BAD_CMD as i16));
+        assert_eq!(unsafe { network_write(std::ptr::null(), buffer.as_ptr(), 4) }, FN_ERR_BAD_CMD);
+
+        // Valid spec but nothing open on the unit
+        let url = CString::new("N1:file:///test.txt").unwrap();
+        assert!(unsafe { network_read(url.as_ptr(), buffer.as_mut_ptr(), 4) } < 0);
+        assert_ne!(unsafe { network_write(url.as_ptr(), buffer.as_ptr(), 4) }, FN_ERR_OK);
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_ws_configuration() {
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:ws://game.example/")
+            .with_protocol_device(Box::new(WebSocketProtocol::new(Arc::new(MockWebSocketClientProvider))));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:ws://game.example/").unwrap();
+        let header = CString::new("X-Player: 7").unwrap();
+        assert_eq!(unsafe { network_ws_add_header(url.as_ptr(), header.as_ptr()) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_ws_set_message_type(url.as_ptr(), 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_ws_add_header(url.as_ptr(), std::ptr::null()) }, FN_ERR_BAD_CMD);
+
+        let bad_header = CString::new("no colon").unwrap();
+        assert_eq!(unsafe { network_ws_add_header(url.as_ptr(), bad_header.as_ptr()) }, FN_ERR_IO_ERROR);
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_mqtt_subscribe_publish() {
+        let client = MockMqttClient::default();
+        let mut protocol = MqttProtocol::new(Arc::new(MockMqttClientProvider::new(client.clone())));
+        Runtime::new().unwrap().block_on(protocol.open("mqtt://broker.local")).unwrap();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:mqtt://broker.local")
+            .with_protocol_device(Box::new(protocol));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:mqtt://broker.local").unwrap();
+        let topic = CString::new("chat/#").unwrap();
+        assert_eq!(unsafe { network_mqtt_subscribe(url.as_ptr(), topic.as_ptr(), 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_mqtt_subscribe(url.as_ptr(), topic.as_ptr(), 2) }, FN_ERR_IO_ERROR);
+
+        let topic = CString::new("chat/lobby").unwrap();
+        let data = b"HELLO";
+        assert_eq!(unsafe { network_mqtt_publish(url.as_ptr(), topic.as_ptr(), data.as_ptr(), 5, 0, 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_mqtt_publish(url.as_ptr(), topic.as_ptr(), std::ptr::null(), 5, 0, 0) }, FN_ERR_BAD_CMD);
+
+        assert_eq!(client.subscriptions.lock().unwrap().as_slice(), &[("chat/#".to_string(), MqttQos::AtLeastOnce)]);
+        assert_eq!(
+            client.published.lock().unwrap().as_slice(),
+            &[("chat/lobby".to_string(), b"HELLO".to_vec(), MqttQos::AtMostOnce, true)]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_directory_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("games")).unwrap();
+        std::fs::write(root.join("readme.txt"), b"hello").unwrap();
+
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///")
+            .with_protocol_device(Box::new(FileProtocol::new(root)));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///").unwrap();
+        assert_eq!(unsafe { network_open_directory(url.as_ptr(), std::ptr::null(), 1) }, FN_ERR_OK);
+
+        let mut entry = NetworkDirectoryEntry { name: [0xff; DIRECTORY_NAME_LEN], size: 0, mtime: 0, is_dir: 0 };
+        assert_eq!(unsafe { network_read_directory_entry(url.as_ptr(), &mut entry) }, 1);
+        assert_eq!(CStr::from_bytes_until_nul(&entry.name).unwrap().to_str().unwrap(), "games");
+        assert_eq!(entry.is_dir, 1);
+        assert_eq!(unsafe { network_read_directory_entry(url.as_ptr(), &mut entry) }, 1);
+        assert_eq!(CStr::from_bytes_until_nul(&entry.name).unwrap().to_str().unwrap(), "readme.txt");
+        assert_eq!(entry.size, 5);
+        assert!(entry.mtime > 0);
+        assert_eq!(unsafe { network_read_directory_entry(url.as_ptr(), &mut entry) }, 0);
+
+        let mut buf = [0u8; 256];
+        let len = unsafe { network_read_directory(url.as_ptr(), buf.as_mut_ptr(), buf.len() as u16) };
+        let listing = String::from_utf8_lossy(&buf[..len as usize]);
+        assert!(listing.starts_with("games"));
+        assert!(listing.ends_with("FREE SECTORS\n"));
+
+        let filter = CString::new("*.md").unwrap();
+        assert_eq!(unsafe { network_open_directory(url.as_ptr(), filter.as_ptr(), 0) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_read_directory(url.as_ptr(), buf.as_mut_ptr(), buf.len() as u16) }, 0);
+        assert_eq!(unsafe { network_read_directory_entry(std::ptr::null(), &mut entry) }, -(FN_ERR_BAD_CMD as i16));
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_fs_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///games")
+            .with_protocol_device(Box::new(FileProtocol::new(root)));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///games").unwrap();
+        assert_eq!(unsafe { network_fs_mkdir(url.as_ptr()) }, FN_ERR_OK);
+        assert!(root.join("games").is_dir());
+        let rename = CString::new("N1:file:///games,arcade").unwrap();
+        assert_eq!(unsafe { network_fs_rename(rename.as_ptr()) }, FN_ERR_OK);
+        assert!(root.join("arcade").is_dir());
+        assert_eq!(unsafe { network_fs_rmdir(url.as_ptr()) }, FN_ERR_IO_ERROR);
+        assert_eq!(unsafe { network_fs_delete(std::ptr::null()) }, FN_ERR_BAD_CMD);
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_fs_cd() {
+        setup_test_context(TestNetworkManager::new());
+
+        let cd = CString::new("N3:http://host/games/").unwrap();
+        assert_eq!(unsafe { network_fs_cd(cd.as_ptr()) }, FN_ERR_OK);
+        let cd = CString::new("N3:arcade").unwrap();
+        assert_eq!(unsafe { network_fs_cd(cd.as_ptr()) }, FN_ERR_OK);
+
+        let mut buf = [0 as c_char; 64];
+        let unit = CString::new("N3:").unwrap();
+        let len = unsafe { network_fs_get_prefix(unit.as_ptr(), buf.as_mut_ptr(), buf.len() as u16) };
+        assert_eq!(len, 25);
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str().unwrap(), "http://host/games/arcade/");
+
+        let mut small = [0 as c_char; 5];
+        assert_eq!(unsafe { network_fs_get_prefix(unit.as_ptr(), small.as_mut_ptr(), 5) }, 4);
+        assert_eq!(unsafe { CStr::from_ptr(small.as_ptr()) }.to_str().unwrap(), "http");
+
+        // Climbing stops at the host root; a unit without a prefix has nothing to navigate from
+        let up = CString::new("N3:../../..").unwrap();
+        assert_eq!(unsafe { network_fs_cd(up.as_ptr()) }, FN_ERR_OK);
+        let none = CString::new("N1:..").unwrap();
+        assert_eq!(unsafe { network_fs_cd(none.as_ptr()) }, FN_ERR_NO_DEVICE);
+
+        // Units beyond the configured count are bad commands, not an index past the end
+        let past_end = CString::new("N9:http://host/").unwrap();
+        assert_eq!(unsafe { network_fs_cd(past_end.as_ptr()) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { network_fs_get_prefix(past_end.as_ptr(), buf.as_mut_ptr(), buf.len() as u16) }, -(FN_ERR_BAD_CMD as i16));
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_read_caps_length() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("big.bin"), vec![0x55; 40000]).unwrap();
+        let mut protocol = FileProtocol::new(dir.path());
+        Runtime::new().unwrap().block_on(protocol.open("file:///big.bin")).unwrap();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///big.bin")
+            .with_protocol_device(Box::new(protocol));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///big.bin").unwrap();
+        let mut buf = vec![0u8; 40000];
+        assert_eq!(unsafe { network_read(url.as_ptr(), buf.as_mut_ptr(), 40000) }, i16::MAX);
+        assert_eq!(unsafe { network_read(url.as_ptr(), buf.as_mut_ptr(), 40000) } as usize, 40000 - i16::MAX as usize);
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_read_directory_caps_length() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..2000 {
+            std::fs::write(dir.path().join(format!("FILE{:04}.DAT", i)), b"").unwrap();
+        }
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///")
+            .with_protocol_device(Box::new(FileProtocol::new(dir.path())));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///").unwrap();
+        assert_eq!(unsafe { network_open_directory(url.as_ptr(), std::ptr::null(), 0) }, FN_ERR_OK);
+        let mut buf = vec![0u8; u16::MAX as usize];
+        assert_eq!(unsafe { network_read_directory(url.as_ptr(), buf.as_mut_ptr(), u16::MAX) }, i16::MAX);
+        assert!(unsafe { network_read_directory(url.as_ptr(), buf.as_mut_ptr(), u16::MAX) } > 0);
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_note_point() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("disk.atr"), b"0123456789").unwrap();
+        let mut protocol = FileProtocol::new(root);
+        Runtime::new().unwrap().block_on(protocol.open("file:///disk.atr")).unwrap();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:file:///disk.atr")
+            .with_protocol_device(Box::new(protocol));
+        setup_test_context(manager);
+
+        let url = CString::new("N1:file:///disk.atr").unwrap();
+        let mut position = 0u32;
+        assert_eq!(unsafe { network_point(url.as_ptr(), 7) }, FN_ERR_OK);
+        let mut buf = [0u8; 2];
+        assert_eq!(unsafe { network_read(url.as_ptr(), buf.as_mut_ptr(), 2) }, 2);
+        assert_eq!(&buf, b"78");
+        assert_eq!(unsafe { network_note(url.as_ptr(), &mut position) }, FN_ERR_OK);
+        assert_eq!(position, 9);
+        assert_eq!(unsafe { network_note(url.as_ptr(), std::ptr::null_mut()) }, FN_ERR_BAD_CMD);
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_nonblocking_read() {
+        cleanup_test_context();
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::with_read_data(b"READY")));
+        setup_test_context(manager);
+
+        let spec = CString::new("N1:tcp://host:23").unwrap();
+        assert_eq!(unsafe { network_set_nonblocking(spec.as_ptr(), 1) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_set_nonblocking(std::ptr::null(), 1) }, FN_ERR_BAD_CMD);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(unsafe { network_read(spec.as_ptr(), buf.as_mut_ptr(), 16) }, -(FN_ERR_WOULD_BLOCK as i16));
+        let mut result = -(FN_ERR_WOULD_BLOCK as i16);
+        for _ in 0..200 {
+            result = unsafe { network_read(spec.as_ptr(), buf.as_mut_ptr(), 16) };
+            if result != -(FN_ERR_WOULD_BLOCK as i16) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(result, 5);
+        assert_eq!(&buf[..5], b"READY");
+
+        cleanup_test_context();
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_status_flush() {
+        cleanup_test_context();
+        let spec = CString::new("N1:tcp://host:23").unwrap();
+        let (mut bw, mut c, mut err, mut pending) = (0u16, 0u8, 0u8, 0u16);
+        assert_eq!(unsafe { network_status(spec.as_ptr(), &mut bw, &mut c, &mut err) }, FN_ERR_NOT_INITIALIZED);
+        assert_eq!(unsafe { network_pending(spec.as_ptr(), &mut pending) }, FN_ERR_NOT_INITIALIZED);
+
+        let manager = TestNetworkManager::new()
+            .with_parse_result(1, "N1:tcp://host:23")
+            .with_protocol_device(Box::new(MockStreamProtocol::with_read_data(b"READY")));
+        setup_test_context(manager);
+
+        assert_eq!(unsafe { network_status(spec.as_ptr(), &mut bw, &mut c, &mut err) }, FN_ERR_OK);
+        assert_eq!((bw, c, err), (5, 1, NETWORK_STATUS_OK));
+        assert_eq!(unsafe { network_pending(spec.as_ptr(), &mut pending) }, FN_ERR_OK);
+        assert_eq!(pending, 0);
+        assert_eq!(unsafe { network_status(spec.as_ptr(), &mut bw, &mut c, std::ptr::null_mut()) }, FN_ERR_BAD_CMD);
+        assert_eq!(unsafe { network_pending(spec.as_ptr(), std::ptr::null_mut()) }, FN_ERR_BAD_CMD);
+
+        assert_eq!(unsafe { network_flush(spec.as_ptr()) }, FN_ERR_OK);
+        assert_eq!(unsafe { network_flush(std::ptr::null()) }, FN_ERR_BAD_CMD);
+
+        cleanup_test_context();
+    }
+
+    static RECEIVED_EVENTS: std::sync::Mutex<Vec<(u8, u8, usize)>> = std::sync::Mutex::new(Vec::new());
+
+    extern "C" fn record_event(unit: u8, event: u8, user_data: *mut c_void) {
+        RECEIVED_EVENTS.lock().unwrap().push((unit, event, user_data as usize));
+    }
+
+    #[test]
+    #[serial]
+    fn test_network_events() {
+        cleanup_test_context();
+        let mut info = NetworkEventInfo::default();
+        assert_eq!(unsafe { network_poll_event(&mut info) }, -(FN_ERR_NOT_INITIALIZED as i16));
+
+        let manager = TestNetworkManager::new();
+        let events = manager.events().clone();
+        setup_test_context(manager);
+
+        // Polling
+        assert_eq!(unsafe { network_poll_event(&mut info) }, 0);
+        events.notifier(2).notify(NetworkEventKind::DataAvailable);
+        assert_eq!(unsafe { network_poll_event(&mut info) }, 1);
+        assert_eq!((info.unit, info.event), (3, NetworkEventKind::DataAvailable as u8));
+        assert_eq!(unsafe { network_poll_event(std::ptr::null_mut()) }, -(FN_ERR_BAD_CMD as i16));
+
+        // Callback, with user data passed back untouched
+        RECEIVED_EVENTS.lock().unwrap().clear();
+        assert_eq!(network_set_event_callback(Some(record_event), 0x1234 as *mut c_void), FN_ERR_OK);
+        events.notifier(0).notify(NetworkEventKind::ConnectionClosed);
+        assert_eq!(*RECEIVED_EVENTS.lock().unwrap(), vec![(1, 2, 0x1234)]);
+        assert_eq!(unsafe { network_poll_event(&mut info) }, 0);
+
+        assert_eq!(network_set_event_callback(None, std::ptr::null_mut()), FN_ERR_OK);
+        events.notifier(0).notify(NetworkEventKind::ClientWaiting);
+        assert_eq!(RECEIVED_EVENTS.lock().unwrap().len(), 1);
+        assert_eq!(unsafe { network_poll_event(&mut info) }, 1);
+        assert_eq!(info.event, 3);
+
+        cleanup_test_context();
+    }
+
     #[test]
     #[serial]
     fn test_network_close_success() {
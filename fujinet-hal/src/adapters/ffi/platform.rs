@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use crate::platform::{Platform, X86Platform};
+use super::{FujiError, FujiPlatform};
+
+/// A platform and the runtime its devices run on, handed to C as a FujiPlatform
+pub struct PlatformContext {
+    pub(crate) platform: X86Platform,
+    pub(crate) runtime: Arc<Runtime>,
+}
+
+/// Create and initialize the platform, returning null if it can't be brought up
+/// Free it with fuji_platform_destroy
+#[no_mangle]
+pub extern "C" fn fuji_platform_create() -> *mut FujiPlatform {
+    let Ok(runtime) = Runtime::new() else {
+        return std::ptr::null_mut();
+    };
+    let mut platform = X86Platform::new();
+    if runtime.block_on(platform.initialize()).is_err() {
+        return std::ptr::null_mut();
+    }
+
+    let context = PlatformContext { platform, runtime: Arc::new(runtime) };
+    Box::into_raw(Box::new(context)).cast()
+}
+
+/// Shut the platform down and free it
+/// Devices created from it keep working until they are destroyed, as they share its runtime
+///
+/// # Safety
+/// `platform` must be null or a platform from fuji_platform_create that has not been destroyed; it is freed here
+#[no_mangle]
+pub unsafe extern "C" fn fuji_platform_destroy(platform: *mut FujiPlatform) -> FujiError {
+    if platform.is_null() {
+        return FujiError::InvalidParameter;
+    }
+    let mut context = unsafe { Box::from_raw(platform.cast::<PlatformContext>()) };
+    match context.runtime.block_on(context.platform.shutdown()) {
+        Ok(_) => FujiError::Ok,
+        Err(e) => e.into(),
+    }
+}
@@ -0,0 +1,484 @@
+use std::path::{Path, PathBuf};
+use crate::device::{DeviceError, DeviceResult, FujiConfig};
+use crate::device::fuji::{MAX_HOSTS, MAX_DISK_DEVICES, HOST_SLOT_EMPTY, MOUNT_MODE_READ, MOUNT_MODE_WRITE};
+use super::ini::Ini;
+
+/// Number of printers the file can configure
+pub const MAX_PRINTERS: usize = 4;
+
+const GENERAL: &str = "General";
+const NETWORK: &str = "Network";
+const MODEM: &str = "Modem";
+
+/// Where a host slot's images come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostType {
+    /// The local SD card (a directory on x86)
+    Sd,
+    Tnfs,
+}
+
+impl HostType {
+    fn name(self) -> &'static str {
+        match self {
+            HostType::Sd => "SD",
+            HostType::Tnfs => "TNFS",
+        }
+    }
+
+    fn parse(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("SD") { HostType::Sd } else { HostType::Tnfs }
+    }
+}
+
+/// A [HostN] section
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostConfig {
+    pub host_type: HostType,
+    pub name: String,
+}
+
+/// A [MountN] section: the image a device slot mounts at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountConfig {
+    pub host_slot: u8,
+    /// MOUNT_MODE_READ or MOUNT_MODE_WRITE
+    pub mode: u8,
+    pub path: String,
+}
+
+/// A [PrinterN] section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrinterConfig {
+    pub printer_type: u8,
+    pub port: u8,
+}
+
+/// A setting that changed; `key` is empty when a whole section was removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub section: String,
+    pub key: String,
+}
+
+/// Callback invoked for each setting that changes
+pub type ConfigCallback = Box<dyn Fn(&ConfigChange) + Send + Sync>;
+
+/// FujiNet settings in the layout of the firmware's fnconfig.ini
+///
+/// Typed accessors cover the General, HostN, MountN, PrinterN, Modem and Network
+/// sections; anything else in the file is kept as it was. Settings that are missing
+/// read as the firmware's defaults. Callbacks registered with `on_change` hear about
+/// every setting a setter actually changes.
+#[derive(Default)]
+pub struct FnConfig {
+    ini: Ini,
+    path: Option<PathBuf>,
+    listeners: Vec<ConfigCallback>,
+}
+
+impl FnConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        Self { ini: Ini::parse(text), ..Self::default() }
+    }
+
+    /// Load the file at `path`, starting from the defaults if it doesn't exist yet
+    /// `save` writes back to the same path
+    pub fn load(path: impl Into<PathBuf>) -> DeviceResult<Self> {
+        let path = path.into();
+        let ini = match std::fs::read_to_string(&path) {
+            Ok(text) => Ini::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ini::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { ini, path: Some(path), ..Self::default() })
+    }
+
+    /// Write the settings back to the file they were loaded from
+    pub fn save(&self) -> DeviceResult<()> {
+        let path = self.path.as_ref().ok_or(DeviceError::InvalidOperation)?;
+        self.save_to(path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> DeviceResult<()> {
+        std::fs::write(path, self.ini.to_string())?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The underlying document, including sections without typed accessors
+    pub fn ini(&self) -> &Ini {
+        &self.ini
+    }
+
+    pub fn on_change(&mut self, callback: ConfigCallback) {
+        self.listeners.push(callback);
+    }
+
+    fn notify(&self, section: &str, key: &str) {
+        let change = ConfigChange { section: section.to_string(), key: key.to_string() };
+        for listener in &self.listeners {
+            listener(&change);
+        }
+    }
+
+    fn get_str(&self, section: &str, key: &str, default: &str) -> String {
+        self.ini.get(section, key).unwrap_or(default).to_string()
+    }
+
+    fn get_bool(&self, section: &str, key: &str, default: bool) -> bool {
+        match self.ini.get(section, key) {
+            Some(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+            None => default,
+        }
+    }
+
+    fn get_u8(&self, section: &str, key: &str, default: u8) -> u8 {
+        self.ini.get(section, key).and_then(|value| value.parse().ok()).unwrap_or(default)
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) {
+        if self.ini.set(section, key, value) {
+            self.notify(section, key);
+        }
+    }
+
+    fn set_bool(&mut self, section: &str, key: &str, value: bool) {
+        self.set(section, key, if value { "1" } else { "0" });
+    }
+
+    fn remove(&mut self, section: &str) {
+        if self.ini.remove_section(section) {
+            self.notify(section, "");
+        }
+    }
+
+    pub fn device_name(&self) -> String {
+        self.get_str(GENERAL, "devicename", "fujinet")
+    }
+
+    pub fn set_device_name(&mut self, name: &str) {
+        self.set(GENERAL, "devicename", name);
+    }
+
+    pub fn hsio_index(&self) -> u8 {
+        self.get_u8(GENERAL, "hsioindex", 8)
+    }
+
+    pub fn set_hsio_index(&mut self, index: u8) {
+        self.set(GENERAL, "hsioindex", &index.to_string());
+    }
+
+    pub fn timezone(&self) -> String {
+        self.get_str(GENERAL, "timezone", "")
+    }
+
+    pub fn set_timezone(&mut self, timezone: &str) {
+        self.set(GENERAL, "timezone", timezone);
+    }
+
+    pub fn rotation_sounds(&self) -> bool {
+        self.get_bool(GENERAL, "rotationsounds", true)
+    }
+
+    pub fn set_rotation_sounds(&mut self, enabled: bool) {
+        self.set_bool(GENERAL, "rotationsounds", enabled);
+    }
+
+    /// Whether CONFIG boots when no disk is mounted in D1:
+    pub fn config_enabled(&self) -> bool {
+        self.get_bool(GENERAL, "configenabled", true)
+    }
+
+    pub fn set_config_enabled(&mut self, enabled: bool) {
+        self.set_bool(GENERAL, "configenabled", enabled);
+    }
+
+    /// Host slot `slot` (0-based, stored as [Host1] to [Host8]), if it is set
+    pub fn host(&self, slot: usize) -> Option<HostConfig> {
+        let section = format!("Host{}", slot + 1);
+        let name = self.ini.get(&section, "name").filter(|name| !name.is_empty())?;
+        Some(HostConfig {
+            host_type: HostType::parse(self.ini.get(&section, "type").unwrap_or("TNFS")),
+            name: name.to_string(),
+        })
+    }
+
+    pub fn set_host(&mut self, slot: usize, host: Option<&HostConfig>) -> DeviceResult<()> {
+        if slot >= MAX_HOSTS {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        let section = format!("Host{}", slot + 1);
+        match host {
+            Some(host) => {
+                self.set(&section, "type", host.host_type.name());
+                self.set(&section, "name", &host.name);
+            }
+            None => self.remove(&section),
+        }
+        Ok(())
+    }
+
+    /// The image device slot `slot` (0-based, stored as [Mount1] to [Mount8]) mounts, if any
+    pub fn mount(&self, slot: usize) -> Option<MountConfig> {
+        let section = format!("Mount{}", slot + 1);
+        let path = self.ini.get(&section, "path").filter(|path| !path.is_empty())?;
+        let host_slot = self.ini.get(&section, "hostslot")?.parse().ok()?;
+        let mode = match self.ini.get(&section, "mode") {
+            Some(mode) if mode.eq_ignore_ascii_case("w") => MOUNT_MODE_WRITE,
+            _ => MOUNT_MODE_READ,
+        };
+        Some(MountConfig { host_slot, mode, path: path.to_string() })
+    }
+
+    pub fn set_mount(&mut self, slot: usize, mount: Option<&MountConfig>) -> DeviceResult<()> {
+        if slot >= MAX_DISK_DEVICES {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        let section = format!("Mount{}", slot + 1);
+        match mount {
+            Some(mount) => {
+                self.set(&section, "hostslot", &mount.host_slot.to_string());
+                self.set(&section, "mode", if mount.mode == MOUNT_MODE_WRITE { "w" } else { "r" });
+                self.set(&section, "path", &mount.path);
+            }
+            None => self.remove(&section),
+        }
+        Ok(())
+    }
+
+    /// Printer `slot` (0-based, stored as [Printer1] and up), if configured
+    pub fn printer(&self, slot: usize) -> Option<PrinterConfig> {
+        let section = format!("Printer{}", slot + 1);
+        if !self.ini.has_section(&section) {
+            return None;
+        }
+        Some(PrinterConfig {
+            printer_type: self.get_u8(&section, "type", 0),
+            port: self.get_u8(&section, "port", slot as u8 + 1),
+        })
+    }
+
+    pub fn set_printer(&mut self, slot: usize, printer: Option<&PrinterConfig>) -> DeviceResult<()> {
+        if slot >= MAX_PRINTERS {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        let section = format!("Printer{}", slot + 1);
+        match printer {
+            Some(printer) => {
+                self.set(&section, "type", &printer.printer_type.to_string());
+                self.set(&section, "port", &printer.port.to_string());
+            }
+            None => self.remove(&section),
+        }
+        Ok(())
+    }
+
+    pub fn modem_enabled(&self) -> bool {
+        self.get_bool(MODEM, "sioenabled", true)
+    }
+
+    pub fn set_modem_enabled(&mut self, enabled: bool) {
+        self.set_bool(MODEM, "sioenabled", enabled);
+    }
+
+    pub fn modem_emulation(&self) -> bool {
+        self.get_bool(MODEM, "modememulation", true)
+    }
+
+    pub fn set_modem_emulation(&mut self, enabled: bool) {
+        self.set_bool(MODEM, "modememulation", enabled);
+    }
+
+    pub fn modem_sniffer_enabled(&self) -> bool {
+        self.get_bool(MODEM, "sniffer_enabled", false)
+    }
+
+    pub fn set_modem_sniffer_enabled(&mut self, enabled: bool) {
+        self.set_bool(MODEM, "sniffer_enabled", enabled);
+    }
+
+    pub fn sntp_server(&self) -> String {
+        self.get_str(NETWORK, "sntpserver", "pool.ntp.org")
+    }
+
+    pub fn set_sntp_server(&mut self, server: &str) {
+        self.set(NETWORK, "sntpserver", server);
+    }
+
+    /// Load the configured hosts and mounts into a config device's slots
+    /// Nothing gets mounted; the slots are only filled in. A mount on a host slot
+    /// that doesn't exist is logged and its device slot left empty.
+    pub fn apply_to(&self, fuji: &mut FujiConfig) -> DeviceResult<()> {
+        for slot in 0..MAX_HOSTS {
+            let name = self.host(slot).map(|host| host.name).unwrap_or_default();
+            fuji.set_host_name(slot, &name)?;
+        }
+        for slot in 0..MAX_DISK_DEVICES {
+            let mount = self.mount(slot).filter(|mount| {
+                let valid = mount.host_slot == HOST_SLOT_EMPTY || (mount.host_slot as usize) < MAX_HOSTS;
+                if !valid {
+                    log::warn!("ignoring [Mount{}]: no host slot {}", slot + 1, mount.host_slot);
+                }
+                valid
+            });
+            match mount {
+                Some(mount) => fuji.set_device_filename(slot, mount.host_slot, mount.mode, &mount.path)?,
+                None => fuji.set_device_filename(slot, HOST_SLOT_EMPTY, MOUNT_MODE_READ, "")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the hosts and mounts from a config device's slots
+    /// A host keeps the type it was saved with unless its name changed
+    pub fn update_from(&mut self, fuji: &FujiConfig) -> DeviceResult<()> {
+        for (slot, host) in fuji.host_slots().iter().enumerate() {
+            let host_type = match self.host(slot) {
+                Some(stored) if stored.name == host.name => stored.host_type,
+                // The firmware's convention: a host named SD is the local card
+                _ => HostType::parse(&host.name),
+            };
+            let host = (!host.name.is_empty()).then(|| HostConfig {
+                host_type,
+                name: host.name.clone(),
+            });
+            self.set_host(slot, host.as_ref())?;
+        }
+        for (slot, disk) in fuji.disk_slots().iter().enumerate() {
+            let mount = (!disk.is_empty()).then(|| MountConfig {
+                host_slot: disk.host_slot,
+                mode: disk.mode,
+                path: disk.filename.clone(),
+            });
+            self.set_mount(slot, mount.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const FNCONFIG: &str = "\
+[General]
+devicename=atari800
+hsioindex=1
+configenabled=0
+
+[Host1]
+type=SD
+name=SD
+
+[Host2]
+type=TNFS
+name=tnfs.example.com
+
+[Mount1]
+hostslot=1
+mode=w
+path=/dos/mydos.atr
+
+[Printer1]
+type=2
+port=1
+
+[Modem]
+sioenabled=1
+sniffer_enabled=1
+
+[Bluetooth]
+enabled=0
+";
+
+    #[test]
+    fn test_typed_accessors() {
+        let config = FnConfig::parse(FNCONFIG);
+        assert_eq!(config.device_name(), "atari800");
+        assert_eq!(config.hsio_index(), 1);
+        assert!(!config.config_enabled());
+        assert!(config.rotation_sounds());
+        assert_eq!(config.sntp_server(), "pool.ntp.org");
+
+        assert_eq!(config.host(0), Some(HostConfig { host_type: HostType::Sd, name: "SD".to_string() }));
+        assert_eq!(config.host(1).unwrap().host_type, HostType::Tnfs);
+        assert_eq!(config.host(2), None);
+        assert_eq!(config.mount(0), Some(MountConfig { host_slot: 1, mode: MOUNT_MODE_WRITE, path: "/dos/mydos.atr".to_string() }));
+        assert_eq!(config.mount(1), None);
+        assert_eq!(config.printer(0), Some(PrinterConfig { printer_type: 2, port: 1 }));
+        assert!(config.modem_enabled() && config.modem_emulation() && config.modem_sniffer_enabled());
+    }
+
+    #[test]
+    fn test_change_notifications() {
+        let mut config = FnConfig::parse(FNCONFIG);
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        config.on_change(Box::new(move |change| sink.lock().unwrap().push(change.clone())));
+
+        config.set_device_name("atari800");
+        config.set_sntp_server("time.example.com");
+        config.set_mount(0, None).unwrap();
+        assert_eq!(config.set_mount(8, None), Err(DeviceError::InvalidDeviceId));
+
+        let change = |section: &str, key: &str| ConfigChange { section: section.to_string(), key: key.to_string() };
+        assert_eq!(*changes.lock().unwrap(), [change("Network", "sntpserver"), change("Mount1", "")]);
+    }
+
+    #[test]
+    fn test_save_and_load_with_fuji_slots() -> DeviceResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("fnconfig.ini");
+        std::fs::write(&path, FNCONFIG)?;
+
+        // The host app loads its slots, CONFIG changes them, and they are saved again
+        let mut config = FnConfig::load(&path)?;
+        let mut fuji = FujiConfig::new();
+        config.apply_to(&mut fuji)?;
+        assert_eq!(fuji.host_slot(1).unwrap().name, "tnfs.example.com");
+        assert_eq!(fuji.disk_slot(0).unwrap().filename, "/dos/mydos.atr");
+
+        fuji.set_host_name(2, "irata.online")?;
+        fuji.set_device_filename(1, 2, MOUNT_MODE_READ, "/games/archon.atr")?;
+        config.update_from(&fuji)?;
+        config.save()?;
+
+        let reloaded = FnConfig::load(&path)?;
+        assert_eq!(reloaded.host(2).unwrap().name, "irata.online");
+        assert_eq!(reloaded.mount(1).unwrap().path, "/games/archon.atr");
+        assert_eq!(reloaded.mount(0).unwrap().mode, MOUNT_MODE_WRITE);
+        assert_eq!(reloaded.ini().get("Bluetooth", "enabled"), Some("0"));
+
+        // A host keeps its type even when its name says otherwise
+        let mut config = FnConfig::parse("[Host1]\ntype=TNFS\nname=sd\n");
+        let mut fuji = FujiConfig::new();
+        config.apply_to(&mut fuji)?;
+        config.update_from(&fuji)?;
+        assert_eq!(config.host(0).unwrap().host_type, HostType::Tnfs);
+
+        // A missing file is just an empty configuration
+        let fresh = FnConfig::load(dir.path().join("missing.ini"))?;
+        assert_eq!(fresh.host(0), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_skips_mounts_on_missing_hosts() -> DeviceResult<()> {
+        let config = FnConfig::parse("[Host1]\nname=SD\n\n[Mount1]\nhostslot=9\npath=/a.atr\n\n[Mount2]\nhostslot=0\npath=/b.atr\n");
+        let mut fuji = FujiConfig::new();
+        config.apply_to(&mut fuji)?;
+        assert!(fuji.disk_slot(0).unwrap().is_empty());
+        assert_eq!(fuji.disk_slot(1).unwrap().filename, "/b.atr");
+        assert_eq!(fuji.host_slot(0).unwrap().name, "SD");
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    name: String,
+    entries: Vec<(String, String)>,
+}
+
+/// An INI document: `[Section]` headers followed by `key=value` lines
+///
+/// Section and key names match case-insensitively but keep the case they were written
+/// with. Order is preserved, and so are sections and keys nobody asks about, so a file
+/// can be loaded, changed and saved without losing what it held. Lines starting with
+/// `;` or `#` are comments and are not kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ini {
+    sections: Vec<Section>,
+}
+
+impl Ini {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse INI text; anything that isn't a header or a key=value line is skipped
+    pub fn parse(text: &str) -> Self {
+        let mut ini = Self::new();
+        let mut current = String::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                current = name.trim().to_string();
+                ini.section_mut(&current);
+            } else if let Some((key, value)) = line.split_once('=') {
+                ini.set(&current, key.trim(), value.trim());
+            }
+        }
+        ini
+    }
+
+    fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name.eq_ignore_ascii_case(name))
+    }
+
+    fn section_mut(&mut self, name: &str) -> &mut Section {
+        let index = match self.sections.iter().position(|section| section.name.eq_ignore_ascii_case(name)) {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section { name: name.to_string(), entries: Vec::new() });
+                self.sections.len() - 1
+            }
+        };
+        &mut self.sections[index]
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.section(section)?
+            .entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set a value, adding the section and key if needed; returns whether anything changed
+    pub fn set(&mut self, section: &str, key: &str, value: &str) -> bool {
+        let section = self.section_mut(section);
+        match section.entries.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some((_, current)) if current == value => false,
+            Some((_, current)) => {
+                *current = value.to_string();
+                true
+            }
+            None => {
+                section.entries.push((key.to_string(), value.to_string()));
+                true
+            }
+        }
+    }
+
+    pub fn has_section(&self, section: &str) -> bool {
+        self.section(section).is_some()
+    }
+
+    /// Drop a whole section; returns whether it was there
+    pub fn remove_section(&mut self, section: &str) -> bool {
+        let before = self.sections.len();
+        self.sections.retain(|s| !s.name.eq_ignore_ascii_case(section));
+        self.sections.len() != before
+    }
+
+    /// Section names in file order
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(|section| section.name.as_str())
+    }
+}
+
+impl fmt::Display for Ini {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, section) in self.sections.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            // Keys from before the first header live in a section with no name
+            if !section.name.is_empty() {
+                writeln!(f, "[{}]", section.name)?;
+            }
+            for (key, value) in &section.entries {
+                writeln!(f, "{}={}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "; written by FujiNet\n[General]\ndevicename=fujinet\n hsioindex = 8 \n\n[Host1]\ntype=SD\nname=SD\n[Custom]\nkeep=me\n";
+        let mut ini = Ini::parse(text);
+        assert_eq!(ini.get("general", "DeviceName"), Some("fujinet"));
+        assert_eq!(ini.get("General", "hsioindex"), Some("8"));
+        assert_eq!(ini.get("Host1", "missing"), None);
+        assert_eq!(ini.sections().collect::<Vec<_>>(), ["General", "Host1", "Custom"]);
+
+        assert!(!ini.set("General", "devicename", "fujinet"));
+        assert!(ini.set("GENERAL", "devicename", "retro"));
+        assert!(ini.set("Host2", "name", "tnfs.example.com"));
+        assert!(ini.remove_section("host1"));
+        assert!(!ini.has_section("Host1"));
+
+        let saved = ini.to_string();
+        assert_eq!(saved, "[General]\ndevicename=retro\nhsioindex=8\n\n[Custom]\nkeep=me\n\n[Host2]\nname=tnfs.example.com\n");
+        assert_eq!(Ini::parse(&saved), ini);
+    }
+}
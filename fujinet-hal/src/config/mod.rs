@@ -0,0 +1,5 @@
+pub mod ini;
+pub mod fnconfig;
+
+pub use ini::Ini;
+pub use fnconfig::{FnConfig, ConfigChange, ConfigCallback, HostConfig, HostType, MountConfig, PrinterConfig};
@@ -0,0 +1,63 @@
+use std::net::Ipv4Addr;
+
+/// Bytes for an SSID, NUL padded
+pub const SSID_LEN: usize = 33;
+/// Bytes for the adapter's host name, NUL padded
+pub const ADAPTER_HOSTNAME_LEN: usize = 64;
+/// Bytes for the firmware version, NUL padded
+pub const FIRMWARE_VERSION_LEN: usize = 15;
+/// Bytes in an adapter config record: SSID, host name, IP, gateway, netmask, DNS, MAC, BSSID, version
+pub const ADAPTER_CONFIG_LEN: usize = SSID_LEN + ADAPTER_HOSTNAME_LEN + 4 * 4 + 6 + 6 + FIRMWARE_VERSION_LEN;
+/// Bytes in a scan result record: SSID and RSSI
+pub const SCAN_RESULT_LEN: usize = SSID_LEN + 1;
+
+/// What the network adapter is connected to and how it is set up
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdapterConfig {
+    pub ssid: String,
+    pub hostname: String,
+    pub local_ip: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub dns: Ipv4Addr,
+    pub mac: [u8; 6],
+    pub bssid: [u8; 6],
+    pub firmware_version: String,
+}
+
+impl Default for AdapterConfig {
+    fn default() -> Self {
+        Self {
+            ssid: String::new(),
+            hostname: String::new(),
+            local_ip: Ipv4Addr::UNSPECIFIED,
+            gateway: Ipv4Addr::UNSPECIFIED,
+            netmask: Ipv4Addr::UNSPECIFIED,
+            dns: Ipv4Addr::UNSPECIFIED,
+            mac: [0; 6],
+            bssid: [0; 6],
+            firmware_version: String::new(),
+        }
+    }
+}
+
+/// A network found by a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanResult {
+    pub ssid: String,
+    /// Signal strength in dBm
+    pub rssi: i8,
+}
+
+/// Source of the network adapter details the config device reports
+///
+/// On the real hardware this is the WiFi radio; platforms without one still have to
+/// answer, with whatever their own network connection looks like.
+pub trait AdapterInfoProvider: Send + Sync {
+    fn adapter_config(&self) -> AdapterConfig;
+
+    /// Networks in range, strongest first
+    fn scan_networks(&self) -> Vec<ScanResult>;
+
+    fn is_connected(&self) -> bool;
+}
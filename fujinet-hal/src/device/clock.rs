@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use std::any::Any;
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::{Device, DeviceError, DeviceResult, DeviceStatus};
+
+/// Length of a time reading
+pub const CLOCK_TIME_LEN: usize = 6;
+
+/// Real-time clock giving the host the current UTC time
+///
+/// Each read returns the time in the FujiNet APETIME layout:
+/// day, month, year (two digits), hour, minute, second, one byte each.
+#[derive(Default)]
+pub struct ClockDevice {
+    open: bool,
+}
+
+impl ClockDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The APETIME bytes for a time given in seconds since the Unix epoch
+    pub fn time_bytes(unix_secs: u64) -> [u8; CLOCK_TIME_LEN] {
+        let days = (unix_secs / 86_400) as i64;
+        let secs = unix_secs % 86_400;
+
+        // Civil date from a day count (Howard Hinnant's days_from_civil, inverted)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        [
+            day as u8,
+            month as u8,
+            (year % 100) as u8,
+            (secs / 3600) as u8,
+            (secs / 60 % 60) as u8,
+            (secs % 60) as u8,
+        ]
+    }
+}
+
+#[async_trait]
+impl Device for ClockDevice {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    async fn open(&mut self) -> DeviceResult<()> {
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        self.open = false;
+        Ok(())
+    }
+
+    /// Fills as much of the time reading as fits in `buf`
+    async fn read_bytes(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| DeviceError::IoError(e.to_string()))?;
+        let time = Self::time_bytes(now.as_secs());
+        let len = buf.len().min(CLOCK_TIME_LEN);
+        buf[..len].copy_from_slice(&time[..len]);
+        Ok(len)
+    }
+
+    /// The clock follows the host's time and can't be set
+    async fn write_bytes(&mut self, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn read_block(&mut self, _block: u32, _buf: &mut [u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn write_block(&mut self, _block: u32, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn get_status(&self) -> DeviceResult<DeviceStatus> {
+        Ok(if self.open { DeviceStatus::Ready } else { DeviceStatus::Disconnected })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_bytes() {
+        assert_eq!(ClockDevice::time_bytes(0), [1, 1, 70, 0, 0, 0]);
+        // 2024-02-29 23:59:58 UTC
+        assert_eq!(ClockDevice::time_bytes(1_709_251_198), [29, 2, 24, 23, 59, 58]);
+        // 2000-03-01 12:34:56 UTC
+        assert_eq!(ClockDevice::time_bytes(951_914_096), [1, 3, 0, 12, 34, 56]);
+    }
+
+    #[tokio::test]
+    async fn test_read_time() -> DeviceResult<()> {
+        let mut clock = ClockDevice::new();
+        let mut buf = [0u8; 8];
+        assert_eq!(clock.read_bytes(&mut buf).await, Err(DeviceError::NotReady));
+        clock.open().await?;
+        assert_eq!(clock.read_bytes(&mut buf).await?, CLOCK_TIME_LEN);
+        assert!((1..=31).contains(&buf[0]) && (1..=12).contains(&buf[1]));
+        Ok(())
+    }
+}
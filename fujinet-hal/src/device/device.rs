@@ -23,6 +23,8 @@ pub enum DeviceError {
     UnsupportedProtocol,
     InvalidUrl,
     InvalidDeviceId,
+    /// The peer closed the connection with the given close code
+    ConnectionClosed(u16),
 }
 
 impl From<std::io::Error> for DeviceError {
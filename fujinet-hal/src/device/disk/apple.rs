@@ -0,0 +1,246 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::image::{read_u32, DiskImage, ImageFormat};
+
+/// Bytes in a ProDOS block
+pub const BLOCK_SIZE: usize = 512;
+/// Bytes in a 5.25" sector
+pub const APPLE_SECTOR_SIZE: usize = 256;
+/// Sectors on a 5.25" track
+pub const SECTORS_PER_TRACK: usize = 16;
+/// Size of a 35 track 5.25" disk image
+pub const DISK_525_SIZE: usize = 35 * SECTORS_PER_TRACK * APPLE_SECTOR_SIZE;
+
+/// The first four bytes of a 2MG file
+pub const TWO_MG_MAGIC: &[u8; 4] = b"2IMG";
+const TWO_MG_HEADER_LEN: usize = 64;
+const TWO_MG_FORMAT_DOS: u32 = 0;
+const TWO_MG_FORMAT_PRODOS: u32 = 1;
+const TWO_MG_LOCKED: u32 = 0x8000_0000;
+
+/// DOS 3.3 sector holding each ProDOS sector of a track; block n of a track is ProDOS
+/// sectors 2n and 2n + 1
+pub(super) const PRODOS_TO_DOS: [usize; 16] = [0, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 15];
+
+/// The order a 5.25" image file stores each track's sectors in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorOrder {
+    /// ProDOS blocks one after another
+    ProDos,
+    /// DOS 3.3 sectors one after another; only 5.25" images come this way
+    Dos,
+}
+
+/// An Apple II disk image addressed by 512 byte ProDOS blocks, counted from zero
+///
+/// Covers ProDOS-order (.po) and DOS-order (.do, .dsk) sector dumps and 2MG files, which
+/// wrap either in a 64 byte header. DOS-order images hold the two halves of each block in
+/// the DOS 3.3 sectors ProDOS maps them to. A 2MG marked as locked can't be written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppleImage {
+    bytes: Vec<u8>,
+    format: ImageFormat,
+    order: SectorOrder,
+    data_offset: usize,
+    data_len: usize,
+    locked: bool,
+    buffer: Vec<u8>,
+}
+
+impl AppleImage {
+    /// A ProDOS-order image: any whole number of blocks
+    pub fn prodos_order(bytes: Vec<u8>) -> DeviceResult<Self> {
+        let data_len = bytes.len();
+        Self::new(bytes, ImageFormat::Po, SectorOrder::ProDos, 0, data_len, false)
+    }
+
+    /// A DOS-order image of a 140K 5.25" disk
+    pub fn dos_order(bytes: Vec<u8>) -> DeviceResult<Self> {
+        let data_len = bytes.len();
+        Self::new(bytes, ImageFormat::Do, SectorOrder::Dos, 0, data_len, false)
+    }
+
+    /// A 2MG file holding a ProDOS or DOS-order image; nibble images aren't supported
+    pub fn parse_2mg(bytes: Vec<u8>) -> DeviceResult<Self> {
+        if bytes.len() < TWO_MG_HEADER_LEN || !bytes.starts_with(TWO_MG_MAGIC) {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let order = match read_u32(&bytes, 12)? {
+            TWO_MG_FORMAT_PRODOS => SectorOrder::ProDos,
+            TWO_MG_FORMAT_DOS => SectorOrder::Dos,
+            _ => return Err(DeviceError::NotSupported),
+        };
+        let locked = read_u32(&bytes, 16)? & TWO_MG_LOCKED != 0;
+        let data_offset = read_u32(&bytes, 24)? as usize;
+        // Some tools leave the length at zero, meaning the data runs to the end of the file
+        let data_len = match read_u32(&bytes, 28)? as usize {
+            0 => bytes.len().checked_sub(data_offset).ok_or(DeviceError::InvalidOperation)?,
+            len => len,
+        };
+        Self::new(bytes, ImageFormat::TwoMg, order, data_offset, data_len, locked)
+    }
+
+    fn new(bytes: Vec<u8>, format: ImageFormat, order: SectorOrder, data_offset: usize, data_len: usize, locked: bool) -> DeviceResult<Self> {
+        let fits = data_offset.checked_add(data_len).is_some_and(|end| end <= bytes.len());
+        let whole_blocks = data_len > 0 && data_len.is_multiple_of(BLOCK_SIZE);
+        if !fits || !whole_blocks || (order == SectorOrder::Dos && data_len != DISK_525_SIZE) {
+            return Err(DeviceError::InvalidOperation);
+        }
+        Ok(Self { bytes, format, order, data_offset, data_len, locked, buffer: vec![0; BLOCK_SIZE] })
+    }
+
+    pub fn sector_order(&self) -> SectorOrder {
+        self.order
+    }
+
+    /// File offsets of the two 256 byte halves of a block
+    fn block_halves(&self, block: u32) -> DeviceResult<[usize; 2]> {
+        if block as usize >= self.data_len / BLOCK_SIZE {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let block = block as usize;
+        Ok(match self.order {
+            SectorOrder::ProDos => {
+                let start = self.data_offset + block * BLOCK_SIZE;
+                [start, start + APPLE_SECTOR_SIZE]
+            }
+            SectorOrder::Dos => {
+                let track = self.data_offset + block / 8 * SECTORS_PER_TRACK * APPLE_SECTOR_SIZE;
+                let first = (block % 8) * 2;
+                [
+                    track + PRODOS_TO_DOS[first] * APPLE_SECTOR_SIZE,
+                    track + PRODOS_TO_DOS[first + 1] * APPLE_SECTOR_SIZE,
+                ]
+            }
+        })
+    }
+}
+
+impl DiskImage for AppleImage {
+    fn image_format(&self) -> ImageFormat {
+        self.format
+    }
+
+    fn sector_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn sector_count(&self) -> u32 {
+        (self.data_len / BLOCK_SIZE) as u32
+    }
+
+    fn first_sector(&self) -> u32 {
+        0
+    }
+
+    fn read_sector(&mut self, block: u32) -> DeviceResult<&[u8]> {
+        let halves = self.block_halves(block)?;
+        for (half, start) in halves.into_iter().enumerate() {
+            let range = half * APPLE_SECTOR_SIZE..(half + 1) * APPLE_SECTOR_SIZE;
+            self.buffer[range].copy_from_slice(&self.bytes[start..start + APPLE_SECTOR_SIZE]);
+        }
+        Ok(&self.buffer)
+    }
+
+    fn write_sector(&mut self, block: u32, data: &[u8]) -> DeviceResult<usize> {
+        let halves = self.block_halves(block)?;
+        if self.locked || data.len() < BLOCK_SIZE {
+            return Err(DeviceError::InvalidOperation);
+        }
+        for (half, start) in halves.into_iter().enumerate() {
+            let range = half * APPLE_SECTOR_SIZE..(half + 1) * APPLE_SECTOR_SIZE;
+            self.bytes[start..start + APPLE_SECTOR_SIZE].copy_from_slice(&data[range]);
+        }
+        Ok(BLOCK_SIZE)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        if self.locked {
+            return Err(DeviceError::InvalidOperation);
+        }
+        self.bytes[self.data_offset..self.data_offset + self.data_len].fill(0);
+        Ok(())
+    }
+
+    fn is_writable(&self) -> bool {
+        !self.locked
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_mg(format: u32, flags: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; TWO_MG_HEADER_LEN];
+        bytes[..4].copy_from_slice(TWO_MG_MAGIC);
+        bytes[4..8].copy_from_slice(b"FUJI");
+        bytes[8..10].copy_from_slice(&(TWO_MG_HEADER_LEN as u16).to_le_bytes());
+        bytes[10..12].copy_from_slice(&1u16.to_le_bytes());
+        bytes[12..16].copy_from_slice(&format.to_le_bytes());
+        bytes[16..20].copy_from_slice(&flags.to_le_bytes());
+        bytes[20..24].copy_from_slice(&((data.len() / BLOCK_SIZE) as u32).to_le_bytes());
+        bytes[24..28].copy_from_slice(&(TWO_MG_HEADER_LEN as u32).to_le_bytes());
+        bytes[28..32].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_prodos_and_dos_order() -> DeviceResult<()> {
+        let mut prodos = AppleImage::prodos_order(vec![0; 800 * 1024])?;
+        assert_eq!(prodos.sector_count(), 1600);
+        prodos.write_sector(2, &[0x22; BLOCK_SIZE])?;
+        assert_eq!(&prodos.as_bytes()[1024..1536], &[0x22; BLOCK_SIZE]);
+        assert_eq!(prodos.read_sector(1600).err(), Some(DeviceError::InvalidOperation));
+
+        // Block 9 is the second block of track 1: DOS sectors 13 and 12
+        let mut dos = AppleImage::dos_order(vec![0; DISK_525_SIZE])?;
+        assert_eq!(dos.sector_count(), 280);
+        let mut block = vec![0xAA; APPLE_SECTOR_SIZE];
+        block.extend([0xBB; APPLE_SECTOR_SIZE]);
+        dos.write_sector(9, &block)?;
+        let track = 16 * APPLE_SECTOR_SIZE;
+        assert_eq!(dos.as_bytes()[track + 13 * APPLE_SECTOR_SIZE], 0xAA);
+        assert_eq!(dos.as_bytes()[track + 12 * APPLE_SECTOR_SIZE], 0xBB);
+        assert_eq!(dos.read_sector(9)?, &block[..]);
+
+        assert_eq!(AppleImage::dos_order(vec![0; 800 * 1024]).err(), Some(DeviceError::InvalidOperation));
+        assert_eq!(AppleImage::prodos_order(vec![0; 1000]).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn test_2mg_container() -> DeviceResult<()> {
+        let mut data = vec![0u8; DISK_525_SIZE];
+        data[14 * APPLE_SECTOR_SIZE] = 0x5A;
+        let mut image = AppleImage::parse_2mg(two_mg(TWO_MG_FORMAT_DOS, 0, &data))?;
+        assert_eq!(image.sector_order(), SectorOrder::Dos);
+        assert_eq!(image.read_sector(0)?[APPLE_SECTOR_SIZE], 0x5A);
+        image.write_sector(0, &[1; BLOCK_SIZE])?;
+        assert_eq!(image.as_bytes()[TWO_MG_HEADER_LEN], 1);
+        assert_eq!(&image.as_bytes()[..4], TWO_MG_MAGIC);
+
+        let mut locked = AppleImage::parse_2mg(two_mg(TWO_MG_FORMAT_PRODOS, TWO_MG_LOCKED, &[0; 4 * BLOCK_SIZE]))?;
+        assert_eq!((locked.sector_count(), locked.is_writable()), (4, false));
+        assert_eq!(locked.write_sector(0, &[0; BLOCK_SIZE]), Err(DeviceError::InvalidOperation));
+
+        assert_eq!(AppleImage::parse_2mg(two_mg(2, 0, &[0; BLOCK_SIZE])).err(), Some(DeviceError::NotSupported));
+
+        // A zero data length takes everything after the header
+        let mut unsized_image = two_mg(TWO_MG_FORMAT_PRODOS, 0, &[0; 8 * BLOCK_SIZE]);
+        unsized_image[28..32].fill(0);
+        assert_eq!(AppleImage::parse_2mg(unsized_image)?.sector_count(), 8);
+        let mut past_end = two_mg(TWO_MG_FORMAT_PRODOS, 0, &[]);
+        past_end[24..28].copy_from_slice(&1000u32.to_le_bytes());
+        assert_eq!(AppleImage::parse_2mg(past_end).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::image::{DiskImage, ImageFormat};
+
+/// Bytes in an ATR file header
+pub const ATR_HEADER_LEN: usize = 16;
+/// The first two header bytes, 0x0296 little-endian
+pub const ATR_MAGIC: [u8; 2] = [0x96, 0x02];
+/// Boot sectors 1 to 3 are always 128 bytes, even on double density disks
+pub const BOOT_SECTORS: u32 = 3;
+
+pub(super) const SHORT_SECTOR_SIZE: usize = 128;
+
+/// Recording density, from the sector size and count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Density {
+    /// 720 sectors of 128 bytes, as on an 810
+    Single,
+    /// 1040 sectors of 128 bytes, as on a 1050
+    Enhanced,
+    /// 720 sectors of 256 bytes
+    Double,
+    /// Any other size, such as a hard disk image
+    Other,
+}
+
+impl Density {
+    pub fn of(sector_size: usize, sector_count: u32) -> Self {
+        match (sector_size, sector_count) {
+            (128, 720) => Density::Single,
+            (128, 1040) => Density::Enhanced,
+            (256, 720) => Density::Double,
+            _ => Density::Other,
+        }
+    }
+}
+
+/// The 12 byte PERCOM block describing a drive's geometry
+///
+/// Floppy densities report 40 tracks on one side; anything else is one track holding
+/// every sector. 256 byte sectors are MFM, 128 byte ones FM unless enhanced density.
+pub fn percom_block(sector_size: usize, sector_count: u32) -> [u8; 12] {
+    let density = Density::of(sector_size, sector_count);
+    let (tracks, sectors_per_track) = match density {
+        Density::Single | Density::Double => (40, 18),
+        Density::Enhanced => (40, 26),
+        Density::Other => (1, sector_count),
+    };
+    let mfm = if density == Density::Enhanced || sector_size != SHORT_SECTOR_SIZE { 0x04 } else { 0x00 };
+    let spt = (sectors_per_track.min(u16::MAX as u32) as u16).to_be_bytes();
+    let size = (sector_size as u16).to_be_bytes();
+    [tracks, 1, spt[0], spt[1], 0, mfm, size[0], size[1], 0xFF, 0, 0, 0]
+}
+
+/// Where the sectors of an Atari disk sit in an image file, after `offset` header bytes
+///
+/// On 256 byte sector disks the three boot sectors are usually stored as 128 bytes each;
+/// images storing them full size (with the second half unused) work too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SectorLayout {
+    offset: usize,
+    sector_size: usize,
+    sector_count: u32,
+    short_boot_sectors: bool,
+}
+
+impl SectorLayout {
+    /// The layout of `data_len` bytes of sectors, telling the boot sector sizes apart by
+    /// which of them leaves no partial sector over
+    pub(super) fn of(offset: usize, sector_size: usize, data_len: usize) -> Self {
+        let boot_len = BOOT_SECTORS as usize * SHORT_SECTOR_SIZE;
+        let short_boot_sectors = sector_size > SHORT_SECTOR_SIZE
+            && data_len >= boot_len
+            && (data_len - boot_len).is_multiple_of(sector_size);
+        let sector_count = if short_boot_sectors {
+            BOOT_SECTORS + ((data_len - boot_len) / sector_size) as u32
+        } else {
+            (data_len / sector_size) as u32
+        };
+        Self { offset, sector_size, sector_count, short_boot_sectors }
+    }
+
+    /// Bytes taken by `sector_count` sectors with short boot sectors
+    pub(super) fn data_len(sector_size: usize, sector_count: u32) -> usize {
+        if sector_size > SHORT_SECTOR_SIZE && sector_count >= BOOT_SECTORS {
+            BOOT_SECTORS as usize * SHORT_SECTOR_SIZE + (sector_count - BOOT_SECTORS) as usize * sector_size
+        } else {
+            sector_count as usize * sector_size
+        }
+    }
+
+    pub(super) fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    pub(super) fn sector_count(&self) -> u32 {
+        self.sector_count
+    }
+
+    /// 128 bytes for the boot sectors, the sector size otherwise
+    pub(super) fn sector_len(&self, sector: u32) -> usize {
+        if sector <= BOOT_SECTORS { SHORT_SECTOR_SIZE.min(self.sector_size) } else { self.sector_size }
+    }
+
+    /// Byte range of a sector in the file
+    pub(super) fn range(&self, sector: u32) -> DeviceResult<std::ops::Range<usize>> {
+        if sector == 0 || sector > self.sector_count {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let index = (sector - 1) as usize;
+        let position = if self.short_boot_sectors && sector > BOOT_SECTORS {
+            BOOT_SECTORS as usize * SHORT_SECTOR_SIZE + (index - BOOT_SECTORS as usize) * self.sector_size
+        } else if self.short_boot_sectors {
+            index * SHORT_SECTOR_SIZE
+        } else {
+            index * self.sector_size
+        };
+        let start = self.offset + position;
+        Ok(start..start + self.sector_len(sector))
+    }
+
+    /// Overwrite a sector in `bytes` from the start of `data`
+    pub(super) fn write(&self, bytes: &mut [u8], sector: u32, data: &[u8]) -> DeviceResult<usize> {
+        let range = self.range(sector)?;
+        let len = range.len();
+        if data.len() < len {
+            return Err(DeviceError::InvalidOperation);
+        }
+        bytes[range].copy_from_slice(&data[..len]);
+        Ok(len)
+    }
+}
+
+/// An ATR disk image: a 16 byte header and the sectors, numbered from 1
+///
+/// The header gives the sector size and the image size in 16 byte paragraphs. The
+/// whole file, header included, is kept so it can be written back as it was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtrImage {
+    bytes: Vec<u8>,
+    layout: SectorLayout,
+}
+
+impl AtrImage {
+    /// Check an ATR file's header and work out its layout
+    pub fn parse(bytes: Vec<u8>) -> DeviceResult<Self> {
+        if bytes.len() < ATR_HEADER_LEN || bytes[..2] != ATR_MAGIC {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let sector_size = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+        if sector_size == 0 {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let paragraphs = u16::from_le_bytes([bytes[2], bytes[3]]) as usize | (bytes[6] as usize) << 16;
+        // Trust the data that's there over a header that claims more
+        let data_len = (paragraphs * 16).min(bytes.len() - ATR_HEADER_LEN);
+        let layout = SectorLayout::of(ATR_HEADER_LEN, sector_size, data_len);
+        Ok(Self { bytes, layout })
+    }
+
+    /// A blank image with `sector_count` sectors of `sector_size` bytes
+    pub fn new(sector_size: usize, sector_count: u32) -> Self {
+        let data_len = SectorLayout::data_len(sector_size, sector_count);
+        let paragraphs = (data_len / 16) as u32;
+        let mut bytes = vec![0u8; ATR_HEADER_LEN + data_len];
+        bytes[..2].copy_from_slice(&ATR_MAGIC);
+        bytes[2..4].copy_from_slice(&(paragraphs as u16).to_le_bytes());
+        bytes[4..6].copy_from_slice(&(sector_size as u16).to_le_bytes());
+        bytes[6] = (paragraphs >> 16) as u8;
+        let layout = SectorLayout::of(ATR_HEADER_LEN, sector_size, data_len);
+        Self { bytes, layout }
+    }
+}
+
+impl DiskImage for AtrImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Atr
+    }
+
+    fn sector_size(&self) -> usize {
+        self.layout.sector_size()
+    }
+
+    fn sector_count(&self) -> u32 {
+        self.layout.sector_count()
+    }
+
+    fn sector_len(&self, sector: u32) -> usize {
+        self.layout.sector_len(sector)
+    }
+
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]> {
+        Ok(&self.bytes[self.layout.range(sector)?])
+    }
+
+    fn write_sector(&mut self, sector: u32, data: &[u8]) -> DeviceResult<usize> {
+        self.layout.write(&mut self.bytes, sector, data)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        self.bytes[ATR_HEADER_LEN..].fill(0);
+        Ok(())
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_densities_and_layout() -> DeviceResult<()> {
+        let single = AtrImage::new(128, 720);
+        assert_eq!(single.as_bytes().len(), ATR_HEADER_LEN + 92160);
+        assert_eq!(single.density(), Density::Single);
+        assert_eq!(AtrImage::new(128, 1040).density(), Density::Enhanced);
+
+        // Double density keeps the boot sectors short: 3 * 128 + 717 * 256 bytes
+        let mut double = AtrImage::new(256, 720);
+        assert_eq!(double.as_bytes().len(), ATR_HEADER_LEN + 183936);
+        assert_eq!(&double.as_bytes()[..7], &[0x96, 0x02, 0xE8, 0x2C, 0x00, 0x01, 0x00]);
+        assert_eq!(double.density(), Density::Double);
+        assert_eq!(double.sector_len(3), 128);
+        assert_eq!(double.write_sector(3, &[3; 256])?, 128);
+        assert_eq!(double.write_sector(4, &[4; 256])?, 256);
+        assert_eq!(double.as_bytes()[ATR_HEADER_LEN + 383], 3);
+        assert_eq!(double.as_bytes()[ATR_HEADER_LEN + 384], 4);
+        assert_eq!(double.write_sector(5, &[5; 128]), Err(DeviceError::InvalidOperation));
+        assert_eq!(double.read_sector(0).err(), Some(DeviceError::InvalidOperation));
+        assert_eq!(double.read_sector(721).err(), Some(DeviceError::InvalidOperation));
+
+        // Parsing the file gives back the same image
+        let mut reparsed = AtrImage::parse(double.as_bytes().to_vec())?;
+        assert_eq!(reparsed, double);
+        assert_eq!(reparsed.read_sector(4)?, &[4; 256]);
+        double.format()?;
+        assert_eq!(double.read_sector(4)?, &[0; 256]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_size_boot_sectors() -> DeviceResult<()> {
+        let mut bytes = AtrImage::new(256, 4).as_bytes()[..ATR_HEADER_LEN].to_vec();
+        bytes[2..4].copy_from_slice(&64u16.to_le_bytes());
+        bytes.extend((1..=4u8).flat_map(|sector| [sector; 256]));
+        let mut image = AtrImage::parse(bytes)?;
+        assert_eq!(image.sector_count(), 4);
+        assert_eq!(image.read_sector(2)?, &[2; 128]);
+        assert_eq!(image.read_sector(4)?, &[4; 256]);
+
+        assert_eq!(AtrImage::parse(vec![0; 400]).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn test_percom_block() {
+        assert_eq!(percom_block(128, 720), [40, 1, 0, 18, 0, 0, 0, 128, 0xFF, 0, 0, 0]);
+        assert_eq!(percom_block(128, 1040), [40, 1, 0, 26, 0, 4, 0, 128, 0xFF, 0, 0, 0]);
+        assert_eq!(percom_block(256, 720), [40, 1, 0, 18, 0, 4, 1, 0, 0xFF, 0, 0, 0]);
+        assert_eq!(percom_block(256, 65535), [1, 1, 0xFF, 0xFF, 0, 4, 1, 0, 0xFF, 0, 0, 0]);
+    }
+}
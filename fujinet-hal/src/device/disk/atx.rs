@@ -0,0 +1,373 @@
+use std::time::Duration;
+use crate::device::{DeviceError, DeviceResult};
+use super::atr::Density;
+use super::image::{read_u8, read_u16, read_u32, DiskImage, ImageFormat};
+
+/// The first four bytes of an ATX file
+pub const ATX_SIGNATURE: &[u8; 4] = b"AT8X";
+
+const HEADER_LEN: usize = 48;
+const TRACK_COUNT: usize = 40;
+const RECORD_TYPE_TRACK: u16 = 0;
+const CHUNK_SECTOR_DATA: u8 = 0x00;
+const CHUNK_SECTOR_LIST: u8 = 0x01;
+const CHUNK_WEAK_SECTOR: u8 = 0x10;
+
+/// FDC status bits recorded for a sector
+pub const FDC_LOST_DATA: u8 = 0x04;
+pub const FDC_CRC_ERROR: u8 = 0x08;
+pub const FDC_RECORD_NOT_FOUND: u8 = 0x10;
+pub const FDC_DELETED: u8 = 0x20;
+/// Not an FDC bit: marks a sector with an extended header chunk
+const SECTOR_EXTENDED: u8 = 0x40;
+
+/// Sector positions are in 8µs units; a disk turning at 288 RPM comes round in 26042
+pub const ROTATION_UNITS: u32 = 26042;
+/// Time for a sector to pass under the head, in the same units
+const SECTOR_READ_UNITS: u32 = 1042;
+const UNIT: Duration = Duration::from_micros(8);
+
+#[derive(Debug, Clone, PartialEq)]
+struct AtxSector {
+    number: u8,
+    status: u8,
+    position: u16,
+    /// Offset of the sector's data in the file, if it has any
+    data: Option<usize>,
+    /// Bytes from this offset on read back differently every time
+    weak_from: Option<usize>,
+}
+
+/// An ATX disk image, recording a copy-protected disk as the drive saw it
+///
+/// Each track lists its sectors in the order they pass the head, with their angular
+/// position and the status the FDC reported. That can include the same sector number
+/// more than once (phantom sectors), sectors that fail with CRC errors or can't be found
+/// at all, and weak sectors whose tail reads differently every time.
+///
+/// Reads follow a simulated head around the disk: the copy picked for a sector is the
+/// next one to come round, and `read_delay` is how long the drive would have waited for
+/// it. Only the turning of the disk between reads is modelled, not the time in between.
+/// The image is read only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtxImage {
+    bytes: Vec<u8>,
+    density: Density,
+    tracks: Vec<Vec<AtxSector>>,
+    head_position: u32,
+    buffer: Vec<u8>,
+    last_status: u8,
+    last_delay: Duration,
+    noise: u32,
+}
+
+/// `base + offset`, if that falls at or before `end`
+fn offset_within(base: usize, offset: usize, end: usize) -> DeviceResult<usize> {
+    base.checked_add(offset).filter(|&at| at <= end).ok_or(DeviceError::InvalidOperation)
+}
+
+impl AtxImage {
+    pub fn parse(bytes: Vec<u8>) -> DeviceResult<Self> {
+        if bytes.len() < HEADER_LEN || !bytes.starts_with(ATX_SIGNATURE) {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let density = match bytes[18] {
+            0 => Density::Single,
+            1 => Density::Enhanced,
+            2 => Density::Double,
+            _ => return Err(DeviceError::NotSupported),
+        };
+        let sector_size = if density == Density::Double { 256 } else { 128 };
+
+        let mut tracks = vec![Vec::new(); TRACK_COUNT];
+        let mut record = read_u32(&bytes, 28)? as usize;
+        while record + 9 <= bytes.len() {
+            let record_len = read_u32(&bytes, record)? as usize;
+            if record_len == 0 {
+                break;
+            }
+            // A record running past the end of the file is a truncated image
+            let record_end = offset_within(record, record_len, bytes.len())?;
+            if read_u16(&bytes, record + 4)? == RECORD_TYPE_TRACK {
+                let track = read_u8(&bytes, record + 8)? as usize;
+                let sectors = Self::parse_track(&bytes, record, record_end, sector_size)?;
+                if let Some(slot) = tracks.get_mut(track) {
+                    *slot = sectors;
+                }
+            }
+            record += record_len;
+        }
+
+        Ok(Self {
+            bytes,
+            density,
+            tracks,
+            head_position: 0,
+            buffer: Vec::with_capacity(sector_size),
+            last_status: 0,
+            last_delay: Duration::ZERO,
+            noise: 0x2545_F491,
+        })
+    }
+
+    /// The sectors of one track record, from its chunks
+    fn parse_track(bytes: &[u8], record: usize, record_end: usize, sector_size: usize) -> DeviceResult<Vec<AtxSector>> {
+        let mut sectors = Vec::new();
+        let mut chunk = offset_within(record, read_u32(bytes, record + 20)? as usize, record_end)?;
+        while chunk + 8 <= record_end {
+            let chunk_len = read_u32(bytes, chunk)? as usize;
+            if chunk_len == 0 {
+                break;
+            }
+            let chunk_end = offset_within(chunk, chunk_len, record_end)?;
+            let index = read_u8(bytes, chunk + 5)? as usize;
+            let header_data = read_u16(bytes, chunk + 6)? as usize;
+            match read_u8(bytes, chunk + 4)? {
+                CHUNK_SECTOR_LIST => {
+                    let count = read_u16(bytes, record + 10)? as usize;
+                    if count > (chunk_end - chunk - 8) / 8 {
+                        return Err(DeviceError::InvalidOperation);
+                    }
+                    for entry in (0..count).map(|i| chunk + 8 + i * 8) {
+                        let status = read_u8(bytes, entry + 1)?;
+                        let data = record.checked_add(read_u32(bytes, entry + 4)? as usize);
+                        let data = data.filter(|&data| {
+                            status & FDC_RECORD_NOT_FOUND == 0 && offset_within(data, sector_size, record_end).is_ok()
+                        });
+                        sectors.push(AtxSector {
+                            number: read_u8(bytes, entry)?,
+                            status,
+                            position: read_u16(bytes, entry + 2)?,
+                            data,
+                            weak_from: None,
+                        });
+                    }
+                }
+                CHUNK_WEAK_SECTOR => {
+                    if let Some(sector) = sectors.get_mut(index) {
+                        sector.weak_from = Some(header_data.min(sector_size));
+                    }
+                }
+                // Sector data is found through the sector list
+                CHUNK_SECTOR_DATA => {}
+                // Extended headers only describe long sectors, which read back at their normal size
+                _ => {}
+            }
+            chunk = chunk_end;
+        }
+        Ok(sectors)
+    }
+
+    fn sectors_per_track(&self) -> u32 {
+        if self.density == Density::Enhanced { 26 } else { 18 }
+    }
+}
+
+/// The next pseudo-random byte for a weak sector, from xorshift state `noise`
+fn next_noise(noise: &mut u32) -> u8 {
+    *noise ^= *noise << 13;
+    *noise ^= *noise >> 17;
+    *noise ^= *noise << 5;
+    *noise as u8
+}
+
+impl DiskImage for AtxImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Atx
+    }
+
+    fn sector_size(&self) -> usize {
+        if self.density == Density::Double { 256 } else { 128 }
+    }
+
+    fn sector_count(&self) -> u32 {
+        TRACK_COUNT as u32 * self.sectors_per_track()
+    }
+
+    fn density(&self) -> Density {
+        self.density
+    }
+
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]> {
+        if sector == 0 || sector > self.sector_count() {
+            self.last_delay = Duration::ZERO;
+            return Err(DeviceError::InvalidOperation);
+        }
+        let track = ((sector - 1) / self.sectors_per_track()) as usize;
+        let number = ((sector - 1) % self.sectors_per_track() + 1) as u8;
+
+        // Whichever copy of the sector comes round first
+        let head = self.head_position;
+        let found = self.tracks[track]
+            .iter()
+            .filter(|candidate| candidate.number == number)
+            .min_by_key(|candidate| (candidate.position as u32 + ROTATION_UNITS - head) % ROTATION_UNITS)
+            .cloned();
+        let Some(found) = found else {
+            // The drive gives up after a whole turn without seeing the sector
+            self.last_status = FDC_RECORD_NOT_FOUND;
+            self.last_delay = UNIT * ROTATION_UNITS;
+            return Err(DeviceError::IoError(format!("sector {} not found", sector)));
+        };
+
+        let wait = (found.position as u32 + ROTATION_UNITS - head) % ROTATION_UNITS;
+        self.last_delay = UNIT * (wait + SECTOR_READ_UNITS);
+        self.head_position = (found.position as u32 + SECTOR_READ_UNITS) % ROTATION_UNITS;
+        self.last_status = found.status & !SECTOR_EXTENDED;
+        let Some(data) = found.data else {
+            return Err(DeviceError::IoError(format!("sector {} has no data", sector)));
+        };
+
+        let sector_size = self.sector_size();
+        let data = self.bytes.get(data..data + sector_size).ok_or(DeviceError::InvalidOperation)?;
+        self.buffer.clear();
+        self.buffer.extend_from_slice(data);
+        if let Some(weak_from) = found.weak_from {
+            for byte in &mut self.buffer[weak_from..] {
+                *byte = next_noise(&mut self.noise);
+            }
+        }
+        Ok(&self.buffer)
+    }
+
+    fn write_sector(&mut self, _sector: u32, _data: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::InvalidOperation)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        Err(DeviceError::InvalidOperation)
+    }
+
+    fn is_writable(&self) -> bool {
+        false
+    }
+
+    fn controller_status(&self) -> u8 {
+        !self.last_status
+    }
+
+    fn read_delay(&self) -> Duration {
+        self.last_delay
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An enhanced density ATX with one recorded track, holding
+    /// (sector number, status, position, data byte) entries
+    fn atx_image(sectors: &[(u8, u8, u16, u8)], weak: Option<(u8, u16)>) -> Vec<u8> {
+        let list_len = 8 + sectors.len() * 8;
+        let data_start = 32 + list_len + 8;
+        let mut track = vec![0u8; 32];
+        track[8] = 0;
+        track[10..12].copy_from_slice(&(sectors.len() as u16).to_le_bytes());
+        track[20..24].copy_from_slice(&32u32.to_le_bytes());
+
+        track.extend_from_slice(&(list_len as u32).to_le_bytes());
+        track.extend_from_slice(&[CHUNK_SECTOR_LIST, 0, 0, 0]);
+        for (i, &(number, status, position, _)) in sectors.iter().enumerate() {
+            track.extend_from_slice(&[number, status]);
+            track.extend_from_slice(&position.to_le_bytes());
+            track.extend_from_slice(&((data_start + i * 128) as u32).to_le_bytes());
+        }
+        track.extend_from_slice(&((8 + sectors.len() * 128) as u32).to_le_bytes());
+        track.extend_from_slice(&[CHUNK_SECTOR_DATA, 0, 0, 0]);
+        for &(_, _, _, fill) in sectors {
+            track.extend_from_slice(&[fill; 128]);
+        }
+        if let Some((index, offset)) = weak {
+            track.extend_from_slice(&8u32.to_le_bytes());
+            track.extend_from_slice(&[CHUNK_WEAK_SECTOR, index]);
+            track.extend_from_slice(&offset.to_le_bytes());
+        }
+        track.extend_from_slice(&[0; 8]);
+        let track_len = track.len() as u32;
+        track[..4].copy_from_slice(&track_len.to_le_bytes());
+
+        let mut image = vec![0u8; HEADER_LEN];
+        image[..4].copy_from_slice(ATX_SIGNATURE);
+        image[4] = 1;
+        image[18] = 1;
+        image[28..32].copy_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+        image.extend_from_slice(&track);
+        let end = image.len() as u32;
+        image[32..36].copy_from_slice(&end.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn test_sector_status_and_timing() -> DeviceResult<()> {
+        let mut image = AtxImage::parse(atx_image(
+            &[(1, 0, 100, 0x11), (2, FDC_CRC_ERROR, 5000, 0x22), (3, FDC_RECORD_NOT_FOUND, 9000, 0x33)],
+            None,
+        ))?;
+        assert_eq!(image.density(), Density::Enhanced);
+        assert_eq!((image.sector_size(), image.sector_count()), (128, 1040));
+        assert!(!image.is_writable());
+
+        assert_eq!(image.read_sector(1)?, &[0x11; 128]);
+        assert_eq!(image.controller_status(), 0xFF);
+        assert_eq!(image.read_delay(), UNIT * (100 + SECTOR_READ_UNITS));
+
+        // A CRC error still reads its data, but the controller reports it
+        assert_eq!(image.read_sector(2)?, &[0x22; 128]);
+        assert_eq!(image.controller_status(), !FDC_CRC_ERROR);
+        assert_eq!(image.read_delay(), UNIT * (5000 - 100));
+
+        assert!(matches!(image.read_sector(3), Err(DeviceError::IoError(_))));
+        assert_eq!(image.controller_status(), !FDC_RECORD_NOT_FOUND);
+        assert!(matches!(image.read_sector(27), Err(DeviceError::IoError(_))));
+        assert_eq!(image.read_delay(), UNIT * ROTATION_UNITS);
+        assert_eq!(image.write_sector(1, &[0; 128]), Err(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn test_phantom_and_weak_sectors() -> DeviceResult<()> {
+        let mut image = AtxImage::parse(atx_image(
+            &[(5, 0, 1000, 0xAA), (5, 0, 14000, 0xBB), (6, 0, 20000, 0x66)],
+            Some((2, 64)),
+        ))?;
+
+        // The two copies of sector 5 take turns as the disk goes round
+        assert_eq!(image.read_sector(5)?[0], 0xAA);
+        assert_eq!(image.read_sector(5)?[0], 0xBB);
+        assert_eq!(image.read_sector(5)?[0], 0xAA);
+
+        let first = image.read_sector(6)?.to_vec();
+        let second = image.read_sector(6)?.to_vec();
+        assert_eq!(&first[..64], &[0x66; 64]);
+        assert_eq!(first[..64], second[..64]);
+        assert_ne!(first[64..], second[64..]);
+
+        assert_eq!(AtxImage::parse(b"AT8X".to_vec()).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_track_record() {
+        let image = atx_image(&[(1, 0, 100, 0x11), (2, 0, 5000, 0x22)], None);
+        for len in [HEADER_LEN + 9, HEADER_LEN + 40, image.len() - 100, image.len() - 1] {
+            assert_eq!(AtxImage::parse(image[..len].to_vec()).err(), Some(DeviceError::InvalidOperation), "cut at {}", len);
+        }
+
+        // Too short for even a record header is a disk with nothing recorded
+        let mut empty = AtxImage::parse(image[..HEADER_LEN + 8].to_vec()).unwrap();
+        assert!(matches!(empty.read_sector(1), Err(DeviceError::IoError(_))));
+
+        // A sector list claiming more entries than its chunk holds
+        let mut image = image;
+        image[HEADER_LEN + 10] = 40;
+        assert_eq!(AtxImage::parse(image).err(), Some(DeviceError::InvalidOperation));
+    }
+}
@@ -0,0 +1,272 @@
+use std::path::Path;
+use std::time::Duration;
+use crate::device::{DeviceError, DeviceResult};
+use super::atr::{percom_block, AtrImage, Density, ATR_MAGIC};
+use super::apple::{AppleImage, TWO_MG_MAGIC};
+use super::atx::{AtxImage, ATX_SIGNATURE};
+use super::xex::{XexImage, XEX_MAGIC};
+use super::woz::{WozImage, WOZ1_SIGNATURE, WOZ2_SIGNATURE};
+use super::xfd::XfdImage;
+
+/// The disk image file formats the disk device can mount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Headerless sectors counted from zero
+    Raw,
+    Atr,
+    Atx,
+    /// An Atari executable, served on a virtual boot disk
+    Xex,
+    Xfd,
+    /// Apple II ProDOS-order sectors
+    Po,
+    /// Apple II DOS 3.3-order sectors
+    Do,
+    /// An Apple II image in a 2MG container
+    TwoMg,
+    /// An Apple II disk recorded bit by bit
+    Woz,
+}
+
+impl ImageFormat {
+    /// Work out a file's format from its extension, falling back to its first bytes
+    /// when the extension is missing or unknown
+    ///
+    /// The extension comes first as a headerless image can start with anything,
+    /// including another format's magic.
+    pub fn detect(bytes: &[u8], name: &str) -> Option<Self> {
+        Self::from_extension(name).or_else(|| Self::from_magic(bytes))
+    }
+
+    fn from_extension(name: &str) -> Option<Self> {
+        let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "atr" => Some(ImageFormat::Atr),
+            "atx" => Some(ImageFormat::Atx),
+            "xfd" => Some(ImageFormat::Xfd),
+            "xex" | "com" | "exe" => Some(ImageFormat::Xex),
+            "img" | "raw" => Some(ImageFormat::Raw),
+            "po" => Some(ImageFormat::Po),
+            "do" | "dsk" => Some(ImageFormat::Do),
+            "2mg" => Some(ImageFormat::TwoMg),
+            "woz" => Some(ImageFormat::Woz),
+            _ => None,
+        }
+    }
+
+    fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&ATR_MAGIC) {
+            return Some(ImageFormat::Atr);
+        }
+        if bytes.starts_with(ATX_SIGNATURE) {
+            return Some(ImageFormat::Atx);
+        }
+        if bytes.starts_with(&XEX_MAGIC) {
+            return Some(ImageFormat::Xex);
+        }
+        if bytes.starts_with(TWO_MG_MAGIC) {
+            return Some(ImageFormat::TwoMg);
+        }
+        if bytes.starts_with(WOZ1_SIGNATURE) || bytes.starts_with(WOZ2_SIGNATURE) {
+            return Some(ImageFormat::Woz);
+        }
+        None
+    }
+}
+
+/// A mounted disk image: numbered sectors of fixed size, plus whatever else the format
+/// records about how a real drive would behave
+///
+/// Sector numbers count from `first_sector`. Images are held in memory; `as_bytes`
+/// gives the file as it stands so changes can be written back.
+pub trait DiskImage: Send + Sync {
+    fn image_format(&self) -> ImageFormat;
+
+    fn sector_size(&self) -> usize;
+
+    fn sector_count(&self) -> u32;
+
+    /// Number of the first sector: 1 on Atari disks
+    fn first_sector(&self) -> u32 {
+        1
+    }
+
+    /// Bytes in one sector, which can be less than the sector size for boot sectors
+    fn sector_len(&self, _sector: u32) -> usize {
+        self.sector_size()
+    }
+
+    fn density(&self) -> Density {
+        Density::of(self.sector_size(), self.sector_count())
+    }
+
+    /// The 12 byte PERCOM block describing the image's geometry
+    fn percom(&self) -> [u8; 12] {
+        percom_block(self.sector_size(), self.sector_count())
+    }
+
+    /// Read a sector; some formats change state as they are read, like the disk turning
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]>;
+
+    /// Overwrite a sector from the start of `data`, returning the sector's length
+    fn write_sector(&mut self, sector: u32, data: &[u8]) -> DeviceResult<usize>;
+
+    /// Clear every sector, keeping the geometry
+    fn format(&mut self) -> DeviceResult<()>;
+
+    /// Whether the format can be written at all, whatever the mount mode
+    fn is_writable(&self) -> bool {
+        true
+    }
+
+    /// Controller status after the last read, active low: 0xFF when nothing went wrong
+    fn controller_status(&self) -> u8 {
+        0xFF
+    }
+
+    /// How long a real drive would have taken over the last read
+    fn read_delay(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// The image file as it stands
+    fn as_bytes(&self) -> &[u8];
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8>;
+}
+
+// Header fields, little-endian, failing when the file is too short to hold them
+
+pub(super) fn read_u8(bytes: &[u8], offset: usize) -> DeviceResult<u8> {
+    bytes.get(offset).copied().ok_or(DeviceError::InvalidOperation)
+}
+
+pub(super) fn read_u16(bytes: &[u8], offset: usize) -> DeviceResult<u16> {
+    let field = bytes.get(offset..offset + 2).ok_or(DeviceError::InvalidOperation)?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+pub(super) fn read_u32(bytes: &[u8], offset: usize) -> DeviceResult<u32> {
+    let field = bytes.get(offset..offset + 4).ok_or(DeviceError::InvalidOperation)?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+/// Open an image file, detecting its format from `name`'s extension or its header
+pub fn open_image(bytes: Vec<u8>, name: &str) -> DeviceResult<Box<dyn DiskImage>> {
+    match ImageFormat::detect(&bytes, name).ok_or(DeviceError::NotSupported)? {
+        ImageFormat::Raw => Ok(Box::new(RawImage::new(bytes, super::DEFAULT_SECTOR_SIZE))),
+        ImageFormat::Atr => Ok(Box::new(AtrImage::parse(bytes)?)),
+        ImageFormat::Atx => Ok(Box::new(AtxImage::parse(bytes)?)),
+        ImageFormat::Xex => Ok(Box::new(XexImage::new(bytes)?)),
+        ImageFormat::Xfd => Ok(Box::new(XfdImage::parse(bytes)?)),
+        ImageFormat::Po => Ok(Box::new(AppleImage::prodos_order(bytes)?)),
+        ImageFormat::Do => Ok(Box::new(AppleImage::dos_order(bytes)?)),
+        ImageFormat::TwoMg => Ok(Box::new(AppleImage::parse_2mg(bytes)?)),
+        ImageFormat::Woz => Ok(Box::new(WozImage::parse(bytes)?)),
+    }
+}
+
+/// Headerless sectors of one size, counted from zero
+///
+/// Reading or writing with a buffer shorter than a sector is fine; the image just has to
+/// hold as much as the buffer asks for.
+pub struct RawImage {
+    bytes: Vec<u8>,
+    sector_size: usize,
+}
+
+impl RawImage {
+    pub fn new(bytes: Vec<u8>, sector_size: usize) -> Self {
+        Self { bytes, sector_size }
+    }
+
+    fn sector_range(&self, sector: u32) -> DeviceResult<std::ops::Range<usize>> {
+        let start = sector as usize * self.sector_size;
+        let end = (start + self.sector_size).min(self.bytes.len());
+        if start >= end {
+            return Err(DeviceError::InvalidOperation);
+        }
+        Ok(start..end)
+    }
+}
+
+impl DiskImage for RawImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Raw
+    }
+
+    fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    fn sector_count(&self) -> u32 {
+        (self.bytes.len() / self.sector_size) as u32
+    }
+
+    fn first_sector(&self) -> u32 {
+        0
+    }
+
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]> {
+        let range = self.sector_range(sector)?;
+        Ok(&self.bytes[range])
+    }
+
+    fn write_sector(&mut self, sector: u32, data: &[u8]) -> DeviceResult<usize> {
+        let range = self.sector_range(sector)?;
+        let len = data.len().min(self.sector_size);
+        if len > range.len() {
+            return Err(DeviceError::InvalidOperation);
+        }
+        self.bytes[range.start..range.start + len].copy_from_slice(&data[..len]);
+        Ok(len)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        self.bytes.fill(0);
+        Ok(())
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        let atr = AtrImage::new(128, 720);
+        assert_eq!(ImageFormat::detect(atr.as_bytes(), "dos.atr"), Some(ImageFormat::Atr));
+        assert_eq!(ImageFormat::detect(b"AT8X\x01\x00", "game.atx"), Some(ImageFormat::Atx));
+        assert_eq!(ImageFormat::detect(&[0; 92160], "/dos/DOS25.XFD"), Some(ImageFormat::Xfd));
+        assert_eq!(ImageFormat::detect(&[0; 512], "game.com"), Some(ImageFormat::Xex));
+        assert_eq!(ImageFormat::detect(b"2IMGFUJI", "disk.2mg"), Some(ImageFormat::TwoMg));
+        assert_eq!(ImageFormat::detect(b"WOZ2\xFF\x0A\x0D\x0A", "game.woz"), Some(ImageFormat::Woz));
+
+        // The extension wins over what the first bytes look like
+        assert_eq!(ImageFormat::detect(&[0xFF, 0xFF, 0x00, 0x20], "boot.xfd"), Some(ImageFormat::Xfd));
+        assert_eq!(ImageFormat::detect(&[0xFF, 0xFF, 0x00, 0x20], "blank.img"), Some(ImageFormat::Raw));
+
+        // Headers only decide for names that don't say
+        assert_eq!(ImageFormat::detect(atr.as_bytes(), "anything"), Some(ImageFormat::Atr));
+        assert_eq!(ImageFormat::detect(b"AT8X\x01\x00", "game.bin"), Some(ImageFormat::Atx));
+        assert_eq!(ImageFormat::detect(&[0xFF, 0xFF, 0x00, 0x20], "GAME"), Some(ImageFormat::Xex));
+        assert_eq!(ImageFormat::detect(b"2IMGFUJI", "disk"), Some(ImageFormat::TwoMg));
+        assert_eq!(ImageFormat::detect(&[0; 512], "ProDOS.PO"), Some(ImageFormat::Po));
+        assert_eq!(ImageFormat::detect(&[0; 512], "dos33.dsk"), Some(ImageFormat::Do));
+        assert_eq!(ImageFormat::detect(&[0; 512], "notes.txt"), None);
+        assert_eq!(ImageFormat::detect(&[0; 512], "noextension"), None);
+
+        assert_eq!(open_image(atr.as_bytes().to_vec(), "dos.atr").unwrap().image_format(), ImageFormat::Atr);
+        assert_eq!(open_image(vec![0; 1024], "blank.img").unwrap().sector_count(), 8);
+        assert_eq!(open_image(vec![0; 143360], "dos33.do").unwrap().sector_size(), 512);
+        assert_eq!(open_image(vec![0; 1024], "notes.txt").err(), Some(DeviceError::NotSupported));
+    }
+}
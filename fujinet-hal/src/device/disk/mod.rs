@@ -0,0 +1,550 @@
+use async_trait::async_trait;
+use std::any::Any;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use super::{Device, DeviceError, DeviceResult, DeviceStatus};
+use super::network::NetworkDevice;
+
+pub mod image;
+pub mod apple;
+pub mod atr;
+pub mod atx;
+pub mod woz;
+pub mod xex;
+pub mod xfd;
+
+pub use image::{open_image, DiskImage, ImageFormat, RawImage};
+pub use apple::{AppleImage, SectorOrder};
+pub use atr::{AtrImage, Density};
+pub use atx::AtxImage;
+pub use woz::WozImage;
+pub use xex::XexImage;
+pub use xfd::XfdImage;
+
+/// Sector size used unless a disk is created with another
+pub const DEFAULT_SECTOR_SIZE: usize = 128;
+
+/// Drive status bits in the first byte of a status block
+pub const DRIVE_STATUS_WRITE_PROTECTED: u8 = 0x08;
+pub const DRIVE_STATUS_DOUBLE_DENSITY: u8 = 0x20;
+pub const DRIVE_STATUS_ENHANCED_DENSITY: u8 = 0x80;
+/// Format timeout reported in a status block, in seconds
+pub const DRIVE_FORMAT_TIMEOUT: u8 = 0xE0;
+
+/// Block device over an in-memory disk image
+///
+/// Blocks are the mounted image's sectors: counted from zero on a raw image, from one, as
+/// the Atari numbers them, on the Atari formats, and as 512 byte ProDOS blocks counted
+/// from zero on the Apple II formats. Reads and writes need a mounted
+/// image and must stay inside it; a block buffer longer than a sector is cut to one
+/// sector. Reads take as long as the image's `read_delay` says a real drive would.
+///
+/// An image mounted writable from a file keeps writes and formats in memory until `flush`,
+/// `close` or `eject` saves them back to it.
+pub struct DiskDevice {
+    sector_size: usize,
+    image: Option<Box<dyn DiskImage>>,
+    read_only: bool,
+    open: bool,
+    // The file a writable image was mounted from, and whether it is behind the image
+    backing: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl DiskDevice {
+    pub fn new() -> Self {
+        Self::with_sector_size(DEFAULT_SECTOR_SIZE)
+    }
+
+    /// A drive whose raw images have `sector_size` byte sectors
+    pub fn with_sector_size(sector_size: usize) -> Self {
+        Self {
+            sector_size,
+            image: None,
+            read_only: false,
+            open: false,
+            backing: None,
+            dirty: false,
+        }
+    }
+
+    /// Insert an image, replacing any mounted one
+    pub fn mount_image(&mut self, image: Box<dyn DiskImage>, read_only: bool) {
+        self.unmount();
+        self.image = Some(image);
+        self.read_only = read_only;
+    }
+
+    /// Insert a raw image
+    pub fn mount(&mut self, image: Vec<u8>, read_only: bool) {
+        self.mount_image(Box::new(RawImage::new(image, self.sector_size)), read_only);
+    }
+
+    /// Insert an ATR image
+    pub fn mount_atr(&mut self, image: Vec<u8>, read_only: bool) -> DeviceResult<()> {
+        self.mount_image(Box::new(AtrImage::parse(image)?), read_only);
+        Ok(())
+    }
+
+    /// Insert an image file, in whatever format `name`'s extension or its header says
+    pub fn mount_bytes(&mut self, image: Vec<u8>, name: &str, read_only: bool) -> DeviceResult<()> {
+        let mut image = open_image(image, name)?;
+        if image.image_format() == ImageFormat::Raw {
+            image = Box::new(RawImage::new(image.into_bytes(), self.sector_size));
+        }
+        self.mount_image(image, read_only);
+        Ok(())
+    }
+
+    /// Insert an image from the local filesystem, saving changes back to it unless read only
+    pub async fn mount_file(&mut self, path: &Path, read_only: bool) -> DeviceResult<()> {
+        // Save the outgoing image here rather than leave it to `unmount`
+        self.flush().await?;
+        let image = tokio::fs::read(path).await?;
+        self.mount_bytes(image, &path.to_string_lossy(), read_only)?;
+        if !self.is_read_only() {
+            self.backing = Some(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Save the image to the file it was mounted from, if it has changed since
+    pub async fn flush(&mut self) -> DeviceResult<()> {
+        if let (true, Some(path), Some(image)) = (self.dirty, &self.backing, &self.image) {
+            tokio::fs::write(path, image.as_bytes()).await?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Insert an image read to the end from an opened network device; `name` is the
+    /// image's file name, for telling formats apart by extension
+    pub async fn mount_from(&mut self, source: &mut dyn NetworkDevice, name: &str, read_only: bool) -> DeviceResult<()> {
+        let mut image = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = source.read_bytes(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            image.extend_from_slice(&buf[..len]);
+        }
+        self.mount_bytes(image, name, read_only)
+    }
+
+    /// Save unsaved changes to the file the image was mounted from, then eject it
+    pub async fn eject(&mut self) -> DeviceResult<Option<Vec<u8>>> {
+        self.flush().await?;
+        Ok(self.unmount())
+    }
+
+    /// Eject the image, handing back its current contents as a file
+    ///
+    /// Unsaved changes still go back to the file it was mounted from, but on a runtime the
+    /// save runs in the background and its failure is only logged; `eject` waits for it.
+    pub fn unmount(&mut self) -> Option<Vec<u8>> {
+        self.read_only = false;
+        let backing = self.backing.take();
+        let image = self.image.take()?.into_bytes();
+        if let (true, Some(path)) = (std::mem::take(&mut self.dirty), backing) {
+            let contents = image.clone();
+            let save = move || {
+                if let Err(e) = std::fs::write(&path, contents) {
+                    log::warn!("failed to save {}: {}", path.display(), e);
+                }
+            };
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => drop(runtime.spawn_blocking(save)),
+                Err(_) => save(),
+            }
+        }
+        Some(image)
+    }
+
+    pub fn image(&self) -> Option<&[u8]> {
+        Some(self.image.as_ref()?.as_bytes())
+    }
+
+    pub fn disk_image(&self) -> Option<&dyn DiskImage> {
+        self.image.as_deref()
+    }
+
+    pub fn sector_size(&self) -> usize {
+        self.image.as_ref().map_or(self.sector_size, |image| image.sector_size())
+    }
+
+    /// Number of whole sectors in the mounted image
+    pub fn sector_count(&self) -> usize {
+        self.image.as_ref().map_or(0, |image| image.sector_count() as usize)
+    }
+
+    /// Whether writes are refused, by the mount mode or the image format
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.image.as_ref().is_some_and(|image| !image.is_writable())
+    }
+
+    /// How long a real drive would have taken over the last read
+    pub fn read_delay(&self) -> Duration {
+        self.image.as_ref().map_or(Duration::ZERO, |image| image.read_delay())
+    }
+
+    fn mounted(&self) -> DeviceResult<&dyn DiskImage> {
+        self.image.as_deref().ok_or(DeviceError::NotReady)
+    }
+
+    /// The 4 byte drive status block: drive flags, controller status, format timeout, unused
+    pub fn status(&self) -> DeviceResult<[u8; 4]> {
+        let image = self.mounted()?;
+        let mut flags = match image.density() {
+            Density::Enhanced => DRIVE_STATUS_ENHANCED_DENSITY,
+            _ if image.sector_size() > DEFAULT_SECTOR_SIZE => DRIVE_STATUS_DOUBLE_DENSITY,
+            _ => 0,
+        };
+        if self.is_read_only() {
+            flags |= DRIVE_STATUS_WRITE_PROTECTED;
+        }
+        Ok([flags, image.controller_status(), DRIVE_FORMAT_TIMEOUT, 0])
+    }
+
+    /// The 12 byte PERCOM block describing the mounted image's geometry
+    pub fn percom(&self) -> DeviceResult<[u8; 12]> {
+        Ok(self.mounted()?.percom())
+    }
+
+    /// Clear the whole disk, returning the drive's bad sector list: one sector holding
+    /// 0xFFFF, the end marker, as nothing on an image can go bad
+    pub fn format(&mut self) -> DeviceResult<Vec<u8>> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        if self.is_read_only() {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let image = self.image.as_mut().ok_or(DeviceError::NotReady)?;
+        image.format()?;
+        self.dirty = true;
+        let mut bad_sectors = vec![0u8; image.sector_size()];
+        bad_sectors[..2].fill(0xFF);
+        Ok(bad_sectors)
+    }
+}
+
+impl Default for DiskDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Device for DiskDevice {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    async fn open(&mut self) -> DeviceResult<()> {
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        self.open = false;
+        self.flush().await
+    }
+
+    /// Disks are only addressed by sector
+    async fn read_bytes(&mut self, _buf: &mut [u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn write_bytes(&mut self, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn read_block(&mut self, block: u32, buf: &mut [u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        let image = self.image.as_mut().ok_or(DeviceError::NotReady)?;
+        let result = image.read_sector(block).map(|sector| {
+            let len = sector.len().min(buf.len());
+            buf[..len].copy_from_slice(&sector[..len]);
+            len
+        });
+        // Take as long as the drive would have, failed reads included
+        let delay = image.read_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        result
+    }
+
+    async fn write_block(&mut self, block: u32, buf: &[u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        let read_only = self.is_read_only();
+        let image = self.image.as_mut().ok_or(DeviceError::NotReady)?;
+        if block < image.first_sector() || block >= image.first_sector() + image.sector_count() {
+            return Err(DeviceError::InvalidOperation);
+        }
+        if read_only {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let len = image.write_sector(block, buf)?;
+        self.dirty = true;
+        Ok(len)
+    }
+
+    async fn get_status(&self) -> DeviceResult<DeviceStatus> {
+        Ok(if self.open && self.image.is_some() {
+            DeviceStatus::Ready
+        } else {
+            DeviceStatus::Disconnected
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::network::NetworkDeviceImpl;
+    use crate::device::network::protocols::FileProtocol;
+    use atr::ATR_HEADER_LEN;
+
+    #[tokio::test]
+    async fn test_sector_io() -> DeviceResult<()> {
+        let mut disk = DiskDevice::new();
+        let mut sector = [0u8; 128];
+        disk.open().await?;
+        assert_eq!(disk.read_block(0, &mut sector).await, Err(DeviceError::NotReady));
+        assert_eq!(disk.get_status().await?, DeviceStatus::Disconnected);
+
+        disk.mount(vec![0; 128 * 4], false);
+        assert_eq!(disk.sector_count(), 4);
+        assert_eq!(disk.get_status().await?, DeviceStatus::Ready);
+        assert_eq!(disk.write_block(2, &[0xAA; 128]).await?, 128);
+        assert_eq!(disk.read_block(2, &mut sector).await?, 128);
+        assert_eq!(sector, [0xAA; 128]);
+        assert_eq!(disk.read_block(4, &mut sector).await, Err(DeviceError::InvalidOperation));
+        assert_eq!(disk.read_bytes(&mut sector).await, Err(DeviceError::NotSupported));
+
+        let image = disk.unmount().unwrap();
+        assert_eq!(&image[256..384], &[0xAA; 128]);
+        disk.mount(image, true);
+        assert_eq!(disk.write_block(0, &[1; 128]).await, Err(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_atr_sector_io() -> DeviceResult<()> {
+        let mut disk = DiskDevice::new();
+        assert_eq!(disk.status(), Err(DeviceError::NotReady));
+        assert_eq!(disk.mount_atr(vec![0; 128], false), Err(DeviceError::InvalidOperation));
+
+        disk.mount_atr(AtrImage::new(256, 720).as_bytes().to_vec(), false)?;
+        disk.open().await?;
+        assert_eq!((disk.sector_size(), disk.sector_count()), (256, 720));
+        assert_eq!(disk.status()?, [DRIVE_STATUS_DOUBLE_DENSITY, 0xFF, DRIVE_FORMAT_TIMEOUT, 0]);
+        assert_eq!(disk.percom()?[6..8], [1, 0]);
+
+        // ATR blocks are sector numbers, and the boot sectors are short
+        let mut sector = [0u8; 256];
+        assert_eq!(disk.read_block(0, &mut sector).await, Err(DeviceError::InvalidOperation));
+        assert_eq!(disk.write_block(1, &[0x11; 256]).await?, 128);
+        assert_eq!(disk.write_block(720, &[0x22; 256]).await?, 256);
+        assert_eq!(disk.read_block(1, &mut sector).await?, 128);
+        assert_eq!(sector[..128], [0x11; 128]);
+        assert_eq!(disk.read_block(720, &mut sector).await?, 256);
+        assert_eq!(sector, [0x22; 256]);
+
+        let bad_sectors = disk.format()?;
+        assert_eq!((bad_sectors.len(), &bad_sectors[..3]), (256, &[0xFF, 0xFF, 0][..]));
+        assert_eq!(disk.read_block(720, &mut sector).await?, 256);
+        assert_eq!(sector, [0; 256]);
+
+        let image = disk.unmount().unwrap();
+        assert_eq!(image.len(), ATR_HEADER_LEN + 183936);
+        disk.mount_atr(image, true)?;
+        assert_eq!(disk.status()?[0], DRIVE_STATUS_DOUBLE_DENSITY | DRIVE_STATUS_WRITE_PROTECTED);
+        assert_eq!(disk.write_block(4, &[0; 256]).await, Err(DeviceError::InvalidOperation));
+        assert_eq!(disk.format(), Err(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mount_from_file_and_network() -> DeviceResult<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path();
+        let mut atr = AtrImage::new(128, 1040);
+        atr.write_sector(1040, &[0x5A; 128])?;
+        std::fs::write(root.join("enhanced.atr"), atr.as_bytes())?;
+
+        let mut disk = DiskDevice::new();
+        disk.mount_file(&root.join("enhanced.atr"), false).await?;
+        assert_eq!(disk.disk_image().unwrap().density(), Density::Enhanced);
+        assert_eq!(disk.status()?[0], DRIVE_STATUS_ENHANCED_DENSITY);
+
+        let mut source = NetworkDeviceImpl::new("file:///enhanced.atr".to_string(), Box::new(FileProtocol::new(root)));
+        source.open().await?;
+        let mut network_disk = DiskDevice::new();
+        network_disk.mount_from(&mut source, "enhanced.atr", true).await?;
+        network_disk.open().await?;
+        let mut sector = [0u8; 128];
+        network_disk.read_block(1040, &mut sector).await?;
+        assert_eq!(sector, [0x5A; 128]);
+        assert_eq!(network_disk.image(), disk.image());
+
+        assert!(matches!(disk.mount_file(&root.join("missing.atr"), false).await, Err(DeviceError::IoError(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_writes_saved_to_file() -> DeviceResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("work.atr");
+        std::fs::write(&path, AtrImage::new(128, 720).as_bytes())?;
+
+        let mut disk = DiskDevice::new();
+        disk.mount_file(&path, false).await?;
+        disk.open().await?;
+        disk.write_block(4, &[0x5A; 128]).await?;
+
+        // Writes stay in memory until flushed
+        let mut reopened = DiskDevice::new();
+        reopened.mount_file(&path, true).await?;
+        reopened.open().await?;
+        let mut sector = [0u8; 128];
+        reopened.read_block(4, &mut sector).await?;
+        assert_eq!(sector, [0; 128]);
+        disk.flush().await?;
+        reopened.mount_file(&path, true).await?;
+        reopened.read_block(4, &mut sector).await?;
+        assert_eq!(sector, [0x5A; 128]);
+
+        // A format is saved when the disk is ejected
+        disk.format()?;
+        assert!(disk.eject().await?.is_some());
+        reopened.mount_file(&path, true).await?;
+        reopened.read_block(4, &mut sector).await?;
+        assert_eq!(sector, [0; 128]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mount_detects_format() -> DeviceResult<()> {
+        let mut disk = DiskDevice::new();
+        disk.open().await?;
+
+        // An executable boots from a virtual disk that can't be written
+        let xex = vec![0xFF, 0xFF, 0x00, 0x20, 0x00, 0x20, 0x60];
+        disk.mount_bytes(xex.clone(), "hello.xex", false)?;
+        assert_eq!(disk.disk_image().unwrap().image_format(), ImageFormat::Xex);
+        assert_eq!(disk.status()?[0], DRIVE_STATUS_WRITE_PROTECTED);
+        let mut sector = [0u8; 128];
+        assert_eq!(disk.read_block(4, &mut sector).await?, 128);
+        assert_eq!(sector[..7], xex[..]);
+        assert_eq!(disk.write_block(4, &sector).await, Err(DeviceError::InvalidOperation));
+        assert_eq!(disk.format(), Err(DeviceError::InvalidOperation));
+        assert_eq!(disk.unmount(), Some(xex));
+
+        disk.mount_bytes(vec![0; 92160], "DOS.XFD", false)?;
+        assert_eq!((disk.sector_count(), disk.read_block(1, &mut sector).await?), (720, 128));
+        assert_eq!(disk.read_block(0, &mut sector).await, Err(DeviceError::InvalidOperation));
+
+        // Raw images keep the drive's sector size
+        let mut raw_disk = DiskDevice::with_sector_size(256);
+        raw_disk.mount_bytes(vec![0; 1024], "blank.img", false)?;
+        assert_eq!(raw_disk.sector_count(), 4);
+        assert_eq!(disk.mount_bytes(vec![0; 100], "readme.txt", false), Err(DeviceError::NotSupported));
+        Ok(())
+    }
+
+    // One sector that takes READ_DELAY to come round
+    struct SlowImage(Vec<u8>);
+
+    const READ_DELAY: Duration = Duration::from_millis(50);
+
+    impl DiskImage for SlowImage {
+        fn image_format(&self) -> ImageFormat {
+            ImageFormat::Raw
+        }
+
+        fn sector_size(&self) -> usize {
+            self.0.len()
+        }
+
+        fn sector_count(&self) -> u32 {
+            1
+        }
+
+        fn read_sector(&mut self, _sector: u32) -> DeviceResult<&[u8]> {
+            Ok(&self.0)
+        }
+
+        fn write_sector(&mut self, _sector: u32, _data: &[u8]) -> DeviceResult<usize> {
+            Err(DeviceError::InvalidOperation)
+        }
+
+        fn format(&mut self) -> DeviceResult<()> {
+            Err(DeviceError::InvalidOperation)
+        }
+
+        fn read_delay(&self) -> Duration {
+            READ_DELAY
+        }
+
+        fn as_bytes(&self) -> &[u8] {
+            &self.0
+        }
+
+        fn into_bytes(self: Box<Self>) -> Vec<u8> {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_take_read_delay() -> DeviceResult<()> {
+        let mut disk = DiskDevice::new();
+        disk.mount_image(Box::new(SlowImage(vec![0x42; 128])), true);
+        disk.open().await?;
+
+        let start = std::time::Instant::now();
+        let mut sector = [0u8; 128];
+        assert_eq!(disk.read_block(1, &mut sector).await?, 128);
+        assert!(start.elapsed() >= READ_DELAY, "read took {:?}", start.elapsed());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apple_blocks() -> DeviceResult<()> {
+        let mut disk = DiskDevice::new();
+        disk.open().await?;
+        disk.mount_bytes(vec![0; apple::DISK_525_SIZE], "MASTER.DSK", false)?;
+        assert_eq!(disk.disk_image().unwrap().image_format(), ImageFormat::Do);
+        assert_eq!((disk.sector_size(), disk.sector_count()), (512, 280));
+
+        // Block 2 of a DOS-order disk is DOS sectors 11 and 10 of track 0
+        let mut block = [0x11u8; 512];
+        block[256..].fill(0x22);
+        assert_eq!(disk.write_block(2, &block).await?, 512);
+        assert_eq!(disk.write_block(280, &block).await, Err(DeviceError::InvalidOperation));
+        let mut read = [0u8; 512];
+        assert_eq!(disk.read_block(2, &mut read).await?, 512);
+        assert_eq!(read, block);
+        let image = disk.unmount().unwrap();
+        assert_eq!((image[11 * 256], image[10 * 256]), (0x11, 0x22));
+
+        disk.mount_bytes(vec![0; 800 * 1024], "system.po", false)?;
+        assert_eq!(disk.sector_count(), 1600);
+        assert_eq!(disk.read_block(0, &mut read).await?, 512);
+        Ok(())
+    }
+}
@@ -0,0 +1,564 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::apple::{APPLE_SECTOR_SIZE, BLOCK_SIZE, SECTORS_PER_TRACK};
+use super::image::{read_u16, read_u32, DiskImage, ImageFormat};
+
+/// The first four bytes of a WOZ file, for each version
+pub const WOZ1_SIGNATURE: &[u8; 4] = b"WOZ1";
+pub const WOZ2_SIGNATURE: &[u8; 4] = b"WOZ2";
+/// Follows the signature, to catch files mangled by newline conversion
+const HEADER_TAIL: [u8; 4] = [0xFF, 0x0A, 0x0D, 0x0A];
+const HEADER_LEN: usize = 12;
+const CRC_OFFSET: usize = 8;
+
+const TRACK_COUNT: usize = 35;
+const DISK_TYPE_525: u8 = 1;
+const TMAP_LEN: usize = 160;
+const NO_TRACK: u8 = 0xFF;
+/// WOZ1 tracks are fixed size records: the bits, then how many of them are used
+const WOZ1_TRACK_LEN: usize = 6656;
+const WOZ1_TRACK_BITS_LEN: usize = 6646;
+const WOZ1_BIT_COUNT: usize = 6648;
+/// WOZ2 track entries give their bits' position in 512 byte file blocks
+const WOZ2_TRACK_ENTRY_LEN: usize = 8;
+const WOZ2_BLOCK_SIZE: usize = 512;
+
+const ADDRESS_PROLOGUE: [u8; 3] = [0xD5, 0xAA, 0x96];
+const DATA_PROLOGUE: [u8; 3] = [0xD5, 0xAA, 0xAD];
+/// How far past an address field its data field can start, in nibbles
+const DATA_FIELD_SEARCH: usize = 48;
+/// 342 nibbles of 6 and 2 encoded data and a checksum
+const DATA_NIBBLES: usize = 343;
+const TWOS_LEN: usize = 86;
+
+/// Physical sector holding each ProDOS sector of a track
+const PRODOS_TO_PHYSICAL: [usize; 16] = [0, 2, 4, 6, 8, 10, 12, 14, 1, 3, 5, 7, 9, 11, 13, 15];
+
+/// Disk bytes for each 6 bit value, as DOS 3.3 and ProDOS write them
+#[rustfmt::skip]
+const WRITE_TRANSLATE: [u8; 64] = [
+    0x96, 0x97, 0x9A, 0x9B, 0x9D, 0x9E, 0x9F, 0xA6, 0xA7, 0xAB, 0xAC, 0xAD, 0xAE, 0xAF, 0xB2, 0xB3,
+    0xB4, 0xB5, 0xB6, 0xB7, 0xB9, 0xBA, 0xBB, 0xBC, 0xBD, 0xBE, 0xBF, 0xCB, 0xCD, 0xCE, 0xCF, 0xD3,
+    0xD6, 0xD7, 0xD9, 0xDA, 0xDB, 0xDC, 0xDD, 0xDE, 0xDF, 0xE5, 0xE6, 0xE7, 0xE9, 0xEA, 0xEB, 0xEC,
+    0xED, 0xEE, 0xEF, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF,
+];
+
+/// The 6 bit value of each disk byte, 0xFF for bytes that can't appear in a data field
+const READ_TRANSLATE: [u8; 256] = {
+    let mut table = [0xFF; 256];
+    let mut i = 0;
+    while i < WRITE_TRANSLATE.len() {
+        table[WRITE_TRANSLATE[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct WozSector {
+    /// Bit of the track where the data field's nibbles start
+    data_bit: usize,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WozTrack {
+    /// Offset of the track's bits in the file
+    bits: usize,
+    bit_count: usize,
+    /// Sectors by physical number, as found in their address fields
+    sectors: Vec<Option<WozSector>>,
+}
+
+/// A WOZ 1 or 2 image of a 5.25" disk, recording the bits on each track
+///
+/// Tracks are decoded on load: address fields give each sector's physical number and the
+/// 6 and 2 encoded data field after it gives its contents. Sectors that are missing or
+/// fail their checksum can't be read, so the copy protection these images exist to keep
+/// shows up as read errors. Writing a block re-encodes its two data fields in place,
+/// leaving the rest of the track, and the header CRC is brought up to date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WozImage {
+    bytes: Vec<u8>,
+    write_protected: bool,
+    tracks: Vec<Option<WozTrack>>,
+    buffer: Vec<u8>,
+}
+
+fn bit(bytes: &[u8], bits: usize, index: usize) -> u8 {
+    (bytes[bits + index / 8] >> (7 - index % 8)) & 1
+}
+
+fn set_bit(bytes: &mut [u8], bits: usize, index: usize, value: u8) {
+    let mask = 0x80 >> (index % 8);
+    if value != 0 {
+        bytes[bits + index / 8] |= mask;
+    } else {
+        bytes[bits + index / 8] &= !mask;
+    }
+}
+
+/// Disk bytes as the Disk II's latch would see them, going twice round a track so fields
+/// crossing its end are caught: zero bits are skipped until a one starts the next byte
+struct NibbleReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+    bit_count: usize,
+    position: usize,
+}
+
+impl Iterator for NibbleReader<'_> {
+    /// The bit a disk byte starts at, and the byte
+    type Item = (usize, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.bit_count * 2;
+        while self.position < end && bit(self.bytes, self.bits, self.position % self.bit_count) == 0 {
+            self.position += 1;
+        }
+        if self.position + 8 > end {
+            return None;
+        }
+        let start = self.position % self.bit_count;
+        let mut nibble = 0;
+        for _ in 0..8 {
+            nibble = nibble << 1 | bit(self.bytes, self.bits, self.position % self.bit_count);
+            self.position += 1;
+        }
+        Some((start, nibble))
+    }
+}
+
+/// A 4 and 4 encoded address field byte
+fn decode_4and4(odd: u8, even: u8) -> u8 {
+    ((odd << 1) | 1) & even
+}
+
+/// Encode a 256 byte sector as the nibbles of a data field
+fn encode_6and2(data: &[u8]) -> [u8; DATA_NIBBLES] {
+    let mut values = [0u8; DATA_NIBBLES - 1];
+    for (i, &byte) in data.iter().enumerate().take(APPLE_SECTOR_SIZE) {
+        let two = ((byte & 1) << 1) | ((byte >> 1) & 1);
+        values[i % TWOS_LEN] |= two << (i / TWOS_LEN * 2);
+        values[TWOS_LEN + i] = byte >> 2;
+    }
+    // Each value goes to disk XORed with the one before, the last on its own as a checksum
+    let mut nibbles = [0u8; DATA_NIBBLES];
+    let mut previous = 0;
+    for (nibble, &value) in nibbles.iter_mut().zip(&values) {
+        *nibble = WRITE_TRANSLATE[(value ^ previous) as usize];
+        previous = value;
+    }
+    nibbles[DATA_NIBBLES - 1] = WRITE_TRANSLATE[previous as usize];
+    nibbles
+}
+
+/// Decode a data field's nibbles to its 256 byte sector, if they check out
+fn decode_6and2(nibbles: &[u8]) -> Option<Vec<u8>> {
+    let mut values = [0u8; DATA_NIBBLES - 1];
+    let mut previous = 0;
+    for (value, &nibble) in values.iter_mut().zip(nibbles) {
+        let decoded = READ_TRANSLATE[nibble as usize];
+        if decoded == 0xFF {
+            return None;
+        }
+        previous ^= decoded;
+        *value = previous;
+    }
+    if READ_TRANSLATE[nibbles[DATA_NIBBLES - 1] as usize] != previous {
+        return None;
+    }
+    let (twos, sixes) = values.split_at(TWOS_LEN);
+    Some(
+        (0..APPLE_SECTOR_SIZE)
+            .map(|i| {
+                let two = (twos[i % TWOS_LEN] >> (i / TWOS_LEN * 2)) & 3;
+                sixes[i] << 2 | (two >> 1) | ((two & 1) << 1)
+            })
+            .collect(),
+    )
+}
+
+/// CRC-32 as used in the WOZ header
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+impl WozImage {
+    pub fn parse(bytes: Vec<u8>) -> DeviceResult<Self> {
+        let woz2 = bytes.starts_with(WOZ2_SIGNATURE);
+        if !(woz2 || bytes.starts_with(WOZ1_SIGNATURE)) || bytes.get(4..8) != Some(&HEADER_TAIL[..]) {
+            return Err(DeviceError::InvalidOperation);
+        }
+
+        let (mut info, mut tmap, mut trks) = (None, None, None);
+        let mut chunk = HEADER_LEN;
+        while chunk + 8 <= bytes.len() {
+            let data = chunk + 8;
+            let end = data + read_u32(&bytes, chunk + 4)? as usize;
+            if end > bytes.len() {
+                return Err(DeviceError::InvalidOperation);
+            }
+            match &bytes[chunk..chunk + 4] {
+                b"INFO" => info = Some(data),
+                b"TMAP" if end - data >= TMAP_LEN => tmap = Some(data),
+                b"TRKS" => trks = Some(data),
+                _ => {}
+            }
+            chunk = end;
+        }
+        let (Some(info), Some(tmap), Some(trks)) = (info, tmap, trks) else {
+            return Err(DeviceError::InvalidOperation);
+        };
+        if bytes.get(info + 1) != Some(&DISK_TYPE_525) {
+            return Err(DeviceError::NotSupported);
+        }
+        let write_protected = bytes.get(info + 2) == Some(&1);
+
+        // The map is by quarter track; whole tracks are every fourth entry
+        let mut tracks = Vec::with_capacity(TRACK_COUNT);
+        for track in 0..TRACK_COUNT {
+            let index = bytes[tmap + track * 4];
+            if index == NO_TRACK {
+                tracks.push(None);
+                continue;
+            }
+            let (bits, bit_count) = if woz2 {
+                let entry = trks + index as usize * WOZ2_TRACK_ENTRY_LEN;
+                (read_u16(&bytes, entry)? as usize * WOZ2_BLOCK_SIZE, read_u32(&bytes, entry + 4)? as usize)
+            } else {
+                let entry = trks + index as usize * WOZ1_TRACK_LEN;
+                let bit_count = read_u16(&bytes, entry + WOZ1_BIT_COUNT)? as usize;
+                (entry, bit_count.min(WOZ1_TRACK_BITS_LEN * 8))
+            };
+            if bit_count == 0 || bits + bit_count.div_ceil(8) > bytes.len() {
+                return Err(DeviceError::InvalidOperation);
+            }
+            let sectors = Self::decode_track(&bytes, bits, bit_count);
+            tracks.push(Some(WozTrack { bits, bit_count, sectors }));
+        }
+
+        Ok(Self { bytes, write_protected, tracks, buffer: vec![0; BLOCK_SIZE] })
+    }
+
+    /// The sectors found on one track, by physical sector number
+    fn decode_track(bytes: &[u8], bits: usize, bit_count: usize) -> Vec<Option<WozSector>> {
+        let mut sectors = vec![None; SECTORS_PER_TRACK];
+        let mut nibbles = NibbleReader { bytes, bits, bit_count, position: 0 };
+        let mut window = [0u8; 3];
+        while let Some((_, nibble)) = nibbles.next() {
+            window = [window[1], window[2], nibble];
+            if window != ADDRESS_PROLOGUE {
+                continue;
+            }
+            window = [0; 3];
+            let address: Vec<u8> = nibbles.by_ref().take(8).map(|(_, nibble)| nibble).collect();
+            if address.len() < 8 {
+                break;
+            }
+            let [volume, track, sector, checksum] = [0, 2, 4, 6].map(|i| decode_4and4(address[i], address[i + 1]));
+            if volume ^ track ^ sector != checksum || sector as usize >= SECTORS_PER_TRACK {
+                continue;
+            }
+
+            let data_field = nibbles.by_ref().take(DATA_FIELD_SEARCH).any(|(_, nibble)| {
+                window = [window[1], window[2], nibble];
+                window == DATA_PROLOGUE
+            });
+            window = [0; 3];
+            if !data_field {
+                continue;
+            }
+            let field: Vec<(usize, u8)> = nibbles.by_ref().take(DATA_NIBBLES).collect();
+            if field.len() < DATA_NIBBLES {
+                break;
+            }
+            let field_nibbles: Vec<u8> = field.iter().map(|&(_, nibble)| nibble).collect();
+            if let Some(data) = decode_6and2(&field_nibbles) {
+                sectors[sector as usize].get_or_insert(WozSector { data_bit: field[0].0, data });
+            }
+            if sectors.iter().all(Option::is_some) {
+                break;
+            }
+        }
+        sectors
+    }
+
+    /// Track and physical sectors of the two halves of a block
+    fn block_sectors(&self, block: u32) -> DeviceResult<(usize, [usize; 2])> {
+        if block >= self.sector_count() {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let first = (block as usize % 8) * 2;
+        Ok((block as usize / 8, [PRODOS_TO_PHYSICAL[first], PRODOS_TO_PHYSICAL[first + 1]]))
+    }
+
+    fn sector(tracks: &[Option<WozTrack>], track: usize, sector: usize) -> DeviceResult<&WozSector> {
+        tracks[track]
+            .as_ref()
+            .and_then(|found| found.sectors[sector].as_ref())
+            .ok_or_else(|| DeviceError::IoError(format!("track {} sector {} not found", track, sector)))
+    }
+
+    /// Re-encode a sector's data field over its old bits
+    fn write_physical(&mut self, track: usize, sector: usize, data: &[u8]) -> DeviceResult<()> {
+        let data_bit = Self::sector(&self.tracks, track, sector)?.data_bit;
+        let Some(woz_track) = self.tracks[track].as_mut() else {
+            return Err(DeviceError::InvalidOperation);
+        };
+        for (i, nibble) in encode_6and2(data).into_iter().enumerate() {
+            for b in 0..8 {
+                let index = (data_bit + i * 8 + b) % woz_track.bit_count;
+                set_bit(&mut self.bytes, woz_track.bits, index, (nibble >> (7 - b)) & 1);
+            }
+        }
+        if let Some(found) = woz_track.sectors[sector].as_mut() {
+            found.data.copy_from_slice(&data[..APPLE_SECTOR_SIZE]);
+        }
+        Ok(())
+    }
+
+    fn update_crc(&mut self) {
+        let crc = crc32(&self.bytes[HEADER_LEN..]);
+        self.bytes[CRC_OFFSET..HEADER_LEN].copy_from_slice(&crc.to_le_bytes());
+    }
+}
+
+impl DiskImage for WozImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Woz
+    }
+
+    fn sector_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn sector_count(&self) -> u32 {
+        (TRACK_COUNT * SECTORS_PER_TRACK / 2) as u32
+    }
+
+    fn first_sector(&self) -> u32 {
+        0
+    }
+
+    fn read_sector(&mut self, block: u32) -> DeviceResult<&[u8]> {
+        let (track, halves) = self.block_sectors(block)?;
+        for (half, sector) in halves.into_iter().enumerate() {
+            let data = &Self::sector(&self.tracks, track, sector)?.data;
+            self.buffer[half * APPLE_SECTOR_SIZE..(half + 1) * APPLE_SECTOR_SIZE].copy_from_slice(data);
+        }
+        Ok(&self.buffer)
+    }
+
+    fn write_sector(&mut self, block: u32, data: &[u8]) -> DeviceResult<usize> {
+        let (track, halves) = self.block_sectors(block)?;
+        if self.write_protected || data.len() < BLOCK_SIZE {
+            return Err(DeviceError::InvalidOperation);
+        }
+        // Both halves have to be there before either is written
+        for sector in halves {
+            Self::sector(&self.tracks, track, sector)?;
+        }
+        for (half, sector) in halves.into_iter().enumerate() {
+            self.write_physical(track, sector, &data[half * APPLE_SECTOR_SIZE..(half + 1) * APPLE_SECTOR_SIZE])?;
+        }
+        self.update_crc();
+        Ok(BLOCK_SIZE)
+    }
+
+    /// Clears every sector found on the disk; the tracks themselves aren't laid out again
+    fn format(&mut self) -> DeviceResult<()> {
+        if self.write_protected {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let blank = [0u8; APPLE_SECTOR_SIZE];
+        for track in 0..TRACK_COUNT {
+            for sector in 0..SECTORS_PER_TRACK {
+                if Self::sector(&self.tracks, track, sector).is_ok() {
+                    self.write_physical(track, sector, &blank)?;
+                }
+            }
+        }
+        self.update_crc();
+        Ok(())
+    }
+
+    fn is_writable(&self) -> bool {
+        !self.write_protected
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sector_fill(track: usize, sector: usize) -> Vec<u8> {
+        (0..APPLE_SECTOR_SIZE).map(|i| (track * 16 + sector) as u8 ^ i as u8).collect()
+    }
+
+    /// The bits of a 16 sector track as DOS 3.3 formats it, turned so the first sector's
+    /// data field runs over the end of the track
+    fn track_bits(track: usize) -> (Vec<u8>, usize) {
+        let mut bits = Vec::new();
+        let mut nibbles = |bytes: &[u8], sync: bool| {
+            for &byte in bytes {
+                bits.extend((0..8).rev().map(|b| (byte >> b) & 1));
+                if sync {
+                    bits.extend([0, 0]);
+                }
+            }
+        };
+        nibbles(&[0xFF; 16], true);
+        for sector in 0..SECTORS_PER_TRACK {
+            let address = [254, track as u8, sector as u8, 254 ^ track as u8 ^ sector as u8];
+            nibbles(&ADDRESS_PROLOGUE, false);
+            for value in address {
+                nibbles(&[(value >> 1) | 0xAA, value | 0xAA], false);
+            }
+            nibbles(&[0xDE, 0xAA, 0xEB], false);
+            nibbles(&[0xFF; 6], true);
+            nibbles(&DATA_PROLOGUE, false);
+            nibbles(&encode_6and2(&sector_fill(track, sector)), false);
+            nibbles(&[0xDE, 0xAA, 0xEB], false);
+            nibbles(&[0xFF; 16], true);
+        }
+        bits.rotate_left(1000);
+        let packed = bits.chunks(8).map(|byte| byte.iter().fold(0, |acc, &b| acc << 1 | b) << (8 - byte.len())).collect();
+        (packed, bits.len())
+    }
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        chunk
+    }
+
+    /// A WOZ file of a 35 track disk, with `missing` left off the track map
+    fn woz_image(woz2: bool, write_protected: bool, missing: Option<usize>) -> Vec<u8> {
+        let mut info = vec![0u8; 60];
+        info[0] = if woz2 { 2 } else { 1 };
+        info[1] = DISK_TYPE_525;
+        info[2] = write_protected as u8;
+        let mut tmap = vec![NO_TRACK; TMAP_LEN];
+        for track in (0..TRACK_COUNT).filter(|&track| Some(track) != missing) {
+            tmap[track * 4] = track as u8;
+        }
+
+        let mut trks = Vec::new();
+        if woz2 {
+            // Track bits start at file block 3, right after the entries
+            let mut entries = vec![0u8; TMAP_LEN * WOZ2_TRACK_ENTRY_LEN];
+            for track in 0..TRACK_COUNT {
+                let (bits, bit_count) = track_bits(track);
+                let block_count = bits.len().div_ceil(WOZ2_BLOCK_SIZE);
+                let entry = &mut entries[track * WOZ2_TRACK_ENTRY_LEN..];
+                entry[..2].copy_from_slice(&((3 + track * block_count) as u16).to_le_bytes());
+                entry[2..4].copy_from_slice(&(block_count as u16).to_le_bytes());
+                entry[4..8].copy_from_slice(&(bit_count as u32).to_le_bytes());
+                trks.extend_from_slice(&bits);
+                trks.resize(trks.len().next_multiple_of(WOZ2_BLOCK_SIZE), 0);
+            }
+            trks.splice(0..0, entries);
+        } else {
+            for track in 0..TRACK_COUNT {
+                let (mut bits, bit_count) = track_bits(track);
+                let used = bits.len() as u16;
+                bits.resize(WOZ1_TRACK_LEN, 0);
+                bits[WOZ1_TRACK_BITS_LEN..WOZ1_BIT_COUNT].copy_from_slice(&used.to_le_bytes());
+                bits[WOZ1_BIT_COUNT..WOZ1_BIT_COUNT + 2].copy_from_slice(&(bit_count as u16).to_le_bytes());
+                trks.extend_from_slice(&bits);
+            }
+        }
+
+        let mut image = if woz2 { WOZ2_SIGNATURE.to_vec() } else { WOZ1_SIGNATURE.to_vec() };
+        image.extend_from_slice(&HEADER_TAIL);
+        image.extend_from_slice(&[0; 4]);
+        image.extend(chunk(b"INFO", &info));
+        image.extend(chunk(b"TMAP", &tmap));
+        image.extend(chunk(b"TRKS", &trks));
+        assert!(!woz2 || image.len() - trks.len() + TMAP_LEN * WOZ2_TRACK_ENTRY_LEN == 3 * WOZ2_BLOCK_SIZE);
+        image
+    }
+
+    fn expected_block(block: usize) -> Vec<u8> {
+        let (track, first) = (block / 8, block % 8 * 2);
+        let mut data = sector_fill(track, PRODOS_TO_PHYSICAL[first]);
+        data.extend(sector_fill(track, PRODOS_TO_PHYSICAL[first + 1]));
+        data
+    }
+
+    #[test]
+    fn test_6and2_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let nibbles = encode_6and2(&data);
+        assert!(nibbles.iter().all(|&nibble| nibble & 0x80 != 0));
+        assert_eq!(decode_6and2(&nibbles), Some(data));
+
+        let mut corrupt = nibbles;
+        corrupt[100] = WRITE_TRANSLATE[(READ_TRANSLATE[corrupt[100] as usize] ^ 1) as usize];
+        assert_eq!(decode_6and2(&corrupt), None);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_read_blocks() -> DeviceResult<()> {
+        for woz2 in [false, true] {
+            let mut image = WozImage::parse(woz_image(woz2, false, None))?;
+            assert_eq!((image.sector_size(), image.sector_count()), (BLOCK_SIZE, 280));
+            for block in [0, 1, 7, 9, 279] {
+                assert_eq!(image.read_sector(block)?, &expected_block(block as usize)[..]);
+            }
+            assert_eq!(image.read_sector(280).err(), Some(DeviceError::InvalidOperation));
+        }
+
+        let mut missing = WozImage::parse(woz_image(true, false, Some(2)))?;
+        assert!(matches!(missing.read_sector(16), Err(DeviceError::IoError(_))));
+        assert!(missing.read_sector(24).is_ok());
+
+        let mut bytes = woz_image(true, false, None);
+        bytes[HEADER_LEN + 9] = 2;
+        assert_eq!(WozImage::parse(bytes).err(), Some(DeviceError::NotSupported));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_blocks() -> DeviceResult<()> {
+        for woz2 in [false, true] {
+            let mut image = WozImage::parse(woz_image(woz2, false, None))?;
+            let data: Vec<u8> = (0..BLOCK_SIZE).map(|i| (i * 7) as u8).collect();
+            // Block 0's first half is the sector whose data field wraps round the track
+            for block in [0, 100] {
+                assert_eq!(image.write_sector(block, &data)?, BLOCK_SIZE);
+                assert_eq!(image.read_sector(block)?, &data[..]);
+            }
+            assert_eq!(read_u32(image.as_bytes(), CRC_OFFSET)?, crc32(&image.as_bytes()[HEADER_LEN..]));
+
+            let mut reloaded = WozImage::parse(image.as_bytes().to_vec())?;
+            assert_eq!(reloaded.read_sector(100)?, &data[..]);
+            assert_eq!(reloaded.read_sector(0)?, &data[..]);
+            assert_eq!(reloaded.read_sector(1)?, &expected_block(1)[..]);
+            reloaded.format()?;
+            assert_eq!(reloaded.read_sector(100)?, &[0; BLOCK_SIZE]);
+        }
+
+        let mut protected = WozImage::parse(woz_image(true, true, None))?;
+        assert!(!protected.is_writable());
+        assert_eq!(protected.write_sector(0, &[0; BLOCK_SIZE]), Err(DeviceError::InvalidOperation));
+        Ok(())
+    }
+}
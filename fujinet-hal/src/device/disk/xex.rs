@@ -0,0 +1,229 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::atr::{BOOT_SECTORS, SHORT_SECTOR_SIZE};
+use super::image::{DiskImage, ImageFormat};
+
+/// The first two bytes of an Atari executable
+pub const XEX_MAGIC: [u8; 2] = [0xFF, 0xFF];
+
+/// Offset in the loader of the 24 bit count of executable bytes still to load
+const LOADER_LENGTH_OFFSET: usize = 0xFE;
+
+/// Boot loader for the virtual disk, loaded by the OS to $0700 from the boot sectors
+///
+/// It reads the executable from sector 4 on, a byte stream with no links, copying each
+/// segment where it belongs. After each segment it calls INITAD if the segment set it,
+/// and once the stream ends it jumps to RUNAD, or to the start of the first segment if
+/// nothing set that. Sectors come through SIOV into the cassette buffer at $0400, so an
+/// executable can load anywhere except over $0400-$047F and the loader at $0700-$0800.
+#[rustfmt::skip]
+const XEX_LOADER: [u8; 257] = [
+    // RUNAD = $02E0, INITAD = $02E2, SIOV = $E459, PTR = $43, BUFFER = $0400
+    // DDEVIC..DAUX2 = $0300..$030B
+    0x00, 0x03,              // 0700           .BYTE 0, 3
+    0x00, 0x07, 0xAC, 0x07,  // 0702           .WORD $0700, RETURN
+    0xA9, 0x00,              // 0706 START:    LDA #0
+    0x8D, 0xE0, 0x02,        // 0708           STA RUNAD
+    0x8D, 0xE1, 0x02,        // 070B           STA RUNAD+1
+    0x20, 0x7A, 0x07,        // 070E SEGMENT:  JSR GETBYTE
+    0xB0, 0x64,              // 0711           BCS DONE
+    0x85, 0x43,              // 0713           STA PTR
+    0x20, 0x7A, 0x07,        // 0715           JSR GETBYTE
+    0xB0, 0x5D,              // 0718           BCS DONE
+    0x85, 0x44,              // 071A           STA PTR+1
+    0x25, 0x43,              // 071C           AND PTR
+    0xC9, 0xFF,              // 071E           CMP #$FF
+    0xF0, 0xEC,              // 0720           BEQ SEGMENT
+    0x20, 0x7A, 0x07,        // 0722           JSR GETBYTE
+    0xB0, 0x50,              // 0725           BCS DONE
+    0x8D, 0xFC, 0x07,        // 0727           STA LAST
+    0x20, 0x7A, 0x07,        // 072A           JSR GETBYTE
+    0xB0, 0x48,              // 072D           BCS DONE
+    0x8D, 0xFD, 0x07,        // 072F           STA LAST+1
+    0xAD, 0xE0, 0x02,        // 0732           LDA RUNAD
+    0x0D, 0xE1, 0x02,        // 0735           ORA RUNAD+1
+    0xD0, 0x0A,              // 0738           BNE NOINIT
+    0xA5, 0x43,              // 073A           LDA PTR
+    0x8D, 0xE0, 0x02,        // 073C           STA RUNAD
+    0xA5, 0x44,              // 073F           LDA PTR+1
+    0x8D, 0xE1, 0x02,        // 0741           STA RUNAD+1
+    0xA9, 0xAC,              // 0744 NOINIT:   LDA #<RETURN
+    0x8D, 0xE2, 0x02,        // 0746           STA INITAD
+    0xA9, 0x07,              // 0749           LDA #>RETURN
+    0x8D, 0xE3, 0x02,        // 074B           STA INITAD+1
+    0x20, 0x7A, 0x07,        // 074E COPY:     JSR GETBYTE
+    0xB0, 0x24,              // 0751           BCS DONE
+    0xA0, 0x00,              // 0753           LDY #0
+    0x91, 0x43,              // 0755           STA (PTR),Y
+    0xA5, 0x43,              // 0757           LDA PTR
+    0xCD, 0xFC, 0x07,        // 0759           CMP LAST
+    0xD0, 0x07,              // 075C           BNE NEXT
+    0xA5, 0x44,              // 075E           LDA PTR+1
+    0xCD, 0xFD, 0x07,        // 0760           CMP LAST+1
+    0xF0, 0x09,              // 0763           BEQ ENDSEG
+    0xE6, 0x43,              // 0765 NEXT:     INC PTR
+    0xD0, 0xE5,              // 0767           BNE COPY
+    0xE6, 0x44,              // 0769           INC PTR+1
+    0x4C, 0x4E, 0x07,        // 076B           JMP COPY
+    0x20, 0x74, 0x07,        // 076E ENDSEG:   JSR INIT
+    0x4C, 0x0E, 0x07,        // 0771           JMP SEGMENT
+    0x6C, 0xE2, 0x02,        // 0774 INIT:     JMP (INITAD)
+    0x6C, 0xE0, 0x02,        // 0777 DONE:     JMP (RUNAD)
+    0xAD, 0xFE, 0x07,        // 077A GETBYTE:  LDA REMAIN
+    0x0D, 0xFF, 0x07,        // 077D           ORA REMAIN+1
+    0x0D, 0x00, 0x08,        // 0780           ORA REMAIN+2
+    0xF0, 0x28,              // 0783           BEQ EOF
+    0xAD, 0xFE, 0x07,        // 0785           LDA REMAIN
+    0xD0, 0x0B,              // 0788           BNE DEC0
+    0xAD, 0xFF, 0x07,        // 078A           LDA REMAIN+1
+    0xD0, 0x03,              // 078D           BNE DEC1
+    0xCE, 0x00, 0x08,        // 078F           DEC REMAIN+2
+    0xCE, 0xFF, 0x07,        // 0792 DEC1:     DEC REMAIN+1
+    0xCE, 0xFE, 0x07,        // 0795 DEC0:     DEC REMAIN
+    0xAE, 0xFB, 0x07,        // 0798           LDX INDEX
+    0xE0, 0x80,              // 079B           CPX #128
+    0x90, 0x05,              // 079D           BCC LOADED
+    0x20, 0xAF, 0x07,        // 079F           JSR READ
+    0xA2, 0x00,              // 07A2           LDX #0
+    0xBD, 0x00, 0x04,        // 07A4 LOADED:   LDA BUFFER,X
+    0xE8,                    // 07A7           INX
+    0x8E, 0xFB, 0x07,        // 07A8           STX INDEX
+    0x18,                    // 07AB           CLC
+    0x60,                    // 07AC RETURN:   RTS
+    0x38,                    // 07AD EOF:      SEC
+    0x60,                    // 07AE           RTS
+    0xA9, 0x31,              // 07AF READ:     LDA #$31
+    0x8D, 0x00, 0x03,        // 07B1           STA DDEVIC
+    0xA9, 0x01,              // 07B4           LDA #1
+    0x8D, 0x01, 0x03,        // 07B6           STA DUNIT
+    0xA9, 0x52,              // 07B9           LDA #$52
+    0x8D, 0x02, 0x03,        // 07BB           STA DCOMND
+    0xA9, 0x40,              // 07BE           LDA #$40
+    0x8D, 0x03, 0x03,        // 07C0           STA DSTATS
+    0xA9, 0x00,              // 07C3           LDA #<BUFFER
+    0x8D, 0x04, 0x03,        // 07C5           STA DBUFLO
+    0xA9, 0x04,              // 07C8           LDA #>BUFFER
+    0x8D, 0x05, 0x03,        // 07CA           STA DBUFHI
+    0xA9, 0x07,              // 07CD           LDA #7
+    0x8D, 0x06, 0x03,        // 07CF           STA DTIMLO
+    0xA9, 0x80,              // 07D2           LDA #128
+    0x8D, 0x08, 0x03,        // 07D4           STA DBYTLO
+    0xA9, 0x00,              // 07D7           LDA #0
+    0x8D, 0x09, 0x03,        // 07D9           STA DBYTHI
+    0xAD, 0xF9, 0x07,        // 07DC           LDA SECTOR
+    0x8D, 0x0A, 0x03,        // 07DF           STA DAUX1
+    0xAD, 0xFA, 0x07,        // 07E2           LDA SECTOR+1
+    0x8D, 0x0B, 0x03,        // 07E5           STA DAUX2
+    0x20, 0x59, 0xE4,        // 07E8           JSR SIOV
+    0xAD, 0x03, 0x03,        // 07EB           LDA DSTATS
+    0x30, 0xBF,              // 07EE           BMI READ
+    0xEE, 0xF9, 0x07,        // 07F0           INC SECTOR
+    0xD0, 0x03,              // 07F3           BNE READOK
+    0xEE, 0xFA, 0x07,        // 07F5           INC SECTOR+1
+    0x60,                    // 07F8 READOK:   RTS
+    0x04, 0x00,              // 07F9 SECTOR:   .WORD 4
+    0x80,                    // 07FB INDEX:    .BYTE 128
+    0x00, 0x00,              // 07FC LAST:     .WORD 0
+    0x00, 0x00, 0x00,        // 07FE REMAIN:   .BYTE 0, 0, 0
+];
+
+/// An Atari executable on a virtual single density boot disk
+///
+/// Sectors 1 to 3 hold the boot loader with the executable's length patched in, and
+/// the executable follows from sector 4, 128 bytes a sector. The disk can't be written;
+/// the file it gives back is the executable itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XexImage {
+    xex: Vec<u8>,
+    disk: Vec<u8>,
+}
+
+impl XexImage {
+    pub fn new(xex: Vec<u8>) -> DeviceResult<Self> {
+        // The loader counts down 24 bits; nothing bigger could fit in memory anyway
+        if xex.len() < 6 || xex.len() > 0xFF_FFFF {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let boot_len = BOOT_SECTORS as usize * SHORT_SECTOR_SIZE;
+        let mut disk = Vec::with_capacity(boot_len + xex.len() + SHORT_SECTOR_SIZE);
+        disk.extend_from_slice(&XEX_LOADER);
+        disk[LOADER_LENGTH_OFFSET..LOADER_LENGTH_OFFSET + 3].copy_from_slice(&(xex.len() as u32).to_le_bytes()[..3]);
+        disk.resize(boot_len, 0);
+        disk.extend_from_slice(&xex);
+        disk.resize(disk.len().next_multiple_of(SHORT_SECTOR_SIZE), 0);
+        Ok(Self { xex, disk })
+    }
+}
+
+impl DiskImage for XexImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Xex
+    }
+
+    fn sector_size(&self) -> usize {
+        SHORT_SECTOR_SIZE
+    }
+
+    fn sector_count(&self) -> u32 {
+        (self.disk.len() / SHORT_SECTOR_SIZE) as u32
+    }
+
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]> {
+        if sector == 0 || sector > self.sector_count() {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let start = (sector - 1) as usize * SHORT_SECTOR_SIZE;
+        Ok(&self.disk[start..start + SHORT_SECTOR_SIZE])
+    }
+
+    fn write_sector(&mut self, _sector: u32, _data: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::InvalidOperation)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        Err(DeviceError::InvalidOperation)
+    }
+
+    fn is_writable(&self) -> bool {
+        false
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.xex
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.xex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_boot_disk() -> DeviceResult<()> {
+        // One segment of 300 bytes at $2000, run from $2000
+        let mut xex = vec![0xFF, 0xFF, 0x00, 0x20, 0x2B, 0x21];
+        xex.extend((0..300).map(|i| i as u8));
+        xex.extend_from_slice(&[0xE0, 0x02, 0xE1, 0x02, 0x00, 0x20]);
+        let mut image = XexImage::new(xex.clone())?;
+        assert_eq!(image.sector_count(), 3 + 3);
+
+        // The boot sector header: 3 sectors loaded to $0700, DOSINI pointing at an RTS
+        let boot = image.read_sector(1)?.to_vec();
+        assert_eq!(&boot[..4], &[0x00, 0x03, 0x00, 0x07]);
+        let init = u16::from_le_bytes([boot[4], boot[5]]) as usize - 0x0700;
+        assert_eq!(XEX_LOADER[init], 0x60);
+        assert_eq!(&image.read_sector(2)?[LOADER_LENGTH_OFFSET - 128..], &[0x38, 0x01]);
+        assert_eq!(image.read_sector(3)?[0], 0x00);
+
+        assert_eq!(image.read_sector(4)?, &xex[..128]);
+        assert_eq!(&image.read_sector(6)?[..xex.len() - 256], &xex[256..]);
+        assert_eq!(image.read_sector(7).err(), Some(DeviceError::InvalidOperation));
+        assert_eq!(image.write_sector(4, &[0; 128]), Err(DeviceError::InvalidOperation));
+        assert_eq!(image.as_bytes(), xex);
+
+        assert_eq!(XexImage::new(vec![0xFF, 0xFF]).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::atr::{SectorLayout, SHORT_SECTOR_SIZE};
+use super::image::{DiskImage, ImageFormat};
+
+/// Size of a 720 sector double density XFD with short boot sectors
+const DOUBLE_DENSITY_SHORT_BOOT: usize = 183936;
+/// Size of a 720 sector double density XFD with full size boot sectors
+const DOUBLE_DENSITY_FULL_BOOT: usize = 184320;
+
+/// An XFD disk image: the sectors of an Atari disk with no header
+///
+/// With nothing to say how big the sectors are, the two double density file sizes are
+/// taken as 256 byte sectors and anything else as 128 byte ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XfdImage {
+    bytes: Vec<u8>,
+    layout: SectorLayout,
+}
+
+impl XfdImage {
+    pub fn parse(bytes: Vec<u8>) -> DeviceResult<Self> {
+        let sector_size = match bytes.len() {
+            DOUBLE_DENSITY_SHORT_BOOT | DOUBLE_DENSITY_FULL_BOOT => 256,
+            len if len > 0 && len.is_multiple_of(SHORT_SECTOR_SIZE) => SHORT_SECTOR_SIZE,
+            _ => return Err(DeviceError::InvalidOperation),
+        };
+        let layout = SectorLayout::of(0, sector_size, bytes.len());
+        Ok(Self { bytes, layout })
+    }
+}
+
+impl DiskImage for XfdImage {
+    fn image_format(&self) -> ImageFormat {
+        ImageFormat::Xfd
+    }
+
+    fn sector_size(&self) -> usize {
+        self.layout.sector_size()
+    }
+
+    fn sector_count(&self) -> u32 {
+        self.layout.sector_count()
+    }
+
+    fn sector_len(&self, sector: u32) -> usize {
+        self.layout.sector_len(sector)
+    }
+
+    fn read_sector(&mut self, sector: u32) -> DeviceResult<&[u8]> {
+        Ok(&self.bytes[self.layout.range(sector)?])
+    }
+
+    fn write_sector(&mut self, sector: u32, data: &[u8]) -> DeviceResult<usize> {
+        self.layout.write(&mut self.bytes, sector, data)
+    }
+
+    fn format(&mut self) -> DeviceResult<()> {
+        self.bytes.fill(0);
+        Ok(())
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(self: Box<Self>) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::disk::Density;
+
+    #[test]
+    fn test_geometry_from_size() -> DeviceResult<()> {
+        assert_eq!(XfdImage::parse(vec![0; 92160])?.density(), Density::Single);
+        assert_eq!(XfdImage::parse(vec![0; 133120])?.density(), Density::Enhanced);
+
+        let mut short_boot = XfdImage::parse(vec![0; DOUBLE_DENSITY_SHORT_BOOT])?;
+        assert_eq!(short_boot.density(), Density::Double);
+        short_boot.write_sector(4, &[4; 256])?;
+        assert_eq!(short_boot.as_bytes()[384], 4);
+
+        let mut full_boot = XfdImage::parse(vec![0; DOUBLE_DENSITY_FULL_BOOT])?;
+        assert_eq!(full_boot.sector_count(), 720);
+        full_boot.write_sector(4, &[4; 256])?;
+        assert_eq!(full_boot.as_bytes()[768], 4);
+        assert_eq!(full_boot.read_sector(1)?.len(), 128);
+
+        assert_eq!(XfdImage::parse(vec![0; 1000]).err(), Some(DeviceError::InvalidOperation));
+        Ok(())
+    }
+}
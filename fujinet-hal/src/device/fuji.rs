@@ -0,0 +1,521 @@
+use std::fmt;
+use super::{DeviceError, DeviceResult};
+use super::adapter::{AdapterConfig, AdapterInfoProvider, ScanResult, SSID_LEN, ADAPTER_HOSTNAME_LEN, FIRMWARE_VERSION_LEN, ADAPTER_CONFIG_LEN, SCAN_RESULT_LEN};
+
+/// Number of host slots (TNFS servers, SD, ...)
+pub const MAX_HOSTS: usize = 8;
+/// Number of device slots (D1: to D8:)
+pub const MAX_DISK_DEVICES: usize = 8;
+/// Bytes per host name, NUL padded
+pub const HOST_NAME_LEN: usize = 32;
+/// Bytes per device slot filename, NUL padded
+pub const DEVICE_FILENAME_LEN: usize = 36;
+/// Bytes per device slot record: host slot, mode, filename
+pub const DEVICE_SLOT_LEN: usize = 2 + DEVICE_FILENAME_LEN;
+/// Host slot number of a device slot with nothing in it
+pub const HOST_SLOT_EMPTY: u8 = 0xFF;
+
+/// Mount modes for a device slot
+pub const MOUNT_MODE_READ: u8 = 1;
+pub const MOUNT_MODE_WRITE: u8 = 2;
+
+/// Bytes for a WiFi password in an SSID record
+pub const PASSWORD_LEN: usize = 64;
+
+/// WiFi status codes, as the ESP32 reports them
+pub const WIFI_STATUS_CONNECTED: u8 = 3;
+pub const WIFI_STATUS_DISCONNECTED: u8 = 6;
+
+/// Fuji device ($70) commands, as sent by CONFIG
+pub const FUJICMD_UNMOUNT_HOST: u8 = 0xE6;
+pub const FUJICMD_SET_DEVICE_FULLPATH: u8 = 0xE2;
+pub const FUJICMD_UNMOUNT_IMAGE: u8 = 0xE9;
+pub const FUJICMD_WRITE_DEVICE_SLOTS: u8 = 0xF1;
+pub const FUJICMD_READ_DEVICE_SLOTS: u8 = 0xF2;
+pub const FUJICMD_WRITE_HOST_SLOTS: u8 = 0xF3;
+pub const FUJICMD_READ_HOST_SLOTS: u8 = 0xF4;
+pub const FUJICMD_MOUNT_IMAGE: u8 = 0xF8;
+pub const FUJICMD_MOUNT_HOST: u8 = 0xF9;
+pub const FUJICMD_GET_ADAPTERCONFIG: u8 = 0xE8;
+pub const FUJICMD_GET_WIFI_ENABLED: u8 = 0xEA;
+pub const FUJICMD_GET_WIFISTATUS: u8 = 0xFA;
+pub const FUJICMD_GET_SCAN_RESULT: u8 = 0xFC;
+pub const FUJICMD_SCAN_NETWORKS: u8 = 0xFD;
+pub const FUJICMD_GET_SSID: u8 = 0xFE;
+
+/// A server the device slots can take images from; empty when the name is
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostSlot {
+    pub name: String,
+    pub mounted: bool,
+}
+
+/// A disk drive and the image assigned to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSlot {
+    /// Host slot the image is on, or HOST_SLOT_EMPTY
+    pub host_slot: u8,
+    pub mode: u8,
+    pub filename: String,
+    pub mounted: bool,
+}
+
+impl Default for DiskSlot {
+    fn default() -> Self {
+        Self {
+            host_slot: HOST_SLOT_EMPTY,
+            mode: MOUNT_MODE_READ,
+            filename: String::new(),
+            mounted: false,
+        }
+    }
+}
+
+impl DiskSlot {
+    pub fn is_empty(&self) -> bool {
+        self.host_slot == HOST_SLOT_EMPTY || self.filename.is_empty()
+    }
+}
+
+/// The FujiNet configuration device: host slots, device slots and the network adapter
+///
+/// Slot contents travel as fixed-size NUL-padded records, the layout CONFIG reads and
+/// writes. Mounting only checks and records what is mounted; loading the image itself is
+/// up to the disk device it ends up on. Adapter queries are answered by the
+/// AdapterInfoProvider given to `with_adapter`, and fail with NotReady without one.
+#[derive(Default)]
+pub struct FujiConfig {
+    hosts: [HostSlot; MAX_HOSTS],
+    disks: [DiskSlot; MAX_DISK_DEVICES],
+    adapter: Option<Box<dyn AdapterInfoProvider>>,
+    // Results of the last scan, which CONFIG then reads back one at a time
+    scan: Vec<ScanResult>,
+}
+
+impl fmt::Debug for FujiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FujiConfig")
+            .field("hosts", &self.hosts)
+            .field("disks", &self.disks)
+            .field("adapter", &self.adapter.is_some())
+            .field("scan", &self.scan)
+            .finish()
+    }
+}
+
+impl FujiConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A config device reporting the network adapter described by `adapter`
+    pub fn with_adapter(adapter: Box<dyn AdapterInfoProvider>) -> Self {
+        Self { adapter: Some(adapter), ..Self::default() }
+    }
+
+    pub fn set_adapter(&mut self, adapter: Box<dyn AdapterInfoProvider>) {
+        self.adapter = Some(adapter);
+        self.scan.clear();
+    }
+
+    fn adapter(&self) -> DeviceResult<&dyn AdapterInfoProvider> {
+        self.adapter.as_deref().ok_or(DeviceError::NotReady)
+    }
+
+    pub fn host_slot(&self, slot: usize) -> Option<&HostSlot> {
+        self.hosts.get(slot)
+    }
+
+    pub fn disk_slot(&self, slot: usize) -> Option<&DiskSlot> {
+        self.disks.get(slot)
+    }
+
+    pub fn host_slots(&self) -> &[HostSlot] {
+        &self.hosts
+    }
+
+    pub fn disk_slots(&self) -> &[DiskSlot] {
+        &self.disks
+    }
+
+    /// Name a host slot, unmounting it if the name changes
+    ///
+    /// The device slots that took images from the old host are emptied, as their
+    /// filenames mean nothing on the new one.
+    pub fn set_host_name(&mut self, slot: usize, name: &str) -> DeviceResult<()> {
+        let host = self.hosts.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        let name = truncate(name, HOST_NAME_LEN - 1);
+        if host.name != name {
+            host.name = name.to_string();
+            host.mounted = false;
+            for disk in self.disks.iter_mut().filter(|disk| disk.host_slot as usize == slot) {
+                *disk = DiskSlot::default();
+            }
+        }
+        Ok(())
+    }
+
+    /// All host slots, HOST_NAME_LEN bytes each
+    pub fn read_host_slots(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(MAX_HOSTS * HOST_NAME_LEN);
+        for host in &self.hosts {
+            put_padded(&mut buf, &host.name, HOST_NAME_LEN);
+        }
+        buf
+    }
+
+    /// Replace every host slot from the layout read_host_slots returns
+    pub fn write_host_slots(&mut self, data: &[u8]) -> DeviceResult<()> {
+        if data.len() != MAX_HOSTS * HOST_NAME_LEN {
+            return Err(DeviceError::InvalidOperation);
+        }
+        for (slot, record) in data.chunks(HOST_NAME_LEN).enumerate() {
+            self.set_host_name(slot, &get_padded(record))?;
+        }
+        Ok(())
+    }
+
+    /// All device slots, DEVICE_SLOT_LEN bytes each
+    pub fn read_device_slots(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(MAX_DISK_DEVICES * DEVICE_SLOT_LEN);
+        for disk in &self.disks {
+            buf.push(disk.host_slot);
+            buf.push(disk.mode);
+            put_padded(&mut buf, &disk.filename, DEVICE_FILENAME_LEN);
+        }
+        buf
+    }
+
+    /// Replace every device slot from the layout read_device_slots returns
+    /// Nothing changes if any record names a host slot that doesn't exist
+    pub fn write_device_slots(&mut self, data: &[u8]) -> DeviceResult<()> {
+        if data.len() != MAX_DISK_DEVICES * DEVICE_SLOT_LEN {
+            return Err(DeviceError::InvalidOperation);
+        }
+        if data.chunks(DEVICE_SLOT_LEN).any(|record| !valid_host_slot(record[0])) {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        for (slot, record) in data.chunks(DEVICE_SLOT_LEN).enumerate() {
+            self.set_device_filename(slot, record[0], record[1], &get_padded(&record[2..]))?;
+        }
+        Ok(())
+    }
+
+    /// Assign an image on a host to a device slot, unmounting whatever was there
+    pub fn set_device_filename(&mut self, slot: usize, host_slot: u8, mode: u8, filename: &str) -> DeviceResult<()> {
+        if !valid_host_slot(host_slot) {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        let disk = self.disks.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        *disk = DiskSlot {
+            host_slot,
+            mode,
+            filename: truncate(filename, DEVICE_FILENAME_LEN - 1).to_string(),
+            mounted: false,
+        };
+        Ok(())
+    }
+
+    /// Connect to a host so images can be mounted from it
+    pub fn mount_host(&mut self, slot: usize) -> DeviceResult<()> {
+        let host = self.hosts.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        if host.name.is_empty() {
+            return Err(DeviceError::NotReady);
+        }
+        host.mounted = true;
+        Ok(())
+    }
+
+    /// Disconnect from a host, unmounting the images taken from it
+    pub fn unmount_host(&mut self, slot: usize) -> DeviceResult<()> {
+        let host = self.hosts.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        host.mounted = false;
+        for disk in self.disks.iter_mut().filter(|disk| disk.host_slot as usize == slot) {
+            disk.mounted = false;
+        }
+        Ok(())
+    }
+
+    /// Mount a device slot's image with the given mode; its host must be mounted
+    pub fn mount_disk_image(&mut self, slot: usize, mode: u8) -> DeviceResult<&DiskSlot> {
+        if mode != MOUNT_MODE_READ && mode != MOUNT_MODE_WRITE {
+            return Err(DeviceError::InvalidOperation);
+        }
+        let disk = self.disks.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        if disk.is_empty() {
+            return Err(DeviceError::NotReady);
+        }
+        if !self.hosts[disk.host_slot as usize].mounted {
+            return Err(DeviceError::NotReady);
+        }
+        disk.mode = mode;
+        disk.mounted = true;
+        Ok(disk)
+    }
+
+    pub fn unmount_disk_image(&mut self, slot: usize) -> DeviceResult<()> {
+        let disk = self.disks.get_mut(slot).ok_or(DeviceError::InvalidDeviceId)?;
+        disk.mounted = false;
+        Ok(())
+    }
+
+    pub fn adapter_config(&self) -> DeviceResult<AdapterConfig> {
+        Ok(self.adapter()?.adapter_config())
+    }
+
+    /// The adapter config as an ADAPTER_CONFIG_LEN byte record
+    pub fn read_adapter_config(&self) -> DeviceResult<Vec<u8>> {
+        let config = self.adapter_config()?;
+        let mut buf = Vec::with_capacity(ADAPTER_CONFIG_LEN);
+        put_padded(&mut buf, &config.ssid, SSID_LEN);
+        put_padded(&mut buf, &config.hostname, ADAPTER_HOSTNAME_LEN);
+        for address in [config.local_ip, config.gateway, config.netmask, config.dns] {
+            buf.extend_from_slice(&address.octets());
+        }
+        buf.extend_from_slice(&config.mac);
+        buf.extend_from_slice(&config.bssid);
+        put_padded(&mut buf, &config.firmware_version, FIRMWARE_VERSION_LEN);
+        Ok(buf)
+    }
+
+    /// The connected network's SSID followed by its (never disclosed) password
+    pub fn read_ssid(&self) -> DeviceResult<Vec<u8>> {
+        let mut buf = Vec::with_capacity(SSID_LEN + PASSWORD_LEN);
+        put_padded(&mut buf, &self.adapter_config()?.ssid, SSID_LEN);
+        buf.resize(SSID_LEN + PASSWORD_LEN, 0);
+        Ok(buf)
+    }
+
+    pub fn wifi_status(&self) -> DeviceResult<u8> {
+        Ok(if self.adapter()?.is_connected() { WIFI_STATUS_CONNECTED } else { WIFI_STATUS_DISCONNECTED })
+    }
+
+    /// Scan for networks, returning how many were found
+    pub fn scan_networks(&mut self) -> DeviceResult<u8> {
+        let mut scan = self.adapter()?.scan_networks();
+        scan.truncate(u8::MAX as usize);
+        self.scan = scan;
+        Ok(self.scan.len() as u8)
+    }
+
+    pub fn scan_result(&self, index: usize) -> DeviceResult<&ScanResult> {
+        self.scan.get(index).ok_or(DeviceError::InvalidDeviceId)
+    }
+
+    /// A result from the last scan as a SCAN_RESULT_LEN byte record
+    pub fn read_scan_result(&self, index: usize) -> DeviceResult<Vec<u8>> {
+        let result = self.scan_result(index)?;
+        let mut buf = Vec::with_capacity(SCAN_RESULT_LEN);
+        put_padded(&mut buf, &result.ssid, SSID_LEN);
+        buf.push(result.rssi as u8);
+        Ok(buf)
+    }
+
+    /// Run a Fuji device command frame, returning the data to send back (if any)
+    pub fn command(&mut self, command: u8, aux1: u8, aux2: u8, payload: &[u8]) -> DeviceResult<Vec<u8>> {
+        match command {
+            FUJICMD_READ_HOST_SLOTS => Ok(self.read_host_slots()),
+            FUJICMD_WRITE_HOST_SLOTS => self.write_host_slots(payload).map(|_| Vec::new()),
+            FUJICMD_READ_DEVICE_SLOTS => Ok(self.read_device_slots()),
+            FUJICMD_WRITE_DEVICE_SLOTS => self.write_device_slots(payload).map(|_| Vec::new()),
+            FUJICMD_MOUNT_HOST => self.mount_host(aux1 as usize).map(|_| Vec::new()),
+            FUJICMD_UNMOUNT_HOST => self.unmount_host(aux1 as usize).map(|_| Vec::new()),
+            FUJICMD_MOUNT_IMAGE => self.mount_disk_image(aux1 as usize, aux2).map(|_| Vec::new()),
+            FUJICMD_UNMOUNT_IMAGE => self.unmount_disk_image(aux1 as usize).map(|_| Vec::new()),
+            // The host slot is in the high nibble of aux2 and the mode in the low one
+            FUJICMD_SET_DEVICE_FULLPATH => self
+                .set_device_filename(aux1 as usize, aux2 >> 4, aux2 & 0x0F, &get_padded(payload))
+                .map(|_| Vec::new()),
+            FUJICMD_GET_ADAPTERCONFIG => self.read_adapter_config(),
+            FUJICMD_GET_SSID => self.read_ssid(),
+            FUJICMD_GET_WIFISTATUS => self.wifi_status().map(|status| vec![status]),
+            FUJICMD_GET_WIFI_ENABLED => Ok(vec![self.adapter.is_some() as u8]),
+            FUJICMD_SCAN_NETWORKS => self.scan_networks().map(|count| vec![count]),
+            FUJICMD_GET_SCAN_RESULT => self.read_scan_result(aux1 as usize),
+            _ => Err(DeviceError::NotSupported),
+        }
+    }
+}
+
+fn valid_host_slot(host_slot: u8) -> bool {
+    host_slot == HOST_SLOT_EMPTY || (host_slot as usize) < MAX_HOSTS
+}
+
+/// The longest prefix of `s` that fits in `max` bytes without splitting a character
+fn truncate(s: &str, max: usize) -> &str {
+    let mut end = s.len().min(max);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+fn put_padded(buf: &mut Vec<u8>, s: &str, len: usize) {
+    let s = truncate(s, len - 1);
+    buf.extend_from_slice(s.as_bytes());
+    buf.resize(buf.len() + len - s.len(), 0);
+}
+
+/// The string in a NUL-padded record
+fn get_padded(record: &[u8]) -> String {
+    let end = record.iter().position(|&b| b == 0).unwrap_or(record.len());
+    String::from_utf8_lossy(&record[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_records() -> DeviceResult<()> {
+        let mut fuji = FujiConfig::new();
+        fuji.set_host_name(0, "SD")?;
+        fuji.set_host_name(1, "tnfs.example.com")?;
+        fuji.set_device_filename(0, 1, MOUNT_MODE_READ, "/games/STAR RAIDERS.ATR")?;
+
+        let hosts = fuji.read_host_slots();
+        assert_eq!(hosts.len(), MAX_HOSTS * HOST_NAME_LEN);
+        assert_eq!(&hosts[..3], b"SD\0");
+        assert_eq!(&hosts[32..48], b"tnfs.example.com");
+
+        let devices = fuji.read_device_slots();
+        assert_eq!(devices.len(), MAX_DISK_DEVICES * DEVICE_SLOT_LEN);
+        assert_eq!(&devices[..2], &[1, MOUNT_MODE_READ]);
+        assert_eq!(&devices[2..25], b"/games/STAR RAIDERS.ATR");
+        assert_eq!(devices[DEVICE_SLOT_LEN], HOST_SLOT_EMPTY);
+
+        // Writing the records back into a fresh device gives the same slots
+        let mut copy = FujiConfig::new();
+        copy.command(FUJICMD_WRITE_HOST_SLOTS, 0, 0, &hosts)?;
+        copy.command(FUJICMD_WRITE_DEVICE_SLOTS, 0, 0, &devices)?;
+        assert_eq!(copy.host_slots(), fuji.host_slots());
+        assert_eq!(copy.disk_slots(), fuji.disk_slots());
+        assert_eq!(copy.write_host_slots(&hosts[1..]), Err(DeviceError::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_device_slots_change_nothing() -> DeviceResult<()> {
+        let mut fuji = FujiConfig::new();
+        fuji.set_host_name(1, "tnfs.example.com")?;
+        fuji.set_device_filename(0, 1, MOUNT_MODE_READ, "/dos.atr")?;
+
+        // A bad host slot in the last record leaves the first one as it was
+        let mut devices = vec![0u8; MAX_DISK_DEVICES * DEVICE_SLOT_LEN];
+        devices[(MAX_DISK_DEVICES - 1) * DEVICE_SLOT_LEN] = MAX_HOSTS as u8;
+        assert_eq!(fuji.write_device_slots(&devices), Err(DeviceError::InvalidDeviceId));
+        assert_eq!(fuji.disk_slot(0).unwrap().filename, "/dos.atr");
+        Ok(())
+    }
+
+    #[test]
+    fn test_renaming_host_empties_its_device_slots() -> DeviceResult<()> {
+        let mut fuji = FujiConfig::new();
+        fuji.set_host_name(1, "tnfs.example.com")?;
+        fuji.set_host_name(2, "SD")?;
+        fuji.set_device_filename(0, 1, MOUNT_MODE_READ, "/dos.atr")?;
+        fuji.set_device_filename(1, 2, MOUNT_MODE_READ, "/games.atr")?;
+        fuji.mount_host(1)?;
+        fuji.mount_disk_image(0, MOUNT_MODE_READ)?;
+
+        // Writing the same name back keeps the slot
+        fuji.set_host_name(1, "tnfs.example.com")?;
+        assert!(fuji.disk_slot(0).unwrap().mounted);
+
+        fuji.set_host_name(1, "other.example.com")?;
+        assert!(fuji.disk_slot(0).unwrap().is_empty());
+        assert!(!fuji.disk_slot(0).unwrap().mounted);
+        assert_eq!(fuji.disk_slot(1).unwrap().filename, "/games.atr");
+
+        // Replacing the whole table does the same for the slots whose host changed
+        let mut hosts = fuji.read_host_slots();
+        hosts[2 * HOST_NAME_LEN..3 * HOST_NAME_LEN].fill(0);
+        fuji.write_host_slots(&hosts)?;
+        assert!(fuji.disk_slot(1).unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mount_and_unmount() -> DeviceResult<()> {
+        let mut fuji = FujiConfig::new();
+        fuji.set_host_name(2, "tnfs.example.com")?;
+        fuji.command(FUJICMD_SET_DEVICE_FULLPATH, 3, (2 << 4) | MOUNT_MODE_READ, b"/dos.atr\0\0")?;
+        assert_eq!(fuji.disk_slot(3).unwrap().filename, "/dos.atr");
+
+        // The host has to be mounted before its images
+        assert_eq!(fuji.mount_disk_image(3, MOUNT_MODE_WRITE).err(), Some(DeviceError::NotReady));
+        fuji.command(FUJICMD_MOUNT_HOST, 2, 0, &[])?;
+        fuji.command(FUJICMD_MOUNT_IMAGE, 3, MOUNT_MODE_WRITE, &[])?;
+        assert!(fuji.disk_slot(3).unwrap().mounted);
+        assert_eq!(fuji.disk_slot(3).unwrap().mode, MOUNT_MODE_WRITE);
+
+        assert_eq!(fuji.mount_host(0), Err(DeviceError::NotReady));
+        assert_eq!(fuji.mount_disk_image(0, MOUNT_MODE_READ).err(), Some(DeviceError::NotReady));
+        assert_eq!(fuji.mount_disk_image(3, 7).err(), Some(DeviceError::InvalidOperation));
+
+        // Unmounting the host takes its images with it
+        fuji.command(FUJICMD_UNMOUNT_HOST, 2, 0, &[])?;
+        assert!(!fuji.disk_slot(3).unwrap().mounted);
+        assert_eq!(fuji.command(0x00, 0, 0, &[]), Err(DeviceError::NotSupported));
+        Ok(())
+    }
+
+    struct TestAdapter;
+
+    impl AdapterInfoProvider for TestAdapter {
+        fn adapter_config(&self) -> AdapterConfig {
+            AdapterConfig {
+                ssid: "RetroNet".to_string(),
+                hostname: "fujinet".to_string(),
+                local_ip: std::net::Ipv4Addr::new(192, 168, 1, 64),
+                gateway: std::net::Ipv4Addr::new(192, 168, 1, 1),
+                netmask: std::net::Ipv4Addr::new(255, 255, 255, 0),
+                dns: std::net::Ipv4Addr::new(192, 168, 1, 1),
+                mac: [0x02, 0, 0, 0, 0, 0x64],
+                bssid: [0; 6],
+                firmware_version: "1.2.3".to_string(),
+            }
+        }
+
+        fn scan_networks(&self) -> Vec<ScanResult> {
+            vec![
+                ScanResult { ssid: "RetroNet".to_string(), rssi: -40 },
+                ScanResult { ssid: "Neighbours".to_string(), rssi: -80 },
+            ]
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_adapter_queries() -> DeviceResult<()> {
+        assert_eq!(FujiConfig::new().command(FUJICMD_GET_ADAPTERCONFIG, 0, 0, &[]), Err(DeviceError::NotReady));
+        assert_eq!(FujiConfig::new().command(FUJICMD_GET_WIFI_ENABLED, 0, 0, &[])?, [0]);
+
+        let mut fuji = FujiConfig::with_adapter(Box::new(TestAdapter));
+        let config = fuji.command(FUJICMD_GET_ADAPTERCONFIG, 0, 0, &[])?;
+        assert_eq!(config.len(), ADAPTER_CONFIG_LEN);
+        assert_eq!(&config[..9], b"RetroNet\0");
+        assert_eq!(&config[33..41], b"fujinet\0");
+        assert_eq!(&config[97..101], &[192, 168, 1, 64]);
+        assert_eq!(&config[105..109], &[255, 255, 255, 0]);
+        assert_eq!(&config[113..119], &[0x02, 0, 0, 0, 0, 0x64]);
+        assert_eq!(&config[125..131], b"1.2.3\0");
+
+        let ssid = fuji.command(FUJICMD_GET_SSID, 0, 0, &[])?;
+        assert_eq!(ssid.len(), SSID_LEN + PASSWORD_LEN);
+        assert!(ssid[SSID_LEN..].iter().all(|&b| b == 0));
+        assert_eq!(fuji.command(FUJICMD_GET_WIFISTATUS, 0, 0, &[])?, [WIFI_STATUS_CONNECTED]);
+
+        // Results are only there once a scan has run
+        assert_eq!(fuji.command(FUJICMD_GET_SCAN_RESULT, 0, 0, &[]), Err(DeviceError::InvalidDeviceId));
+        assert_eq!(fuji.command(FUJICMD_SCAN_NETWORKS, 0, 0, &[])?, [2]);
+        let result = fuji.command(FUJICMD_GET_SCAN_RESULT, 1, 0, &[])?;
+        assert_eq!(result.len(), SCAN_RESULT_LEN);
+        assert_eq!(&result[..11], b"Neighbours\0");
+        assert_eq!(result[SSID_LEN] as i8, -80);
+        Ok(())
+    }
+}
@@ -1,31 +1,47 @@
 use crate::device::network::NetworkUrl;
+use crate::device::{DeviceError, DeviceResult};
 
-pub const MAX_NETWORK_DEVICES: usize = 8;
+/// Number of network units on a FujiNet (N1: to N8:), used unless a manager is built with more
+pub const DEFAULT_NETWORK_UNITS: usize = 8;
+
+/// Unit numbers are a single byte, so N255: is the highest a spec can name
+pub const MAX_NETWORK_UNITS: usize = u8::MAX as usize;
 
 #[derive(Default)]
 pub struct DeviceState {
     pub mode: u8,
     pub trans: u8,
     pub url: Option<NetworkUrl>,
+    /// Working directory that relative specs resolve against; kept when the device is closed
+    pub prefix: Option<String>,
+    /// Bumped whenever the unit's state changes, so an open that finishes after the unit
+    /// was closed or reopened doesn't record its state over the newer one
+    generation: u64,
 }
 
 pub struct DeviceManager {
-    devices: [DeviceState; MAX_NETWORK_DEVICES],
+    devices: Vec<DeviceState>,
 }
 
 impl DeviceManager {
     pub fn new() -> Self {
+        Self::with_units(DEFAULT_NETWORK_UNITS)
+    }
+
+    /// State for the given number of units, capped at MAX_NETWORK_UNITS
+    pub fn with_units(units: usize) -> Self {
         Self {
-            devices: std::array::from_fn(|_| DeviceState::default()),
+            devices: (0..units.min(MAX_NETWORK_UNITS)).map(|_| DeviceState::default()).collect(),
         }
     }
 
+    /// How many units there are
+    pub fn unit_count(&self) -> usize {
+        self.devices.len()
+    }
+
     pub fn get_device(&mut self, device_id: usize) -> Option<&mut DeviceState> {
-        if device_id < MAX_NETWORK_DEVICES {
-            Some(&mut self.devices[device_id])
-        } else {
-            None
-        }
+        self.devices.get_mut(device_id)
     }
 
     pub fn set_device_state(&mut self, device_id: usize, mode: u8, trans: u8, url: NetworkUrl) -> bool {
@@ -33,20 +49,48 @@ impl DeviceManager {
             device.mode = mode;
             device.trans = trans;
             device.url = Some(url);
+            device.generation += 1;
             true
         } else {
             false
         }
     }
 
+    /// The unit's current prefix, if one has been set
+    pub fn prefix(&self, device_id: usize) -> Option<&str> {
+        self.devices.get(device_id)?.prefix.as_deref()
+    }
+
+    /// Change the unit's prefix: an absolute URL replaces it, a relative path or ".." navigates
+    /// from it, and an empty path clears it
+    pub fn change_prefix(&mut self, device_id: usize, path: &str) -> DeviceResult<()> {
+        let device = self.get_device(device_id).ok_or(DeviceError::InvalidDeviceId)?;
+        device.prefix = NetworkUrl::change_prefix(device.prefix.as_deref(), path)?;
+        Ok(())
+    }
+
     pub fn clear_device_state(&mut self, device_id: usize) -> bool {
         if let Some(device) = self.get_device(device_id) {
             device.mode = 0;
             device.trans = 0;
             device.url = None;
+            device.generation += 1;
             true
         } else {
             false
         }
     }
+
+    /// The unit's generation, to be handed back to finish_open
+    pub fn generation(&self, device_id: usize) -> Option<u64> {
+        Some(self.devices.get(device_id)?.generation)
+    }
+
+    /// Record the state of an open that has connected, unless the unit changed since `generation`
+    pub fn finish_open(&mut self, device_id: usize, generation: u64, mode: u8, trans: u8, url: NetworkUrl) -> bool {
+        if self.generation(device_id) != Some(generation) {
+            return false;
+        }
+        self.set_device_state(device_id, mode, trans, url)
+    }
 }
\ No newline at end of file
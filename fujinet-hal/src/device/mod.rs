@@ -1,5 +1,17 @@
 mod device;
 pub mod network;
 pub mod manager;
+pub mod disk;
+pub mod printer;
+pub mod clock;
+pub mod modem;
+pub mod fuji;
+pub mod adapter;
 
-pub use device::{Device, DeviceError, DeviceResult, DeviceStatus}; 
\ No newline at end of file
+pub use device::{Device, DeviceError, DeviceResult, DeviceStatus};
+pub use disk::DiskDevice;
+pub use printer::PrinterDevice;
+pub use clock::ClockDevice;
+pub use modem::ModemDevice;
+pub use fuji::FujiConfig;
+pub use adapter::{AdapterConfig, AdapterInfoProvider, ScanResult};
\ No newline at end of file
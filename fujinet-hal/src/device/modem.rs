@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use std::any::Any;
+use std::collections::VecDeque;
+use super::{Device, DeviceError, DeviceResult, DeviceStatus};
+use super::network::protocols::{ConnectionStatus, ProtocolHandler};
+
+/// Escape sequence that drops an online modem back to command mode
+pub const MODEM_ESCAPE: &[u8] = b"+++";
+
+/// Hayes-style modem that "dials" TCP hosts
+///
+/// In command mode, writes are taken as AT command lines ending in CR or ATASCII EOL
+/// and the result codes are read back. `ATDT host:port` connects through the stream
+/// protocol handler and switches to data mode, where bytes pass straight through until
+/// the remote end hangs up or `+++` is written on its own.
+pub struct ModemDevice {
+    protocol: Box<dyn ProtocolHandler>,
+    command: Vec<u8>,
+    responses: VecDeque<u8>,
+    online: bool,
+    connected: bool,
+    open: bool,
+}
+
+impl ModemDevice {
+    /// `protocol` carries the calls and must accept tcp://host:port URLs
+    pub fn new(protocol: Box<dyn ProtocolHandler>) -> Self {
+        Self {
+            protocol,
+            command: Vec::new(),
+            responses: VecDeque::new(),
+            online: false,
+            connected: false,
+            open: false,
+        }
+    }
+
+    /// True while writes go to the remote host rather than the command interpreter
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    fn respond(&mut self, result: &str) {
+        self.responses.extend(result.as_bytes());
+        self.responses.extend(b"\r\n");
+    }
+
+    async fn hang_up(&mut self) -> DeviceResult<()> {
+        self.online = false;
+        if self.connected {
+            self.connected = false;
+            self.protocol.close().await?;
+        }
+        Ok(())
+    }
+
+    async fn run_command(&mut self, line: &str) -> DeviceResult<()> {
+        let line = line.trim();
+        let Some(command) = line.get(..2).filter(|at| at.eq_ignore_ascii_case("AT")).map(|_| &line[2..]) else {
+            if !line.is_empty() {
+                self.respond("ERROR");
+            }
+            return Ok(());
+        };
+        let upper = command.to_ascii_uppercase();
+
+        if let Some(number) = upper.strip_prefix('D') {
+            let host = number.trim_start_matches(['T', 'P']).trim();
+            if host.is_empty() {
+                self.respond("ERROR");
+                return Ok(());
+            }
+            self.hang_up().await?;
+            match self.protocol.open(&format!("tcp://{}", host.to_ascii_lowercase())).await {
+                Ok(()) => {
+                    self.connected = true;
+                    self.online = true;
+                    self.respond("CONNECT");
+                }
+                Err(_) => self.respond("NO CARRIER"),
+            }
+            return Ok(());
+        }
+
+        match upper.as_str() {
+            "" => self.respond("OK"),
+            "Z" | "H" | "H0" => {
+                self.hang_up().await?;
+                self.respond("OK");
+            }
+            "O" if self.connected => {
+                self.online = true;
+                self.respond("CONNECT");
+            }
+            "O" => self.respond("NO CARRIER"),
+            _ => self.respond("ERROR"),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Device for ModemDevice {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "modem"
+    }
+
+    async fn open(&mut self) -> DeviceResult<()> {
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        self.open = false;
+        self.command.clear();
+        self.responses.clear();
+        self.hang_up().await
+    }
+
+    /// Result codes come first; after that, data from the remote host while online
+    async fn read_bytes(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        if self.responses.is_empty() && self.online {
+            let len = self.protocol.read(buf).await?;
+            if len > 0 {
+                return Ok(len);
+            }
+            if self.protocol.status().await? != ConnectionStatus::Connected {
+                self.hang_up().await?;
+                self.respond("NO CARRIER");
+            }
+        }
+
+        let len = buf.len().min(self.responses.len());
+        for (dst, src) in buf.iter_mut().zip(self.responses.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+
+    async fn write_bytes(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        if self.online {
+            if buf == MODEM_ESCAPE {
+                self.online = false;
+                self.respond("OK");
+                return Ok(buf.len());
+            }
+            return self.protocol.write(buf).await;
+        }
+
+        for &byte in buf {
+            if byte == b'\r' || byte == 0x9B {
+                let line = String::from_utf8_lossy(&std::mem::take(&mut self.command)).into_owned();
+                self.run_command(&line).await?;
+            } else if byte != b'\n' {
+                self.command.push(byte);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    async fn read_block(&mut self, _block: u32, _buf: &mut [u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn write_block(&mut self, _block: u32, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn get_status(&self) -> DeviceResult<DeviceStatus> {
+        Ok(if self.open { DeviceStatus::Ready } else { DeviceStatus::Disconnected })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // Echoes writes back while "connected" to any tcp:// URL except ones for host "busy"
+    #[derive(Default)]
+    struct EchoLine {
+        dialled: Arc<Mutex<Vec<String>>>,
+        echo: Vec<u8>,
+        connected: bool,
+    }
+
+    #[async_trait]
+    impl ProtocolHandler for EchoLine {
+        fn as_any(&self) -> &dyn Any { self }
+        fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+        async fn open(&mut self, url: &str) -> DeviceResult<()> {
+            self.dialled.lock().unwrap().push(url.to_string());
+            if url.contains("busy") {
+                return Err(DeviceError::NetworkError("refused".to_string()));
+            }
+            self.connected = true;
+            Ok(())
+        }
+
+        async fn close(&mut self) -> DeviceResult<()> {
+            self.connected = false;
+            Ok(())
+        }
+
+        async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+            let len = buf.len().min(self.echo.len());
+            buf[..len].copy_from_slice(&self.echo[..len]);
+            self.echo.drain(..len);
+            Ok(len)
+        }
+
+        async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            self.echo.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        async fn status(&self) -> DeviceResult<ConnectionStatus> {
+            Ok(if self.connected { ConnectionStatus::Connected } else { ConnectionStatus::Disconnected })
+        }
+
+        async fn available(&self) -> DeviceResult<usize> {
+            Ok(self.echo.len())
+        }
+    }
+
+    async fn command(modem: &mut ModemDevice, line: &str) -> String {
+        modem.write_bytes(format!("{}\r", line).as_bytes()).await.unwrap();
+        let mut buf = [0u8; 64];
+        let len = modem.read_bytes(&mut buf).await.unwrap();
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_dial_and_hang_up() -> DeviceResult<()> {
+        let line = EchoLine::default();
+        let dialled = line.dialled.clone();
+        let mut modem = ModemDevice::new(Box::new(line));
+        modem.open().await?;
+
+        assert_eq!(command(&mut modem, "AT").await, "OK\r\n");
+        assert_eq!(command(&mut modem, "ATX9").await, "ERROR\r\n");
+        assert_eq!(command(&mut modem, "ATDT busy:23").await, "NO CARRIER\r\n");
+        assert_eq!(command(&mut modem, "atdt BBS.Example:6502").await, "CONNECT\r\n");
+        assert_eq!(*dialled.lock().unwrap(), ["tcp://busy:23", "tcp://bbs.example:6502"]);
+        assert!(modem.is_online());
+
+        // Online, bytes go to the remote end untouched
+        modem.write_bytes(b"AT\r").await?;
+        let mut buf = [0u8; 16];
+        assert_eq!(modem.read_bytes(&mut buf).await?, 3);
+        assert_eq!(&buf[..3], b"AT\r");
+
+        modem.write_bytes(MODEM_ESCAPE).await?;
+        assert!(!modem.is_online());
+        assert_eq!(modem.read_bytes(&mut buf).await?, 4);
+        assert_eq!(command(&mut modem, "ATO").await, "CONNECT\r\n");
+        modem.write_bytes(MODEM_ESCAPE).await?;
+        modem.read_bytes(&mut buf).await?;
+        assert_eq!(command(&mut modem, "ATH").await, "OK\r\n");
+        assert_eq!(command(&mut modem, "ATO").await, "NO CARRIER\r\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Why a network unit wants the host's attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NetworkEventKind {
+    /// Data has arrived and can be read
+    DataAvailable = 1,
+    /// The remote end closed the connection
+    ConnectionClosed = 2,
+    /// A client is waiting to be accepted on a listening unit
+    ClientWaiting = 3,
+}
+
+/// An event raised by the protocol handler of one unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkEvent {
+    pub device_id: usize,
+    pub kind: NetworkEventKind,
+}
+
+/// Callback invoked for each event; may run on a background runtime thread
+pub type EventCallback = Box<dyn Fn(NetworkEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct EventState {
+    pending: VecDeque<NetworkEvent>,
+    callback: Option<Arc<dyn Fn(NetworkEvent) + Send + Sync>>,
+}
+
+/// Collects events raised by protocol handlers, in place of the PROCEED/interrupt line
+///
+/// With a callback registered each event is delivered to it as it happens. Otherwise
+/// events queue until polled, with repeats of an event that is still pending coalesced,
+/// so a unit receiving a stream of data only queues one DataAvailable.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    state: Arc<Mutex<EventState>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A handle for the protocol handler of `device_id` to raise events with
+    pub fn notifier(&self, device_id: usize) -> EventNotifier {
+        EventNotifier { device_id, bus: self.clone() }
+    }
+
+    /// Deliver events to `callback` instead of queueing them; None goes back to polling
+    pub fn set_callback(&self, callback: Option<EventCallback>) {
+        self.state.lock().unwrap().callback = callback.map(Arc::from);
+    }
+
+    pub fn emit(&self, event: NetworkEvent) {
+        let callback = {
+            let mut state = self.state.lock().unwrap();
+            match &state.callback {
+                Some(callback) => callback.clone(),
+                None => {
+                    if !state.pending.contains(&event) {
+                        state.pending.push_back(event);
+                    }
+                    return;
+                }
+            }
+        };
+        // Called without the lock held so the callback can poll or register another
+        callback(event);
+    }
+
+    /// Take the oldest queued event
+    pub fn poll(&self) -> Option<NetworkEvent> {
+        self.state.lock().unwrap().pending.pop_front()
+    }
+
+    /// Whether a unit has an event waiting, the equivalent of PROCEED being asserted for it
+    pub fn is_pending(&self, device_id: usize) -> bool {
+        self.state.lock().unwrap().pending.iter().any(|e| e.device_id == device_id)
+    }
+
+    /// Drop any queued events for a unit, e.g. when it is closed
+    pub fn clear(&self, device_id: usize) {
+        self.state.lock().unwrap().pending.retain(|e| e.device_id != device_id);
+    }
+}
+
+/// Raises events for a single unit; cheap to clone into background tasks
+#[derive(Clone)]
+pub struct EventNotifier {
+    device_id: usize,
+    bus: EventBus,
+}
+
+impl EventNotifier {
+    pub fn device_id(&self) -> usize {
+        self.device_id
+    }
+
+    pub fn notify(&self, kind: NetworkEventKind) {
+        self.bus.emit(NetworkEvent { device_id: self.device_id, kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_coalesces_pending_events() {
+        let bus = EventBus::new();
+        let unit1 = bus.notifier(0);
+        let unit2 = bus.notifier(1);
+
+        unit1.notify(NetworkEventKind::DataAvailable);
+        unit1.notify(NetworkEventKind::DataAvailable);
+        unit2.notify(NetworkEventKind::DataAvailable);
+        unit1.notify(NetworkEventKind::ConnectionClosed);
+        assert!(bus.is_pending(0));
+
+        assert_eq!(bus.poll(), Some(NetworkEvent { device_id: 0, kind: NetworkEventKind::DataAvailable }));
+        assert_eq!(bus.poll(), Some(NetworkEvent { device_id: 1, kind: NetworkEventKind::DataAvailable }));
+
+        // Once polled, the same event can be queued again
+        unit1.notify(NetworkEventKind::DataAvailable);
+        bus.clear(0);
+        assert!(!bus.is_pending(0));
+        assert_eq!(bus.poll(), None);
+    }
+
+    #[test]
+    fn test_callback_receives_events() {
+        let bus = EventBus::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        bus.set_callback(Some(Box::new(move |event| sink.lock().unwrap().push(event))));
+
+        let notifier = bus.notifier(2);
+        notifier.notify(NetworkEventKind::ClientWaiting);
+        notifier.notify(NetworkEventKind::ClientWaiting);
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert_eq!(bus.poll(), None);
+
+        bus.set_callback(None);
+        notifier.notify(NetworkEventKind::DataAvailable);
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert_eq!(bus.poll().map(|e| e.kind), Some(NetworkEventKind::DataAvailable));
+    }
+}
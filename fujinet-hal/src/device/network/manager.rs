@@ -1,10 +1,30 @@
-use crate::device::manager::{DeviceManager, DeviceState};
+use crate::device::manager::{DeviceManager, DeviceState, DEFAULT_NETWORK_UNITS, MAX_NETWORK_UNITS};
 use crate::device::network::NetworkUrl;
-use crate::device::network::protocols::{ProtocolFactory, ProtocolRegistry};
-use crate::device::network::network_device::NetworkDevice;
+use crate::device::network::events::EventBus;
+use crate::device::network::protocols::{ProtocolFactory, ProtocolRegistry, DirectoryFormat, DirectoryListing, OPEN_MODE_DIRECTORY};
+use crate::device::network::protocols::directory::split_wildcard;
+use crate::device::network::protocols::{filesystem, FileSystemCommand};
+use crate::device::network::{DeviceGuard, DeviceSlot, NetworkDevice};
+use crate::device::network::network_device::NetworkDeviceImpl;
 use crate::device::DeviceError;
 use crate::device::DeviceResult;
 use async_trait::async_trait;
+use futures_util::future::{BoxFuture, FutureExt};
+
+/// The I/O half of an operation prepared by one of the begin_* methods
+/// It only locks the unit's own slot, so the manager can be released before it is awaited
+/// and a slow connect or listing doesn't hold up the other units
+pub type SlotTask<T> = BoxFuture<'static, DeviceResult<T>>;
+
+/// The state begin_open records for a unit once its device has connected
+pub struct PendingOpen {
+    pub device_id: usize,
+    /// The unit's generation when the open began
+    pub generation: u64,
+    pub mode: u8,
+    pub trans: u8,
+    pub url: NetworkUrl,
+}
 
 /// Interface for network manager operations
 #[async_trait]
@@ -12,8 +32,35 @@ pub trait NetworkManager {
     /// Parses and validates a network URL, returning the device ID and URL
     fn parse_device_spec(&self, spec: &str) -> DeviceResult<(usize, NetworkUrl)>;
 
+    /// How many units specs can address
+    fn unit_count(&self) -> usize;
+
+    /// Splits the unit off a spec such as "N3:games", returning its device ID and the rest
+    /// Fails with InvalidDeviceId unless the unit is between 1 and unit_count()
+    fn split_unit<'a>(&self, spec: &'a str) -> DeviceResult<(usize, &'a str)> {
+        let (unit, rest) = NetworkUrl::split_unit(spec)?;
+        if unit == 0 || unit as usize > self.unit_count() {
+            return Err(DeviceError::InvalidDeviceId);
+        }
+        Ok((unit as usize - 1, rest))
+    }
+
     /// Opens a new device with the given spec, mode, and trans
-    async fn open_device(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<()>;
+    async fn open_device(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<()> {
+        let (pending, task) = self.begin_open(spec, mode, trans)?;
+        task.await?;
+        self.finish_open(pending);
+        Ok(())
+    }
+
+    /// Clears the unit's state and creates its device; the task closes whatever was open
+    /// on the unit and connects the new device. Hand the PendingOpen to finish_open once
+    /// the task succeeds, so a failed open never leaves a URL behind for the unit
+    fn begin_open(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<(PendingOpen, SlotTask<()>)>;
+
+    /// Records the URL and modes of a unit whose open succeeded
+    /// Returns false, recording nothing, if the unit was closed or reopened in the meantime
+    fn finish_open(&mut self, pending: PendingOpen) -> bool;
 
     /// Finds a device by its spec, returning the device ID and state if found
     async fn find_device(&mut self, spec: &str) -> DeviceResult<Option<(usize, &mut DeviceState)>>;
@@ -22,16 +69,83 @@ pub trait NetworkManager {
     fn get_device(&mut self, device_id: usize) -> Option<&mut DeviceState>;
 
     /// Closes a device by its ID
-    async fn close_device(&mut self, device_id: usize) -> DeviceResult<bool>;
+    async fn close_device(&mut self, device_id: usize) -> DeviceResult<bool> {
+        self.begin_close(device_id).await
+    }
+
+    /// Clears the unit's state; the task disconnects its device
+    /// Resolves to false if there is no such unit
+    fn begin_close(&mut self, device_id: usize) -> SlotTask<bool>;
 
-    /// Gets a network device by its ID
-    fn get_network_device(&mut self, device_id: usize) -> Option<&mut Box<dyn NetworkDevice>>;
+    /// Closes every unit, carrying on past failures and returning the first error
+    async fn close_all_devices(&mut self) -> DeviceResult<()> {
+        self.begin_close_all().await
+    }
+
+    /// Clears every unit's state; the task disconnects their devices
+    fn begin_close_all(&mut self) -> SlotTask<()> {
+        let tasks: Vec<_> = (0..self.unit_count()).map(|device_id| self.begin_close(device_id)).collect();
+        async move {
+            let mut result = Ok(());
+            for task in tasks {
+                if let Err(e) = task.await {
+                    result = result.and(Err(e));
+                }
+            }
+            result
+        }.boxed()
+    }
+
+    /// Gets the slot holding a unit's network device
+    /// The slot has its own lock, so the caller can release the manager before doing I/O
+    fn device_slot(&self, device_id: usize) -> Option<DeviceSlot>;
+
+    /// Gets a network device by its ID, waiting for any operation already running on it
+    /// Drop the guard before closing or reopening the unit, which waits for it too
+    async fn get_network_device(&mut self, device_id: usize) -> Option<DeviceGuard> {
+        DeviceGuard::lock(self.device_slot(device_id)?).await
+    }
+
+    /// Opens a directory listing for the spec, keeping entries that match the wildcard filter
+    /// A trailing wildcard in the URL (e.g. "N1:file:///games/*.ATR") is used when no filter is given
+    /// Returns the device ID; the listing is kept on the unit's device until close_device
+    async fn open_directory(&mut self, spec: &str, filter: Option<&str>, format: DirectoryFormat) -> DeviceResult<usize> {
+        let (device_id, task) = self.begin_directory(spec, filter, format)?;
+        task.await?;
+        Ok(device_id)
+    }
+
+    /// Records the unit's new state and creates its device; the task closes whatever was
+    /// open on the unit and fetches the listing. Returns the device ID with the task
+    fn begin_directory(&mut self, spec: &str, filter: Option<&str>, format: DirectoryFormat) -> DeviceResult<(usize, SlotTask<()>)>;
+
+    /// Runs a filesystem command (rename, delete, mkdir, ...) against the URL in spec
+    /// Uses a fresh handler, so any connection already open on the unit is left alone
+    async fn filesystem_command(&mut self, spec: &str, command: FileSystemCommand) -> DeviceResult<()> {
+        self.begin_filesystem_command(spec, command)?.await
+    }
+
+    /// Creates the handler for filesystem_command; the task runs the command
+    fn begin_filesystem_command(&mut self, spec: &str, command: FileSystemCommand) -> DeviceResult<SlotTask<()>>;
+
+    /// Sets a unit's prefix from a spec such as "N1:TNFS://host/dir/", "N1:games" or "N1:.."
+    /// "N1:" on its own clears the prefix. Returns the device ID
+    fn set_prefix(&mut self, spec: &str) -> DeviceResult<usize>;
+
+    /// Gets a unit's current prefix
+    fn get_prefix(&mut self, device_id: usize) -> Option<String> {
+        self.get_device(device_id)?.prefix.clone()
+    }
+
+    /// Events raised by the units' protocol handlers
+    fn events(&self) -> &EventBus;
 }
 
 /// Concrete implementation of the NetworkManager trait
 pub struct NetworkManagerImpl {
     device_manager: DeviceManager,
     protocol_factory: ProtocolFactory,
+    events: EventBus,
 }
 
 impl NetworkManagerImpl {
@@ -44,56 +158,85 @@ impl NetworkManagerImpl {
         Self {
             device_manager: DeviceManager::new(),
             protocol_factory: ProtocolFactory::new(registry),
+            events: EventBus::new(),
         }
     }
 
     /// Creates a new NetworkManager with a custom protocol registry
     pub fn with_registry(registry: ProtocolRegistry) -> Self {
+        Self::with_units(registry, DEFAULT_NETWORK_UNITS)
+    }
+
+    /// Creates a new NetworkManager with a custom protocol registry and number of units
+    /// Hosts can go beyond the FujiNet's eight units, up to N255:
+    pub fn with_units(registry: ProtocolRegistry, units: usize) -> Self {
+        let units = units.min(MAX_NETWORK_UNITS);
         Self {
-            device_manager: DeviceManager::new(),
-            protocol_factory: ProtocolFactory::new(registry),
+            device_manager: DeviceManager::with_units(units),
+            protocol_factory: ProtocolFactory::with_units(registry, units),
+            events: EventBus::new(),
         }
     }
+
+    /// A new, unconnected device for the URL's protocol
+    fn create_device(&self, url: &NetworkUrl) -> DeviceResult<Box<dyn NetworkDevice>> {
+        let handler = self.protocol_factory.create_handler(url.protocol())?;
+        Ok(Box::new(NetworkDeviceImpl::new(url.url.clone(), handler)))
+    }
+}
+
+/// Disconnect the device in a locked slot, if any, leaving the slot empty and the unit's
+/// pending events dropped
+async fn close_slot(slot: &mut Option<Box<dyn NetworkDevice>>, events: &EventBus, device_id: usize) -> DeviceResult<()> {
+    if let Some(device) = slot.as_mut() {
+        device.disconnect().await?;
+        *slot = None;
+    }
+    events.clear(device_id);
+    Ok(())
 }
 
 #[async_trait]
 impl NetworkManager for NetworkManagerImpl {
     fn parse_device_spec(&self, spec: &str) -> DeviceResult<(usize, NetworkUrl)> {
-        // Parse the network URL
-        let url = NetworkUrl::parse(spec)?;
-
-        // Get the device ID from the URL (N1-N8)
-        let device_id = (url.unit - 1) as usize;
-        if device_id >= 8 {
-            return Err(DeviceError::InvalidDeviceId);
-        }
-
+        // Get the device ID (N1 up to the configured number of units), then parse the
+        // network URL, resolving relative names against the unit's prefix
+        let (device_id, _) = self.split_unit(spec)?;
+        let url = NetworkUrl::parse_with_prefix(spec, self.device_manager.prefix(device_id))?;
         Ok((device_id, url))
     }
 
-    // In NetworkManagerImpl::open_device
-    async fn open_device(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<()> {
-        let (device_id, url) = self.parse_device_spec(spec)?;
-
-        // Close any existing device at this ID
-        self.close_device(device_id).await?;
+    fn unit_count(&self) -> usize {
+        self.protocol_factory.unit_count()
+    }
 
-        // Set device state
-        if !self.device_manager.set_device_state(device_id, mode, trans, url.clone()) {
+    fn begin_open(&mut self, spec: &str, mode: u8, trans: u8) -> DeviceResult<(PendingOpen, SlotTask<()>)> {
+        let (device_id, url) = self.parse_device_spec(spec)?;
+        let mut device = self.create_device(&url)?;
+        if !self.device_manager.clear_device_state(device_id) {
             return Err(DeviceError::InvalidDeviceId);
         }
+        let generation = self.device_manager.generation(device_id).ok_or(DeviceError::InvalidDeviceId)?;
+        let endpoint = url.url.clone();
 
-        // Create/get protocol handler and device
-        self.protocol_factory.get_or_create_device(device_id, url.protocol(), &url).await?;
-
-        // Get the newly created device and connect it
-        if let Some(device) = self.protocol_factory.get_device(device_id) {
-            // Connect using the URL from the spec
-            device.connect(&url.url).await?;
+        // Let the handler know how it's being opened; it connects using the URL from the spec
+        device.protocol_handler().set_mode(mode);
+        device.protocol_handler().set_event_notifier(self.events.notifier(device_id));
+        let slot = self.device_slot(device_id).ok_or(DeviceError::InvalidDeviceId)?;
+        let events = self.events.clone();
+        let pending = PendingOpen { device_id, generation, mode, trans, url };
+        Ok((pending, async move {
+            let mut slot = slot.lock_owned().await;
+            close_slot(&mut slot, &events, device_id).await?;
+            device.connect(&endpoint).await?;
+            *slot = Some(device);
             Ok(())
-        } else {
-            Err(DeviceError::NotReady)
-        }
+        }.boxed()))
+    }
+
+    fn finish_open(&mut self, pending: PendingOpen) -> bool {
+        let PendingOpen { device_id, generation, mode, trans, url } = pending;
+        self.device_manager.finish_open(device_id, generation, mode, trans, url)
     }
 
     async fn find_device(&mut self, spec: &str) -> DeviceResult<Option<(usize, &mut DeviceState)>> {
@@ -101,7 +244,7 @@ impl NetworkManager for NetworkManagerImpl {
         
         if let Some(device) = self.device_manager.get_device(device_id) {
             // Get device from protocol factory to ensure it exists
-            if let Some(_) = self.protocol_factory.get_device(device_id) {
+            if self.protocol_factory.get_device(device_id).await.is_some() {
                 Ok(Some((device_id, device)))
             } else {
                 Ok(None)
@@ -115,23 +258,64 @@ impl NetworkManager for NetworkManagerImpl {
         self.device_manager.get_device(device_id)
     }
 
-    async fn close_device(&mut self, device_id: usize) -> DeviceResult<bool> {
-        // Close device in protocol factory
-        self.protocol_factory.close_device(device_id).await?;
-        
-        // Clear device state
-        if let Some(device) = self.device_manager.get_device(device_id) {
-            device.url = None;
-            device.mode = 0;
-            device.trans = 0;
-            Ok(true)
-        } else {
-            Ok(false)
+    fn begin_close(&mut self, device_id: usize) -> SlotTask<bool> {
+        let exists = self.device_manager.clear_device_state(device_id);
+        let slot = self.device_slot(device_id);
+        let events = self.events.clone();
+        async move {
+            if let Some(slot) = slot {
+                close_slot(&mut *slot.lock().await, &events, device_id).await?;
+            }
+            Ok(exists)
+        }.boxed()
+    }
+
+    fn device_slot(&self, device_id: usize) -> Option<DeviceSlot> {
+        // Get the slot directly from protocol factory
+        self.protocol_factory.slot(device_id)
+    }
+
+    fn begin_directory(&mut self, spec: &str, filter: Option<&str>, format: DirectoryFormat) -> DeviceResult<(usize, SlotTask<()>)> {
+        let (device_id, url) = self.parse_device_spec(spec)?;
+        let (dir_url, wildcard) = split_wildcard(&url.url);
+        let dir_url = dir_url.to_string();
+        let filter = filter.or(wildcard).map(str::to_string);
+
+        let mut device = self.create_device(&url)?;
+        if !self.device_manager.set_device_state(device_id, OPEN_MODE_DIRECTORY, 0, url) {
+            return Err(DeviceError::InvalidDeviceId);
         }
+
+        let slot = self.device_slot(device_id).ok_or(DeviceError::InvalidDeviceId)?;
+        let events = self.events.clone();
+        Ok((device_id, async move {
+            let mut slot = slot.lock_owned().await;
+            close_slot(&mut slot, &events, device_id).await?;
+            let handler = device.protocol_handler().as_directory().ok_or(DeviceError::NotSupported)?;
+            let entries = handler.list_directory(&dir_url).await?;
+            let free_space = handler.free_space(&dir_url).await?;
+            device.set_directory(Some(DirectoryListing::new(entries, filter.as_deref(), free_space, format)));
+            *slot = Some(device);
+            Ok(())
+        }.boxed()))
+    }
+
+    fn begin_filesystem_command(&mut self, spec: &str, command: FileSystemCommand) -> DeviceResult<SlotTask<()>> {
+        let (_, url) = self.parse_device_spec(spec)?;
+        let mut handler = self.protocol_factory.create_handler(url.protocol())?;
+        Ok(async move {
+            let fs = handler.as_filesystem().ok_or(DeviceError::NotSupported)?;
+            filesystem::execute(fs, &url.url, &command).await
+        }.boxed())
+    }
+
+    fn set_prefix(&mut self, spec: &str) -> DeviceResult<usize> {
+        let (device_id, path) = self.split_unit(spec)?;
+        self.device_manager.change_prefix(device_id, path)?;
+        Ok(device_id)
     }
 
-    fn get_network_device(&mut self, device_id: usize) -> Option<&mut Box<dyn NetworkDevice>> {
-        // Get the device directly from protocol factory
-        self.protocol_factory.get_device(device_id)
+    fn events(&self) -> &EventBus {
+        &self.events
     }
 } 
\ No newline at end of file
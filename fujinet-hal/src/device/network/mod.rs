@@ -1,8 +1,10 @@
+pub mod events;
 pub mod manager;
 pub mod protocols;
 pub mod url;
 mod network_device;
 
 pub use url::NetworkUrl;
+pub use events::{EventBus, EventNotifier, NetworkEvent, NetworkEventKind};
 pub use manager::NetworkManager;
-pub use network_device::{NetworkDevice, NetworkDeviceImpl}; 
\ No newline at end of file
+pub use network_device::{NetworkDevice, NetworkDeviceImpl, DeviceSlot, DeviceGuard}; 
\ No newline at end of file
@@ -1,7 +1,11 @@
 use async_trait::async_trait;
 use crate::device::{Device, DeviceResult, DeviceError, DeviceStatus};
 use std::any::Any;
-use super::protocols::{ProtocolHandler, ConnectionStatus};
+use std::io::SeekFrom;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tokio::sync::OwnedMutexGuard;
+use super::protocols::{ProtocolHandler, ConnectionStatus, DirectoryListing};
 use super::url::NetworkUrl;
 
 #[async_trait]
@@ -18,11 +22,66 @@ pub trait NetworkDevice: Device + Send + Sync {
 
     /// Gets the protocol handler for this device
     fn protocol_handler(&mut self) -> &mut dyn ProtocolHandler;
+
+    /// The listing opened on this device with NetworkManager::open_directory, if any
+    fn directory(&mut self) -> Option<&mut DirectoryListing>;
+
+    /// Attach a listing to the device, replacing any earlier one
+    fn set_directory(&mut self, listing: Option<DirectoryListing>);
+
+    /// Writes the whole buffer, waiting for the protocol's send buffer to drain whenever it fills
+    /// Fails if the protocol stops accepting data with nothing left to send
+    async fn write_all(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            let len = self.write_bytes(&buf[written..]).await?;
+            written += len;
+            if len == 0 {
+                if self.protocol_handler().pending().await? == 0 {
+                    return Err(DeviceError::IoError(format!("only {} of {} bytes were accepted", written, buf.len())));
+                }
+                self.protocol_handler().flush().await?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// A unit's device behind its own lock, so each unit can be driven from a different thread
+/// while the others carry on. Empty while nothing is open on the unit
+pub type DeviceSlot = Arc<tokio::sync::Mutex<Option<Box<dyn NetworkDevice>>>>;
+
+/// Exclusive access to the device open on one unit
+/// Held across awaits without holding up I/O on any other unit
+pub struct DeviceGuard(OwnedMutexGuard<Option<Box<dyn NetworkDevice>>>);
+
+impl DeviceGuard {
+    /// Lock a slot, waiting for any operation already running on the unit
+    /// Returns None if no device is open on it
+    pub async fn lock(slot: DeviceSlot) -> Option<Self> {
+        let guard = slot.lock_owned().await;
+        guard.is_some().then(|| Self(guard))
+    }
+}
+
+impl Deref for DeviceGuard {
+    type Target = Box<dyn NetworkDevice>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().expect("DeviceGuard is only created for an open device")
+    }
+}
+
+impl DerefMut for DeviceGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut().expect("DeviceGuard is only created for an open device")
+    }
 }
 
 pub struct NetworkDeviceImpl {
     endpoint: String,
     protocol: Box<dyn ProtocolHandler>,
+    directory: Option<DirectoryListing>,
 }
 
 impl NetworkDeviceImpl {
@@ -30,6 +89,7 @@ impl NetworkDeviceImpl {
         Self {
             endpoint,
             protocol,
+            directory: None,
         }
     }
 
@@ -60,6 +120,14 @@ impl NetworkDevice for NetworkDeviceImpl {
     fn protocol_handler(&mut self) -> &mut dyn ProtocolHandler {
         &mut *self.protocol
     }
+
+    fn directory(&mut self) -> Option<&mut DirectoryListing> {
+        self.directory.as_mut()
+    }
+
+    fn set_directory(&mut self, listing: Option<DirectoryListing>) {
+        self.directory = listing;
+    }
 }
 
 #[async_trait]
@@ -84,12 +152,27 @@ impl Device for NetworkDeviceImpl {
         self.protocol.write(buf).await
     }
 
-    async fn read_block(&mut self, _block: u32, _buf: &mut [u8]) -> DeviceResult<usize> {
-        Err(DeviceError::InvalidOperation)
+    /// Blocks are buf.len() bytes long; only protocols with random access support this
+    async fn read_block(&mut self, block: u32, buf: &mut [u8]) -> DeviceResult<usize> {
+        let seekable = self.protocol.as_seekable().ok_or(DeviceError::InvalidOperation)?;
+        seekable.seek(SeekFrom::Start(block as u64 * buf.len() as u64)).await?;
+
+        // Reads may return short, so keep going until the block is full or the resource ends
+        let mut filled = 0;
+        while filled < buf.len() {
+            let len = self.protocol.read(&mut buf[filled..]).await?;
+            if len == 0 {
+                break;
+            }
+            filled += len;
+        }
+        Ok(filled)
     }
 
-    async fn write_block(&mut self, _block: u32, _buf: &[u8]) -> DeviceResult<usize> {
-        Err(DeviceError::InvalidOperation)
+    async fn write_block(&mut self, block: u32, buf: &[u8]) -> DeviceResult<usize> {
+        let seekable = self.protocol.as_seekable().ok_or(DeviceError::InvalidOperation)?;
+        seekable.seek(SeekFrom::Start(block as u64 * buf.len() as u64)).await?;
+        self.protocol.write(buf).await
     }
 
     async fn get_status(&self) -> DeviceResult<DeviceStatus> {
@@ -113,7 +196,7 @@ impl Device for NetworkDeviceImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::device::network::protocols::{ProtocolHandler, ConnectionStatus};
+    use crate::device::network::protocols::{ProtocolHandler, ConnectionStatus, SeekableHandler};
     use async_trait::async_trait;
     use std::sync::{Arc, Mutex};
 
@@ -122,6 +205,8 @@ mod tests {
         status: Arc<Mutex<ConnectionStatus>>,
         write_data: Arc<Mutex<Vec<u8>>>,
         read_data: Arc<Mutex<Vec<u8>>>,
+        /// Accept no more than this many bytes in all
+        write_limit: Option<usize>,
     }
 
     #[async_trait]
@@ -147,8 +232,11 @@ mod tests {
         }
 
         async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
-            self.write_data.lock().unwrap().extend_from_slice(buf);
-            Ok(buf.len())
+            let mut write_data = self.write_data.lock().unwrap();
+            let room = self.write_limit.map_or(buf.len(), |limit| limit - write_data.len());
+            let len = buf.len().min(room);
+            write_data.extend_from_slice(&buf[..len]);
+            Ok(len)
         }
 
         async fn status(&self) -> DeviceResult<ConnectionStatus> {
@@ -160,6 +248,61 @@ mod tests {
         }
     }
 
+    // In-memory random access protocol that returns at most 3 bytes per read
+    #[derive(Default)]
+    struct SeekableTestProtocol {
+        data: Vec<u8>,
+        pos: usize,
+    }
+
+    #[async_trait]
+    impl ProtocolHandler for SeekableTestProtocol {
+        fn as_any(&self) -> &dyn std::any::Any { self }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+        async fn open(&mut self, _endpoint: &str) -> DeviceResult<()> { Ok(()) }
+        async fn close(&mut self) -> DeviceResult<()> { Ok(()) }
+
+        async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+            let len = buf.len().min(3).min(self.data.len().saturating_sub(self.pos));
+            buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+
+        async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            let end = self.pos + buf.len();
+            if self.data.len() < end {
+                self.data.resize(end, 0);
+            }
+            self.data[self.pos..end].copy_from_slice(buf);
+            self.pos = end;
+            Ok(buf.len())
+        }
+
+        async fn status(&self) -> DeviceResult<ConnectionStatus> { Ok(ConnectionStatus::Connected) }
+        async fn available(&self) -> DeviceResult<usize> { Ok(self.data.len() - self.pos) }
+
+        fn as_seekable(&mut self) -> Option<&mut dyn SeekableHandler> {
+            Some(self)
+        }
+    }
+
+    #[async_trait]
+    impl SeekableHandler for SeekableTestProtocol {
+        async fn seek(&mut self, pos: SeekFrom) -> DeviceResult<u64> {
+            let SeekFrom::Start(offset) = pos else {
+                return Err(DeviceError::NotSupported);
+            };
+            self.pos = offset as usize;
+            Ok(offset)
+        }
+
+        fn tell(&self) -> u64 {
+            self.pos as u64
+        }
+    }
+
     // Helper function to create a boxed test protocol
     fn create_test_protocol() -> Box<dyn ProtocolHandler> {
         Box::new(TestProtocol::default())
@@ -223,4 +366,34 @@ mod tests {
         assert!(device.write_block(0, &test_data).await.is_err());
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_block_operations_on_seekable_protocol() -> DeviceResult<()> {
+        let protocol = SeekableTestProtocol { data: (0u8..20).collect(), pos: 0 };
+        let mut device = NetworkDeviceImpl::new("test://disk.img".to_string(), Box::new(protocol));
+
+        let mut block = [0u8; 8];
+        assert_eq!(device.read_block(1, &mut block).await?, 8);
+        assert_eq!(block, [8, 9, 10, 11, 12, 13, 14, 15]);
+
+        // The last block is short
+        assert_eq!(device.read_block(2, &mut block).await?, 4);
+        assert_eq!(&block[..4], &[16, 17, 18, 19]);
+
+        assert_eq!(device.write_block(0, &[0xAA; 8]).await?, 8);
+        assert_eq!(device.read_block(0, &mut block).await?, 8);
+        assert_eq!(block, [0xAA; 8]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_all_fails_when_writes_stop() -> DeviceResult<()> {
+        let protocol = TestProtocol { write_limit: Some(4), ..Default::default() };
+        let mut device = NetworkDeviceImpl::new("test://example.com".to_string(), Box::new(protocol));
+        device.open().await?;
+
+        assert_eq!(device.write_all(b"ABC").await?, 3);
+        assert!(matches!(device.write_all(b"DEF").await, Err(DeviceError::IoError(_))));
+        Ok(())
+    }
+}
@@ -1,7 +1,28 @@
 use super::HttpClient;
+use super::websocket_client::WebSocketClient;
+use super::mqtt_client::MqttClient;
+use super::tcp_client::TcpClient;
 
 /// Trait for creating platform-specific HTTP clients
 pub trait HttpClientProvider: Send {
     /// Creates a new HTTP client
     fn create_http_client(&self) -> Box<dyn HttpClient>;
-} 
\ No newline at end of file
+}
+
+/// Trait for creating platform-specific WebSocket clients
+pub trait WebSocketClientProvider: Send + Sync {
+    /// Creates a new WebSocket client
+    fn create_websocket_client(&self) -> Box<dyn WebSocketClient>;
+}
+
+/// Trait for creating platform-specific MQTT clients
+pub trait MqttClientProvider: Send + Sync {
+    /// Creates a new MQTT client
+    fn create_mqtt_client(&self) -> Box<dyn MqttClient>;
+}
+
+/// Trait for creating platform-specific TCP clients
+pub trait TcpClientProvider: Send + Sync {
+    /// Creates a new TCP client
+    fn create_tcp_client(&self) -> Box<dyn TcpClient>;
+}
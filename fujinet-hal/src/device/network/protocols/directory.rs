@@ -0,0 +1,283 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use async_trait::async_trait;
+use crate::device::DeviceResult;
+
+/// Sector size used when reporting sizes and free space in listings
+const SECTOR_SIZE: u64 = 128;
+
+/// Largest sector count shown before a listing switches to "999+"
+const MAX_SECTORS: u64 = 999;
+
+/// Width of the name column in long listings
+const LONG_NAME_WIDTH: usize = 24;
+
+/// A single entry from a directory listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryEntry {
+    pub name: String,
+    /// Size in bytes (0 for directories)
+    pub size: u64,
+    /// Last modification time, if known
+    pub modified: Option<SystemTime>,
+    pub is_dir: bool,
+}
+
+/// How a listing is rendered when read as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirectoryFormat {
+    /// Atari DOS style 8.3 names with sector counts
+    #[default]
+    Short,
+    /// Full names, byte sizes and dates, followed by a free space line
+    Long,
+}
+
+/// Optional capability for protocol handlers that can list directories
+#[async_trait]
+pub trait DirectoryHandler: Send + Sync {
+    /// List the entries of the directory at `url`
+    async fn list_directory(&mut self, url: &str) -> DeviceResult<Vec<DirectoryEntry>>;
+
+    /// Free space in bytes on the volume holding `url`, if the protocol can tell
+    async fn free_space(&mut self, _url: &str) -> DeviceResult<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Case-insensitive wildcard match supporting `*` (any run) and `?` (any one character)
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Split a trailing wildcard segment (e.g. "*.ATR") off a directory URL
+pub fn split_wildcard(url: &str) -> (&str, Option<&str>) {
+    match url.rsplit_once('/') {
+        Some((dir, last)) if last.contains(['*', '?']) => {
+            // Keep the slash when the wildcard sits directly under the root ("file:///*.ATR")
+            let dir = if dir.ends_with('/') { &url[..url.len() - last.len()] } else { dir };
+            (dir, Some(last))
+        }
+        _ => (url, None),
+    }
+}
+
+fn sectors(bytes: u64) -> String {
+    let sectors = bytes.div_ceil(SECTOR_SIZE);
+    if sectors > MAX_SECTORS {
+        "999+".to_string()
+    } else {
+        format!("{:03}", sectors)
+    }
+}
+
+/// Days since the epoch to (year, month, day) in the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Format a timestamp as "YYYY-MM-DD HH:MM" (UTC)
+fn format_time(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let rem = secs.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, rem / 3600, (rem % 3600) / 60)
+}
+
+/// One line of a short listing: ":" marks directories, then an 8.3 name and a sector count
+pub fn format_short_entry(entry: &DirectoryEntry) -> String {
+    let (base, ext) = match entry.name.rsplit_once('.') {
+        Some((base, ext)) if !base.is_empty() && !entry.is_dir => (base, ext),
+        _ => (entry.name.as_str(), ""),
+    };
+    let base: String = base.to_uppercase().chars().take(8).collect();
+    let ext: String = ext.to_uppercase().chars().take(3).collect();
+    let marker = if entry.is_dir { ':' } else { ' ' };
+    format!("{} {:<8} {:<3} {}", marker, base, ext, sectors(entry.size))
+}
+
+/// One line of a long listing: name, size in bytes (or <DIR>) and modification time
+pub fn format_long_entry(entry: &DirectoryEntry) -> String {
+    let name: String = entry.name.chars().take(LONG_NAME_WIDTH).collect();
+    let size = if entry.is_dir { "<DIR>".to_string() } else { entry.size.to_string() };
+    let modified = entry.modified.map(format_time).unwrap_or_default();
+    format!("{:<width$} {:>10} {}", name, size, modified, width = LONG_NAME_WIDTH).trim_end().to_string()
+}
+
+/// The free space footer line, e.g. "042 FREE SECTORS" or "999+FREE SECTORS"
+pub fn format_free_space(free: Option<u64>) -> String {
+    let sectors = free.map_or("999+".to_string(), sectors);
+    if sectors.len() > 3 {
+        format!("{}FREE SECTORS", sectors)
+    } else {
+        format!("{} FREE SECTORS", sectors)
+    }
+}
+
+/// An open, filtered directory listing
+/// Entries can be taken one at a time, or the whole listing read as formatted text
+#[derive(Debug, Default)]
+pub struct DirectoryListing {
+    entries: Vec<DirectoryEntry>,
+    next: usize,
+    free_space: Option<u64>,
+    text: Vec<u8>,
+    text_pos: usize,
+}
+
+impl DirectoryListing {
+    /// Build a listing, keeping only entries whose names match `filter`
+    pub fn new(entries: Vec<DirectoryEntry>, filter: Option<&str>, free_space: Option<u64>, format: DirectoryFormat) -> Self {
+        let entries: Vec<DirectoryEntry> = match filter {
+            Some(pattern) if !pattern.is_empty() => entries.into_iter().filter(|e| wildcard_match(pattern, &e.name)).collect(),
+            _ => entries,
+        };
+        let text = Self::format(&entries, free_space, format);
+        Self { entries, next: 0, free_space, text, text_pos: 0 }
+    }
+
+    /// Render entries as text, one line per entry
+    pub fn format(entries: &[DirectoryEntry], free_space: Option<u64>, format: DirectoryFormat) -> Vec<u8> {
+        let mut text = String::new();
+        for entry in entries {
+            let line = match format {
+                DirectoryFormat::Short => format_short_entry(entry),
+                DirectoryFormat::Long => format_long_entry(entry),
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+        if format == DirectoryFormat::Long {
+            text.push_str(&format_free_space(free_space));
+            text.push('\n');
+        }
+        text.into_bytes()
+    }
+
+    /// All entries that passed the filter
+    pub fn entries(&self) -> &[DirectoryEntry] {
+        &self.entries
+    }
+
+    pub fn free_space(&self) -> Option<u64> {
+        self.free_space
+    }
+
+    /// Take the next entry, or None at the end of the listing
+    pub fn next_entry(&mut self) -> Option<DirectoryEntry> {
+        let entry = self.entries.get(self.next).cloned();
+        if entry.is_some() {
+            self.next += 1;
+        }
+        entry
+    }
+
+    /// Read the next chunk of the formatted listing
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let remaining = &self.text[self.text_pos..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.text_pos += len;
+        len
+    }
+
+    /// Bytes of formatted text not yet read
+    pub fn available(&self) -> usize {
+        self.text.len() - self.text_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(name: &str, size: u64, is_dir: bool) -> DirectoryEntry {
+        DirectoryEntry { name: name.to_string(), size, modified: None, is_dir }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.atr", "GAME.ATR"));
+        assert!(wildcard_match("G?ME*", "game.xex"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("a*b*c", "aXXbYYc"));
+        assert!(!wildcard_match("*.atr", "game.xex"));
+        assert!(!wildcard_match("g?me", "gaame"));
+    }
+
+    #[test]
+    fn test_split_wildcard() {
+        assert_eq!(split_wildcard("file:///games/*.ATR"), ("file:///games", Some("*.ATR")));
+        assert_eq!(split_wildcard("file:///*.ATR"), ("file:///", Some("*.ATR")));
+        assert_eq!(split_wildcard("file:///games/"), ("file:///games/", None));
+    }
+
+    #[test]
+    fn test_format_entries() {
+        let mut file = entry("Star Raiders.atr", 200_000, false);
+        file.modified = Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert_eq!(format_short_entry(&file), "  STAR RAI ATR 999+");
+        assert_eq!(format_short_entry(&entry("dos.sys", 4_625, false)), "  DOS      SYS 037");
+        assert_eq!(format_short_entry(&entry("games", 0, true)), ": GAMES        000");
+
+        assert_eq!(format_long_entry(&file), "Star Raiders.atr             200000 2023-11-14 22:13");
+        assert_eq!(format_long_entry(&entry("games", 0, true)), "games                         <DIR>");
+
+        assert_eq!(format_free_space(Some(128 * 42)), "042 FREE SECTORS");
+        assert_eq!(format_free_space(Some(10_000_000)), "999+FREE SECTORS");
+        assert_eq!(format_free_space(None), "999+FREE SECTORS");
+    }
+
+    #[test]
+    fn test_listing_filter_iterate_and_read() {
+        let entries = vec![entry("a.atr", 10, false), entry("b.xex", 20, false), entry("c.ATR", 30, false)];
+        let mut listing = DirectoryListing::new(entries, Some("*.atr"), Some(1280), DirectoryFormat::Long);
+
+        assert_eq!(listing.entries().len(), 2);
+        assert_eq!(listing.next_entry().unwrap().name, "a.atr");
+        assert_eq!(listing.next_entry().unwrap().name, "c.ATR");
+        assert_eq!(listing.next_entry(), None);
+
+        let mut buf = vec![0u8; 256];
+        let len = listing.read(&mut buf);
+        let text = String::from_utf8(buf[..len].to_vec()).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), vec![
+            "a.atr                            10",
+            "c.ATR                            30",
+            "010 FREE SECTORS",
+        ]);
+        assert_eq!(listing.available(), 0);
+    }
+}
@@ -1,38 +1,49 @@
 use crate::device::DeviceResult;
 use crate::device::DeviceError;
-use crate::device::network::NetworkDevice;
-use crate::device::network::NetworkUrl;
+use crate::device::manager::DEFAULT_NETWORK_UNITS;
+use crate::device::network::{DeviceGuard, DeviceSlot, NetworkUrl};
 use crate::device::network::network_device::NetworkDeviceImpl;
-use super::NetworkProtocol;
+use super::{NetworkProtocol, ProtocolHandler};
 use super::registry::ProtocolRegistry;
 
 /// Factory for creating and managing network devices
 /// Uses ProtocolRegistry to create appropriate protocol handlers
 pub struct ProtocolFactory {
     // Each device can have one active protocol handler
-    active_devices: [Option<Box<dyn NetworkDevice>>; 8],
+    // The slots are fixed at construction, so looking one up needs no lock at all
+    active_devices: Vec<DeviceSlot>,
     registry: ProtocolRegistry,
 }
 
 impl ProtocolFactory {
     pub fn new(registry: ProtocolRegistry) -> Self {
+        Self::with_units(registry, DEFAULT_NETWORK_UNITS)
+    }
+
+    /// Create a factory with a slot for each of `units` devices
+    pub fn with_units(registry: ProtocolRegistry, units: usize) -> Self {
         Self {
-            active_devices: [None, None, None, None, None, None, None, None],
+            active_devices: (0..units).map(|_| DeviceSlot::default()).collect(),
             registry,
         }
     }
 
+    /// How many devices can be open at once
+    pub fn unit_count(&self) -> usize {
+        self.active_devices.len()
+    }
+
     // Returns device_id if successful
     pub async fn get_or_create_device(
-        &mut self, 
+        &self, 
         device_id: usize,
         protocol: NetworkProtocol,
         url: &NetworkUrl
     ) -> DeviceResult<usize> {
-        debug_assert!(device_id < self.active_devices.len(), "device_id out of bounds");
+        let mut slot = self.slot(device_id).ok_or(DeviceError::InvalidDeviceId)?.lock_owned().await;
         
         // If we already have an active device
-        if self.active_devices[device_id].is_some() {
+        if slot.is_some() {
             return Ok(device_id);
         }
 
@@ -45,19 +56,33 @@ impl ProtocolFactory {
         let handler = self.registry.create_handler(protocol)?;
         let device = NetworkDeviceImpl::new(url.url.clone(), handler);
         
-        self.active_devices[device_id] = Some(Box::new(device));
+        *slot = Some(Box::new(device));
         Ok(device_id)
     }
 
-    // Get device by ID
-    pub fn get_device(&mut self, device_id: usize) -> Option<&mut Box<dyn NetworkDevice>> {
-        self.active_devices[device_id].as_mut()
+    /// Create a standalone handler, not attached to any device, for one-off commands
+    pub fn create_handler(&self, protocol: NetworkProtocol) -> DeviceResult<Box<dyn ProtocolHandler>> {
+        self.registry.create_handler(protocol)
+    }
+
+    /// The slot holding a device, shared so callers can lock it without going through the factory
+    pub fn slot(&self, device_id: usize) -> Option<DeviceSlot> {
+        self.active_devices.get(device_id).cloned()
+    }
+
+    // Get device by ID, waiting for any operation already running on it
+    pub async fn get_device(&self, device_id: usize) -> Option<DeviceGuard> {
+        DeviceGuard::lock(self.slot(device_id)?).await
     }
 
-    pub async fn close_device(&mut self, device_id: usize) -> DeviceResult<()> {
-        if let Some(device) = &mut self.active_devices[device_id] {
+    pub async fn close_device(&self, device_id: usize) -> DeviceResult<()> {
+        let Some(slot) = self.slot(device_id) else {
+            return Ok(());
+        };
+        let mut slot = slot.lock().await;
+        if let Some(device) = slot.as_mut() {
             device.disconnect().await?;
-            self.active_devices[device_id] = None;
+            *slot = None;
         }
         Ok(())
     }
@@ -106,7 +131,7 @@ mod tests {
     #[tokio::test]
     async fn test_protocol_factory() -> DeviceResult<()> {
         let registry = setup_mock_registry();
-        let mut factory = super::ProtocolFactory::new(registry);
+        let factory = super::ProtocolFactory::new(registry);
         
         // Test device creation
         let url = NetworkUrl::parse("N:http://test.com")?;
@@ -114,11 +139,11 @@ mod tests {
         assert_eq!(device_id, 0);
         
         // Test getting existing device
-        assert!(factory.get_device(0).is_some());
+        assert!(factory.get_device(0).await.is_some());
         
         // Test closing device
         factory.close_device(0).await?;
-        assert!(factory.get_device(0).is_none());
+        assert!(factory.get_device(0).await.is_none());
         
         Ok(())
     }
@@ -126,7 +151,7 @@ mod tests {
     #[tokio::test]
     async fn test_device_reuse() -> DeviceResult<()> {
         let registry = setup_mock_registry();
-        let mut factory = super::ProtocolFactory::new(registry);
+        let factory = super::ProtocolFactory::new(registry);
         
         let url = NetworkUrl::parse("N:http://test.com")?;
         
@@ -0,0 +1,447 @@
+use std::any::Any;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use crate::device::{DeviceError, DeviceResult};
+use super::{ProtocolHandler, ConnectionStatus, OPEN_MODE_WRITE, OPEN_MODE_APPEND, OPEN_MODE_READ_WRITE};
+use super::directory::{DirectoryEntry, DirectoryHandler};
+use super::filesystem::FileSystemHandler;
+use super::seek::SeekableHandler;
+
+/// Local filesystem protocol handler for file:// and sd:// URLs
+/// All paths are resolved inside the configured root directory
+pub struct FileProtocol {
+    root: PathBuf,
+    mode: u8,
+    path: Option<PathBuf>,
+    file: Option<File>,
+    position: u64,
+}
+
+impl FileProtocol {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            mode: 0,
+            path: None,
+            file: None,
+            position: 0,
+        }
+    }
+
+    /// The directory all paths are sandboxed to
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The resolved host path of the currently open file
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Resolve a file:// or sd:// URL to a path inside the root directory
+    /// Returns InvalidUrl if the path would escape the root, including through a symlink
+    pub fn resolve_path(&self, url: &str) -> DeviceResult<PathBuf> {
+        let (_, path) = url.split_once("://").ok_or(DeviceError::InvalidUrl)?;
+        let path = percent_decode_str(path).decode_utf8_lossy();
+
+        let mut relative = PathBuf::new();
+        for component in Path::new(path.as_ref()).components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::ParentDir => {
+                    if !relative.pop() {
+                        return Err(DeviceError::InvalidUrl);
+                    }
+                }
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            }
+        }
+        let path = self.root.join(relative);
+
+        // A symlink inside the root can still point outside it, so compare where the path really leads
+        if !canonicalize_existing(&path).starts_with(canonicalize_existing(&self.root)) {
+            return Err(DeviceError::InvalidUrl);
+        }
+        Ok(path)
+    }
+
+    /// Resolve a URL that names something inside the root, never the root itself,
+    /// for commands that would otherwise move or remove the whole sandbox
+    fn resolve_entry(&self, url: &str) -> DeviceResult<PathBuf> {
+        let path = self.resolve_path(url)?;
+        if path == self.root {
+            return Err(DeviceError::InvalidUrl);
+        }
+        Ok(path)
+    }
+
+    fn file_mut(&mut self) -> DeviceResult<&mut File> {
+        self.file.as_mut().ok_or(DeviceError::NotReady)
+    }
+}
+
+#[async_trait]
+impl ProtocolHandler for FileProtocol {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    async fn open(&mut self, endpoint: &str) -> DeviceResult<()> {
+        let path = self.resolve_path(endpoint)?;
+
+        let mut options = OpenOptions::new();
+        match self.mode {
+            OPEN_MODE_WRITE => options.write(true).create(true).truncate(true),
+            OPEN_MODE_APPEND => options.append(true).create(true),
+            OPEN_MODE_READ_WRITE => options.read(true).write(true).create(true).truncate(false),
+            _ => options.read(true),
+        };
+
+        let file = options.open(&path).await?;
+        self.position = if self.mode == OPEN_MODE_APPEND { file.metadata().await?.len() } else { 0 };
+        self.file = Some(file);
+        self.path = Some(path);
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        self.path = None;
+        self.position = 0;
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        let len = self.file_mut()?.read(buf).await?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        self.file_mut()?.write_all(buf).await?;
+        self.position += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        Ok(if self.file.is_some() {
+            ConnectionStatus::Connected
+        } else {
+            ConnectionStatus::Disconnected
+        })
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        let Some(file) = self.file.as_ref() else {
+            return Ok(0);
+        };
+        let len = file.metadata().await?.len();
+        Ok(len.saturating_sub(self.position) as usize)
+    }
+
+    fn set_mode(&mut self, mode: u8) {
+        self.mode = mode;
+    }
+
+    fn as_directory(&mut self) -> Option<&mut dyn DirectoryHandler> {
+        Some(self)
+    }
+
+    fn as_filesystem(&mut self) -> Option<&mut dyn FileSystemHandler> {
+        Some(self)
+    }
+
+    fn as_seekable(&mut self) -> Option<&mut dyn SeekableHandler> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl SeekableHandler for FileProtocol {
+    async fn seek(&mut self, pos: SeekFrom) -> DeviceResult<u64> {
+        self.position = self.file_mut()?.seek(pos).await?;
+        Ok(self.position)
+    }
+
+    fn tell(&self) -> u64 {
+        self.position
+    }
+}
+
+#[async_trait]
+impl DirectoryHandler for FileProtocol {
+    async fn list_directory(&mut self, url: &str) -> DeviceResult<Vec<DirectoryEntry>> {
+        let path = self.resolve_path(url)?;
+        let mut dir = tokio::fs::read_dir(&path).await?;
+
+        let mut entries = Vec::new();
+        while let Some(entry) = dir.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            entries.push(DirectoryEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified().ok(),
+                is_dir: metadata.is_dir(),
+            });
+        }
+        // read_dir order is filesystem dependent
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        Ok(entries)
+    }
+
+    async fn free_space(&mut self, url: &str) -> DeviceResult<Option<u64>> {
+        let path = self.resolve_path(url)?;
+        Ok(free_space(&path))
+    }
+}
+
+#[async_trait]
+impl FileSystemHandler for FileProtocol {
+    async fn rename(&mut self, url: &str, new_name: &str) -> DeviceResult<()> {
+        // The new name stays in the same directory, so it may not contain a path
+        if new_name.is_empty() || new_name.contains(['/', '\\']) || new_name.contains("..") || new_name == "." {
+            return Err(DeviceError::InvalidUrl);
+        }
+        let from = self.resolve_entry(url)?;
+        let to = from.with_file_name(new_name);
+        Ok(tokio::fs::rename(from, to).await?)
+    }
+
+    async fn delete(&mut self, url: &str) -> DeviceResult<()> {
+        Ok(tokio::fs::remove_file(self.resolve_entry(url)?).await?)
+    }
+
+    async fn make_directory(&mut self, url: &str) -> DeviceResult<()> {
+        Ok(tokio::fs::create_dir(self.resolve_path(url)?).await?)
+    }
+
+    async fn remove_directory(&mut self, url: &str) -> DeviceResult<()> {
+        Ok(tokio::fs::remove_dir(self.resolve_entry(url)?).await?)
+    }
+
+    async fn lock(&mut self, url: &str) -> DeviceResult<()> {
+        set_readonly(&self.resolve_path(url)?, true).await
+    }
+
+    async fn unlock(&mut self, url: &str) -> DeviceResult<()> {
+        set_readonly(&self.resolve_path(url)?, false).await
+    }
+}
+
+async fn set_readonly(path: &Path, readonly: bool) -> DeviceResult<()> {
+    let mut permissions = tokio::fs::metadata(path).await?.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(readonly);
+    Ok(tokio::fs::set_permissions(path, permissions).await?)
+}
+
+/// Canonicalize the longest part of `path` that exists and append the rest, so paths
+/// that are about to be created can be checked too
+fn canonicalize_existing(path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(canonical) = std::fs::canonicalize(ancestor) {
+            return match path.strip_prefix(ancestor) {
+                Ok(rest) if !rest.as_os_str().is_empty() => canonical.join(rest),
+                _ => canonical,
+            };
+        }
+    }
+    path.to_path_buf()
+}
+
+#[cfg(unix)]
+fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is NUL terminated and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::network::protocols::OPEN_MODE_READ;
+
+    #[test]
+    fn test_resolve_path_is_sandboxed() {
+        let protocol = FileProtocol::new("/srv/sd");
+        assert_eq!(protocol.resolve_path("file:///games/a.atr").unwrap(), PathBuf::from("/srv/sd/games/a.atr"));
+        assert_eq!(protocol.resolve_path("sd://games/../b%20c.txt").unwrap(), PathBuf::from("/srv/sd/b c.txt"));
+        assert!(matches!(protocol.resolve_path("file:///../etc/passwd"), Err(DeviceError::InvalidUrl)));
+        assert!(matches!(protocol.resolve_path("no-scheme"), Err(DeviceError::InvalidUrl)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_cannot_escape_root() {
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("games")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("games"), root.join("inside")).unwrap();
+        let mut protocol = FileProtocol::new(root);
+
+        for url in ["file:///escape/secret.txt", "file:///escape/new.txt", "file:///escape"] {
+            assert!(matches!(protocol.resolve_path(url), Err(DeviceError::InvalidUrl)), "{}", url);
+        }
+        protocol.set_mode(OPEN_MODE_READ);
+        assert!(matches!(protocol.open("file:///escape/secret.txt").await, Err(DeviceError::InvalidUrl)));
+        protocol.set_mode(OPEN_MODE_WRITE);
+        assert!(matches!(protocol.open("file:///escape/new.txt").await, Err(DeviceError::InvalidUrl)));
+        assert!(!outside.path().join("new.txt").exists());
+        let dir_handler = protocol.as_directory().unwrap();
+        assert!(matches!(dir_handler.list_directory("file:///escape").await, Err(DeviceError::InvalidUrl)));
+
+        // Links that stay inside the root are fine
+        assert_eq!(protocol.resolve_path("file:///inside/a.atr").unwrap(), root.join("inside/a.atr"));
+    }
+
+    #[tokio::test]
+    async fn test_write_then_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut protocol = FileProtocol::new(root);
+
+        protocol.set_mode(OPEN_MODE_WRITE);
+        protocol.open("file:///hello.txt").await.unwrap();
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Connected);
+        assert_eq!(protocol.write(b"Hello, Atari!").await.unwrap(), 13);
+        protocol.close().await.unwrap();
+        assert_eq!(std::fs::read(root.join("hello.txt")).unwrap(), b"Hello, Atari!");
+
+        protocol.set_mode(OPEN_MODE_APPEND);
+        protocol.open("file:///hello.txt").await.unwrap();
+        protocol.write(b"!!").await.unwrap();
+        protocol.close().await.unwrap();
+
+        protocol.set_mode(OPEN_MODE_READ);
+        protocol.open("sd://hello.txt").await.unwrap();
+        assert_eq!(protocol.available().await.unwrap(), 15);
+        let mut buf = [0u8; 8];
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 8);
+        assert_eq!(&buf, b"Hello, A");
+        assert_eq!(protocol.available().await.unwrap(), 7);
+        protocol.close().await.unwrap();
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Disconnected);
+    }
+
+    #[tokio::test]
+    async fn test_list_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("games")).unwrap();
+        std::fs::write(root.join("b.xex"), [0u8; 300]).unwrap();
+        std::fs::write(root.join("A.atr"), [0u8; 10]).unwrap();
+        let mut protocol = FileProtocol::new(root);
+
+        let dir = protocol.as_directory().expect("file protocol lists directories");
+        let entries = dir.list_directory("file:///").await.unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["A.atr", "b.xex", "games"]);
+        assert_eq!(entries[1].size, 300);
+        assert!(entries[2].is_dir);
+        assert!(entries[0].modified.is_some());
+        assert!(dir.free_space("file:///").await.unwrap().is_some());
+        assert!(matches!(dir.list_directory("file:///nope").await, Err(DeviceError::IoError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("old.txt"), b"data").unwrap();
+        let mut protocol = FileProtocol::new(root);
+        let fs = protocol.as_filesystem().expect("file protocol supports filesystem commands");
+
+        fs.rename("file:///old.txt", "new.txt").await.unwrap();
+        assert!(root.join("new.txt").exists() && !root.join("old.txt").exists());
+        assert!(matches!(fs.rename("file:///new.txt", "../escape.txt").await, Err(DeviceError::InvalidUrl)));
+
+        fs.lock("file:///new.txt").await.unwrap();
+        assert!(std::fs::metadata(root.join("new.txt")).unwrap().permissions().readonly());
+        fs.unlock("file:///new.txt").await.unwrap();
+        assert!(!std::fs::metadata(root.join("new.txt")).unwrap().permissions().readonly());
+
+        fs.delete("file:///new.txt").await.unwrap();
+        assert!(!root.join("new.txt").exists());
+
+        fs.make_directory("file:///games").await.unwrap();
+        assert!(root.join("games").is_dir());
+        fs.remove_directory("file:///games").await.unwrap();
+        assert!(!root.join("games").exists());
+        assert!(matches!(fs.delete("file:///missing.txt").await, Err(DeviceError::IoError(_))));
+
+        // The root itself can't be renamed or removed, however it is named
+        for url in ["file:///", "file://", "file:///games/.."] {
+            assert!(matches!(fs.rename(url, "moved").await, Err(DeviceError::InvalidUrl)));
+            assert!(matches!(fs.delete(url).await, Err(DeviceError::InvalidUrl)));
+            assert!(matches!(fs.remove_directory(url).await, Err(DeviceError::InvalidUrl)));
+        }
+        assert!(root.is_dir());
+        std::fs::write(root.join("a.txt"), b"data").unwrap();
+        assert!(matches!(fs.rename("file:///a.txt", "..").await, Err(DeviceError::InvalidUrl)));
+        assert!(matches!(fs.rename("file:///a.txt", "..\\b.txt").await, Err(DeviceError::InvalidUrl)));
+    }
+
+    #[tokio::test]
+    async fn test_seek_and_tell() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("disk.img"), b"0123456789").unwrap();
+        let mut protocol = FileProtocol::new(root);
+        assert!(matches!(protocol.seek(SeekFrom::Start(0)).await, Err(DeviceError::NotReady)));
+
+        protocol.set_mode(OPEN_MODE_READ_WRITE);
+        protocol.open("file:///disk.img").await.unwrap();
+        let seekable = protocol.as_seekable().expect("file protocol is seekable");
+        assert_eq!(seekable.seek(SeekFrom::Start(6)).await.unwrap(), 6);
+        assert_eq!(seekable.tell(), 6);
+
+        let mut buf = [0u8; 2];
+        protocol.read(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"67");
+        assert_eq!(protocol.tell(), 8);
+        assert_eq!(protocol.available().await.unwrap(), 2);
+
+        protocol.seek(SeekFrom::End(-8)).await.unwrap();
+        protocol.write(b"AB").await.unwrap();
+        protocol.seek(SeekFrom::Current(-4)).await.unwrap();
+        protocol.read(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"01");
+        protocol.close().await.unwrap();
+        assert_eq!(std::fs::read(root.join("disk.img")).unwrap(), b"01AB456789");
+    }
+
+    #[tokio::test]
+    async fn test_open_missing_file_for_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut protocol = FileProtocol::new(root);
+        protocol.set_mode(OPEN_MODE_READ);
+        assert!(matches!(protocol.open("file:///nope.txt").await, Err(DeviceError::IoError(_))));
+        let mut buf = [0u8; 4];
+        assert!(matches!(protocol.read(&mut buf).await, Err(DeviceError::NotReady)));
+    }
+}
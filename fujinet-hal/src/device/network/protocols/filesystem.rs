@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use crate::device::{DeviceError, DeviceResult};
+
+// Atari XIO command numbers for N: filesystem operations
+pub const XIO_RENAME: u8 = 32;
+pub const XIO_DELETE: u8 = 33;
+pub const XIO_LOCK: u8 = 35;
+pub const XIO_UNLOCK: u8 = 36;
+pub const XIO_MKDIR: u8 = 42;
+pub const XIO_RMDIR: u8 = 43;
+
+/// A filesystem command issued against a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSystemCommand {
+    /// Rename to a new name in the same directory
+    Rename(String),
+    Delete,
+    Lock,
+    Unlock,
+    MakeDirectory,
+    RemoveDirectory,
+}
+
+impl FileSystemCommand {
+    /// Map an XIO command number to a command
+    /// Rename needs the new name, taken from after the comma in the devicespec
+    pub fn from_xio(command: u8, new_name: Option<&str>) -> DeviceResult<Self> {
+        Ok(match command {
+            XIO_RENAME => match new_name {
+                Some(name) if !name.is_empty() => Self::Rename(name.to_string()),
+                _ => return Err(DeviceError::InvalidUrl),
+            },
+            XIO_DELETE => Self::Delete,
+            XIO_LOCK => Self::Lock,
+            XIO_UNLOCK => Self::Unlock,
+            XIO_MKDIR => Self::MakeDirectory,
+            XIO_RMDIR => Self::RemoveDirectory,
+            _ => return Err(DeviceError::InvalidOperation),
+        })
+    }
+}
+
+/// Split a rename spec such as "N1:TNFS://host/foo.txt,bar.txt" into the spec and the new name
+pub fn split_rename_target(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once(',') {
+        Some((spec, new_name)) => (spec, Some(new_name)),
+        None => (spec, None),
+    }
+}
+
+/// Optional capability for protocol handlers backed by a filesystem
+#[async_trait]
+pub trait FileSystemHandler: Send + Sync {
+    /// Rename the file or directory at `url`; `new_name` is relative to its directory
+    async fn rename(&mut self, url: &str, new_name: &str) -> DeviceResult<()>;
+
+    /// Delete the file at `url`
+    async fn delete(&mut self, url: &str) -> DeviceResult<()>;
+
+    /// Create a directory at `url`
+    async fn make_directory(&mut self, url: &str) -> DeviceResult<()>;
+
+    /// Remove the (empty) directory at `url`
+    async fn remove_directory(&mut self, url: &str) -> DeviceResult<()>;
+
+    /// Make the file at `url` read only
+    async fn lock(&mut self, _url: &str) -> DeviceResult<()> {
+        Err(DeviceError::NotSupported)
+    }
+
+    /// Make the file at `url` writable again
+    async fn unlock(&mut self, _url: &str) -> DeviceResult<()> {
+        Err(DeviceError::NotSupported)
+    }
+}
+
+/// Run a command against a filesystem-capable handler
+pub async fn execute(handler: &mut dyn FileSystemHandler, url: &str, command: &FileSystemCommand) -> DeviceResult<()> {
+    match command {
+        FileSystemCommand::Rename(new_name) => handler.rename(url, new_name).await,
+        FileSystemCommand::Delete => handler.delete(url).await,
+        FileSystemCommand::Lock => handler.lock(url).await,
+        FileSystemCommand::Unlock => handler.unlock(url).await,
+        FileSystemCommand::MakeDirectory => handler.make_directory(url).await,
+        FileSystemCommand::RemoveDirectory => handler.remove_directory(url).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_xio() {
+        assert_eq!(FileSystemCommand::from_xio(XIO_DELETE, None).unwrap(), FileSystemCommand::Delete);
+        assert_eq!(FileSystemCommand::from_xio(XIO_RMDIR, None).unwrap(), FileSystemCommand::RemoveDirectory);
+        assert_eq!(
+            FileSystemCommand::from_xio(XIO_RENAME, Some("new.txt")).unwrap(),
+            FileSystemCommand::Rename("new.txt".to_string())
+        );
+        assert!(matches!(FileSystemCommand::from_xio(XIO_RENAME, None), Err(DeviceError::InvalidUrl)));
+        assert!(matches!(FileSystemCommand::from_xio(99, None), Err(DeviceError::InvalidOperation)));
+    }
+
+    #[test]
+    fn test_split_rename_target() {
+        assert_eq!(split_rename_target("N1:TNFS://host/foo.txt,bar.txt"), ("N1:TNFS://host/foo.txt", Some("bar.txt")));
+        assert_eq!(split_rename_target("N1:TNFS://host/foo.txt"), ("N1:TNFS://host/foo.txt", None));
+    }
+}
@@ -0,0 +1,599 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::{ProtocolHandler, ConnectionStatus, OPEN_MODE_DIRECTORY};
+use super::client_provider::TcpClientProvider;
+use super::directory::{DirectoryEntry, DirectoryHandler};
+use super::tcp_client::TcpClient;
+use async_trait::async_trait;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default port for gopher servers
+pub const GOPHER_DEFAULT_PORT: u16 = 70;
+
+/// How long to wait for each read while fetching a menu
+const MENU_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bytes of a text document held back until the end, so the ".\r\n" terminator
+/// and the line break before it can be recognised
+const TERMINATOR_HOLD: usize = 4;
+
+/// Characters escaped when turning a selector back into a URL
+const SELECTOR_ENCODE: &AsciiSet = &CONTROLS.add(b' ').add(b'\t').add(b'%').add(b'?').add(b'#');
+
+/// Gopher item types (RFC 1436 plus the common extensions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GopherItemType {
+    TextFile,
+    Directory,
+    CsoPhoneBook,
+    Error,
+    BinHex,
+    DosBinary,
+    UuEncoded,
+    Search,
+    Telnet,
+    Binary,
+    Mirror,
+    Gif,
+    Image,
+    Tn3270,
+    Html,
+    Sound,
+    Info,
+    Other(char),
+}
+
+impl GopherItemType {
+    pub fn from_char(c: char) -> Self {
+        match c {
+            '0' => GopherItemType::TextFile,
+            '1' => GopherItemType::Directory,
+            '2' => GopherItemType::CsoPhoneBook,
+            '3' => GopherItemType::Error,
+            '4' => GopherItemType::BinHex,
+            '5' => GopherItemType::DosBinary,
+            '6' => GopherItemType::UuEncoded,
+            '7' => GopherItemType::Search,
+            '8' => GopherItemType::Telnet,
+            '9' => GopherItemType::Binary,
+            '+' => GopherItemType::Mirror,
+            'g' => GopherItemType::Gif,
+            'I' => GopherItemType::Image,
+            'T' => GopherItemType::Tn3270,
+            'h' => GopherItemType::Html,
+            's' => GopherItemType::Sound,
+            'i' => GopherItemType::Info,
+            other => GopherItemType::Other(other),
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            GopherItemType::TextFile => '0',
+            GopherItemType::Directory => '1',
+            GopherItemType::CsoPhoneBook => '2',
+            GopherItemType::Error => '3',
+            GopherItemType::BinHex => '4',
+            GopherItemType::DosBinary => '5',
+            GopherItemType::UuEncoded => '6',
+            GopherItemType::Search => '7',
+            GopherItemType::Telnet => '8',
+            GopherItemType::Binary => '9',
+            GopherItemType::Mirror => '+',
+            GopherItemType::Gif => 'g',
+            GopherItemType::Image => 'I',
+            GopherItemType::Tn3270 => 'T',
+            GopherItemType::Html => 'h',
+            GopherItemType::Sound => 's',
+            GopherItemType::Info => 'i',
+            GopherItemType::Other(c) => *c,
+        }
+    }
+
+    /// True for items that only carry display text and cannot be followed
+    pub fn is_informational(&self) -> bool {
+        matches!(self, GopherItemType::Info | GopherItemType::Error)
+    }
+}
+
+/// A single line of a gopher menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct GopherEntry {
+    pub item_type: GopherItemType,
+    pub display: String,
+    pub selector: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl GopherEntry {
+    /// The gopher:// URL that fetches this item
+    pub fn url(&self) -> String {
+        let selector = utf8_percent_encode(&self.selector, SELECTOR_ENCODE);
+        if self.port == GOPHER_DEFAULT_PORT {
+            format!("gopher://{}/{}{}", self.host, self.item_type.as_char(), selector)
+        } else {
+            format!("gopher://{}:{}/{}{}", self.host, self.port, self.item_type.as_char(), selector)
+        }
+    }
+}
+
+/// Parse a gopher menu into entries
+/// Parsing stops at the terminating "." line; lines without a type character are skipped
+pub fn parse_menu(data: &[u8]) -> Vec<GopherEntry> {
+    let text = String::from_utf8_lossy(data);
+    let mut entries = Vec::new();
+
+    for line in text.lines() {
+        if line == "." {
+            break;
+        }
+        let mut chars = line.chars();
+        let Some(type_char) = chars.next() else { continue };
+
+        let mut fields = chars.as_str().split('\t');
+        let display = fields.next().unwrap_or("").to_string();
+        let selector = fields.next().unwrap_or("").to_string();
+        let host = fields.next().unwrap_or("").to_string();
+        let port = fields.next().and_then(|p| p.trim().parse().ok()).unwrap_or(GOPHER_DEFAULT_PORT);
+
+        entries.push(GopherEntry {
+            item_type: GopherItemType::from_char(type_char),
+            display,
+            selector,
+            host,
+            port,
+        });
+    }
+
+    entries
+}
+
+/// Gopher protocol handler for gopher:// URLs (RFC 4266)
+///
+/// In directory mode the menu is fetched on open and `read` returns one line per entry:
+/// the item type character, the display string, then a tab and the item's gopher:// URL
+/// (omitted for informational lines). In any other mode `read` streams the document,
+/// without the terminating "." line that ends type 0 text files.
+pub struct GopherProtocol {
+    client: Box<dyn TcpClient>,
+    mode: u8,
+    item_type: GopherItemType,
+    entries: Vec<GopherEntry>,
+    listing: Vec<u8>,
+    listing_pos: usize,
+    /// Text document bytes read from the server but not yet returned
+    pending: Vec<u8>,
+    text_done: bool,
+    open: bool,
+}
+
+impl GopherProtocol {
+    pub fn new(client_provider: Arc<dyn TcpClientProvider>) -> Self {
+        Self {
+            client: client_provider.create_tcp_client(),
+            mode: 0,
+            item_type: GopherItemType::Directory,
+            entries: Vec::new(),
+            listing: Vec::new(),
+            listing_pos: 0,
+            pending: Vec::new(),
+            text_done: false,
+            open: false,
+        }
+    }
+
+    /// Split a gopher:// URL into host, port, item type and selector
+    /// A search string given after %09 or ? is appended to the selector after a tab
+    pub fn parse_url(url: &str) -> DeviceResult<(String, u16, GopherItemType, String)> {
+        let (scheme, rest) = url.split_once("://").ok_or(DeviceError::InvalidUrl)?;
+        if !scheme.eq_ignore_ascii_case("gopher") {
+            return Err(DeviceError::InvalidUrl);
+        }
+
+        let (host_port, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| DeviceError::InvalidUrl)?),
+            None => (host_port, GOPHER_DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(DeviceError::InvalidUrl);
+        }
+
+        let (path, search) = path.split_once('?').map_or((path, None), |(p, s)| (p, Some(s)));
+        let mut chars = path.chars();
+        let item_type = chars.next().map_or(GopherItemType::Directory, GopherItemType::from_char);
+        let mut selector = percent_decode_str(chars.as_str()).decode_utf8_lossy().into_owned();
+        if let Some(search) = search {
+            selector.push('\t');
+            selector.push_str(&percent_decode_str(search).decode_utf8_lossy());
+        }
+
+        Ok((host.to_string(), port, item_type, selector))
+    }
+
+    /// Entries of the menu fetched in directory mode
+    pub fn entries(&self) -> &[GopherEntry] {
+        &self.entries
+    }
+
+    /// Fetch and parse the menu at a selector, giving up if the server stalls
+    async fn fetch_menu(&mut self, host: &str, port: u16, selector: &str) -> DeviceResult<Vec<GopherEntry>> {
+        self.client.connect(host, port).await?;
+        self.client.write(format!("{}\r\n", selector).as_bytes()).await?;
+
+        let mut menu = Vec::new();
+        let mut buf = [0u8; 1024];
+        let result = loop {
+            match tokio::time::timeout(MENU_READ_TIMEOUT, self.client.read(&mut buf)).await {
+                Ok(Ok(0)) => break Ok(()),
+                Ok(Ok(len)) => menu.extend_from_slice(&buf[..len]),
+                Ok(Err(e)) => break Err(e),
+                Err(_) => break Err(DeviceError::NetworkError("Timed out waiting for gopher server".to_string())),
+            }
+        };
+        self.client.disconnect().await?;
+        result?;
+        Ok(parse_menu(&menu))
+    }
+
+    /// Stream a text document, dropping the terminating "." line once the server closes
+    async fn read_text(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        let mut chunk = vec![0u8; buf.len().max(TERMINATOR_HOLD)];
+        loop {
+            let ready = if self.text_done {
+                self.pending.len()
+            } else {
+                self.pending.len().saturating_sub(TERMINATOR_HOLD)
+            };
+            if ready > 0 || self.text_done {
+                let len = std::cmp::min(buf.len(), ready);
+                buf[..len].copy_from_slice(&self.pending[..len]);
+                self.pending.drain(..len);
+                return Ok(len);
+            }
+
+            let len = if self.client.is_connected() { self.client.read(&mut chunk).await? } else { 0 };
+            if len == 0 {
+                self.text_done = true;
+                let end = strip_terminator(&self.pending).len();
+                self.pending.truncate(end);
+            } else {
+                self.pending.extend_from_slice(&chunk[..len]);
+            }
+        }
+    }
+
+    fn format_listing(entries: &[GopherEntry]) -> Vec<u8> {
+        let mut listing = String::new();
+        for entry in entries {
+            listing.push(entry.item_type.as_char());
+            listing.push_str(&entry.display);
+            if !entry.item_type.is_informational() {
+                listing.push('\t');
+                listing.push_str(&entry.url());
+            }
+            listing.push('\n');
+        }
+        listing.into_bytes()
+    }
+}
+
+#[async_trait]
+impl ProtocolHandler for GopherProtocol {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    async fn open(&mut self, url: &str) -> DeviceResult<()> {
+        let (host, port, item_type, selector) = Self::parse_url(url)?;
+        self.item_type = item_type;
+        self.entries.clear();
+        self.listing.clear();
+        self.listing_pos = 0;
+        self.pending.clear();
+        self.text_done = false;
+
+        if self.mode == OPEN_MODE_DIRECTORY {
+            self.entries = self.fetch_menu(&host, port, &selector).await?;
+            self.listing = Self::format_listing(&self.entries);
+        } else {
+            self.client.connect(&host, port).await?;
+            self.client.write(format!("{}\r\n", selector).as_bytes()).await?;
+        }
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        let result = self.client.disconnect().await;
+        self.open = false;
+        self.entries.clear();
+        self.listing.clear();
+        self.listing_pos = 0;
+        self.pending.clear();
+        result
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        if self.mode != OPEN_MODE_DIRECTORY {
+            if self.item_type == GopherItemType::TextFile {
+                return self.read_text(buf).await;
+            }
+            if !self.client.is_connected() {
+                return Ok(0);
+            }
+            return self.client.read(buf).await;
+        }
+
+        let remaining = &self.listing[self.listing_pos..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.listing_pos += len;
+        Ok(len)
+    }
+
+    async fn write(&mut self, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        Ok(if self.open {
+            ConnectionStatus::Connected
+        } else {
+            ConnectionStatus::Disconnected
+        })
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        // The length of a streamed document is unknown until the server closes the connection
+        Ok(self.listing.len() - self.listing_pos)
+    }
+
+    fn set_mode(&mut self, mode: u8) {
+        self.mode = mode;
+    }
+
+    fn as_directory(&mut self) -> Option<&mut dyn DirectoryHandler> {
+        Some(self)
+    }
+}
+
+/// Menus listed as directories; informational lines are left out since they can't be opened
+#[async_trait]
+impl DirectoryHandler for GopherProtocol {
+    async fn list_directory(&mut self, url: &str) -> DeviceResult<Vec<DirectoryEntry>> {
+        let (host, port, _, selector) = Self::parse_url(url)?;
+        let entries = self.fetch_menu(&host, port, &selector).await?;
+        Ok(entries.into_iter()
+            .filter(|e| !e.item_type.is_informational())
+            .map(|e| DirectoryEntry {
+                name: e.display,
+                size: 0,
+                modified: None,
+                is_dir: e.item_type == GopherItemType::Directory,
+            })
+            .collect())
+    }
+}
+
+/// A text document without its terminating "." line, if it ends with one
+fn strip_terminator(data: &[u8]) -> &[u8] {
+    for terminator in [&b".\r\n"[..], &b".\n"[..]] {
+        if let Some(body) = data.strip_suffix(terminator) {
+            if body.is_empty() || body.ends_with(b"\n") {
+                return body;
+            }
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const MENU: &[u8] = b"iWelcome to Floodgap\t\terror.host\t1\r\n\
+1Gopher Project\t/gopher\tgopher.floodgap.com\t70\r\n\
+0About this server\t/about.txt\tgopher.floodgap.com\t7070\r\n\
+7Veronica-2 Search\t/v2/vs\tgopher.floodgap.com\t70\r\n\
+.\r\n\
+0After the terminator\t/x\thost\t70\r\n";
+
+    #[derive(Default)]
+    struct MockState {
+        connected_to: Option<(String, u16)>,
+        sent: Vec<u8>,
+        response: Vec<u8>,
+        read_pos: usize,
+        connected: bool,
+    }
+
+    struct MockTcpClient {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[async_trait]
+    impl TcpClient for MockTcpClient {
+        async fn connect(&mut self, host: &str, port: u16) -> DeviceResult<()> {
+            let mut state = self.state.lock().unwrap();
+            state.connected_to = Some((host.to_string(), port));
+            state.connected = true;
+            Ok(())
+        }
+
+        async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+            let mut state = self.state.lock().unwrap();
+            let remaining = &state.response[state.read_pos..];
+            let len = std::cmp::min(std::cmp::min(buf.len(), remaining.len()), 16);
+            buf[..len].copy_from_slice(&remaining[..len]);
+            state.read_pos += len;
+            Ok(len)
+        }
+
+        async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            self.state.lock().unwrap().sent.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        async fn disconnect(&mut self) -> DeviceResult<()> {
+            self.state.lock().unwrap().connected = false;
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            self.state.lock().unwrap().connected
+        }
+    }
+
+    struct MockProvider {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    impl TcpClientProvider for MockProvider {
+        fn create_tcp_client(&self) -> Box<dyn TcpClient> {
+            Box::new(MockTcpClient { state: self.state.clone() })
+        }
+    }
+
+    fn create_protocol(response: &[u8]) -> (GopherProtocol, Arc<Mutex<MockState>>) {
+        let state = Arc::new(Mutex::new(MockState { response: response.to_vec(), ..Default::default() }));
+        let provider = Arc::new(MockProvider { state: state.clone() });
+        (GopherProtocol::new(provider), state)
+    }
+
+    #[test]
+    fn test_parse_menu() {
+        let entries = parse_menu(MENU);
+        assert_eq!(entries.len(), 4, "entries after the terminator are ignored");
+
+        assert_eq!(entries[0].item_type, GopherItemType::Info);
+        assert_eq!(entries[0].display, "Welcome to Floodgap");
+
+        assert_eq!(entries[1], GopherEntry {
+            item_type: GopherItemType::Directory,
+            display: "Gopher Project".to_string(),
+            selector: "/gopher".to_string(),
+            host: "gopher.floodgap.com".to_string(),
+            port: 70,
+        });
+        assert_eq!(entries[2].item_type, GopherItemType::TextFile);
+        assert_eq!(entries[2].port, 7070);
+        assert_eq!(entries[3].item_type, GopherItemType::Search);
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            GopherProtocol::parse_url("gopher://gopher.floodgap.com/0/about%20me.txt").unwrap(),
+            ("gopher.floodgap.com".to_string(), 70, GopherItemType::TextFile, "/about me.txt".to_string())
+        );
+        assert_eq!(
+            GopherProtocol::parse_url("gopher://host:7070").unwrap(),
+            ("host".to_string(), 7070, GopherItemType::Directory, String::new())
+        );
+        assert_eq!(
+            GopherProtocol::parse_url("gopher://host/7/v2/vs?atari").unwrap().3,
+            "/v2/vs\tatari"
+        );
+        assert_eq!(GopherProtocol::parse_url("http://host/").unwrap_err(), DeviceError::InvalidUrl);
+    }
+
+    #[test]
+    fn test_entry_url_round_trips() {
+        let entry = &parse_menu(MENU)[2];
+        assert_eq!(entry.url(), "gopher://gopher.floodgap.com:7070/0/about.txt");
+        let (host, port, item_type, selector) = GopherProtocol::parse_url(&entry.url()).unwrap();
+        assert_eq!((host.as_str(), port, item_type, selector.as_str()), ("gopher.floodgap.com", 7070, GopherItemType::TextFile, "/about.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_directory_mode_lists_entries() {
+        let (mut protocol, state) = create_protocol(MENU);
+        protocol.set_mode(OPEN_MODE_DIRECTORY);
+        protocol.open("gopher://gopher.floodgap.com/1/").await.unwrap();
+
+        {
+            let state = state.lock().unwrap();
+            assert_eq!(state.connected_to, Some(("gopher.floodgap.com".to_string(), 70)));
+            assert_eq!(state.sent, b"/\r\n");
+            assert!(!state.connected, "menu connection is closed once read");
+        }
+        assert_eq!(protocol.entries().len(), 4);
+
+        let mut buf = vec![0u8; 512];
+        let len = protocol.read(&mut buf).await.unwrap();
+        let listing = String::from_utf8(buf[..len].to_vec()).unwrap();
+        assert_eq!(listing, "iWelcome to Floodgap\n\
+1Gopher Project\tgopher://gopher.floodgap.com/1/gopher\n\
+0About this server\tgopher://gopher.floodgap.com:7070/0/about.txt\n\
+7Veronica-2 Search\tgopher://gopher.floodgap.com/7/v2/vs\n");
+        assert_eq!(protocol.available().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_as_directory_lists_menu_items() {
+        let (mut protocol, state) = create_protocol(MENU);
+        let dir = protocol.as_directory().expect("gopher menus list as directories");
+        let entries = dir.list_directory("gopher://gopher.floodgap.com/1/").await.unwrap();
+
+        let names: Vec<(&str, bool)> = entries.iter().map(|e| (e.name.as_str(), e.is_dir)).collect();
+        assert_eq!(names, [("Gopher Project", true), ("About this server", false), ("Veronica-2 Search", false)]);
+        assert!(!state.lock().unwrap().connected, "menu connection is closed once read");
+    }
+
+    #[tokio::test]
+    async fn test_text_file_terminator_is_stripped() {
+        let (mut protocol, _) = create_protocol(b"First line\r\n.. starts with a dot\r\nLast line\r\n.\r\n");
+        protocol.open("gopher://host/0/doc.txt").await.unwrap();
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 5];
+        loop {
+            let len = protocol.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(received, b"First line\r\n.. starts with a dot\r\nLast line\r\n");
+
+        assert_eq!(strip_terminator(b".\r\n"), b"");
+        assert_eq!(strip_terminator(b"line\n.\n"), b"line\n");
+        assert_eq!(strip_terminator(b"ends in a dot.\r\n"), b"ends in a dot.\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_mode_streams_document() {
+        let document = b"Line one of a long document\r\nLine two\r\n";
+        let (mut protocol, state) = create_protocol(document);
+        protocol.open("gopher://host/0/doc.txt").await.unwrap();
+        assert_eq!(state.lock().unwrap().sent, b"/doc.txt\r\n");
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 64];
+        loop {
+            let len = protocol.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            assert!(len <= 16, "reads are passed straight through to the socket");
+            received.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(received, document);
+        assert_eq!(protocol.write(b"x").await, Err(DeviceError::NotSupported));
+
+        protocol.close().await.unwrap();
+        assert_eq!(protocol.read(&mut buf).await, Err(DeviceError::NotReady));
+    }
+}
@@ -1,14 +1,34 @@
 use std::collections::HashMap;
 use crate::device::{DeviceError, DeviceResult};
-use super::{ProtocolHandler, ConnectionStatus, HttpClient, client_provider::HttpClientProvider};
+use super::{ProtocolHandler, ConnectionStatus, HttpClient, client_provider::HttpClientProvider, OPEN_MODE_DIRECTORY, OPEN_MODE_READ};
+use super::webdav::{self, WebDavEntry, PROPFIND_BODY};
+use super::directory::{DirectoryEntry, DirectoryHandler};
+use super::filesystem::FileSystemHandler;
+use super::seek::SeekableHandler;
 use async_trait::async_trait;
 use std::any::Any;
+use std::io::SeekFrom;
 use std::sync::Arc;
 
+/// Bytes requested per Range request when reading a resource in OPEN_MODE_READ
+const RANGE_CHUNK_SIZE: u64 = 16 * 1024;
+
+/// Attempts made for each ranged fetch; a retry resumes from the same offset
+const RANGE_ATTEMPTS: usize = 3;
+
 /// HTTP protocol handler implementation
 pub struct HttpProtocol {
     client: Box<dyn HttpClient>,
     url: Option<String>,
+    mode: u8,
+    read_buffer: Vec<u8>,
+    read_pos: usize,
+    /// Offset of the next byte to read in OPEN_MODE_READ
+    position: u64,
+    /// Size of the resource, once a response has told us
+    content_length: Option<u64>,
+    /// Offset of read_buffer within the resource in OPEN_MODE_READ
+    chunk_start: u64,
 }
 
 impl HttpProtocol {
@@ -16,18 +36,26 @@ impl HttpProtocol {
         Self {
             client: client_provider.create_http_client(),
             url: None,
+            mode: 0,
+            read_buffer: Vec::new(),
+            read_pos: 0,
+            position: 0,
+            content_length: None,
+            chunk_start: 0,
         }
     }
 
     /// Send an HTTP request
+    /// An empty URL uses the one given when the connection was opened
     pub async fn send_request(&mut self, method: &str, url: &str, body: &[u8]) -> DeviceResult<Vec<u8>> {
+        let url = self.resolve_url(url);
         match method.to_uppercase().as_str() {
-            "GET" => self.client.get(url).await,
-            "POST" => self.client.post(url, body).await,
-            "PUT" => self.client.put(url, body).await,
-            "DELETE" => self.client.delete(url).await,
-            "HEAD" => self.client.head(url).await,
-            "PATCH" => self.client.patch(url, body).await,
+            "GET" => self.client.get(&url).await,
+            "POST" => self.client.post(&url, body).await,
+            "PUT" => self.client.put(&url, body).await,
+            "DELETE" => self.client.delete(&url).await,
+            "HEAD" => self.client.head(&url).await,
+            "PATCH" => self.client.patch(&url, body).await,
             _ => Err(DeviceError::InvalidOperation),
         }
     }
@@ -62,6 +90,69 @@ impl HttpProtocol {
         self.send_request("PATCH", url, body).await
     }
 
+    /// GET a resource starting at `offset`, e.g. to resume an interrupted download
+    /// Servers that ignore Range send the whole body, which is trimmed to the requested part
+    pub async fn get_from(&mut self, url: &str, offset: u64) -> DeviceResult<Vec<u8>> {
+        let url = self.resolve_url(url);
+        let response = self.client.get_range(&url, offset, None).await?;
+        match self.client.status_code() {
+            206 => Ok(response.data),
+            200 => Ok(response.data.get(offset as usize..).map(<[u8]>::to_vec).unwrap_or_default()),
+            416 => Ok(Vec::new()),
+            code => Err(DeviceError::NetworkError(format!("HTTP status {}", code))),
+        }
+    }
+
+    /// List a WebDAV collection using PROPFIND with Depth: 1
+    pub async fn propfind(&mut self, url: &str) -> DeviceResult<Vec<WebDavEntry>> {
+        let url = self.resolve_url(url);
+        let headers = [("Depth", "1"), ("Content-Type", "application/xml; charset=utf-8")];
+        let response = self.client.request("PROPFIND", &url, &headers, PROPFIND_BODY.as_bytes()).await?;
+        self.check_status()?;
+
+        let xml = String::from_utf8_lossy(&response);
+        webdav::parse_multistatus(&xml, webdav::url_path(&url))
+    }
+
+    /// Create a WebDAV collection (MKCOL)
+    pub async fn make_directory(&mut self, url: &str) -> DeviceResult<()> {
+        let url = self.resolve_url(url);
+        self.client.request("MKCOL", &url, &[], &[]).await?;
+        self.check_status()
+    }
+
+    /// Remove a WebDAV collection
+    pub async fn remove_directory(&mut self, url: &str) -> DeviceResult<()> {
+        let mut url = self.resolve_url(url);
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        self.client.delete(&url).await?;
+        self.check_status()
+    }
+
+    /// Delete a WebDAV resource
+    pub async fn delete_file(&mut self, url: &str) -> DeviceResult<()> {
+        let url = self.resolve_url(url);
+        self.client.delete(&url).await?;
+        self.check_status()
+    }
+
+    /// Rename a WebDAV resource (MOVE)
+    /// A destination without a scheme is treated as a name in the source's directory
+    pub async fn rename(&mut self, from: &str, to: &str) -> DeviceResult<()> {
+        let from = self.resolve_url(from);
+        let destination = if to.contains("://") {
+            to.to_string()
+        } else {
+            let parent = &from[..from.trim_end_matches('/').rfind('/').map_or(0, |idx| idx + 1)];
+            format!("{}{}", parent, to.trim_start_matches('/'))
+        };
+        let headers = [("Destination", destination.as_str()), ("Overwrite", "F")];
+        self.client.request("MOVE", &from, &headers, &[]).await?;
+        self.check_status()
+    }
+
     /// Set a header for subsequent requests
     pub fn set_header(&mut self, key: &str, value: &str) {
         self.client.set_header(key, value);
@@ -81,6 +172,111 @@ impl HttpProtocol {
     pub fn url(&self) -> Option<&str> {
         self.url.as_deref()
     }
+
+    fn resolve_url(&self, url: &str) -> String {
+        match (url.is_empty(), &self.url) {
+            (true, Some(base)) => base.clone(),
+            _ => url.to_string(),
+        }
+    }
+
+    /// Let the client set itself up for requests made without an open connection
+    async fn ensure_connected(&mut self, url: &str) -> DeviceResult<()> {
+        if self.url.is_none() {
+            self.client.connect(url).await?;
+        }
+        Ok(())
+    }
+
+    fn chunk_contains(&self, offset: u64) -> bool {
+        offset >= self.chunk_start && offset - self.chunk_start < self.read_buffer.len() as u64
+    }
+
+    /// Fetch the chunk of the open resource starting at `start` into the read buffer
+    /// Network errors are retried from the same offset, so an interrupted transfer picks up where it stopped
+    async fn fetch_chunk(&mut self, start: u64) -> DeviceResult<()> {
+        let url = self.url.clone().ok_or(DeviceError::NotReady)?;
+        let end = start + RANGE_CHUNK_SIZE - 1;
+
+        let mut attempt = 1;
+        let response = loop {
+            match self.client.get_range(&url, start, Some(end)).await {
+                Ok(response) => break response,
+                Err(DeviceError::NetworkError(_)) if attempt < RANGE_ATTEMPTS => attempt += 1,
+                Err(e) => return Err(e),
+            }
+        };
+
+        match self.client.status_code() {
+            206 => {
+                self.chunk_start = start;
+                self.content_length = response.total_len.or(self.content_length);
+                self.read_buffer = response.data;
+            }
+            // The server ignored Range and sent the whole resource
+            200 => {
+                self.chunk_start = 0;
+                self.content_length = Some(response.data.len() as u64);
+                self.read_buffer = response.data;
+            }
+            // Range not satisfiable: `start` is at or past the end
+            416 => {
+                self.chunk_start = start;
+                self.content_length = response.total_len.or(self.content_length);
+                self.read_buffer.clear();
+            }
+            code => return Err(DeviceError::NetworkError(format!("HTTP status {}", code))),
+        }
+        Ok(())
+    }
+
+    /// Read the open resource at the current position, fetching chunks with Range requests
+    async fn read_range(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if self.content_length.is_some_and(|len| self.position >= len) {
+            return Ok(0);
+        }
+        if !self.chunk_contains(self.position) {
+            self.fetch_chunk(self.position).await?;
+            if !self.chunk_contains(self.position) {
+                return Ok(0);
+            }
+        }
+
+        let remaining = &self.read_buffer[(self.position - self.chunk_start) as usize..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn reset_read_state(&mut self) {
+        self.read_buffer.clear();
+        self.read_pos = 0;
+        self.position = 0;
+        self.content_length = None;
+        self.chunk_start = 0;
+    }
+
+    /// Map a non-2xx status from the last request to an error
+    fn check_status(&self) -> DeviceResult<()> {
+        match self.client.status_code() {
+            200..=299 => Ok(()),
+            code => Err(DeviceError::NetworkError(format!("HTTP status {}", code))),
+        }
+    }
+
+    /// Render a directory listing as one name per line, with a trailing '/' on directories
+    fn format_listing(entries: &[WebDavEntry]) -> Vec<u8> {
+        let mut listing = Vec::new();
+        for entry in entries {
+            listing.extend_from_slice(entry.name.as_bytes());
+            if entry.is_dir {
+                listing.push(b'/');
+            }
+            listing.push(b'\n');
+        }
+        listing
+    }
 }
 
 #[async_trait]
@@ -95,19 +291,37 @@ impl ProtocolHandler for HttpProtocol {
 
     async fn open(&mut self, url: &str) -> DeviceResult<()> {
         self.url = Some(url.to_string());
-        self.client.connect(url).await
+        self.reset_read_state();
+        self.client.connect(url).await?;
+
+        if self.mode == OPEN_MODE_DIRECTORY {
+            let entries = self.propfind(url).await?;
+            self.read_buffer = Self::format_listing(&entries);
+        }
+        Ok(())
     }
 
     async fn close(&mut self) -> DeviceResult<()> {
         let result = self.client.disconnect().await;
         if result.is_ok() {
             self.url = None;
+            self.reset_read_state();
         }
         result
     }
 
-    async fn read(&mut self, _buf: &mut [u8]) -> DeviceResult<usize> {
-        Err(DeviceError::NotSupported)
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if self.mode == OPEN_MODE_READ {
+            return self.read_range(buf).await;
+        }
+        if self.mode != OPEN_MODE_DIRECTORY {
+            return Err(DeviceError::NotSupported);
+        }
+        let remaining = &self.read_buffer[self.read_pos..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.read_pos += len;
+        Ok(len)
     }
 
     async fn write(&mut self, _buf: &[u8]) -> DeviceResult<usize> {
@@ -123,13 +337,102 @@ impl ProtocolHandler for HttpProtocol {
     }
 
     async fn available(&self) -> DeviceResult<usize> {
-        Ok(0)
+        if self.mode == OPEN_MODE_READ {
+            let buffered = if self.chunk_contains(self.position) {
+                self.chunk_start + self.read_buffer.len() as u64 - self.position
+            } else {
+                0
+            };
+            let remaining = self.content_length.map_or(buffered, |len| len.saturating_sub(self.position));
+            return Ok(remaining as usize);
+        }
+        Ok(self.read_buffer.len() - self.read_pos)
+    }
+
+    fn set_mode(&mut self, mode: u8) {
+        self.mode = mode;
+    }
+
+    fn as_directory(&mut self) -> Option<&mut dyn DirectoryHandler> {
+        Some(self)
+    }
+
+    fn as_filesystem(&mut self) -> Option<&mut dyn FileSystemHandler> {
+        Some(self)
+    }
+
+    fn as_seekable(&mut self) -> Option<&mut dyn SeekableHandler> {
+        if self.mode == OPEN_MODE_READ {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+/// Random access reads; the next read fetches the chunk holding the new position
+#[async_trait]
+impl SeekableHandler for HttpProtocol {
+    async fn seek(&mut self, pos: SeekFrom) -> DeviceResult<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => {
+                if self.content_length.is_none() {
+                    self.fetch_chunk(0).await?;
+                }
+                let len = self.content_length.ok_or(DeviceError::NotSupported)?;
+                len.checked_add_signed(delta)
+            }
+        };
+        self.position = target.ok_or(DeviceError::InvalidOperation)?;
+        Ok(self.position)
+    }
+
+    fn tell(&self) -> u64 {
+        self.position
+    }
+}
+
+#[async_trait]
+impl DirectoryHandler for HttpProtocol {
+    async fn list_directory(&mut self, url: &str) -> DeviceResult<Vec<DirectoryEntry>> {
+        self.ensure_connected(url).await?;
+        let entries = self.propfind(url).await?;
+        Ok(entries.into_iter()
+            .map(|e| DirectoryEntry { name: e.name, size: e.size, modified: e.modified, is_dir: e.is_dir })
+            .collect())
+    }
+}
+
+/// WebDAV backed filesystem commands; locking is not supported
+#[async_trait]
+impl FileSystemHandler for HttpProtocol {
+    async fn rename(&mut self, url: &str, new_name: &str) -> DeviceResult<()> {
+        self.ensure_connected(url).await?;
+        HttpProtocol::rename(self, url, new_name).await
+    }
+
+    async fn delete(&mut self, url: &str) -> DeviceResult<()> {
+        self.ensure_connected(url).await?;
+        self.delete_file(url).await
+    }
+
+    async fn make_directory(&mut self, url: &str) -> DeviceResult<()> {
+        self.ensure_connected(url).await?;
+        HttpProtocol::make_directory(self, url).await
+    }
+
+    async fn remove_directory(&mut self, url: &str) -> DeviceResult<()> {
+        self.ensure_connected(url).await?;
+        HttpProtocol::remove_directory(self, url).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::RangeResponse;
     use std::sync::{Arc, Mutex};
 
     #[derive(Clone, Default, Debug, PartialEq)]
@@ -142,6 +445,9 @@ mod tests {
     struct TestHttpClient {
         headers: HashMap<String, String>,
         recorded_requests: Arc<Mutex<Vec<RequestRecord>>>,
+        request_headers: Arc<Mutex<Vec<(String, String)>>>,
+        method_responses: HashMap<String, Vec<u8>>,
+        status_code: u16,
         is_connected: bool,
         endpoint: String,
     }
@@ -151,6 +457,9 @@ mod tests {
             Self {
                 headers: HashMap::new(),
                 recorded_requests: Arc::new(Mutex::new(Vec::new())),
+                request_headers: Arc::new(Mutex::new(Vec::new())),
+                method_responses: HashMap::new(),
+                status_code: 200,
                 is_connected: false,
                 endpoint: String::new(),
             }
@@ -162,6 +471,9 @@ mod tests {
             Self {
                 headers: self.headers.clone(),
                 recorded_requests: self.recorded_requests.clone(),
+                request_headers: self.request_headers.clone(),
+                method_responses: self.method_responses.clone(),
+                status_code: self.status_code,
                 is_connected: self.is_connected,
                 endpoint: self.endpoint.clone(),
             }
@@ -254,12 +566,26 @@ mod tests {
             Ok(b"test response".to_vec())
         }
 
+        async fn request(&mut self, method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> DeviceResult<Vec<u8>> {
+            if !self.is_connected {
+                return Err(DeviceError::NotReady);
+            }
+            self.recorded_requests.lock().unwrap().push(RequestRecord {
+                method: method.to_string(),
+                url: url.to_string(),
+                body: body.to_vec(),
+            });
+            self.request_headers.lock().unwrap()
+                .extend(headers.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            Ok(self.method_responses.get(method).cloned().unwrap_or_default())
+        }
+
         fn set_header(&mut self, key: &str, value: &str) {
             self.headers.insert(key.to_string(), value.to_string());
         }
 
         fn status_code(&self) -> u16 {
-            200
+            self.status_code
         }
 
         fn headers(&self) -> HashMap<String, String> {
@@ -295,6 +621,19 @@ mod tests {
         assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Disconnected);
     }
 
+    #[tokio::test]
+    async fn test_unknown_method_is_refused() {
+        let provider = Arc::new(TestHttpClientProvider::default());
+        let mut protocol = HttpProtocol::new(provider.clone());
+        protocol.open("http://test.com").await.unwrap();
+
+        // Methods without a client call are refused rather than sent as-is
+        assert_eq!(
+            protocol.send_request("BREW", "http://test.com/pot", &[]).await,
+            Err(DeviceError::InvalidOperation)
+        );
+    }
+
     #[tokio::test]
     async fn test_http_operations() {
         let provider = Arc::new(TestHttpClientProvider::default());
@@ -390,6 +729,318 @@ mod tests {
             Err(DeviceError::NotReady)
         ));
     }
-} 
 
+    const PROPFIND_RESPONSE: &str = r#"<?xml version="1.0"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response><D:href>/dav/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+  <D:response><D:href>/dav/readme.txt</D:href>
+    <D:propstat><D:prop><D:resourcetype/><D:getcontentlength>12</D:getcontentlength></D:prop></D:propstat>
+  </D:response>
+  <D:response><D:href>/dav/games/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+</D:multistatus>"#;
+
+    fn webdav_provider() -> Arc<TestHttpClientProvider> {
+        let mut client = TestHttpClient::default();
+        client.method_responses.insert("PROPFIND".to_string(), PROPFIND_RESPONSE.as_bytes().to_vec());
+        Arc::new(TestHttpClientProvider { client })
+    }
+
+    #[tokio::test]
+    async fn test_directory_mode_lists_propfind_entries() {
+        let provider = webdav_provider();
+        let mut protocol = HttpProtocol::new(provider.clone());
+        protocol.set_mode(OPEN_MODE_DIRECTORY);
+        protocol.open("http://test.com/dav/").await.unwrap();
+
+        let expected = b"readme.txt\ngames/\n";
+        assert_eq!(protocol.available().await.unwrap(), expected.len());
 
+        let mut buf = [0u8; 64];
+        let len = protocol.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], expected);
+        assert_eq!(protocol.available().await.unwrap(), 0);
+
+        let requests = provider.client.recorded_requests.lock().unwrap();
+        assert_eq!(requests[0].method, "PROPFIND");
+        assert_eq!(requests[0].url, "http://test.com/dav/");
+        let headers = provider.client.request_headers.lock().unwrap();
+        assert!(headers.contains(&("Depth".to_string(), "1".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_directory_handler_uses_propfind() {
+        let provider = webdav_provider();
+        let mut protocol = HttpProtocol::new(provider.clone());
+
+        let dir = protocol.as_directory().expect("http protocol lists directories");
+        let entries = dir.list_directory("http://test.com/dav/").await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "readme.txt");
+        assert_eq!(entries[0].size, 12);
+        assert!(entries[1].is_dir);
+        assert_eq!(dir.free_space("http://test.com/dav/").await.unwrap(), None);
+        assert_eq!(provider.client.recorded_requests.lock().unwrap()[0].method, "PROPFIND");
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_handler_uses_webdav() {
+        let provider = webdav_provider();
+        let mut protocol = HttpProtocol::new(provider.clone());
+
+        let fs = protocol.as_filesystem().expect("http protocol supports filesystem commands");
+        fs.make_directory("http://test.com/dav/new").await.unwrap();
+        fs.rename("http://test.com/dav/a.txt", "b.txt").await.unwrap();
+        fs.delete("http://test.com/dav/b.txt").await.unwrap();
+        assert!(matches!(fs.lock("http://test.com/dav/a.txt").await, Err(DeviceError::NotSupported)));
+
+        let requests = provider.client.recorded_requests.lock().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, vec!["MKCOL", "MOVE", "DELETE"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_not_supported_outside_directory_mode() {
+        let provider = webdav_provider();
+        let mut protocol = HttpProtocol::new(provider);
+        protocol.open("http://test.com/dav/").await.unwrap();
+
+        let mut buf = [0u8; 16];
+        assert!(matches!(protocol.read(&mut buf).await, Err(DeviceError::NotSupported)));
+    }
+
+    #[tokio::test]
+    async fn test_webdav_file_management() {
+        let provider = webdav_provider();
+        let mut protocol = HttpProtocol::new(provider.clone());
+        protocol.open("http://test.com/dav/").await.unwrap();
+
+        protocol.make_directory("http://test.com/dav/new").await.unwrap();
+        protocol.rename("http://test.com/dav/old.txt", "new.txt").await.unwrap();
+        protocol.delete_file("http://test.com/dav/new.txt").await.unwrap();
+        protocol.remove_directory("http://test.com/dav/new").await.unwrap();
+
+        let requests = provider.client.recorded_requests.lock().unwrap();
+        let summary: Vec<(&str, &str)> = requests.iter()
+            .map(|r| (r.method.as_str(), r.url.as_str()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("MKCOL", "http://test.com/dav/new"),
+            ("MOVE", "http://test.com/dav/old.txt"),
+            ("DELETE", "http://test.com/dav/new.txt"),
+            ("DELETE", "http://test.com/dav/new/"),
+        ]);
+
+        let headers = provider.client.request_headers.lock().unwrap();
+        assert!(headers.contains(&("Destination".to_string(), "http://test.com/dav/new.txt".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_webdav_error_status() {
+        let client = TestHttpClient { status_code: 405, ..Default::default() };
+        let provider = Arc::new(TestHttpClientProvider { client });
+        let mut protocol = HttpProtocol::new(provider);
+        protocol.open("http://test.com/dav/").await.unwrap();
+
+        assert!(matches!(
+            protocol.make_directory("http://test.com/dav/new").await,
+            Err(DeviceError::NetworkError(_))
+        ));
+    }
+
+    /// First and optional last byte of a requested Range
+    type ByteRange = (u64, Option<u64>);
+
+    /// Serves a fixed body, honouring Range unless told otherwise
+    #[derive(Clone, Default)]
+    struct RangeTestClient {
+        body: Vec<u8>,
+        ignore_range: bool,
+        failures: Arc<Mutex<usize>>,
+        ranges: Arc<Mutex<Vec<ByteRange>>>,
+        status_code: u16,
+    }
+
+    #[async_trait]
+    impl HttpClient for RangeTestClient {
+        async fn connect(&mut self, _url: &str) -> DeviceResult<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> DeviceResult<()> {
+            Ok(())
+        }
+
+        async fn get(&mut self, _url: &str) -> DeviceResult<Vec<u8>> {
+            Ok(self.body.clone())
+        }
+
+        async fn post(&mut self, _url: &str, _body: &[u8]) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn put(&mut self, _url: &str, _body: &[u8]) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn delete(&mut self, _url: &str) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn head(&mut self, _url: &str) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn patch(&mut self, _url: &str, _body: &[u8]) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn request(&mut self, _method: &str, _url: &str, _headers: &[(&str, &str)], _body: &[u8]) -> DeviceResult<Vec<u8>> {
+            Err(DeviceError::NotSupported)
+        }
+
+        async fn get_range(&mut self, _url: &str, start: u64, end: Option<u64>) -> DeviceResult<RangeResponse> {
+            self.ranges.lock().unwrap().push((start, end));
+            {
+                let mut failures = self.failures.lock().unwrap();
+                if *failures > 0 {
+                    *failures -= 1;
+                    return Err(DeviceError::NetworkError("connection reset".to_string()));
+                }
+            }
+            let total = self.body.len() as u64;
+            if self.ignore_range {
+                self.status_code = 200;
+                return Ok(RangeResponse { data: self.body.clone(), total_len: None });
+            }
+            if start >= total {
+                self.status_code = 416;
+                return Ok(RangeResponse { data: Vec::new(), total_len: Some(total) });
+            }
+            let end = end.map_or(total - 1, |end| end.min(total - 1));
+            self.status_code = 206;
+            Ok(RangeResponse { data: self.body[start as usize..=end as usize].to_vec(), total_len: Some(total) })
+        }
+
+        fn set_header(&mut self, _key: &str, _value: &str) {}
+
+        fn status_code(&self) -> u16 {
+            self.status_code
+        }
+
+        fn headers(&self) -> HashMap<String, String> {
+            HashMap::new()
+        }
+    }
+
+    struct RangeTestProvider {
+        client: RangeTestClient,
+    }
+
+    impl HttpClientProvider for RangeTestProvider {
+        fn create_http_client(&self) -> Box<dyn HttpClient> {
+            Box::new(self.client.clone())
+        }
+    }
+
+    fn range_body() -> Vec<u8> {
+        (0..RANGE_CHUNK_SIZE as usize + 100).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn open_for_read(client: RangeTestClient) -> HttpProtocol {
+        let mut protocol = HttpProtocol::new(Arc::new(RangeTestProvider { client }));
+        protocol.set_mode(OPEN_MODE_READ);
+        protocol.open("http://test.com/game.atr").await.unwrap();
+        protocol
+    }
+
+    #[tokio::test]
+    async fn test_read_mode_fetches_chunks_with_range() {
+        let body = range_body();
+        let client = RangeTestClient { body: body.clone(), ..Default::default() };
+        let ranges = client.ranges.clone();
+        let mut protocol = open_for_read(client).await;
+
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = protocol.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(data, body);
+        assert_eq!(protocol.available().await.unwrap(), 0);
+        assert_eq!(*ranges.lock().unwrap(), vec![
+            (0, Some(RANGE_CHUNK_SIZE - 1)),
+            (RANGE_CHUNK_SIZE, Some(2 * RANGE_CHUNK_SIZE - 1)),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_read_mode_retries_from_same_offset() {
+        let body = range_body();
+        let client = RangeTestClient { body: body.clone(), failures: Arc::new(Mutex::new(2)), ..Default::default() };
+        let ranges = client.ranges.clone();
+        let mut protocol = open_for_read(client).await;
+
+        let mut buf = [0u8; 8];
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 8);
+        assert_eq!(&buf, &body[..8]);
+        assert_eq!(ranges.lock().unwrap().len(), 3);
+        assert!(ranges.lock().unwrap().iter().all(|&(start, _)| start == 0));
+    }
+
+    #[tokio::test]
+    async fn test_seek_uses_range_and_reports_position() {
+        let body = range_body();
+        let client = RangeTestClient { body: body.clone(), ..Default::default() };
+        let ranges = client.ranges.clone();
+        let mut protocol = open_for_read(client).await;
+
+        let seekable = protocol.as_seekable().expect("read mode is seekable");
+        assert_eq!(seekable.seek(SeekFrom::End(-10)).await.unwrap(), body.len() as u64 - 10);
+        assert_eq!(seekable.tell(), body.len() as u64 - 10);
+        assert!(matches!(seekable.seek(SeekFrom::Current(-(body.len() as i64))).await, Err(DeviceError::InvalidOperation)));
+
+        let mut buf = [0u8; 32];
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 10);
+        assert_eq!(&buf[..10], &body[body.len() - 10..]);
+        assert_eq!(ranges.lock().unwrap().last().unwrap().0, body.len() as u64 - 10);
+
+        protocol.as_seekable().unwrap().seek(SeekFrom::Start(body.len() as u64 + 5)).await.unwrap();
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_server_ignoring_range_sends_whole_body() {
+        let body = range_body();
+        let client = RangeTestClient { body: body.clone(), ignore_range: true, ..Default::default() };
+        let mut protocol = open_for_read(client).await;
+
+        protocol.as_seekable().unwrap().seek(SeekFrom::Start(RANGE_CHUNK_SIZE)).await.unwrap();
+        let mut buf = [0u8; 200];
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 100);
+        assert_eq!(&buf[..100], &body[RANGE_CHUNK_SIZE as usize..]);
+
+        assert_eq!(protocol.get_from("", 10).await.unwrap(), &body[10..]);
+    }
+
+    #[tokio::test]
+    async fn test_get_from_resumes_download() {
+        let body = range_body();
+        let client = RangeTestClient { body: body.clone(), ..Default::default() };
+        let ranges = client.ranges.clone();
+        let mut protocol = HttpProtocol::new(Arc::new(RangeTestProvider { client }));
+        protocol.open("http://test.com/game.atr").await.unwrap();
+
+        assert_eq!(protocol.get_from("", 1000).await.unwrap(), &body[1000..]);
+        assert_eq!(protocol.get_from("", body.len() as u64).await.unwrap(), Vec::<u8>::new());
+        assert_eq!(ranges.lock().unwrap()[0], (1000, None));
+        assert!(protocol.as_seekable().is_none());
+    }
+}
@@ -63,6 +63,32 @@ impl BaseHttpClient {
     }
 }
 
+/// Body of a ranged GET, plus the resource size reported by the server
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangeResponse {
+    pub data: Vec<u8>,
+    /// Total size of the resource from Content-Range, if the server sent one
+    pub total_len: Option<u64>,
+}
+
+/// Value for a Range header covering start..=end, or from start to the end of the resource
+pub fn range_header(start: u64, end: Option<u64>) -> String {
+    match end {
+        Some(end) => format!("bytes={}-{}", start, end),
+        None => format!("bytes={}-", start),
+    }
+}
+
+/// Total length from a Content-Range value such as "bytes 0-99/1234" or "bytes */1234"
+/// Returns None when the server reports the length as unknown ("/*")
+pub fn parse_content_range_total(value: &str) -> Option<u64> {
+    let (unit, rest) = value.trim().split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    rest.rsplit_once('/')?.1.trim().parse().ok()
+}
+
 /// Platform-agnostic HTTP client interface
 #[async_trait]
 pub trait HttpClient: Send + Sync {
@@ -89,6 +115,18 @@ pub trait HttpClient: Send + Sync {
     
     /// Perform HTTP PATCH request
     async fn patch(&mut self, url: &str, body: &[u8]) -> DeviceResult<Vec<u8>>;
+
+    /// Perform an HTTP request with an arbitrary method (e.g. WebDAV PROPFIND, MKCOL, MOVE)
+    /// The extra headers apply to this request only
+    async fn request(&mut self, method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> DeviceResult<Vec<u8>>;
+
+    /// Perform a GET for part of a resource (start..=end, or from start to the end)
+    /// status_code() tells 206 (partial content) apart from 200 (the server sent everything)
+    async fn get_range(&mut self, url: &str, start: u64, end: Option<u64>) -> DeviceResult<RangeResponse> {
+        let range = range_header(start, end);
+        let data = self.request("GET", url, &[("Range", &range)], &[]).await?;
+        Ok(RangeResponse { data, total_len: None })
+    }
     
     /// Set a header for subsequent requests
     fn set_header(&mut self, key: &str, value: &str);
@@ -130,6 +168,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_range_headers() {
+        assert_eq!(range_header(0, Some(1023)), "bytes=0-1023");
+        assert_eq!(range_header(500, None), "bytes=500-");
+
+        assert_eq!(parse_content_range_total("bytes 0-99/1234"), Some(1234));
+        assert_eq!(parse_content_range_total("bytes */1234"), Some(1234));
+        assert_eq!(parse_content_range_total("bytes 0-99/*"), None);
+        assert_eq!(parse_content_range_total("items 0-1/2"), None);
+    }
+
     #[test]
     fn test_status_code() {
         let mut client = BaseHttpClient::default();
@@ -1,4 +1,17 @@
 pub mod http;
+pub mod file;
+pub mod webdav;
+pub mod websocket;
+pub mod websocket_client;
+pub mod mqtt;
+pub mod mqtt_client;
+pub mod gopher;
+pub mod tcp;
+pub mod tcp_client;
+pub mod send_buffer;
+pub mod directory;
+pub mod filesystem;
+pub mod seek;
 mod protocol_handler;
 mod client_provider;
 mod registry;
@@ -6,8 +19,24 @@ mod http_client;
 mod factory;
 
 pub use http::HttpProtocol;
-pub use protocol_handler::{ProtocolHandler, ConnectionStatus};
-pub use client_provider::HttpClientProvider;
+pub use file::FileProtocol;
+pub use webdav::WebDavEntry;
+pub use websocket::{WebSocketProtocol, WebSocketMessageType};
+pub use websocket_client::{WebSocketClient, WebSocketMessage};
+pub use mqtt::MqttProtocol;
+pub use mqtt_client::{MqttClient, MqttConnectOptions, MqttMessage, MqttQos};
+pub use gopher::{GopherProtocol, GopherEntry, GopherItemType};
+pub use tcp::{TcpProtocol, TCP_SEND_BUFFER_SIZE};
+pub use tcp_client::TcpClient;
+pub use send_buffer::SendBuffer;
+pub use directory::{DirectoryEntry, DirectoryFormat, DirectoryHandler, DirectoryListing};
+pub use filesystem::{FileSystemCommand, FileSystemHandler};
+pub use seek::SeekableHandler;
+pub use protocol_handler::{
+    ProtocolHandler, ConnectionStatus,
+    OPEN_MODE_READ, OPEN_MODE_DIRECTORY, OPEN_MODE_WRITE, OPEN_MODE_APPEND, OPEN_MODE_READ_WRITE,
+};
+pub use client_provider::{HttpClientProvider, WebSocketClientProvider, MqttClientProvider, TcpClientProvider};
 pub use registry::{ProtocolRegistry, ProtocolHandlerFactory, NetworkProtocol};
-pub use http_client::{HttpClient, BaseHttpClient};
+pub use http_client::{HttpClient, BaseHttpClient, RangeResponse, range_header, parse_content_range_total};
 pub use factory::ProtocolFactory;
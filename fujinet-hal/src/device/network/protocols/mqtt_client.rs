@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use crate::device::{DeviceError, DeviceResult};
+use crate::device::network::events::EventNotifier;
+
+/// Default port for unencrypted MQTT
+pub const MQTT_DEFAULT_PORT: u16 = 1883;
+
+/// Supported MQTT delivery guarantees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MqttQos {
+    #[default]
+    AtMostOnce,
+    AtLeastOnce,
+}
+
+impl MqttQos {
+    /// Convert a numeric QoS level; only 0 and 1 are supported
+    pub fn from_u8(qos: u8) -> DeviceResult<Self> {
+        match qos {
+            0 => Ok(MqttQos::AtMostOnce),
+            1 => Ok(MqttQos::AtLeastOnce),
+            _ => Err(DeviceError::NotSupported),
+        }
+    }
+}
+
+/// A message received on a subscribed topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: MqttQos,
+    /// True if the broker delivered this as a stored retained message
+    pub retain: bool,
+}
+
+/// Connection parameters taken from an mqtt:// URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttConnectOptions {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub keep_alive_secs: u16,
+}
+
+/// Platform-agnostic MQTT client interface
+/// Implementations keep the session alive and queue received messages
+#[async_trait]
+pub trait MqttClient: Send + Sync {
+    /// Connect to the broker, returning once the broker has accepted the session
+    async fn connect(&mut self, options: &MqttConnectOptions) -> DeviceResult<()>;
+
+    /// Subscribe to a topic filter
+    async fn subscribe(&mut self, topic: &str, qos: MqttQos) -> DeviceResult<()>;
+
+    /// Remove a subscription
+    async fn unsubscribe(&mut self, topic: &str) -> DeviceResult<()>;
+
+    /// Publish a message
+    async fn publish(&mut self, topic: &str, payload: &[u8], qos: MqttQos, retain: bool) -> DeviceResult<()>;
+
+    /// Take the next received message, if one is waiting
+    async fn receive(&mut self) -> DeviceResult<Option<MqttMessage>>;
+
+    /// Disconnect from the broker
+    async fn disconnect(&mut self) -> DeviceResult<()>;
+
+    /// The next received message, if one is waiting, without removing it
+    fn peek(&self) -> Option<MqttMessage>;
+
+    /// The error that ended the session, if the connection has been lost
+    fn error(&self) -> Option<DeviceError>;
+
+    /// Raise DataAvailable as messages arrive and ConnectionClosed if the session is lost
+    fn set_event_notifier(&mut self, _notifier: EventNotifier) {}
+}
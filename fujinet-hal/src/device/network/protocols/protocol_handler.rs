@@ -1,6 +1,17 @@
 use crate::device::{DeviceError, DeviceResult};
+use crate::device::network::events::EventNotifier;
+use super::directory::DirectoryHandler;
+use super::filesystem::FileSystemHandler;
+use super::seek::SeekableHandler;
 use async_trait::async_trait;
 
+// Atari-style open modes (aux1) passed through network_open
+pub const OPEN_MODE_READ: u8 = 4;
+pub const OPEN_MODE_DIRECTORY: u8 = 6;
+pub const OPEN_MODE_WRITE: u8 = 8;
+pub const OPEN_MODE_APPEND: u8 = 9;
+pub const OPEN_MODE_READ_WRITE: u8 = 12;
+
 #[async_trait]
 pub trait ProtocolHandler: Send + Sync + std::any::Any {
     /// Convert to Any for downcasting
@@ -20,7 +31,8 @@ pub trait ProtocolHandler: Send + Sync + std::any::Any {
     async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize>;
     
     /// Write data to the connection
-    /// Returns the number of bytes written
+    /// Returns the number of bytes accepted, which is less than buf.len() when the
+    /// protocol's send buffer is full; the rest has to be written again
     async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize>;
     
     /// Get the current status of the connection
@@ -28,6 +40,46 @@ pub trait ProtocolHandler: Send + Sync + std::any::Any {
     
     /// Get the number of bytes available to read
     async fn available(&self) -> DeviceResult<usize>;
+
+    /// Get the number of bytes accepted by write that have not been sent yet
+    async fn pending(&self) -> DeviceResult<usize> {
+        Ok(0)
+    }
+
+    /// Send everything accepted by write, returning once it has gone out
+    async fn flush(&mut self) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    /// Whether a read would have to wait for the peer to send something
+    /// Background reads leave the unit unlocked until this turns false; handlers whose
+    /// reads always finish on their own can keep the default
+    async fn read_would_wait(&mut self) -> DeviceResult<bool> {
+        Ok(false)
+    }
+
+    /// Set the open mode (aux1) to be used by the next call to open
+    /// Protocols that behave the same in every mode can ignore this
+    fn set_mode(&mut self, _mode: u8) {}
+
+    /// Hand the handler a notifier for raising events (data available, connection closed, ...)
+    /// Called before open; protocols that only produce data when read can ignore this
+    fn set_event_notifier(&mut self, _notifier: EventNotifier) {}
+
+    /// Directory listing support, for protocols that can enumerate a path
+    fn as_directory(&mut self) -> Option<&mut dyn DirectoryHandler> {
+        None
+    }
+
+    /// Filesystem commands (rename, delete, mkdir, ...), for protocols backed by a filesystem
+    fn as_filesystem(&mut self) -> Option<&mut dyn FileSystemHandler> {
+        None
+    }
+
+    /// Seek and tell, for protocols with random access to the open resource
+    fn as_seekable(&mut self) -> Option<&mut dyn SeekableHandler> {
+        None
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
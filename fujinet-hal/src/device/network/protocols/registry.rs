@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::device::DeviceResult;
 use crate::device::DeviceError;
 use super::ProtocolHandler;
@@ -13,6 +14,10 @@ pub trait ProtocolHandlerFactory: Send + Sync {
 pub enum NetworkProtocol {
     Http, // Represents both HTTP and HTTPS
     Tcp,  // Represents TCP
+    File, // Represents local file:// and sd:// access
+    WebSocket, // Represents both ws:// and wss://
+    Mqtt, // Represents mqtt://
+    Gopher, // Represents gopher://
     // Add other protocols as needed
 }
 
@@ -21,6 +26,10 @@ impl NetworkProtocol {
         match s.to_lowercase().as_str() {
             "http" | "https" => Some(NetworkProtocol::Http),
             "tcp" => Some(NetworkProtocol::Tcp),
+            "file" | "sd" => Some(NetworkProtocol::File),
+            "ws" | "wss" => Some(NetworkProtocol::WebSocket),
+            "mqtt" => Some(NetworkProtocol::Mqtt),
+            "gopher" => Some(NetworkProtocol::Gopher),
             _ => None,
         }
     }
@@ -29,8 +38,10 @@ impl NetworkProtocol {
 /// Registry for protocol handlers
 /// Maps protocol types to their factories
 /// Lives in device layer but accepts platform-specific factories
+/// Clones share the same factories
+#[derive(Clone)]
 pub struct ProtocolRegistry {
-    factories: HashMap<NetworkProtocol, Box<dyn ProtocolHandlerFactory>>,
+    factories: HashMap<NetworkProtocol, Arc<dyn ProtocolHandlerFactory>>,
 }
 
 impl ProtocolRegistry {
@@ -42,7 +53,7 @@ impl ProtocolRegistry {
 
     /// Register a factory for a specific protocol
     pub fn register(&mut self, protocol: NetworkProtocol, factory: Box<dyn ProtocolHandlerFactory>) {
-        self.factories.insert(protocol, factory);
+        self.factories.insert(protocol, Arc::from(factory));
     }
 
     /// Create a handler for the specified protocol
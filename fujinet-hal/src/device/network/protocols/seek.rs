@@ -0,0 +1,13 @@
+use std::io::SeekFrom;
+use async_trait::async_trait;
+use crate::device::DeviceResult;
+
+/// Optional capability for protocols with random access (NOTE/POINT in Atari terms)
+#[async_trait]
+pub trait SeekableHandler: Send + Sync {
+    /// Move the read/write position, returning the new offset from the start
+    async fn seek(&mut self, pos: SeekFrom) -> DeviceResult<u64>;
+
+    /// The current offset from the start
+    fn tell(&self) -> u64;
+}
@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+/// Bounded queue of bytes accepted from the host but not yet sent
+///
+/// Stream protocols take writes into this buffer and drain it as the connection allows.
+/// Once it is full a write only accepts what fits, so a host writing faster than the
+/// network sees short writes rather than having data dropped.
+#[derive(Debug)]
+pub struct SendBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl SendBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Accept as much of `buf` as fits, returning how many bytes were taken
+    pub fn push(&mut self, buf: &[u8]) -> usize {
+        let len = std::cmp::min(buf.len(), self.free());
+        self.data.extend(&buf[..len]);
+        len
+    }
+
+    /// The buffered bytes, oldest first
+    pub fn as_slice(&mut self) -> &[u8] {
+        self.data.make_contiguous()
+    }
+
+    /// Drop the first `len` bytes once they have been sent
+    pub fn consume(&mut self, len: usize) {
+        self.data.drain(..std::cmp::min(len, self.data.len()));
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Room left before writes start coming up short
+    pub fn free(&self) -> usize {
+        self.capacity - self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_is_bounded() {
+        let mut buffer = SendBuffer::new(8);
+        assert_eq!(buffer.push(b"HELLO"), 5);
+        assert_eq!(buffer.push(b"WORLD"), 3);
+        assert_eq!(buffer.push(b"!"), 0);
+        assert_eq!(buffer.as_slice(), b"HELLOWOR");
+
+        buffer.consume(5);
+        assert_eq!(buffer.free(), 5);
+        assert_eq!(buffer.push(b"LD!"), 3);
+        assert_eq!(buffer.as_slice(), b"WORLD!");
+        assert_eq!(buffer.len(), 6);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::{ProtocolHandler, ConnectionStatus};
+use super::client_provider::TcpClientProvider;
+use super::send_buffer::SendBuffer;
+use super::tcp_client::TcpClient;
+use crate::device::network::events::EventNotifier;
+use async_trait::async_trait;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Size of each TCP unit's send buffer
+pub const TCP_SEND_BUFFER_SIZE: usize = 4096;
+
+/// Raw TCP stream protocol handler for tcp://host:port URLs
+///
+/// Writes go into a bounded send buffer that is drained as fast as the connection
+/// takes data. When it is full, writes accept only what fits and return the short
+/// count; `flush` waits for the buffer to empty. Anything still buffered is sent
+/// before a read, so a request is never left waiting behind its own reply.
+/// Received data is counted by `available` and, given a notifier, raises DataAvailable.
+pub struct TcpProtocol {
+    client: Box<dyn TcpClient>,
+    send_buffer: SendBuffer,
+    open: bool,
+}
+
+impl TcpProtocol {
+    pub fn new(client_provider: Arc<dyn TcpClientProvider>) -> Self {
+        Self::with_send_buffer(client_provider, TCP_SEND_BUFFER_SIZE)
+    }
+
+    /// Create a handler whose send buffer holds `capacity` bytes
+    pub fn with_send_buffer(client_provider: Arc<dyn TcpClientProvider>, capacity: usize) -> Self {
+        Self {
+            client: client_provider.create_tcp_client(),
+            send_buffer: SendBuffer::new(capacity),
+            open: false,
+        }
+    }
+
+    /// Split a tcp:// URL into host and port; the port is required
+    pub fn parse_url(url: &str) -> DeviceResult<(String, u16)> {
+        let (scheme, rest) = url.split_once("://").ok_or(DeviceError::InvalidUrl)?;
+        if !scheme.eq_ignore_ascii_case("tcp") {
+            return Err(DeviceError::InvalidUrl);
+        }
+
+        let host_port = rest.split(['/', '?']).next().unwrap_or(rest);
+        let (host, port) = host_port.rsplit_once(':').ok_or(DeviceError::InvalidUrl)?;
+        let port = port.parse().map_err(|_| DeviceError::InvalidUrl)?;
+        if host.is_empty() {
+            return Err(DeviceError::InvalidUrl);
+        }
+        Ok((host.to_string(), port))
+    }
+
+    /// Send buffered data for as long as the connection takes it without waiting
+    async fn send_buffered(&mut self) -> DeviceResult<()> {
+        while !self.send_buffer.is_empty() {
+            let sent = self.client.try_write(self.send_buffer.as_slice()).await?;
+            if sent == 0 {
+                break;
+            }
+            self.send_buffer.consume(sent);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProtocolHandler for TcpProtocol {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    async fn open(&mut self, url: &str) -> DeviceResult<()> {
+        let (host, port) = Self::parse_url(url)?;
+        self.send_buffer.clear();
+        self.client.connect(&host, port).await?;
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        // Don't lose what the host already wrote, but close even if it can't be sent
+        let flushed = if self.open { self.flush().await } else { Ok(()) };
+        let result = self.client.disconnect().await;
+        self.open = false;
+        self.send_buffer.clear();
+        flushed.and(result)
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        self.flush().await?;
+        if !self.client.is_connected() {
+            return Ok(0);
+        }
+        self.client.read(buf).await
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        // Keep making room and taking what fits until the data or the socket runs out
+        let mut accepted = 0;
+        loop {
+            self.send_buffered().await?;
+            let taken = self.send_buffer.push(&buf[accepted..]);
+            accepted += taken;
+            if taken == 0 || accepted == buf.len() {
+                break;
+            }
+        }
+        self.send_buffered().await?;
+        Ok(accepted)
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        Ok(if self.open && self.client.is_connected() {
+            ConnectionStatus::Connected
+        } else {
+            ConnectionStatus::Disconnected
+        })
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        Ok(if self.open { self.client.available() } else { 0 })
+    }
+
+    async fn pending(&self) -> DeviceResult<usize> {
+        Ok(self.send_buffer.len())
+    }
+
+    async fn flush(&mut self) -> DeviceResult<()> {
+        if !self.send_buffer.is_empty() {
+            let len = self.send_buffer.len();
+            self.client.write(self.send_buffer.as_slice()).await?;
+            self.send_buffer.consume(len);
+        }
+        Ok(())
+    }
+
+    fn set_event_notifier(&mut self, notifier: EventNotifier) {
+        self.client.set_event_notifier(notifier);
+    }
+
+    async fn read_would_wait(&mut self) -> DeviceResult<bool> {
+        if !self.open {
+            return Ok(false);
+        }
+        // Keep sending in the meantime, or the peer may never get what it is to answer
+        self.send_buffered().await?;
+        Ok(self.client.is_connected() && !self.client.readable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockState {
+        connected_to: Option<(String, u16)>,
+        sent: Vec<u8>,
+        /// How many more bytes try_write will take before the socket counts as full
+        window: usize,
+        response: Vec<u8>,
+    }
+
+    struct MockTcpClient {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[async_trait]
+    impl TcpClient for MockTcpClient {
+        async fn connect(&mut self, host: &str, port: u16) -> DeviceResult<()> {
+            self.state.lock().unwrap().connected_to = Some((host.to_string(), port));
+            Ok(())
+        }
+
+        async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+            let mut state = self.state.lock().unwrap();
+            let len = std::cmp::min(buf.len(), state.response.len());
+            buf[..len].copy_from_slice(&state.response[..len]);
+            state.response.drain(..len);
+            Ok(len)
+        }
+
+        async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            self.state.lock().unwrap().sent.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        async fn try_write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+            let mut state = self.state.lock().unwrap();
+            let len = std::cmp::min(buf.len(), state.window);
+            state.window -= len;
+            state.sent.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn available(&self) -> usize {
+            self.state.lock().unwrap().response.len()
+        }
+
+        fn readable(&self) -> bool {
+            !self.state.lock().unwrap().response.is_empty()
+        }
+
+        async fn disconnect(&mut self) -> DeviceResult<()> {
+            self.state.lock().unwrap().connected_to = None;
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            self.state.lock().unwrap().connected_to.is_some()
+        }
+    }
+
+    struct MockProvider {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    impl TcpClientProvider for MockProvider {
+        fn create_tcp_client(&self) -> Box<dyn TcpClient> {
+            Box::new(MockTcpClient { state: self.state.clone() })
+        }
+    }
+
+    fn protocol(capacity: usize) -> (TcpProtocol, Arc<Mutex<MockState>>) {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let provider = Arc::new(MockProvider { state: state.clone() });
+        (TcpProtocol::with_send_buffer(provider, capacity), state)
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(TcpProtocol::parse_url("tcp://bbs.example:6502").unwrap(), ("bbs.example".to_string(), 6502));
+        assert_eq!(TcpProtocol::parse_url("TCP://10.0.0.1:23/").unwrap(), ("10.0.0.1".to_string(), 23));
+        assert!(TcpProtocol::parse_url("tcp://bbs.example").is_err());
+        assert!(TcpProtocol::parse_url("http://bbs.example:80").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_bounded_by_send_buffer() -> DeviceResult<()> {
+        let (mut protocol, state) = protocol(8);
+        assert!(matches!(protocol.write(b"X").await, Err(DeviceError::NotReady)));
+        protocol.open("tcp://bbs.example:6502").await?;
+        assert_eq!(state.lock().unwrap().connected_to, Some(("bbs.example".to_string(), 6502)));
+
+        // The socket takes 4 bytes, the buffer holds 8 more, the rest comes back short
+        state.lock().unwrap().window = 4;
+        assert_eq!(protocol.write(b"0123456789ABCDEF").await?, 12);
+        assert_eq!(protocol.pending().await?, 8);
+        assert_eq!(protocol.write(b"CDEF").await?, 0);
+
+        // Once the socket drains, the next write makes room and carries on
+        state.lock().unwrap().window = 6;
+        assert_eq!(protocol.write(b"CDEF").await?, 4);
+        assert_eq!(protocol.pending().await?, 6);
+
+        protocol.flush().await?;
+        assert_eq!(protocol.pending().await?, 0);
+        assert_eq!(state.lock().unwrap().sent, b"0123456789ABCDEF");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_and_close_flush_pending_data() -> DeviceResult<()> {
+        let (mut protocol, state) = protocol(64);
+        protocol.open("tcp://bbs.example:6502").await?;
+        state.lock().unwrap().response = b"WELCOME".to_vec();
+
+        assert_eq!(protocol.write(b"LOGIN\r\n").await?, 7);
+        assert_eq!(protocol.pending().await?, 7);
+        let mut buf = [0u8; 16];
+        assert_eq!(protocol.read(&mut buf).await?, 7);
+        assert_eq!(&buf[..7], b"WELCOME");
+        assert_eq!(state.lock().unwrap().sent, b"LOGIN\r\n");
+
+        protocol.write(b"BYE\r\n").await?;
+        protocol.close().await?;
+        assert_eq!(state.lock().unwrap().sent, b"LOGIN\r\nBYE\r\n");
+        assert!(matches!(protocol.status().await?, ConnectionStatus::Disconnected));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_would_wait_until_data_arrives() -> DeviceResult<()> {
+        let (mut protocol, state) = protocol(64);
+        assert!(!protocol.read_would_wait().await?);
+        protocol.open("tcp://bbs.example:6502").await?;
+
+        assert_eq!(protocol.write(b"LOGIN\r\n").await?, 7);
+        assert!(protocol.read_would_wait().await?);
+
+        // Buffered data still goes out while the read waits
+        state.lock().unwrap().window = 64;
+        assert!(protocol.read_would_wait().await?);
+        assert_eq!(state.lock().unwrap().sent, b"LOGIN\r\n");
+
+        state.lock().unwrap().response = b"WELCOME".to_vec();
+        assert!(!protocol.read_would_wait().await?);
+        assert_eq!(protocol.available().await?, 7);
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use crate::device::DeviceResult;
+use crate::device::network::events::EventNotifier;
+
+/// Platform-agnostic TCP stream client interface
+#[async_trait]
+pub trait TcpClient: Send + Sync {
+    /// Open a connection to host:port
+    async fn connect(&mut self, host: &str, port: u16) -> DeviceResult<()>;
+
+    /// Read whatever data is available, waiting for at least one byte
+    /// Returns 0 once the peer has closed the connection
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize>;
+
+    /// Write the whole buffer
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize>;
+
+    /// Write as much as the connection takes without waiting, possibly nothing
+    /// Clients that cannot tell fall back to writing the whole buffer
+    async fn try_write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        self.write(buf).await
+    }
+
+    /// Bytes received and not yet read, as far as can be told without reading them
+    /// Clients that cannot tell report 0
+    fn available(&self) -> usize {
+        0
+    }
+
+    /// Whether a read would return without waiting: data has arrived or the peer has closed
+    /// Clients that cannot tell report true, so reads go ahead and wait for data
+    fn readable(&self) -> bool {
+        true
+    }
+
+    /// Close the connection
+    async fn disconnect(&mut self) -> DeviceResult<()>;
+
+    /// Whether a connection is currently open
+    fn is_connected(&self) -> bool;
+
+    /// Raise DataAvailable as data arrives and ConnectionClosed when the peer closes
+    /// Called before connect
+    fn set_event_notifier(&mut self, _notifier: EventNotifier) {}
+}
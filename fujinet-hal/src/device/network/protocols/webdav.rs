@@ -0,0 +1,200 @@
+use std::time::SystemTime;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use percent_encoding::percent_decode_str;
+use crate::device::{DeviceError, DeviceResult};
+
+/// Request body for a PROPFIND asking for the properties needed in a directory listing
+pub const PROPFIND_BODY: &str = concat!(
+    r#"<?xml version="1.0" encoding="utf-8"?>"#,
+    r#"<D:propfind xmlns:D="DAV:"><D:prop>"#,
+    r#"<D:resourcetype/><D:getcontentlength/><D:getlastmodified/>"#,
+    r#"</D:prop></D:propfind>"#,
+);
+
+/// A single entry from a WebDAV PROPFIND listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebDavEntry {
+    /// Decoded file or collection name (last path segment)
+    pub name: String,
+    /// Raw href as returned by the server
+    pub href: String,
+    /// Content length in bytes (0 for collections)
+    pub size: u64,
+    /// Last modification time, if the server reported one
+    pub modified: Option<SystemTime>,
+    /// True if the entry is a collection (directory)
+    pub is_dir: bool,
+}
+
+/// Returns the path component of a URL (e.g. "/dav/dir/" for "http://host:80/dav/dir/?x=1")
+pub fn url_path(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = match without_scheme.find('/') {
+        Some(idx) => &without_scheme[idx..],
+        None => "/",
+    };
+    path.split(['?', '#']).next().unwrap_or("/")
+}
+
+/// Strips any scheme and host from an href, leaving a decoded path without trailing slash
+fn normalise_path(href: &str) -> String {
+    let path = if href.contains("://") { url_path(href) } else { href };
+    let decoded = percent_decode_str(path).decode_utf8_lossy();
+    decoded.trim_end_matches('/').to_string()
+}
+
+/// Parses a 207 Multi-Status PROPFIND response into directory entries.
+/// The entry for the requested collection itself (matching `request_path`) is skipped.
+pub fn parse_multistatus(xml: &str, request_path: &str) -> DeviceResult<Vec<WebDavEntry>> {
+    enum Field {
+        None,
+        Href,
+        Length,
+        Modified,
+    }
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let self_path = normalise_path(request_path);
+    let mut entries = Vec::new();
+    let mut current: Option<WebDavEntry> = None;
+    let mut field = Field::None;
+
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| DeviceError::NetworkError(format!("Invalid PROPFIND response: {}", e)))?;
+        match event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"response" => {
+                    current = Some(WebDavEntry {
+                        name: String::new(),
+                        href: String::new(),
+                        size: 0,
+                        modified: None,
+                        is_dir: false,
+                    });
+                }
+                b"href" => field = Field::Href,
+                b"getcontentlength" => field = Field::Length,
+                b"getlastmodified" => field = Field::Modified,
+                b"collection" => {
+                    if let Some(entry) = current.as_mut() {
+                        entry.is_dir = true;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                if let Some(entry) = current.as_mut() {
+                    entry.is_dir = true;
+                }
+            }
+            Event::Text(e) => {
+                let Some(entry) = current.as_mut() else { continue };
+                let text = e.unescape()
+                    .map_err(|e| DeviceError::NetworkError(format!("Invalid PROPFIND response: {}", e)))?;
+                match field {
+                    Field::Href => entry.href = text.into_owned(),
+                    Field::Length => entry.size = text.trim().parse().unwrap_or(0),
+                    Field::Modified => entry.modified = httpdate::parse_http_date(text.trim()).ok(),
+                    Field::None => {}
+                }
+            }
+            Event::End(e) => {
+                field = Field::None;
+                if e.local_name().as_ref() == b"response" {
+                    if let Some(mut entry) = current.take() {
+                        let path = normalise_path(&entry.href);
+                        if path == self_path {
+                            continue;
+                        }
+                        entry.name = path.rsplit('/').next().unwrap_or("").to_string();
+                        if entry.is_dir {
+                            entry.size = 0;
+                        }
+                        entries.push(entry);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const LISTING: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/games/</D:href>
+    <D:propstat>
+      <D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/dav/games/Star%20Raiders.atr</D:href>
+    <D:propstat>
+      <D:prop>
+        <D:resourcetype/>
+        <D:getcontentlength>92176</D:getcontentlength>
+        <D:getlastmodified>Thu, 01 Jan 1970 00:01:40 GMT</D:getlastmodified>
+      </D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>http://host/dav/games/sub/</D:href>
+    <D:propstat>
+      <D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+</D:multistatus>"#;
+
+    #[test]
+    fn test_url_path() {
+        assert_eq!(url_path("http://host:8080/dav/dir/"), "/dav/dir/");
+        assert_eq!(url_path("https://host/file.txt?x=1"), "/file.txt");
+        assert_eq!(url_path("http://host"), "/");
+    }
+
+    #[test]
+    fn test_parse_multistatus() {
+        let entries = parse_multistatus(LISTING, "/dav/games").unwrap();
+        assert_eq!(entries.len(), 2, "collection itself should be skipped");
+
+        assert_eq!(entries[0].name, "Star Raiders.atr");
+        assert_eq!(entries[0].size, 92176);
+        assert!(!entries[0].is_dir);
+        assert_eq!(entries[0].modified, Some(UNIX_EPOCH + Duration::from_secs(100)));
+
+        assert_eq!(entries[1].name, "sub");
+        assert!(entries[1].is_dir);
+        assert_eq!(entries[1].modified, None);
+    }
+
+    #[test]
+    fn test_parse_multistatus_other_prefixes() {
+        let xml = r#"<multistatus xmlns="DAV:"><response><href>/a.txt</href>
+            <propstat><prop><getcontentlength>5</getcontentlength></prop></propstat>
+            </response></multistatus>"#;
+        let entries = parse_multistatus(xml, "/").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "a.txt");
+        assert_eq!(entries[0].size, 5);
+    }
+
+    #[test]
+    fn test_parse_multistatus_invalid() {
+        assert!(parse_multistatus("<D:multistatus><D:response></D:multistatus>", "/").is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::device::{DeviceError, DeviceResult};
+use super::{ProtocolHandler, ConnectionStatus};
+use crate::device::network::events::EventNotifier;
+use super::client_provider::WebSocketClientProvider;
+use super::websocket_client::{WebSocketClient, WebSocketMessage, CLOSE_NORMAL};
+use async_trait::async_trait;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Frame type used for messages sent by `write`
+///
+/// Text frames must hold UTF-8, so in Text mode data that isn't (such as ATASCII above
+/// 0x7F) goes out unchanged in a Binary frame instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebSocketMessageType {
+    #[default]
+    Text,
+    Binary,
+}
+
+/// WebSocket protocol handler for ws:// and wss:// URLs
+///
+/// The opening handshake is deferred until the first read or write (or an explicit
+/// `connect`), so that headers can be added after the device has been opened.
+/// Each `write` is sent as one message and each `read` returns bytes from at most
+/// one received message, so message boundaries are preserved.
+pub struct WebSocketProtocol {
+    client: Box<dyn WebSocketClient>,
+    url: Option<String>,
+    headers: Vec<(String, String)>,
+    message_type: WebSocketMessageType,
+    connected: bool,
+    current: Vec<u8>,
+    current_pos: usize,
+}
+
+impl WebSocketProtocol {
+    pub fn new(client_provider: Arc<dyn WebSocketClientProvider>) -> Self {
+        Self {
+            client: client_provider.create_websocket_client(),
+            url: None,
+            headers: Vec::new(),
+            message_type: WebSocketMessageType::default(),
+            connected: false,
+            current: Vec::new(),
+            current_pos: 0,
+        }
+    }
+
+    /// Add a header to be sent with the opening handshake
+    /// Fails with InvalidOperation once the handshake has been performed
+    pub fn set_header(&mut self, key: &str, value: &str) -> DeviceResult<()> {
+        if self.connected {
+            return Err(DeviceError::InvalidOperation);
+        }
+        self.headers.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    /// Headers that will be (or were) sent with the opening handshake
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Choose whether `write` sends text or binary frames
+    pub fn set_message_type(&mut self, message_type: WebSocketMessageType) {
+        self.message_type = message_type;
+    }
+
+    pub fn message_type(&self) -> WebSocketMessageType {
+        self.message_type
+    }
+
+    /// Perform the opening handshake if it has not happened yet
+    pub async fn connect(&mut self) -> DeviceResult<()> {
+        if self.connected {
+            return Ok(());
+        }
+        let url = self.url.as_deref().ok_or(DeviceError::NotReady)?;
+        self.client.connect(url, &self.headers).await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    /// Send a single message regardless of the configured message type
+    pub async fn send(&mut self, message: WebSocketMessage) -> DeviceResult<()> {
+        self.connect().await?;
+        self.check_open()?;
+        self.client.send(message).await
+    }
+
+    /// Fail with ConnectionClosed if the peer has closed the connection
+    fn check_open(&self) -> DeviceResult<()> {
+        match self.client.close_code() {
+            Some(code) => Err(DeviceError::ConnectionClosed(code)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ProtocolHandler for WebSocketProtocol {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    async fn open(&mut self, url: &str) -> DeviceResult<()> {
+        self.url = Some(url.to_string());
+        self.connected = false;
+        self.current.clear();
+        self.current_pos = 0;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        let result = if self.connected {
+            self.client.close(CLOSE_NORMAL).await
+        } else {
+            Ok(())
+        };
+        self.url = None;
+        self.connected = false;
+        self.headers.clear();
+        self.current.clear();
+        self.current_pos = 0;
+        result
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        self.connect().await?;
+
+        if self.current_pos >= self.current.len() {
+            match self.client.receive().await? {
+                Some(message) => {
+                    self.current = message.into_bytes();
+                    self.current_pos = 0;
+                }
+                None => {
+                    self.check_open()?;
+                    return Ok(0);
+                }
+            }
+        }
+
+        let remaining = &self.current[self.current_pos..];
+        let len = std::cmp::min(buf.len(), remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.current_pos += len;
+        Ok(len)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        let message = match self.message_type {
+            WebSocketMessageType::Text => match String::from_utf8(buf.to_vec()) {
+                Ok(text) => WebSocketMessage::Text(text),
+                Err(not_utf8) => WebSocketMessage::Binary(not_utf8.into_bytes()),
+            },
+            WebSocketMessageType::Binary => WebSocketMessage::Binary(buf.to_vec()),
+        };
+        self.send(message).await?;
+        Ok(buf.len())
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        if self.url.is_none() {
+            return Ok(ConnectionStatus::Disconnected);
+        }
+        if !self.connected {
+            return Ok(ConnectionStatus::Connecting);
+        }
+        Ok(match self.client.close_code() {
+            // Data already received stays readable after the peer closes
+            Some(_) if self.current_pos < self.current.len() || self.client.next_message_len() > 0 => {
+                ConnectionStatus::Connected
+            }
+            Some(code) => ConnectionStatus::Error(DeviceError::ConnectionClosed(code)),
+            None => ConnectionStatus::Connected,
+        })
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        let remaining = self.current.len() - self.current_pos;
+        if remaining > 0 {
+            return Ok(remaining);
+        }
+        Ok(self.client.next_message_len())
+    }
+
+    fn set_event_notifier(&mut self, notifier: EventNotifier) {
+        self.client.set_event_notifier(notifier);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockState {
+        url: Option<String>,
+        headers: Vec<(String, String)>,
+        sent: Vec<WebSocketMessage>,
+        inbox: VecDeque<WebSocketMessage>,
+        close_code: Option<u16>,
+        closed_with: Option<u16>,
+    }
+
+    struct MockWebSocketClient {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[async_trait]
+    impl WebSocketClient for MockWebSocketClient {
+        async fn connect(&mut self, url: &str, headers: &[(String, String)]) -> DeviceResult<()> {
+            let mut state = self.state.lock().unwrap();
+            state.url = Some(url.to_string());
+            state.headers = headers.to_vec();
+            Ok(())
+        }
+
+        async fn send(&mut self, message: WebSocketMessage) -> DeviceResult<()> {
+            self.state.lock().unwrap().sent.push(message);
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> DeviceResult<Option<WebSocketMessage>> {
+            Ok(self.state.lock().unwrap().inbox.pop_front())
+        }
+
+        async fn close(&mut self, code: u16) -> DeviceResult<()> {
+            self.state.lock().unwrap().closed_with = Some(code);
+            Ok(())
+        }
+
+        fn next_message_len(&self) -> usize {
+            self.state.lock().unwrap().inbox.front().map_or(0, |m| m.as_bytes().len())
+        }
+
+        fn close_code(&self) -> Option<u16> {
+            self.state.lock().unwrap().close_code
+        }
+    }
+
+    struct MockProvider {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    impl WebSocketClientProvider for MockProvider {
+        fn create_websocket_client(&self) -> Box<dyn WebSocketClient> {
+            Box::new(MockWebSocketClient { state: self.state.clone() })
+        }
+    }
+
+    fn create_protocol() -> (WebSocketProtocol, Arc<Mutex<MockState>>) {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let provider = Arc::new(MockProvider { state: state.clone() });
+        (WebSocketProtocol::new(provider), state)
+    }
+
+    #[tokio::test]
+    async fn test_deferred_handshake_sends_headers() {
+        let (mut protocol, state) = create_protocol();
+        protocol.open("ws://game.example/lobby").await.unwrap();
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Connecting);
+
+        protocol.set_header("Authorization", "Bearer abc").unwrap();
+        protocol.write(b"hello").await.unwrap();
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Connected);
+
+        {
+            let state = state.lock().unwrap();
+            assert_eq!(state.url.as_deref(), Some("ws://game.example/lobby"));
+            assert_eq!(state.headers, vec![("Authorization".to_string(), "Bearer abc".to_string())]);
+            assert_eq!(state.sent, vec![WebSocketMessage::Text("hello".to_string())]);
+        }
+
+        assert_eq!(protocol.set_header("X-Late", "1"), Err(DeviceError::InvalidOperation));
+
+        // ATASCII isn't UTF-8, so it is sent untouched rather than as replacement characters
+        protocol.write(&[0x48, 0x49, 0x9B]).await.unwrap();
+        assert_eq!(state.lock().unwrap().sent[1], WebSocketMessage::Binary(vec![0x48, 0x49, 0x9B]));
+    }
+
+    #[tokio::test]
+    async fn test_binary_message_type() {
+        let (mut protocol, state) = create_protocol();
+        protocol.open("wss://game.example/").await.unwrap();
+        protocol.set_message_type(WebSocketMessageType::Binary);
+        protocol.write(&[0x00, 0xff]).await.unwrap();
+        assert_eq!(state.lock().unwrap().sent, vec![WebSocketMessage::Binary(vec![0x00, 0xff])]);
+    }
+
+    #[tokio::test]
+    async fn test_read_preserves_message_boundaries() {
+        let (mut protocol, state) = create_protocol();
+        {
+            let mut state = state.lock().unwrap();
+            state.inbox.push_back(WebSocketMessage::Text("first".to_string()));
+            state.inbox.push_back(WebSocketMessage::Binary(vec![1, 2, 3]));
+        }
+        protocol.open("ws://game.example/").await.unwrap();
+        assert_eq!(protocol.available().await.unwrap(), 5);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(protocol.read(&mut buf[..3]).await.unwrap(), 3);
+        assert_eq!(&buf[..3], b"fir");
+        assert_eq!(protocol.available().await.unwrap(), 2);
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 2, "read must stop at the end of a message");
+        assert_eq!(&buf[..2], b"st");
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 3);
+        assert_eq!(&buf[..3], &[1, 2, 3]);
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_peer_close_reported_in_status() {
+        let (mut protocol, state) = create_protocol();
+        state.lock().unwrap().inbox.push_back(WebSocketMessage::Text("bye".to_string()));
+        protocol.open("ws://game.example/").await.unwrap();
+        protocol.connect().await.unwrap();
+        state.lock().unwrap().close_code = Some(4001);
+
+        // Buffered data is still readable after the close
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Connected);
+        let mut buf = [0u8; 8];
+        assert_eq!(protocol.read(&mut buf).await.unwrap(), 3);
+
+        assert_eq!(
+            protocol.status().await.unwrap(),
+            ConnectionStatus::Error(DeviceError::ConnectionClosed(4001))
+        );
+        assert_eq!(protocol.read(&mut buf).await, Err(DeviceError::ConnectionClosed(4001)));
+        assert_eq!(protocol.write(b"x").await, Err(DeviceError::ConnectionClosed(4001)));
+
+        protocol.close().await.unwrap();
+        assert_eq!(state.lock().unwrap().closed_with, Some(CLOSE_NORMAL));
+        assert_eq!(protocol.status().await.unwrap(), ConnectionStatus::Disconnected);
+    }
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use crate::device::DeviceResult;
+use crate::device::network::events::EventNotifier;
+
+/// Normal closure status code (RFC 6455 section 7.4.1)
+pub const CLOSE_NORMAL: u16 = 1000;
+
+/// A complete WebSocket data message
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl WebSocketMessage {
+    /// The message payload as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            WebSocketMessage::Text(text) => text.as_bytes(),
+            WebSocketMessage::Binary(data) => data,
+        }
+    }
+
+    /// Consume the message, returning its payload
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            WebSocketMessage::Text(text) => text.into_bytes(),
+            WebSocketMessage::Binary(data) => data,
+        }
+    }
+}
+
+/// Platform-agnostic WebSocket client interface
+/// Implementations answer pings internally and buffer received data messages
+#[async_trait]
+pub trait WebSocketClient: Send + Sync {
+    /// Perform the opening handshake, sending the given extra headers
+    async fn connect(&mut self, url: &str, headers: &[(String, String)]) -> DeviceResult<()>;
+
+    /// Send a single message
+    async fn send(&mut self, message: WebSocketMessage) -> DeviceResult<()>;
+
+    /// Take the next received message, if one is waiting
+    async fn receive(&mut self) -> DeviceResult<Option<WebSocketMessage>>;
+
+    /// Close the connection with the given status code
+    async fn close(&mut self, code: u16) -> DeviceResult<()>;
+
+    /// Size in bytes of the next received message, 0 if none is waiting
+    fn next_message_len(&self) -> usize;
+
+    /// Close code sent by the peer, once it has closed the connection
+    fn close_code(&self) -> Option<u16>;
+
+    /// Raise DataAvailable as messages arrive and ConnectionClosed when the peer closes
+    fn set_event_notifier(&mut self, _notifier: EventNotifier) {}
+}
@@ -4,7 +4,7 @@ use super::protocols::NetworkProtocol;
 /// Represents a parsed network URL with unit number
 #[derive(Debug, Clone, PartialEq)]
 pub struct NetworkUrl {
-    /// The network unit number (1-8)
+    /// The network unit number (1-255); how many units exist is up to the manager
     pub unit: u8,
     /// The actual URL without the N[x]: prefix
     pub url: String,
@@ -13,30 +13,57 @@ pub struct NetworkUrl {
 
 impl NetworkUrl {
     /// Parse a network URL of the form N[x]:protocol://...
-    /// where x is an optional unit number 1-8 (defaults to 1)
+    /// where x is an optional unit number, one or more digits from 1 (the default)
     /// The network indicator (N) is case-insensitive
     pub fn parse(spec: &str) -> DeviceResult<Self> {
+        Self::parse_with_prefix(spec, None)
+    }
+
+    /// Parse a network URL, resolving relative specs such as "N1:FOO.TXT" against the unit's prefix
+    pub fn parse_with_prefix(spec: &str, prefix: Option<&str>) -> DeviceResult<Self> {
         println!("NetworkUrl::parse() called with spec: {}", spec);
 
+        let (unit, rest) = Self::split_unit(spec)?;
+
+        let url = match prefix {
+            Some(prefix) if !rest.contains("://") => Self::resolve_relative(prefix, rest)?,
+            _ => rest.to_string(),
+        };
+        println!("NetworkUrl::parse() url: {}", url);
+        
+        // Parse and validate protocol from URL scheme
+        let scheme = Self::extract_scheme(&url)?;
+        println!("NetworkUrl::parse() scheme: {}", scheme);
+        
+        let protocol = NetworkProtocol::from_str(scheme)
+            .ok_or(DeviceError::UnsupportedProtocol)?;
+        println!("NetworkUrl::parse() protocol: {:?}", protocol);
+
+        Ok(Self { url, unit, protocol })
+    }
+
+    /// Split the N[x]: device prefix off a spec, returning the unit number and the rest
+    pub fn split_unit(spec: &str) -> DeviceResult<(u8, &str)> {
         // Parse N: prefix and unit number (case insensitive)
         if !spec.to_uppercase().starts_with('N') {
             println!("NetworkUrl::parse() failed: missing N prefix");
             return Err(DeviceError::InvalidUrl);
         }
 
-        // Extract unit number (N1-N8, or just N for N1)
-        let (unit, rest) = if spec.len() > 1 && spec.chars().nth(1).unwrap().is_ascii_digit() {
-            let unit = spec.chars().nth(1).unwrap().to_digit(10).unwrap() as u8;
-            println!("NetworkUrl::parse() explicit unit: {}", unit);
-            // Validate unit is in range 1-8
-            if unit < 1 || unit > 8 {
+        // Extract unit number (N1, N12, ..., or just N for N1)
+        let rest = &spec[1..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let (unit, rest) = if digits > 0 {
+            // Validate unit is in range 1-255; the manager checks it against its unit count
+            let Some(unit) = rest[..digits].parse::<u8>().ok().filter(|&unit| unit >= 1) else {
                 println!("NetworkUrl::parse() failed: invalid unit number");
                 return Err(DeviceError::InvalidUrl);
-            }
-            (unit, &spec[2..])
+            };
+            println!("NetworkUrl::parse() explicit unit: {}", unit);
+            (unit, &rest[digits..])
         } else {
             println!("NetworkUrl::parse() using default unit: 1");
-            (1, &spec[1..])
+            (1, rest)
         };
 
         // Must start with colon
@@ -45,18 +72,57 @@ impl NetworkUrl {
             return Err(DeviceError::InvalidUrl);
         }
 
-        let url = rest[1..].to_string();
-        println!("NetworkUrl::parse() url: {}", url);
-        
-        // Parse and validate protocol from URL scheme
-        let scheme = Self::extract_scheme(&url)?;
-        println!("NetworkUrl::parse() scheme: {}", scheme);
-        
-        let protocol = NetworkProtocol::from_str(scheme)
-            .ok_or(DeviceError::UnsupportedProtocol)?;
-        println!("NetworkUrl::parse() protocol: {:?}", protocol);
+        Ok((unit, &rest[1..]))
+    }
 
-        Ok(Self { url, unit, protocol })
+    /// Resolve a relative path against a base URL such as "tnfs://host/dir/"
+    /// A leading '/' starts from the host root; "." and ".." segments are applied, never climbing above the root
+    pub fn resolve_relative(base: &str, path: &str) -> DeviceResult<String> {
+        let (scheme, rest) = base.split_once("://").ok_or(DeviceError::InvalidUrl)?;
+        let (host, base_path) = rest.split_once('/').unwrap_or((rest, ""));
+
+        let mut segments: Vec<&str> = if path.starts_with('/') {
+            Vec::new()
+        } else {
+            base_path.split('/').filter(|s| !s.is_empty()).collect()
+        };
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                name => segments.push(name),
+            }
+        }
+
+        let mut url = format!("{}://{}/{}", scheme, host, segments.join("/"));
+        if path.ends_with('/') && !segments.is_empty() {
+            url.push('/');
+        }
+        Ok(url)
+    }
+
+    /// Work out a unit's new prefix from the path given to a change-directory command
+    /// An absolute URL replaces the prefix, anything else (including "..") is resolved against it,
+    /// and an empty path clears it. Prefixes always end in '/'
+    pub fn change_prefix(current: Option<&str>, path: &str) -> DeviceResult<Option<String>> {
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let mut prefix = if path.contains("://") {
+            path.to_string()
+        } else {
+            let base = current.ok_or(DeviceError::InvalidUrl)?;
+            Self::resolve_relative(base, path)?
+        };
+        if !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        let scheme = Self::extract_scheme(&prefix)?;
+        NetworkProtocol::from_str(scheme).ok_or(DeviceError::UnsupportedProtocol)?;
+        Ok(Some(prefix))
     }
 
     fn extract_scheme(url: &str) -> DeviceResult<&str> {
@@ -128,6 +194,12 @@ mod tests {
             assert_eq!(parsed.unit, unit as u8);
             assert_eq!(parsed.url, "http://test.com");
         }
+
+        // Units past N8 take more than one digit
+        let url = NetworkUrl::parse("N12:tcp://host:23").unwrap();
+        assert_eq!(url.unit, 12);
+        assert_eq!(url.url, "tcp://host:23");
+        assert_eq!(NetworkUrl::split_unit("n255:").unwrap(), (255, ""));
     }
 
     #[test]
@@ -144,9 +216,9 @@ mod tests {
             Err(DeviceError::InvalidUrl)
         ));
 
-        // Test invalid unit number (9)
+        // Test invalid unit number (too big for a unit byte)
         assert!(matches!(
-            NetworkUrl::parse("N9:http://ficticious_example.madeup"),
+            NetworkUrl::parse("N256:http://ficticious_example.madeup"),
             Err(DeviceError::InvalidUrl)
         ));
 
@@ -202,6 +274,37 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_relative_to_prefix() {
+        let prefix = Some("tcp://host:23/dir/");
+        assert_eq!(NetworkUrl::parse_with_prefix("N2:FOO.TXT", prefix).unwrap().url, "tcp://host:23/dir/FOO.TXT");
+        assert_eq!(NetworkUrl::parse_with_prefix("N2:../FOO.TXT", prefix).unwrap().url, "tcp://host:23/FOO.TXT");
+        assert_eq!(NetworkUrl::parse_with_prefix("N2:/top/FOO.TXT", prefix).unwrap().url, "tcp://host:23/top/FOO.TXT");
+        assert_eq!(NetworkUrl::parse_with_prefix("N2:http://other/", prefix).unwrap().url, "http://other/");
+
+        // Without a prefix a relative spec is still rejected
+        assert!(matches!(NetworkUrl::parse_with_prefix("N2:FOO.TXT", None), Err(DeviceError::InvalidUrl)));
+    }
+
+    #[test]
+    fn test_change_prefix() {
+        let prefix = NetworkUrl::change_prefix(None, "http://host/games").unwrap();
+        assert_eq!(prefix.as_deref(), Some("http://host/games/"));
+
+        let prefix = NetworkUrl::change_prefix(prefix.as_deref(), "arcade").unwrap();
+        assert_eq!(prefix.as_deref(), Some("http://host/games/arcade/"));
+
+        let prefix = NetworkUrl::change_prefix(prefix.as_deref(), "..").unwrap();
+        assert_eq!(prefix.as_deref(), Some("http://host/games/"));
+
+        let prefix = NetworkUrl::change_prefix(prefix.as_deref(), "../../..").unwrap();
+        assert_eq!(prefix.as_deref(), Some("http://host/"));
+
+        assert_eq!(NetworkUrl::change_prefix(prefix.as_deref(), "").unwrap(), None);
+        assert!(matches!(NetworkUrl::change_prefix(None, ".."), Err(DeviceError::InvalidUrl)));
+        assert!(matches!(NetworkUrl::change_prefix(None, "ftp://host/"), Err(DeviceError::UnsupportedProtocol)));
+    }
+
     #[test]
     fn test_has_same_base_url() {
         let base = NetworkUrl::parse("N1:http://192.168.1.100:8085/").unwrap();
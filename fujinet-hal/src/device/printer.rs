@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use std::any::Any;
+use super::{Device, DeviceError, DeviceResult, DeviceStatus};
+
+/// Printer that collects everything written to it
+///
+/// The host side takes the output with `take_output` and renders or saves it.
+#[derive(Default)]
+pub struct PrinterDevice {
+    output: Vec<u8>,
+    open: bool,
+}
+
+impl PrinterDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything printed since the output was last taken
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Hand over the printed output, leaving the printer empty
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+}
+
+#[async_trait]
+impl Device for PrinterDevice {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "printer"
+    }
+
+    async fn open(&mut self) -> DeviceResult<()> {
+        self.open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        self.open = false;
+        Ok(())
+    }
+
+    /// Nothing ever comes back from a printer
+    async fn read_bytes(&mut self, _buf: &mut [u8]) -> DeviceResult<usize> {
+        Ok(0)
+    }
+
+    async fn write_bytes(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        if !self.open {
+            return Err(DeviceError::NotReady);
+        }
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    async fn read_block(&mut self, _block: u32, _buf: &mut [u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn write_block(&mut self, _block: u32, _buf: &[u8]) -> DeviceResult<usize> {
+        Err(DeviceError::NotSupported)
+    }
+
+    async fn get_status(&self) -> DeviceResult<DeviceStatus> {
+        Ok(if self.open { DeviceStatus::Ready } else { DeviceStatus::Disconnected })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collects_output() -> DeviceResult<()> {
+        let mut printer = PrinterDevice::new();
+        assert_eq!(printer.write_bytes(b"X").await, Err(DeviceError::NotReady));
+
+        printer.open().await?;
+        printer.write_bytes(b"HELLO ").await?;
+        printer.write_bytes(b"WORLD\x9B").await?;
+        assert_eq!(printer.output(), b"HELLO WORLD\x9B");
+        assert_eq!(printer.take_output(), b"HELLO WORLD\x9B");
+        assert!(printer.output().is_empty());
+        Ok(())
+    }
+}
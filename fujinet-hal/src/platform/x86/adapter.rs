@@ -0,0 +1,161 @@
+use std::net::{Ipv4Addr, UdpSocket};
+use crate::device::{AdapterConfig, AdapterInfoProvider, ScanResult};
+
+/// The SSID the host's connection is reported under when it has no better name
+pub const VIRTUAL_SSID: &str = "FujiNet-x86";
+
+/// Adapter details for a PC, which has a network connection but no FujiNet WiFi
+///
+/// The "adapter" is whichever interface carries the default route. Its address, gateway,
+/// netmask and MAC come from the routing table and /sys on Linux, the DNS server from
+/// /etc/resolv.conf; anything that can't be found reads as zeros. The SSID is the
+/// interface name and a scan finds just that one network, at full strength.
+#[derive(Debug, Default)]
+pub struct X86AdapterInfo;
+
+impl X86AdapterInfo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AdapterInfoProvider for X86AdapterInfo {
+    fn adapter_config(&self) -> AdapterConfig {
+        let routes = std::fs::read_to_string("/proc/net/route").unwrap_or_default();
+        let route = parse_default_route(&routes);
+        let interface = route.as_ref().map(|(interface, _)| interface.as_str());
+
+        AdapterConfig {
+            ssid: interface.unwrap_or(VIRTUAL_SSID).to_string(),
+            hostname: hostname().unwrap_or_default(),
+            local_ip: local_ip().unwrap_or(Ipv4Addr::UNSPECIFIED),
+            gateway: route.as_ref().map_or(Ipv4Addr::UNSPECIFIED, |(_, gateway)| *gateway),
+            netmask: interface.and_then(|interface| parse_netmask(&routes, interface)).unwrap_or(Ipv4Addr::UNSPECIFIED),
+            dns: std::fs::read_to_string("/etc/resolv.conf")
+                .ok()
+                .and_then(|text| parse_nameserver(&text))
+                .unwrap_or(Ipv4Addr::UNSPECIFIED),
+            mac: interface
+                .and_then(|interface| std::fs::read_to_string(format!("/sys/class/net/{}/address", interface)).ok())
+                .and_then(|text| parse_mac(&text))
+                .unwrap_or([0; 6]),
+            bssid: [0; 6],
+            firmware_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    fn scan_networks(&self) -> Vec<ScanResult> {
+        vec![ScanResult { ssid: self.adapter_config().ssid, rssi: -30 }]
+    }
+
+    fn is_connected(&self) -> bool {
+        local_ip().is_some()
+    }
+}
+
+/// The address outgoing traffic would leave from
+///
+/// Connecting a UDP socket only picks a route; nothing is sent.
+fn local_ip() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: buf is valid for buf.len() bytes
+    if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+        return None;
+    }
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..end]).into_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+/// /proc/net/route holds addresses as little-endian hex
+fn parse_route_address(hex: &str) -> Option<Ipv4Addr> {
+    u32::from_str_radix(hex, 16).ok().map(|value| Ipv4Addr::from(value.to_le_bytes()))
+}
+
+/// Interface and gateway of the default route in /proc/net/route
+fn parse_default_route(routes: &str) -> Option<(String, Ipv4Addr)> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [interface, "00000000", gateway, ..] => Some((interface.to_string(), parse_route_address(gateway)?)),
+            _ => None,
+        }
+    })
+}
+
+/// Netmask of the interface's local network route in /proc/net/route
+fn parse_netmask(routes: &str, interface: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [name, destination, "00000000", _, _, _, _, mask, ..] if *name == interface && *destination != "00000000" => {
+                parse_route_address(mask)
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The first IPv4 nameserver in resolv.conf
+fn parse_nameserver(text: &str) -> Option<Ipv4Addr> {
+    text.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|address| address.trim().parse().ok())
+}
+
+fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    let mut mac = [0u8; 6];
+    let mut octets = text.trim().split(':');
+    for byte in mac.iter_mut() {
+        *byte = u8::from_str_radix(octets.next()?, 16).ok()?;
+    }
+    octets.next().is_none().then_some(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+";
+
+    #[test]
+    fn test_parse_host_network_details() {
+        assert_eq!(parse_default_route(ROUTES), Some(("eth0".to_string(), Ipv4Addr::new(192, 168, 1, 1))));
+        assert_eq!(parse_netmask(ROUTES, "eth0"), Some(Ipv4Addr::new(255, 255, 255, 0)));
+        assert_eq!(parse_netmask(ROUTES, "wlan0"), None);
+        assert_eq!(parse_default_route("Iface\tDestination\n"), None);
+
+        let resolv = "# generated\nsearch example.com\nnameserver fe80::1\nnameserver 10.0.0.53\n";
+        assert_eq!(parse_nameserver(resolv), Some(Ipv4Addr::new(10, 0, 0, 53)));
+        assert_eq!(parse_mac("02:42:ac:11:00:02\n"), Some([0x02, 0x42, 0xac, 0x11, 0x00, 0x02]));
+        assert_eq!(parse_mac("02:42:ac"), None);
+    }
+
+    #[test]
+    fn test_always_answers() {
+        // Whatever the host looks like there is a network to report and to find in a scan
+        let adapter = X86AdapterInfo::new();
+        let config = adapter.adapter_config();
+        assert!(!config.ssid.is_empty());
+        assert_eq!(config.firmware_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(adapter.scan_networks(), [ScanResult { ssid: config.ssid, rssi: -30 }]);
+    }
+}
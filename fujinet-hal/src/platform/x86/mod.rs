@@ -1,8 +1,20 @@
 #[cfg(target_arch = "x86_64")]
 pub mod network;
 
+#[cfg(target_arch = "x86_64")]
+mod platform;
+
+#[cfg(target_arch = "x86_64")]
+mod adapter;
+
 #[cfg(target_arch = "x86_64")]
 pub use network::*;
 
+#[cfg(target_arch = "x86_64")]
+pub use platform::{X86Platform, default_config_path, CONFIG_PATH_ENV};
+
+#[cfg(target_arch = "x86_64")]
+pub use adapter::{X86AdapterInfo, VIRTUAL_SSID};
+
 #[cfg(not(target_arch = "x86_64"))]
 compile_error!("x86 platform implementation can only be used on x86_64 targets"); 
\ No newline at end of file
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use reqwest;
 
 use crate::device::{DeviceResult, DeviceError};
-use crate::device::network::protocols::{HttpClient, BaseHttpClient, HttpClientProvider};
+use crate::device::network::protocols::{HttpClient, BaseHttpClient, HttpClientProvider, RangeResponse, range_header, parse_content_range_total};
 
 /// Platform-specific HTTP client implementation for x86
 pub struct X86HttpClient {
@@ -116,6 +116,43 @@ impl HttpClient for X86HttpClient {
         Ok(response.bytes().await?.to_vec())
     }
 
+    async fn request(&mut self, method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> DeviceResult<Vec<u8>> {
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|_| DeviceError::InvalidOperation)?;
+        let mut request = self.client.request(method, url).body(body.to_vec());
+
+        // Add persistent headers, then the per-request ones
+        for (key, value) in self.base.headers() {
+            request = request.header(key, value);
+        }
+        for (key, value) in headers {
+            request = request.header(*key, *value);
+        }
+
+        let response = request.send().await?;
+        self.base.set_status_code(response.status().as_u16());
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn get_range(&mut self, url: &str, start: u64, end: Option<u64>) -> DeviceResult<RangeResponse> {
+        let mut request = self.client.get(url);
+
+        // Add headers
+        for (key, value) in self.base.headers() {
+            request = request.header(key, value);
+        }
+        request = request.header(reqwest::header::RANGE, range_header(start, end));
+
+        let response = request.send().await?;
+        self.base.set_status_code(response.status().as_u16());
+        let total_len = response.headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range_total);
+        let data = response.bytes().await?.to_vec();
+        Ok(RangeResponse { data, total_len })
+    }
+
     fn set_header(&mut self, key: &str, value: &str) {
         self.base.set_header(key.to_string(), value.to_string());
     }
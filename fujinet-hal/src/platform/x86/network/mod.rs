@@ -1,7 +1,17 @@
 mod http_client;
-mod manager;
 mod protocol_factory;
+mod websocket_client;
+mod mqtt_client;
+mod tcp_client;
 
 pub use http_client::{X86HttpClient, DefaultHttpClientProvider};
-pub use manager::{get_network_manager, create_network_manager};
-pub use protocol_factory::create_protocol_registry;
\ No newline at end of file
+pub use websocket_client::{X86WebSocketClient, DefaultWebSocketClientProvider};
+pub use mqtt_client::{X86MqttClient, DefaultMqttClientProvider};
+pub use tcp_client::{X86TcpClient, DefaultTcpClientProvider};
+pub use protocol_factory::{
+    create_protocol_registry,
+    create_protocol_registry_with_file_root,
+    create_protocol_registry_with_optional_file_root,
+    default_file_root,
+    FILE_ROOT_ENV,
+};
\ No newline at end of file
@@ -0,0 +1,239 @@
+use async_trait::async_trait;
+use rumqttc::{AsyncClient, ClientError, ConnectReturnCode, Event, EventLoop, MqttOptions, Outgoing, Packet, QoS};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::device::{DeviceError, DeviceResult};
+use crate::device::network::{EventNotifier, NetworkEventKind};
+use crate::device::network::protocols::{MqttClient, MqttClientProvider, MqttConnectOptions, MqttMessage, MqttQos};
+
+/// How long to wait for the broker to acknowledge a connect, subscribe or QoS 1 publish
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Capacity of the request channel between the client and its event loop
+const REQUEST_CAPACITY: usize = 16;
+
+impl From<ClientError> for DeviceError {
+    fn from(err: ClientError) -> Self {
+        DeviceError::NetworkError(err.to_string())
+    }
+}
+
+impl From<MqttQos> for QoS {
+    fn from(qos: MqttQos) -> Self {
+        match qos {
+            MqttQos::AtMostOnce => QoS::AtMostOnce,
+            MqttQos::AtLeastOnce => QoS::AtLeastOnce,
+        }
+    }
+}
+
+/// Messages received by the event loop task, waiting to be read
+#[derive(Default)]
+struct Inbox {
+    messages: VecDeque<MqttMessage>,
+    error: Option<DeviceError>,
+}
+
+/// Acknowledgements received from the broker since connecting
+///
+/// Brokers acknowledge requests in the order they were sent, so the nth
+/// acknowledgement of a kind answers the nth request of that kind.
+#[derive(Debug, Clone, Copy, Default)]
+struct Acks {
+    subscriptions: u64,
+    publishes: u64,
+}
+
+/// Platform-specific MQTT client implementation for x86
+///
+/// After connecting, a background task drives the rumqttc event loop: it sends
+/// keep-alives, acknowledges QoS 1 deliveries and queues received publishes.
+#[derive(Default)]
+pub struct X86MqttClient {
+    client: Option<AsyncClient>,
+    inbox: Arc<Mutex<Inbox>>,
+    acks: Option<watch::Receiver<Acks>>,
+    /// QoS 1 publishes sent since connecting
+    publishes: u64,
+    task: Option<JoinHandle<()>>,
+    notifier: Option<EventNotifier>,
+}
+
+impl X86MqttClient {
+    fn client(&self) -> DeviceResult<&AsyncClient> {
+        self.client.as_ref().ok_or(DeviceError::NotReady)
+    }
+
+    /// Wait until the acknowledgements received satisfy `done`
+    async fn wait_for_ack(&self, done: impl FnMut(&Acks) -> bool) -> DeviceResult<()> {
+        let mut acks = self.acks.clone().ok_or(DeviceError::NotReady)?;
+        let result = tokio::time::timeout(ACK_TIMEOUT, acks.wait_for(done))
+            .await
+            .map(|acked| acked.is_ok());
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(self.error().unwrap_or(DeviceError::NotReady)),
+            Err(_) => Err(DeviceError::NetworkError("Timed out waiting for broker".to_string())),
+        }
+    }
+
+    fn subscription_acks(&self) -> u64 {
+        self.acks.as_ref().map_or(0, |acks| acks.borrow().subscriptions)
+    }
+
+    fn spawn_event_loop(
+        mut eventloop: EventLoop,
+        inbox: Arc<Mutex<Inbox>>,
+        acks: watch::Sender<Acks>,
+        notifier: Option<EventNotifier>,
+    ) -> JoinHandle<()> {
+        let notify = move |kind| if let Some(notifier) = &notifier {
+            notifier.notify(kind);
+        };
+        tokio::spawn(async move {
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let message = MqttMessage {
+                            topic: publish.topic,
+                            payload: publish.payload.to_vec(),
+                            qos: if publish.qos == QoS::AtMostOnce { MqttQos::AtMostOnce } else { MqttQos::AtLeastOnce },
+                            retain: publish.retain,
+                        };
+                        inbox.lock().unwrap().messages.push_back(message);
+                        notify(NetworkEventKind::DataAvailable);
+                    }
+                    Ok(Event::Incoming(Packet::SubAck(_))) | Ok(Event::Incoming(Packet::UnsubAck(_))) => {
+                        acks.send_modify(|acks| acks.subscriptions += 1);
+                    }
+                    Ok(Event::Incoming(Packet::PubAck(_))) => {
+                        acks.send_modify(|acks| acks.publishes += 1);
+                    }
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        // Stop rather than let rumqttc reconnect, so the error is seen by the caller
+                        inbox.lock().unwrap().error = Some(DeviceError::NetworkError(err.to_string()));
+                        notify(NetworkEventKind::ConnectionClosed);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Drop for X86MqttClient {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl MqttClient for X86MqttClient {
+    async fn connect(&mut self, options: &MqttConnectOptions) -> DeviceResult<()> {
+        let mut mqtt_options = MqttOptions::new(options.client_id.clone(), options.host.clone(), options.port);
+        mqtt_options.set_keep_alive(Duration::from_secs(options.keep_alive_secs.into()));
+        if let Some(username) = &options.username {
+            mqtt_options.set_credentials(username.clone(), options.password.clone().unwrap_or_default());
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(mqtt_options, REQUEST_CAPACITY);
+
+        // Drive the event loop until the broker accepts or refuses the session
+        let connack = async {
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(ack))) if ack.code == ConnectReturnCode::Success => return Ok(()),
+                    Ok(Event::Incoming(Packet::ConnAck(ack))) => {
+                        return Err(DeviceError::NetworkError(format!("Connection refused: {:?}", ack.code)));
+                    }
+                    Ok(_) => {}
+                    Err(err) => return Err(DeviceError::NetworkError(err.to_string())),
+                }
+            }
+        };
+        tokio::time::timeout(ACK_TIMEOUT, connack)
+            .await
+            .map_err(|_| DeviceError::NetworkError("Timed out waiting for broker".to_string()))??;
+
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let (ack_tx, ack_rx) = watch::channel(Acks::default());
+        if let Some(old) = self.task.replace(Self::spawn_event_loop(eventloop, inbox.clone(), ack_tx, self.notifier.clone())) {
+            old.abort();
+        }
+        self.client = Some(client);
+        self.inbox = inbox;
+        self.acks = Some(ack_rx);
+        self.publishes = 0;
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, topic: &str, qos: MqttQos) -> DeviceResult<()> {
+        let seen = self.subscription_acks();
+        self.client()?.subscribe(topic, qos.into()).await?;
+        self.wait_for_ack(|acks| acks.subscriptions > seen).await
+    }
+
+    async fn unsubscribe(&mut self, topic: &str) -> DeviceResult<()> {
+        let seen = self.subscription_acks();
+        self.client()?.unsubscribe(topic).await?;
+        self.wait_for_ack(|acks| acks.subscriptions > seen).await
+    }
+
+    async fn publish(&mut self, topic: &str, payload: &[u8], qos: MqttQos, retain: bool) -> DeviceResult<()> {
+        self.client()?.publish(topic, qos.into(), retain, payload.to_vec()).await?;
+        if qos == MqttQos::AtMostOnce {
+            return Ok(());
+        }
+        // Count the publish even if the wait fails, so a late PubAck isn't taken for the next one
+        self.publishes += 1;
+        let sent = self.publishes;
+        self.wait_for_ack(|acks| acks.publishes >= sent).await
+    }
+
+    async fn receive(&mut self) -> DeviceResult<Option<MqttMessage>> {
+        self.client()?;
+        Ok(self.inbox.lock().unwrap().messages.pop_front())
+    }
+
+    async fn disconnect(&mut self) -> DeviceResult<()> {
+        let Some(client) = self.client.take() else {
+            return Ok(());
+        };
+        self.acks = None;
+        // The broker may already have dropped the connection, in which case there is nothing to do
+        let _ = client.disconnect().await;
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<MqttMessage> {
+        self.inbox.lock().unwrap().messages.front().cloned()
+    }
+
+    fn error(&self) -> Option<DeviceError> {
+        self.inbox.lock().unwrap().error.clone()
+    }
+
+    fn set_event_notifier(&mut self, notifier: EventNotifier) {
+        self.notifier = Some(notifier);
+    }
+}
+
+/// Default MQTT client provider for x86 platform
+pub struct DefaultMqttClientProvider;
+
+impl MqttClientProvider for DefaultMqttClientProvider {
+    fn create_mqtt_client(&self) -> Box<dyn MqttClient> {
+        Box::new(X86MqttClient::default())
+    }
+}
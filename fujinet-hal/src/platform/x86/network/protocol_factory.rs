@@ -4,10 +4,22 @@ use crate::device::network::protocols::{
     NetworkProtocol,
     ProtocolRegistry,
     HttpProtocol,
+    FileProtocol,
+    WebSocketProtocol,
+    MqttProtocol,
+    GopherProtocol,
+    TcpProtocol,
 };
 use super::http_client::DefaultHttpClientProvider;
+use super::websocket_client::DefaultWebSocketClientProvider;
+use super::mqtt_client::DefaultMqttClientProvider;
+use super::tcp_client::DefaultTcpClientProvider;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Environment variable used to choose the root directory for file:// and sd:// URLs
+pub const FILE_ROOT_ENV: &str = "FUJINET_SD_ROOT";
+
 /// Factory for creating HTTP protocol handlers
 pub struct HttpProtocolFactory {
     provider: Arc<DefaultHttpClientProvider>,
@@ -19,13 +31,105 @@ impl ProtocolHandlerFactory for HttpProtocolFactory {
     }
 }
 
+/// Factory for creating local filesystem protocol handlers
+pub struct FileProtocolFactory {
+    root: PathBuf,
+}
+
+impl ProtocolHandlerFactory for FileProtocolFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(FileProtocol::new(self.root.clone()))
+    }
+}
+
+/// Factory for creating WebSocket protocol handlers
+pub struct WebSocketProtocolFactory {
+    provider: Arc<DefaultWebSocketClientProvider>,
+}
+
+impl ProtocolHandlerFactory for WebSocketProtocolFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(WebSocketProtocol::new(self.provider.clone()))
+    }
+}
+
+/// Factory for creating MQTT protocol handlers
+pub struct MqttProtocolFactory {
+    provider: Arc<DefaultMqttClientProvider>,
+}
+
+impl ProtocolHandlerFactory for MqttProtocolFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(MqttProtocol::new(self.provider.clone()))
+    }
+}
+
+/// Factory for creating gopher protocol handlers
+pub struct GopherProtocolFactory {
+    provider: Arc<DefaultTcpClientProvider>,
+}
+
+impl ProtocolHandlerFactory for GopherProtocolFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(GopherProtocol::new(self.provider.clone()))
+    }
+}
+
+/// Factory for creating raw TCP stream protocol handlers
+pub struct TcpProtocolFactory {
+    provider: Arc<DefaultTcpClientProvider>,
+}
+
+impl ProtocolHandlerFactory for TcpProtocolFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(TcpProtocol::new(self.provider.clone()))
+    }
+}
+
+/// Root directory for file access: $FUJINET_SD_ROOT, if set
+/// There is no fallback, so local files are only reachable once a root is chosen
+pub fn default_file_root() -> Option<PathBuf> {
+    std::env::var_os(FILE_ROOT_ENV).map(PathBuf::from)
+}
+
 /// Create a protocol registry with platform-specific handlers
+/// file:// and sd:// are only supported when $FUJINET_SD_ROOT names a root for them
 pub fn create_protocol_registry() -> ProtocolRegistry {
+    create_protocol_registry_with_optional_file_root(default_file_root())
+}
+
+/// Create a protocol registry whose file:// and sd:// handlers are sandboxed to `root`
+pub fn create_protocol_registry_with_file_root(root: impl Into<PathBuf>) -> ProtocolRegistry {
+    create_protocol_registry_with_optional_file_root(Some(root.into()))
+}
+
+/// Create a protocol registry, leaving out the file:// and sd:// handlers without a root
+pub fn create_protocol_registry_with_optional_file_root(root: Option<PathBuf>) -> ProtocolRegistry {
     let mut registry = ProtocolRegistry::new();
     
     // Register HTTP protocol handler
     let provider = Arc::new(DefaultHttpClientProvider);
     registry.register(NetworkProtocol::Http, Box::new(HttpProtocolFactory { provider }));
+
+    // Register local filesystem handler
+    if let Some(root) = root {
+        registry.register(NetworkProtocol::File, Box::new(FileProtocolFactory { root }));
+    }
+
+    // Register WebSocket protocol handler
+    let provider = Arc::new(DefaultWebSocketClientProvider);
+    registry.register(NetworkProtocol::WebSocket, Box::new(WebSocketProtocolFactory { provider }));
+
+    // Register MQTT protocol handler
+    let provider = Arc::new(DefaultMqttClientProvider);
+    registry.register(NetworkProtocol::Mqtt, Box::new(MqttProtocolFactory { provider }));
+
+    // Register gopher protocol handler
+    let provider = Arc::new(DefaultTcpClientProvider);
+    registry.register(NetworkProtocol::Gopher, Box::new(GopherProtocolFactory { provider: provider.clone() }));
+
+    // Register raw TCP stream handler
+    registry.register(NetworkProtocol::Tcp, Box::new(TcpProtocolFactory { provider }));
     
     registry
-} 
\ No newline at end of file
+}
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use futures_util::FutureExt;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::device::{DeviceError, DeviceResult};
+use crate::device::network::{EventNotifier, NetworkEventKind};
+use crate::device::network::protocols::{TcpClient, TcpClientProvider};
+
+/// Most received bytes `available` counts; hosts can't be told about more than this anyway
+const PEEK_LIMIT: usize = u16::MAX as usize;
+
+/// Platform-specific TCP client implementation for x86
+///
+/// With an event notifier set, a background task watches the socket while it is open:
+/// it raises DataAvailable when data arrives, waits for a read to take it, and raises
+/// ConnectionClosed when the peer closes the connection.
+#[derive(Default)]
+pub struct X86TcpClient {
+    stream: Option<Arc<TcpStream>>,
+    watcher: Option<JoinHandle<()>>,
+    /// Wakes the watcher once a read has taken the data it reported
+    data_taken: Arc<Notify>,
+    notifier: Option<EventNotifier>,
+}
+
+impl X86TcpClient {
+    fn stream(&self) -> DeviceResult<&TcpStream> {
+        self.stream.as_deref().ok_or(DeviceError::NotReady)
+    }
+
+    fn spawn_watcher(stream: Arc<TcpStream>, data_taken: Arc<Notify>, notifier: EventNotifier) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut byte = [0u8; 1];
+            loop {
+                match stream.peek(&mut byte).await {
+                    Ok(len) if len > 0 => {
+                        notifier.notify(NetworkEventKind::DataAvailable);
+                        data_taken.notified().await;
+                    }
+                    _ => {
+                        notifier.notify(NetworkEventKind::ConnectionClosed);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Stop watching the socket, returning once the watcher has let go of it
+    async fn stop_watcher(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+            let _ = watcher.await;
+        }
+    }
+}
+
+impl Drop for X86TcpClient {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl TcpClient for X86TcpClient {
+    async fn connect(&mut self, host: &str, port: u16) -> DeviceResult<()> {
+        let stream = TcpStream::connect((host, port))
+            .await
+            .map_err(|e| DeviceError::NetworkError(e.to_string()))?;
+        stream.set_nodelay(true)?;
+        let stream = Arc::new(stream);
+
+        self.stop_watcher().await;
+        if let Some(notifier) = self.notifier.clone() {
+            self.data_taken = Arc::new(Notify::new());
+            self.watcher = Some(Self::spawn_watcher(stream.clone(), self.data_taken.clone(), notifier));
+        }
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        let stream = self.stream()?;
+        let len = loop {
+            stream.readable().await?;
+            match stream.try_read(buf) {
+                Ok(len) => break len,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        self.data_taken.notify_one();
+        Ok(len)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        let stream = self.stream()?;
+        let mut written = 0;
+        while written < buf.len() {
+            stream.writable().await?;
+            match stream.try_write(&buf[written..]) {
+                Ok(len) => written += len,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(buf.len())
+    }
+
+    async fn try_write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        match self.stream()?.try_write(buf) {
+            Ok(len) => Ok(len),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn available(&self) -> usize {
+        let Some(stream) = &self.stream else {
+            return 0;
+        };
+        let mut buf = vec![0u8; PEEK_LIMIT];
+        match stream.peek(&mut buf).now_or_never() {
+            Some(Ok(len)) => len,
+            _ => 0,
+        }
+    }
+
+    fn readable(&self) -> bool {
+        let Some(stream) = &self.stream else {
+            return true;
+        };
+        // A peek finishes at once when data or the peer's close is waiting
+        let mut byte = [0u8; 1];
+        stream.peek(&mut byte).now_or_never().is_some()
+    }
+
+    async fn disconnect(&mut self) -> DeviceResult<()> {
+        self.stop_watcher().await;
+        if let Some(stream) = self.stream.take() {
+            if let Ok(mut stream) = Arc::try_unwrap(stream) {
+                // The peer may already have closed its side
+                let _ = stream.shutdown().await;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn set_event_notifier(&mut self, notifier: EventNotifier) {
+        self.notifier = Some(notifier);
+    }
+}
+
+/// Default TCP client provider for x86 platform
+pub struct DefaultTcpClientProvider;
+
+impl TcpClientProvider for DefaultTcpClientProvider {
+    fn create_tcp_client(&self) -> Box<dyn TcpClient> {
+        Box::new(X86TcpClient::default())
+    }
+}
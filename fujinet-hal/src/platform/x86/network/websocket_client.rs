@@ -0,0 +1,170 @@
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::device::{DeviceError, DeviceResult};
+use crate::device::network::{EventNotifier, NetworkEventKind};
+use crate::device::network::protocols::{WebSocketClient, WebSocketClientProvider, WebSocketMessage};
+
+/// Close code reported when the connection drops without a close frame (RFC 6455 section 7.4.1)
+const CLOSE_ABNORMAL: u16 = 1006;
+
+impl From<tungstenite::Error> for DeviceError {
+    fn from(err: tungstenite::Error) -> Self {
+        DeviceError::NetworkError(err.to_string())
+    }
+}
+
+/// Messages received by the background task, waiting to be read
+#[derive(Default)]
+struct Inbox {
+    messages: VecDeque<WebSocketMessage>,
+    close_code: Option<u16>,
+}
+
+/// Platform-specific WebSocket client implementation for x86
+///
+/// After the handshake a background task owns the socket: it forwards outgoing
+/// messages, queues incoming data messages and answers pings.
+#[derive(Default)]
+pub struct X86WebSocketClient {
+    inbox: Arc<Mutex<Inbox>>,
+    outgoing: Option<mpsc::UnboundedSender<Message>>,
+    task: Option<JoinHandle<()>>,
+    notifier: Option<EventNotifier>,
+}
+
+impl X86WebSocketClient {
+    fn sender(&self) -> DeviceResult<&mpsc::UnboundedSender<Message>> {
+        self.outgoing.as_ref().ok_or(DeviceError::NotReady)
+    }
+}
+
+impl Drop for X86WebSocketClient {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl WebSocketClient for X86WebSocketClient {
+    async fn connect(&mut self, url: &str, headers: &[(String, String)]) -> DeviceResult<()> {
+        let mut request = url.into_client_request()?;
+        for (key, value) in headers {
+            let name = HeaderName::from_bytes(key.as_bytes()).map_err(|_| DeviceError::InvalidOperation)?;
+            let value = HeaderValue::from_str(value).map_err(|_| DeviceError::InvalidOperation)?;
+            request.headers_mut().append(name, value);
+        }
+
+        let (mut stream, _) = tokio_tungstenite::connect_async(request).await?;
+        let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let task_inbox = inbox.clone();
+        let notify = self.notifier.clone();
+        let notify = move |kind| if let Some(notifier) = &notify {
+            notifier.notify(kind);
+        };
+
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    outgoing = rx.recv() => {
+                        let Some(message) = outgoing else { break };
+                        let closing = matches!(message, Message::Close(_));
+                        if stream.send(message).await.is_err() || closing {
+                            break;
+                        }
+                    }
+                    incoming = stream.next() => {
+                        let message = match incoming {
+                            Some(Ok(Message::Text(text))) => WebSocketMessage::Text(text.to_string()),
+                            Some(Ok(Message::Binary(data))) => WebSocketMessage::Binary(data.to_vec()),
+                            Some(Ok(Message::Close(frame))) => {
+                                let code = frame.map_or(CloseCode::Status.into(), |f| f.code.into());
+                                task_inbox.lock().unwrap().close_code = Some(code);
+                                notify(NetworkEventKind::ConnectionClosed);
+                                break;
+                            }
+                            // Pongs to received pings are queued by tungstenite and flushed on the next poll
+                            Some(Ok(_)) => continue,
+                            Some(Err(_)) | None => {
+                                task_inbox.lock().unwrap().close_code = Some(CLOSE_ABNORMAL);
+                                notify(NetworkEventKind::ConnectionClosed);
+                                break;
+                            }
+                        };
+                        task_inbox.lock().unwrap().messages.push_back(message);
+                        notify(NetworkEventKind::DataAvailable);
+                    }
+                }
+            }
+            let _ = stream.close(None).await;
+        });
+
+        if let Some(old) = self.task.replace(task) {
+            old.abort();
+        }
+        self.inbox = inbox;
+        self.outgoing = Some(tx);
+        Ok(())
+    }
+
+    async fn send(&mut self, message: WebSocketMessage) -> DeviceResult<()> {
+        let message = match message {
+            WebSocketMessage::Text(text) => Message::text(text),
+            WebSocketMessage::Binary(data) => Message::binary(data),
+        };
+        self.sender()?
+            .send(message)
+            .map_err(|_| DeviceError::NetworkError("WebSocket connection closed".to_string()))
+    }
+
+    async fn receive(&mut self) -> DeviceResult<Option<WebSocketMessage>> {
+        self.sender()?;
+        Ok(self.inbox.lock().unwrap().messages.pop_front())
+    }
+
+    async fn close(&mut self, code: u16) -> DeviceResult<()> {
+        let Some(tx) = self.outgoing.take() else {
+            return Ok(());
+        };
+        let frame = CloseFrame { code: CloseCode::from(code), reason: "".into() };
+        // The peer may already have closed the connection, in which case there is nothing to do
+        let _ = tx.send(Message::Close(Some(frame)));
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+        Ok(())
+    }
+
+    fn next_message_len(&self) -> usize {
+        self.inbox.lock().unwrap().messages.front().map_or(0, |m| m.as_bytes().len())
+    }
+
+    fn close_code(&self) -> Option<u16> {
+        self.inbox.lock().unwrap().close_code
+    }
+
+    fn set_event_notifier(&mut self, notifier: EventNotifier) {
+        self.notifier = Some(notifier);
+    }
+}
+
+/// Default WebSocket client provider for x86 platform
+pub struct DefaultWebSocketClientProvider;
+
+impl WebSocketClientProvider for DefaultWebSocketClientProvider {
+    fn create_websocket_client(&self) -> Box<dyn WebSocketClient> {
+        Box::new(X86WebSocketClient::default())
+    }
+}
@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+use crate::device::{Device, DeviceError, DeviceResult, DiskDevice, PrinterDevice, ClockDevice, ModemDevice};
+use crate::device::disk::DEFAULT_SECTOR_SIZE;
+use crate::device::manager::DEFAULT_NETWORK_UNITS;
+use crate::device::network::NetworkDeviceImpl;
+use crate::device::network::manager::NetworkManagerImpl;
+use crate::device::network::protocols::{NetworkProtocol, ProtocolRegistry};
+use crate::platform::Platform;
+use super::network::{create_protocol_registry_with_optional_file_root, default_file_root};
+
+/// Environment variable naming the configuration file
+pub const CONFIG_PATH_ENV: &str = "FUJINET_CONFIG";
+
+/// Path of fnconfig.ini: $FUJINET_CONFIG, or fnconfig.ini in the current directory
+pub fn default_config_path() -> PathBuf {
+    std::env::var_os(CONFIG_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("fnconfig.ini"))
+}
+
+/// The x86 host platform
+///
+/// `initialize` sets up the protocol handlers the devices use and `shutdown` releases
+/// them; no devices or network managers can be created outside that window.
+///
+/// `create_device` takes a type name, optionally followed by `:` and an argument:
+/// - `network:<url>` a network device for the URL, e.g. `network:tcp://bbs.example:23`
+/// - `disk` or `disk:<sector size>` an empty disk drive, 128 byte sectors by default
+/// - `printer`, `clock`
+/// - `modem` a modem dialling out over TCP
+///
+/// file:// and sd:// URLs are only served from the root given to `with_file_root`,
+/// or $FUJINET_SD_ROOT when none was given.
+pub struct X86Platform {
+    units: usize,
+    file_root: Option<PathBuf>,
+    registry: Option<ProtocolRegistry>,
+}
+
+impl X86Platform {
+    pub fn new() -> Self {
+        Self::with_units(DEFAULT_NETWORK_UNITS)
+    }
+
+    /// A platform whose network managers have `units` network units
+    pub fn with_units(units: usize) -> Self {
+        Self {
+            units,
+            file_root: default_file_root(),
+            registry: None,
+        }
+    }
+
+    /// Sandbox file:// and sd:// to `root`, taking effect at the next `initialize`
+    pub fn with_file_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.file_root = Some(root.into());
+        self
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.registry.is_some()
+    }
+
+    /// Create a network manager for the platform's N: units
+    pub fn create_network_manager(&self) -> DeviceResult<NetworkManagerImpl> {
+        let registry = self.registry.clone().ok_or(DeviceError::NotReady)?;
+        Ok(NetworkManagerImpl::with_units(registry, self.units))
+    }
+}
+
+impl Default for X86Platform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Platform for X86Platform {
+    async fn initialize(&mut self) -> DeviceResult<()> {
+        if self.registry.is_none() {
+            self.registry = Some(create_protocol_registry_with_optional_file_root(self.file_root.clone()));
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> DeviceResult<()> {
+        self.registry = None;
+        Ok(())
+    }
+
+    async fn create_device(&self, device_type: &str) -> DeviceResult<Box<dyn Device>> {
+        let registry = self.registry.as_ref().ok_or(DeviceError::NotReady)?;
+        let (kind, argument) = match device_type.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (device_type, None),
+        };
+
+        match (kind.to_ascii_lowercase().as_str(), argument) {
+            ("network", Some(url)) => {
+                let (scheme, _) = url.split_once("://").ok_or(DeviceError::InvalidUrl)?;
+                let protocol = NetworkProtocol::from_str(scheme).ok_or(DeviceError::UnsupportedProtocol)?;
+                let handler = registry.create_handler(protocol)?;
+                Ok(Box::new(NetworkDeviceImpl::new(url.to_string(), handler)))
+            }
+            ("network", None) => Err(DeviceError::InvalidUrl),
+            ("disk", sector_size) => {
+                let sector_size = match sector_size {
+                    Some(size) => size.parse().ok().filter(|&size| size > 0).ok_or(DeviceError::InvalidOperation)?,
+                    None => DEFAULT_SECTOR_SIZE,
+                };
+                Ok(Box::new(DiskDevice::with_sector_size(sector_size)))
+            }
+            ("printer", None) => Ok(Box::new(PrinterDevice::new())),
+            ("clock", None) => Ok(Box::new(ClockDevice::new())),
+            ("modem", None) => Ok(Box::new(ModemDevice::new(registry.create_handler(NetworkProtocol::Tcp)?))),
+            _ => Err(DeviceError::NotSupported),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::network::manager::NetworkManager;
+
+    #[tokio::test]
+    async fn test_lifecycle() -> DeviceResult<()> {
+        let mut platform = X86Platform::with_units(4);
+        assert_eq!(platform.create_device("clock").await.err(), Some(DeviceError::NotReady));
+        assert!(matches!(platform.create_network_manager(), Err(DeviceError::NotReady)));
+
+        platform.initialize().await?;
+        assert!(platform.is_initialized());
+        assert_eq!(platform.create_network_manager()?.unit_count(), 4);
+
+        platform.shutdown().await?;
+        assert!(!platform.is_initialized());
+        assert_eq!(platform.create_device("clock").await.err(), Some(DeviceError::NotReady));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_device_by_type() -> DeviceResult<()> {
+        let mut platform = X86Platform::new();
+        platform.initialize().await?;
+
+        for (device_type, name) in [
+            ("network:tcp://bbs.example:23", "network"),
+            ("disk", "disk"),
+            ("DISK:256", "disk"),
+            ("printer", "printer"),
+            ("clock", "clock"),
+            ("modem", "modem"),
+        ] {
+            assert_eq!(platform.create_device(device_type).await?.name(), name);
+        }
+
+        let disk = platform.create_device("disk:256").await?;
+        assert_eq!(disk.as_any().downcast_ref::<DiskDevice>().unwrap().sector_size(), 256);
+
+        assert_eq!(platform.create_device("network").await.err(), Some(DeviceError::InvalidUrl));
+        assert_eq!(platform.create_device("network:nntp://news").await.err(), Some(DeviceError::UnsupportedProtocol));
+        assert_eq!(platform.create_device("disk:0").await.err(), Some(DeviceError::InvalidOperation));
+        assert_eq!(platform.create_device("cassette").await.err(), Some(DeviceError::NotSupported));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_urls_need_a_root() -> DeviceResult<()> {
+        let mut platform = X86Platform::new();
+        platform.file_root = None;
+        platform.initialize().await?;
+        assert_eq!(platform.create_device("network:file:///boot.atr").await.err(), Some(DeviceError::UnsupportedProtocol));
+
+        let root = tempfile::tempdir().unwrap();
+        let mut platform = X86Platform::new().with_file_root(root.path());
+        platform.initialize().await?;
+        assert_eq!(platform.create_device("network:file:///boot.atr").await?.name(), "network");
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
 #ifndef FUJINET_HAL_H
 #define FUJINET_HAL_H
 
+#include <stddef.h>
 #include <stdint.h>
 
 // Network functions
 uint8_t network_init(void);
+uint8_t network_shutdown(void);
+uint8_t network_reset(void);
+uint8_t network_pending(const char* devicespec, uint16_t* pending);
 uint8_t network_http_get(const char* devicespec);
 uint8_t network_http_post(const char* devicespec, const char* data);
 uint8_t network_http_post_bin(const char* devicespec, const uint8_t* data, uint16_t len);
@@ -14,4 +18,36 @@ uint8_t network_http_start_add_headers(const char* devicespec);
 uint8_t network_http_end_add_headers(const char* devicespec);
 uint8_t network_http_add_header(const char* devicespec, const char* header);
 
+// Platform and device functions
+typedef void FujiPlatform;
+typedef void FujiDevice;
+
+typedef enum {
+    FUJI_OK = 0,
+    FUJI_IO_ERROR = 1,
+    FUJI_INVALID_PARAMETER = 2,
+    FUJI_NOT_READY = 3,
+    FUJI_NETWORK_ERROR = 4,
+    FUJI_NOT_SUPPORTED = 5,
+} FujiError;
+
+typedef enum {
+    FUJI_DEVICE_READY = 0,
+    FUJI_DEVICE_ERROR = 1,
+    FUJI_DEVICE_DISCONNECTED = 2,
+} FujiDeviceStatus;
+
+FujiPlatform* fuji_platform_create(void);
+FujiError fuji_platform_destroy(FujiPlatform* platform);
+FujiDevice* fuji_device_create(FujiPlatform* platform, const char* device_type);
+FujiError fuji_device_destroy(FujiDevice* device);
+FujiError fuji_device_open(FujiDevice* device);
+FujiError fuji_device_close(FujiDevice* device);
+FujiError fuji_device_read_bytes(FujiDevice* device, uint8_t* buffer, size_t len, size_t* bytes_read);
+FujiError fuji_device_write_bytes(FujiDevice* device, const uint8_t* buffer, size_t len, size_t* bytes_written);
+FujiError fuji_device_read_block(FujiDevice* device, uint32_t block, uint8_t* buffer, size_t len, size_t* bytes_read);
+FujiError fuji_device_write_block(FujiDevice* device, uint32_t block, const uint8_t* buffer, size_t len, size_t* bytes_written);
+FujiError fuji_device_get_status(FujiDevice* device, FujiDeviceStatus* status);
+FujiError fuji_device_name(FujiDevice* device, char* buffer, size_t len);
+
 #endif // FUJINET_HAL_H 
\ No newline at end of file
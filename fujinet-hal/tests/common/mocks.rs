@@ -68,6 +68,11 @@ impl HttpClient for MockHttpClient {
         Ok(b"test response".to_vec())
     }
 
+    async fn request(&mut self, _method: &str, url: &str, _headers: &[(&str, &str)], body: &[u8]) -> DeviceResult<Vec<u8>> {
+        self.recorded_requests.lock().unwrap().push((url.to_string(), body.to_vec()));
+        Ok(Vec::new())
+    }
+
     fn set_header(&mut self, key: &str, value: &str) {
         self.base.set_header(key.to_string(), value.to_string());
     }
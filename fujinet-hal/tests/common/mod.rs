@@ -1,3 +1,4 @@
 pub mod mocks;
+pub mod mqtt_broker;
 
 // pub use mocks::MockHttpClient; 
\ No newline at end of file
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use bytes::BytesMut;
+use rumqttc::{
+    matches, ConnAck, ConnectReturnCode, Packet, PubAck, Publish, QoS, SubAck, SubscribeReasonCode, UnsubAck,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+
+/// A client's topic filters and the channel publishes are routed to it on
+type Subscriber = (Vec<(String, QoS)>, mpsc::UnboundedSender<Publish>);
+
+#[derive(Default)]
+struct BrokerState {
+    next_client: usize,
+    subscriptions: HashMap<usize, Subscriber>,
+    retained: HashMap<String, Publish>,
+    /// Drop the connection instead of acknowledging a publish
+    drop_on_publish: bool,
+}
+
+/// Minimal in-process MQTT 3.1.1 broker for tests
+///
+/// Supports QoS 0/1 publishes, wildcard subscriptions and retained messages.
+/// No persistence, no QoS 2, no authentication.
+pub struct TestBroker {
+    port: u16,
+    state: Arc<Mutex<BrokerState>>,
+}
+
+impl TestBroker {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(BrokerState::default()));
+
+        let accept_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle_client(socket, accept_state.clone()));
+            }
+        });
+
+        Self { port, state }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Make the broker drop any client that publishes, without acknowledging it
+    pub fn drop_on_publish(&self) {
+        self.state.lock().unwrap().drop_on_publish = true;
+    }
+
+    /// The payload currently retained for a topic
+    pub fn retained(&self, topic: &str) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().retained.get(topic).map(|p| p.payload.to_vec())
+    }
+}
+
+async fn send(socket: &mut TcpStream, packet: Packet) -> std::io::Result<()> {
+    let mut buf = BytesMut::new();
+    let written = match packet {
+        Packet::ConnAck(p) => p.write(&mut buf),
+        Packet::Publish(p) => p.write(&mut buf),
+        Packet::PubAck(p) => p.write(&mut buf),
+        Packet::SubAck(p) => p.write(&mut buf),
+        Packet::UnsubAck(p) => p.write(&mut buf),
+        Packet::PingResp => rumqttc::PingResp.write(&mut buf),
+        other => panic!("test broker cannot send {:?}", other),
+    };
+    written.expect("encode MQTT packet");
+    socket.write_all(&buf).await
+}
+
+fn min_qos(a: QoS, b: QoS) -> QoS {
+    if a < b { a } else { b }
+}
+
+/// Deliver a publish to a subscriber at the lower of the publish and subscription QoS
+fn deliver(publish: &Publish, granted: QoS, retain: bool, pkid: &mut u16) -> Publish {
+    let mut out = Publish::new(publish.topic.clone(), min_qos(publish.qos, granted), publish.payload.to_vec());
+    out.retain = retain;
+    if out.qos != QoS::AtMostOnce {
+        *pkid = pkid.wrapping_add(1).max(1);
+        out.pkid = *pkid;
+    }
+    out
+}
+
+async fn handle_client(mut socket: TcpStream, state: Arc<Mutex<BrokerState>>) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Publish>();
+    let id = {
+        let mut state = state.lock().unwrap();
+        state.next_client += 1;
+        let id = state.next_client;
+        state.subscriptions.insert(id, (Vec::new(), tx));
+        id
+    };
+
+    let mut buf = BytesMut::with_capacity(4096);
+    let mut pkid = 0u16;
+    'outer: loop {
+        tokio::select! {
+            read = socket.read_buf(&mut buf) => {
+                if !matches!(read, Ok(n) if n > 0) {
+                    break;
+                }
+                while let Ok(packet) = rumqttc::mqttbytes::v4::read(&mut buf, MAX_PACKET_SIZE) {
+                    let reply = match packet {
+                        Packet::Connect(_) => vec![Packet::ConnAck(ConnAck::new(ConnectReturnCode::Success, false))],
+                        Packet::PingReq => vec![Packet::PingResp],
+                        Packet::Disconnect => break 'outer,
+                        Packet::Subscribe(subscribe) => {
+                            let mut replies = vec![Packet::SubAck(SubAck::new(
+                                subscribe.pkid,
+                                subscribe.filters.iter().map(|f| SubscribeReasonCode::Success(f.qos)).collect(),
+                            ))];
+                            let mut state = state.lock().unwrap();
+                            for filter in &subscribe.filters {
+                                for retained in state.retained.values() {
+                                    if matches(&retained.topic, &filter.path) {
+                                        replies.push(Packet::Publish(deliver(retained, filter.qos, true, &mut pkid)));
+                                    }
+                                }
+                            }
+                            let (filters, _) = state.subscriptions.get_mut(&id).unwrap();
+                            filters.extend(subscribe.filters.into_iter().map(|f| (f.path, f.qos)));
+                            replies
+                        }
+                        Packet::Unsubscribe(unsubscribe) => {
+                            let mut state = state.lock().unwrap();
+                            let (filters, _) = state.subscriptions.get_mut(&id).unwrap();
+                            filters.retain(|(path, _)| !unsubscribe.topics.contains(path));
+                            vec![Packet::UnsubAck(UnsubAck::new(unsubscribe.pkid))]
+                        }
+                        Packet::Publish(publish) => {
+                            let mut state = state.lock().unwrap();
+                            if state.drop_on_publish {
+                                break 'outer;
+                            }
+                            if publish.retain {
+                                if publish.payload.is_empty() {
+                                    state.retained.remove(&publish.topic);
+                                } else {
+                                    state.retained.insert(publish.topic.clone(), publish.clone());
+                                }
+                            }
+                            for (filters, sender) in state.subscriptions.values() {
+                                if let Some((_, qos)) = filters.iter().find(|(path, _)| matches(&publish.topic, path)) {
+                                    let mut routed = publish.clone();
+                                    routed.qos = min_qos(publish.qos, *qos);
+                                    let _ = sender.send(routed);
+                                }
+                            }
+                            if publish.qos == QoS::AtLeastOnce {
+                                vec![Packet::PubAck(PubAck::new(publish.pkid))]
+                            } else {
+                                Vec::new()
+                            }
+                        }
+                        _ => Vec::new(),
+                    };
+                    for packet in reply {
+                        if send(&mut socket, packet).await.is_err() {
+                            break 'outer;
+                        }
+                    }
+                }
+            }
+            routed = rx.recv() => {
+                let Some(publish) = routed else { break };
+                let qos = publish.qos;
+                if send(&mut socket, Packet::Publish(deliver(&publish, qos, false, &mut pkid))).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    state.lock().unwrap().subscriptions.remove(&id);
+}
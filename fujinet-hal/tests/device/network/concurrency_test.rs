@@ -0,0 +1,231 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fujinet_hal::adapters::common::network::operations::{
+    OperationsContext, DeviceOpenRequest, ReadRequest, WriteRequest,
+};
+use fujinet_hal::device::{DeviceError, DeviceResult};
+use fujinet_hal::device::network::protocols::{
+    ProtocolHandler,
+    ProtocolHandlerFactory,
+    NetworkProtocol,
+    ConnectionStatus,
+    ProtocolRegistry,
+};
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+
+const UNITS: usize = 8;
+const ROUNDS: usize = 5;
+const LATENCY: Duration = Duration::from_millis(40);
+
+// Echoes back whatever was last written, taking LATENCY for every read and write
+// like a round trip to a remote host. Hosts named slow-connect take ten round trips to open
+// and hosts named refused never open
+#[derive(Default)]
+struct SlowEchoProtocol {
+    echo: Vec<u8>,
+}
+
+#[async_trait]
+impl ProtocolHandler for SlowEchoProtocol {
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+
+    async fn open(&mut self, endpoint: &str) -> DeviceResult<()> {
+        if endpoint.contains("slow-connect") {
+            tokio::time::sleep(LATENCY * 10).await;
+        }
+        if endpoint.contains("refused") {
+            return Err(DeviceError::NetworkError("connection refused".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> DeviceResult<()> {
+        Ok(())
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> DeviceResult<usize> {
+        tokio::time::sleep(LATENCY).await;
+        let len = std::cmp::min(buf.len(), self.echo.len());
+        buf[..len].copy_from_slice(&self.echo[..len]);
+        self.echo.drain(..len);
+        Ok(len)
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> DeviceResult<usize> {
+        tokio::time::sleep(LATENCY).await;
+        self.echo.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    async fn status(&self) -> DeviceResult<ConnectionStatus> {
+        Ok(ConnectionStatus::Connected)
+    }
+
+    async fn available(&self) -> DeviceResult<usize> {
+        Ok(self.echo.len())
+    }
+}
+
+struct SlowEchoFactory;
+
+impl ProtocolHandlerFactory for SlowEchoFactory {
+    fn create_handler(&self) -> Box<dyn ProtocolHandler> {
+        Box::new(SlowEchoProtocol::default())
+    }
+}
+
+fn spec(unit: usize) -> String {
+    format!("N{}:tcp://echo-{}:23", unit, unit)
+}
+
+/// A context with every unit open on its own slow echo connection
+fn open_all_units() -> Arc<OperationsContext<NetworkManagerImpl>> {
+    let mut registry = ProtocolRegistry::new();
+    registry.register(NetworkProtocol::Tcp, Box::new(SlowEchoFactory));
+    let context = OperationsContext::new(NetworkManagerImpl::with_registry(registry));
+
+    for unit in 1..=UNITS {
+        let request = DeviceOpenRequest { device_spec: spec(unit), mode: 12, translation: 0 };
+        assert_eq!(context.open_device(request).unwrap(), unit - 1);
+    }
+    Arc::new(context)
+}
+
+fn echo(context: &OperationsContext<NetworkManagerImpl>, unit: usize, payload: &[u8]) -> Vec<u8> {
+    let written = context.write(WriteRequest::new(spec(unit), payload.to_vec())).unwrap();
+    assert_eq!(written, payload.len());
+
+    let mut request = ReadRequest::new(spec(unit), vec![0; 64]);
+    let len = context.read(&mut request).unwrap();
+    request.buffer.truncate(len);
+    request.buffer
+}
+
+#[test]
+fn test_parallel_io_on_all_units() {
+    let context = open_all_units();
+    let start = Instant::now();
+
+    let workers: Vec<_> = (1..=UNITS).map(|unit| {
+        let context = context.clone();
+        thread::spawn(move || {
+            for round in 0..ROUNDS {
+                let payload = format!("unit {} round {}", unit, round);
+                assert_eq!(echo(&context, unit, payload.as_bytes()), payload.as_bytes());
+            }
+        })
+    }).collect();
+    for worker in workers {
+        worker.join().expect("worker thread panicked");
+    }
+
+    // One after another this would take UNITS * ROUNDS * 2 * LATENCY (3.2s); in parallel
+    // it should take little more than a single unit's share
+    let serial = LATENCY * (UNITS * ROUNDS * 2) as u32;
+    let elapsed = start.elapsed();
+    assert!(elapsed < serial / 3, "units were serialized: took {:?}", elapsed);
+
+    for unit in 1..=UNITS {
+        context.close_device(unit - 1).unwrap();
+    }
+}
+
+#[test]
+fn test_busy_unit_does_not_block_others() {
+    let context = open_all_units();
+
+    // Keep N1 busy with a long run of round trips
+    let busy = {
+        let context = context.clone();
+        thread::spawn(move || {
+            for round in 0..ROUNDS * 2 {
+                echo(&context, 1, &[round as u8]);
+            }
+        })
+    };
+    thread::sleep(LATENCY / 2);
+
+    // N2 gets its answer after one round trip rather than queueing behind N1
+    let start = Instant::now();
+    assert_eq!(echo(&context, 2, b"PING"), b"PING");
+    assert!(start.elapsed() < LATENCY * 5, "unit 2 waited on unit 1: {:?}", start.elapsed());
+
+    busy.join().expect("busy thread panicked");
+}
+
+#[test]
+fn test_slow_connect_does_not_block_others() {
+    let context = open_all_units();
+
+    // Reopen N1 on a host that takes a long time to answer
+    let opening = {
+        let context = context.clone();
+        thread::spawn(move || {
+            let request = DeviceOpenRequest { device_spec: "N1:tcp://slow-connect:23".to_string(), mode: 12, translation: 0 };
+            context.open_device(request).unwrap();
+        })
+    };
+    thread::sleep(LATENCY / 2);
+
+    // The manager isn't held while N1 connects, so N2 carries on
+    let start = Instant::now();
+    assert_eq!(echo(&context, 2, b"PING"), b"PING");
+    assert!(start.elapsed() < LATENCY * 5, "unit 2 waited on unit 1 connecting: {:?}", start.elapsed());
+
+    opening.join().expect("opening thread panicked");
+}
+
+#[test]
+fn test_only_successful_opens_are_recorded() {
+    let context = open_all_units();
+
+    // A failed reopen leaves the unit closed, with neither URL accepted
+    let request = DeviceOpenRequest { device_spec: "N1:tcp://refused:23".to_string(), mode: 12, translation: 0 };
+    assert!(context.open_device(request).is_err());
+    assert!(context.validate_device_spec("N1:tcp://refused:23").is_err());
+    assert!(context.validate_device_spec(&spec(1)).is_err());
+
+    // Nor is the URL of an open that was still connecting when the unit was closed
+    let opening = {
+        let context = context.clone();
+        thread::spawn(move || {
+            let request = DeviceOpenRequest { device_spec: "N2:tcp://slow-connect:23".to_string(), mode: 12, translation: 0 };
+            context.open_device(request).unwrap();
+        })
+    };
+    thread::sleep(LATENCY / 2);
+    assert!(context.validate_device_spec("N2:tcp://slow-connect:23").is_err());
+    context.close_device(1).unwrap();
+    opening.join().expect("opening thread panicked");
+    assert!(context.validate_device_spec("N2:tcp://slow-connect:23").is_err());
+
+    assert_eq!(context.validate_device_spec(&spec(3)).unwrap(), 2);
+}
+
+#[test]
+fn test_units_beyond_eight() {
+    let registry = || {
+        let mut registry = ProtocolRegistry::new();
+        registry.register(NetworkProtocol::Tcp, Box::new(SlowEchoFactory));
+        registry
+    };
+
+    // The FujiNet default stops at N8
+    let manager = NetworkManagerImpl::with_registry(registry());
+    assert_eq!(manager.unit_count(), 8);
+    assert!(matches!(manager.parse_device_spec(&spec(9)), Err(DeviceError::InvalidDeviceId)));
+
+    let manager = NetworkManagerImpl::with_units(registry(), 16);
+    assert_eq!(manager.unit_count(), 16);
+    assert!(matches!(manager.parse_device_spec(&spec(17)), Err(DeviceError::InvalidDeviceId)));
+
+    let context = OperationsContext::new(manager);
+    let request = DeviceOpenRequest { device_spec: spec(12), mode: 12, translation: 0 };
+    assert_eq!(context.open_device(request).unwrap(), 11);
+    assert_eq!(echo(&context, 12, b"TWELVE"), b"TWELVE");
+    context.close_device(11).unwrap();
+}
@@ -0,0 +1,66 @@
+use fujinet_hal::device::DeviceResult;
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::{OPEN_MODE_READ, OPEN_MODE_WRITE};
+use fujinet_hal::platform::create_protocol_registry_with_file_root;
+
+#[tokio::test]
+async fn test_file_protocol_through_network_device() -> DeviceResult<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("docs"))?;
+
+    let registry = create_protocol_registry_with_file_root(root);
+    let mut manager = NetworkManagerImpl::with_registry(registry);
+
+    // Write a file on unit 2
+    manager.open_device("N2:FILE:///docs/readme.txt", OPEN_MODE_WRITE, 0).await?;
+    let mut device = manager.get_network_device(1).await.expect("unit 2 should be open");
+    assert_eq!(device.write_bytes(b"HELLO FROM N2").await?, 13);
+    drop(device);
+    manager.close_device(1).await?;
+    assert_eq!(std::fs::read(root.join("docs/readme.txt"))?, b"HELLO FROM N2");
+
+    // Read it back through the SD-style scheme
+    manager.open_device("N:sd://docs/readme.txt", OPEN_MODE_READ, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+    let mut buf = [0u8; 32];
+    let len = device.read_bytes(&mut buf).await?;
+    assert_eq!(&buf[..len], b"HELLO FROM N2");
+    drop(device);
+    manager.close_device(0).await?;
+
+    // Paths outside the root are rejected
+    assert!(manager.open_device("N:file:///../outside.txt", OPEN_MODE_WRITE, 0).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relative_specs_resolve_against_unit_prefix() -> DeviceResult<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("games/arcade"))?;
+    std::fs::write(root.join("games/arcade/pacman.txt"), b"WAKA")?;
+
+    let registry = create_protocol_registry_with_file_root(root);
+    let mut manager = NetworkManagerImpl::with_registry(registry);
+
+    manager.set_prefix("N3:file:///games/")?;
+    manager.set_prefix("N3:arcade")?;
+    assert_eq!(manager.get_prefix(2).as_deref(), Some("file:///games/arcade/"));
+
+    // The prefix survives closing the unit and only applies to that unit
+    manager.open_device("N3:pacman.txt", OPEN_MODE_READ, 0).await?;
+    let mut device = manager.get_network_device(2).await.expect("unit 3 should be open");
+    let mut buf = [0u8; 8];
+    let len = device.read_bytes(&mut buf).await?;
+    assert_eq!(&buf[..len], b"WAKA");
+    drop(device);
+    manager.close_device(2).await?;
+    assert_eq!(manager.get_prefix(2).as_deref(), Some("file:///games/arcade/"));
+    assert!(manager.open_device("N1:pacman.txt", OPEN_MODE_READ, 0).await.is_err());
+
+    manager.set_prefix("N3:..")?;
+    let (_, url) = manager.parse_device_spec("N3:arcade/pacman.txt")?;
+    assert_eq!(url.url, "file:///games/arcade/pacman.txt");
+    Ok(())
+}
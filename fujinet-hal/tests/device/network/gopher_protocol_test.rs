@@ -0,0 +1,77 @@
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use fujinet_hal::device::DeviceResult;
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::{GopherItemType, GopherProtocol, OPEN_MODE_DIRECTORY, OPEN_MODE_READ};
+use fujinet_hal::platform::create_protocol_registry;
+
+/// Gopher server answering the root menu and a single text document
+async fn spawn_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let mut socket = BufReader::new(socket);
+            let mut selector = String::new();
+            socket.read_line(&mut selector).await.unwrap();
+
+            let response = match selector.trim_end() {
+                "" => format!(
+                    "iRetro gopher hole\t\tnull.host\t1\r\n\
+                     0Read me\t/readme.txt\t127.0.0.1\t{port}\r\n\
+                     1Games\t/games\t127.0.0.1\t{port}\r\n\
+                     .\r\n"
+                ),
+                "/readme.txt" => "READY\r\n".repeat(200),
+                _ => "3Not found\t\terror.host\t1\r\n.\r\n".to_string(),
+            };
+            socket.get_mut().write_all(response.as_bytes()).await.unwrap();
+            socket.get_mut().shutdown().await.unwrap();
+        }
+    });
+
+    port
+}
+
+#[tokio::test]
+async fn test_gopher_menu_and_document() -> DeviceResult<()> {
+    let port = spawn_server().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    // Directory mode parses the menu into typed entries
+    manager.open_device(&format!("N1:gopher://127.0.0.1:{port}/"), OPEN_MODE_DIRECTORY, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+    let gopher = device.protocol_handler().as_any().downcast_ref::<GopherProtocol>().unwrap();
+    let entries = gopher.entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].item_type, GopherItemType::Info);
+    assert_eq!(entries[1].item_type, GopherItemType::TextFile);
+    assert_eq!(entries[1].selector, "/readme.txt");
+    assert_eq!(entries[2].item_type, GopherItemType::Directory);
+    let readme_url = entries[1].url();
+
+    let mut buf = [0u8; 256];
+    let len = device.read_bytes(&mut buf).await?;
+    let listing = String::from_utf8_lossy(&buf[..len]);
+    assert_eq!(listing.lines().next(), Some("iRetro gopher hole"));
+    assert!(listing.contains(&format!("0Read me\t{}", readme_url)));
+    drop(device);
+    manager.close_device(0).await?;
+
+    // Read mode streams the document an entry points at
+    manager.open_device(&format!("N1:{}", readme_url), OPEN_MODE_READ, 0).await?;
+    let mut device = manager.get_network_device(0).await.unwrap();
+    let mut document = Vec::new();
+    loop {
+        let len = device.read_bytes(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        document.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(document, "READY\r\n".repeat(200).as_bytes());
+    drop(device);
+    manager.close_device(0).await?;
+    Ok(())
+}
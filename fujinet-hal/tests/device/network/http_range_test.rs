@@ -0,0 +1,127 @@
+use std::io::SeekFrom;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use fujinet_hal::device::DeviceResult;
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::{HttpClient, OPEN_MODE_READ};
+use fujinet_hal::platform::create_protocol_registry;
+use fujinet_hal::platform::x86::X86HttpClient;
+
+fn disk_image() -> Vec<u8> {
+    (0..40_000u32).map(|i| (i % 253) as u8).collect()
+}
+
+/// Parse "Range: bytes=a-b" / "bytes=a-" into an inclusive range
+fn parse_range(value: &str, total: usize) -> Option<(usize, usize)> {
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let start: usize = start.parse().ok()?;
+    let end = if end.is_empty() { total - 1 } else { end.parse::<usize>().ok()?.min(total - 1) };
+    Some((start, end))
+}
+
+/// HTTP server serving a single file and honouring Range requests
+async fn spawn_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let body = disk_image();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let body = body.clone();
+            tokio::spawn(async move {
+                let mut socket = BufReader::new(socket);
+                loop {
+                    let mut range = None;
+                    let mut line = String::new();
+                    if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    loop {
+                        line.clear();
+                        socket.read_line(&mut line).await.unwrap();
+                        if line.trim_end().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("range") {
+                                range = Some(value.trim().to_string());
+                            }
+                        }
+                    }
+
+                    let (head, data) = match range {
+                        None => (format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()), &body[..]),
+                        Some(range) => match parse_range(&range, body.len()) {
+                            Some((start, end)) if start < body.len() => (
+                                format!(
+                                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\r\n",
+                                    start, end, body.len(), end - start + 1
+                                ),
+                                &body[start..=end],
+                            ),
+                            _ => (
+                                format!("HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\n\r\n", body.len()),
+                                &body[..0],
+                            ),
+                        },
+                    };
+                    socket.get_mut().write_all(head.as_bytes()).await.unwrap();
+                    socket.get_mut().write_all(data).await.unwrap();
+                }
+            });
+        }
+    });
+
+    port
+}
+
+#[tokio::test]
+async fn test_client_range_request() -> DeviceResult<()> {
+    let port = spawn_server().await;
+    let url = format!("http://127.0.0.1:{port}/game.atr");
+    let body = disk_image();
+
+    let mut client = X86HttpClient::default();
+    client.connect(&url).await?;
+    let response = client.get_range(&url, 100, Some(199)).await?;
+    assert_eq!(client.status_code(), 206);
+    assert_eq!(response.data, &body[100..200]);
+    assert_eq!(response.total_len, Some(body.len() as u64));
+
+    client.get_range(&url, body.len() as u64, None).await?;
+    assert_eq!(client.status_code(), 416);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seek_and_read_over_http() -> DeviceResult<()> {
+    let port = spawn_server().await;
+    let body = disk_image();
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    manager.open_device(&format!("N1:http://127.0.0.1:{port}/game.atr"), OPEN_MODE_READ, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+
+    // Read the whole image in sector sized pieces
+    let mut data = Vec::new();
+    let mut buf = [0u8; 128];
+    loop {
+        let len = device.read_bytes(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(data, body);
+
+    // POINT back into the middle of the image
+    let seekable = device.protocol_handler().as_seekable().expect("http read mode should be seekable");
+    assert_eq!(seekable.seek(SeekFrom::Start(20_000)).await?, 20_000);
+    let len = device.read_bytes(&mut buf).await?;
+    assert_eq!(&buf[..len], &body[20_000..20_000 + len]);
+    assert_eq!(device.protocol_handler().as_seekable().unwrap().tell(), 20_000 + len as u64);
+
+    drop(device);
+    manager.close_device(0).await?;
+    Ok(())
+}
@@ -1,2 +1,9 @@
+mod file_protocol_test;
 mod protocol_factory_device_management_test;
-mod protocol_registry_test;
\ No newline at end of file
+mod protocol_registry_test;
+mod websocket_protocol_test;
+mod mqtt_protocol_test;
+mod gopher_protocol_test;
+mod http_range_test;
+mod concurrency_test;
+mod tcp_protocol_test;
@@ -0,0 +1,102 @@
+use std::time::Duration;
+use fujinet_hal::device::DeviceResult;
+use fujinet_hal::device::network::NetworkDevice;
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::{MqttMessage, MqttProtocol, MqttQos, OPEN_MODE_READ_WRITE};
+use fujinet_hal::platform::create_protocol_registry;
+use crate::common::mqtt_broker::TestBroker;
+
+fn mqtt(device: &mut Box<dyn NetworkDevice>) -> &mut MqttProtocol {
+    device.protocol_handler().as_any_mut().downcast_mut::<MqttProtocol>().unwrap()
+}
+
+/// Poll until a message arrives on the device
+async fn next_message(device: &mut Box<dyn NetworkDevice>) -> DeviceResult<MqttMessage> {
+    for _ in 0..200 {
+        if let Some(message) = mqtt(device).receive_message().await? {
+            return Ok(message);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("timed out waiting for an MQTT message");
+}
+
+/// Poll until a non-empty read completes
+async fn read_message(device: &mut Box<dyn NetworkDevice>, buf: &mut [u8]) -> DeviceResult<usize> {
+    for _ in 0..200 {
+        let len = device.read_bytes(buf).await?;
+        if len > 0 {
+            return Ok(len);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("timed out waiting for an MQTT message");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mqtt_protocol_against_test_broker() -> DeviceResult<()> {
+    let broker = TestBroker::start().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    // N2 publishes a retained QoS 1 message before anyone is subscribed
+    let publisher = format!("N2:mqtt://127.0.0.1:{}?client_id=publisher", broker.port());
+    manager.open_device(&publisher, OPEN_MODE_READ_WRITE, 0).await?;
+    let mut device = manager.get_network_device(1).await.expect("unit 2 should be open");
+    // A QoS 1 publish only returns once the broker has acknowledged it
+    mqtt(&mut device).publish("home/lamp", b"on", MqttQos::AtLeastOnce, true).await?;
+    assert_eq!(broker.retained("home/lamp").as_deref(), Some(&b"on"[..]));
+
+    // N1 subscribes to its path topic on open, and to home/# explicitly
+    let subscriber = format!("N1:mqtt://127.0.0.1:{}/chat/lobby", broker.port());
+    drop(device);
+    manager.open_device(&subscriber, OPEN_MODE_READ_WRITE, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+    mqtt(&mut device).subscribe("home/#", MqttQos::AtLeastOnce).await?;
+
+    let retained = next_message(&mut device).await?;
+    assert_eq!(retained.topic, "home/lamp");
+    assert_eq!(retained.payload, b"on");
+    assert!(retained.retain);
+
+    // A live publish from N2 is read by N1 as topic, newline, payload
+    drop(device);
+    let mut device = manager.get_network_device(1).await.unwrap();
+    mqtt(&mut device).publish("chat/lobby", b"HELLO", MqttQos::AtMostOnce, false).await?;
+
+    drop(device);
+    let mut device = manager.get_network_device(0).await.unwrap();
+    let mut buf = [0u8; 64];
+    let len = read_message(&mut device, &mut buf).await?;
+    assert_eq!(&buf[..len], b"chat/lobby\nHELLO");
+
+    // Writes on N1 publish to its path topic, which it is also subscribed to
+    device.write_bytes(b"ECHO").await?;
+    let message = next_message(&mut device).await?;
+    assert_eq!(message.topic, "chat/lobby");
+    assert_eq!(message.payload, b"ECHO");
+    assert!(!message.retain);
+
+    drop(device);
+    manager.close_device(0).await?;
+    manager.close_device(1).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mqtt_qos1_publish_reports_lost_connection() -> DeviceResult<()> {
+    let broker = TestBroker::start().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    let url = format!("N1:mqtt://127.0.0.1:{}?client_id=publisher", broker.port());
+    manager.open_device(&url, OPEN_MODE_READ_WRITE, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+
+    // The publish is never acknowledged, so it must fail rather than report success
+    broker.drop_on_publish();
+    assert!(mqtt(&mut device).publish("home/lamp", b"on", MqttQos::AtLeastOnce, false).await.is_err());
+    assert!(broker.retained("home/lamp").is_none());
+
+    drop(device);
+    let _ = manager.close_device(0).await;
+    Ok(())
+}
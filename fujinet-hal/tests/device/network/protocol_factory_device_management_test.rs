@@ -30,17 +30,18 @@ fn setup_test_registry() -> ProtocolRegistry {
 #[tokio::test]
 async fn test_protocol_factory_device_management() -> DeviceResult<()> {
     let registry = setup_test_registry();
-    let mut factory = ProtocolFactory::new(registry);
+    let factory = ProtocolFactory::new(registry);
     
     // Test HTTP protocol creation
     let url = NetworkUrl::parse("N:http://ficticious_example.madeup")?;
     let device_id = factory.get_or_create_device(0, NetworkProtocol::Http, &url).await?;
-    let device = factory.get_device(device_id).unwrap();
+    let mut device = factory.get_device(device_id).await.unwrap();
     
     // Test device lifecycle
     device.open().await?;
     assert!(device.get_status().await.is_ok());
     device.close().await?;
+    drop(device);
 
     // Test device reuse
     let same_device_id = factory.get_or_create_device(0, NetworkProtocol::Http, &url).await?;
@@ -167,7 +167,7 @@ async fn test_protocol_registry_with_mocks() -> DeviceResult<()> {
     manager.open_device("N:http://api.example.com", 0, 0).await?;
     
     // Verify device 0 is connected and is HTTP protocol
-    let device = manager.get_network_device(0)
+    let mut device = manager.get_network_device(0).await
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Device 0 not found"))?;
     
     // Get the protocol handler from the device
@@ -183,10 +183,11 @@ async fn test_protocol_registry_with_mocks() -> DeviceResult<()> {
     http.write(b"POST data").await?;
 
     // Test TCP read - using device 1
+    drop(device);
     manager.open_device("N2:tcp://test-server:8080", 1, 1).await?;
     
     // Verify device 1 is connected and is TCP protocol
-    let device = manager.get_network_device(1)
+    let mut device = manager.get_network_device(1).await
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Device 1 not found"))?;
     
     // Get the protocol handler from the device
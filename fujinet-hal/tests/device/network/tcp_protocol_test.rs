@@ -0,0 +1,106 @@
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use fujinet_hal::device::DeviceResult;
+use fujinet_hal::device::network::{NetworkEvent, NetworkEventKind};
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::TCP_SEND_BUFFER_SIZE;
+use fujinet_hal::platform::create_protocol_registry;
+
+/// TCP server that waits before reading, so the client's writes back up, then
+/// hands back everything it received and answers with a byte count
+async fn spawn_slow_server() -> (u16, oneshot::Receiver<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 8192];
+        while !received.ends_with(b"\r\n") {
+            let len = socket.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..len]);
+        }
+        socket.write_all(format!("{}\r\n", received.len()).as_bytes()).await.unwrap();
+        tx.send(received).unwrap();
+    });
+
+    (port, rx)
+}
+
+#[tokio::test]
+async fn test_large_write_is_delivered_intact() -> DeviceResult<()> {
+    let (port, received) = spawn_slow_server().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+    manager.open_device(&format!("N1:tcp://127.0.0.1:{port}"), 12, 0).await?;
+
+    // Far more than the send buffer and the socket can hold while the server isn't reading
+    let mut payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    payload.extend_from_slice(b"\r\n");
+    assert!(payload.len() > TCP_SEND_BUFFER_SIZE);
+
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+    device.write_all(&payload).await?;
+    device.protocol_handler().flush().await?;
+    assert_eq!(device.protocol_handler().pending().await?, 0);
+
+    let mut reply = [0u8; 32];
+    let len = device.read_bytes(&mut reply).await?;
+    assert_eq!(&reply[..len], format!("{}\r\n", payload.len()).as_bytes());
+    assert_eq!(received.await.unwrap(), payload);
+
+    drop(device);
+    manager.close_device(0).await?;
+    Ok(())
+}
+
+/// Poll the manager's events until one turns up
+async fn next_event(manager: &NetworkManagerImpl) -> NetworkEvent {
+    for _ in 0..200 {
+        if let Some(event) = manager.events().poll() {
+            return event;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("no event was raised");
+}
+
+#[tokio::test]
+async fn test_incoming_data_and_close_are_reported() -> DeviceResult<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (send, go) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        go.await.unwrap();
+        socket.write_all(b"HI").await.unwrap();
+    });
+
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+    manager.open_device(&format!("N1:tcp://127.0.0.1:{port}"), 12, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+    assert!(device.protocol_handler().read_would_wait().await?);
+    assert_eq!(device.protocol_handler().available().await?, 0);
+
+    send.send(()).unwrap();
+    assert_eq!(next_event(&manager).await, NetworkEvent { device_id: 0, kind: NetworkEventKind::DataAvailable });
+    assert!(!device.protocol_handler().read_would_wait().await?);
+    assert_eq!(device.protocol_handler().available().await?, 2);
+
+    let mut buf = [0u8; 8];
+    assert_eq!(device.read_bytes(&mut buf).await?, 2);
+    assert_eq!(&buf[..2], b"HI");
+    assert_eq!(next_event(&manager).await, NetworkEvent { device_id: 0, kind: NetworkEventKind::ConnectionClosed });
+    assert_eq!(device.read_bytes(&mut buf).await?, 0);
+
+    drop(device);
+    manager.close_device(0).await?;
+    Ok(())
+}
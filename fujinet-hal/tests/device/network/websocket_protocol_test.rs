@@ -0,0 +1,168 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use fujinet_hal::device::{DeviceError, DeviceResult};
+use fujinet_hal::device::network::{NetworkDevice, NetworkEvent, NetworkEventKind};
+use fujinet_hal::device::network::manager::{NetworkManager, NetworkManagerImpl};
+use fujinet_hal::device::network::protocols::{
+    ConnectionStatus, ProtocolHandler, WebSocketProtocol, WebSocketMessageType, OPEN_MODE_READ_WRITE,
+};
+use fujinet_hal::platform::create_protocol_registry;
+
+/// Records the X-Player header of the handshake request
+struct PlayerHeader(Arc<Mutex<Option<String>>>);
+
+impl Callback for PlayerHeader {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let header = request.headers().get("X-Player").and_then(|v| v.to_str().ok());
+        *self.0.lock().unwrap() = header.map(str::to_string);
+        Ok(response)
+    }
+}
+
+/// Server that pings the client, greets it using the X-Player header, echoes
+/// every message back with the same frame type and closes with 4000 on "quit"
+async fn spawn_server() -> (u16, Arc<Mutex<Option<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let player = Arc::new(Mutex::new(None));
+    let seen = player.clone();
+
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_hdr_async(socket, PlayerHeader(seen.clone())).await.unwrap();
+
+        ws.send(Message::Ping(b"are you there".to_vec().into())).await.unwrap();
+        match ws.next().await {
+            Some(Ok(Message::Pong(data))) => assert_eq!(data.as_ref(), b"are you there"),
+            other => panic!("expected pong, got {:?}", other),
+        }
+
+        let name = seen.lock().unwrap().clone().unwrap_or_default();
+        ws.send(Message::text(format!("hello {}", name))).await.unwrap();
+
+        while let Some(Ok(message)) = ws.next().await {
+            match message {
+                Message::Text(text) if text.as_str() == "quit" => {
+                    let frame = CloseFrame { code: CloseCode::from(4000), reason: "game over".into() };
+                    ws.close(Some(frame)).await.unwrap();
+                }
+                Message::Text(_) | Message::Binary(_) => ws.send(message).await.unwrap(),
+                _ => {}
+            }
+        }
+    });
+
+    (port, player)
+}
+
+/// Poll the device until a non-empty read completes
+async fn read_message(device: &mut Box<dyn NetworkDevice>, buf: &mut [u8]) -> DeviceResult<usize> {
+    for _ in 0..200 {
+        let len = device.read_bytes(buf).await?;
+        if len > 0 {
+            return Ok(len);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("timed out waiting for a WebSocket message");
+}
+
+/// Poll the manager's events until one arrives
+async fn next_event(manager: &NetworkManagerImpl) -> NetworkEvent {
+    for _ in 0..200 {
+        if let Some(event) = manager.events().poll() {
+            return event;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("timed out waiting for a network event");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_websocket_protocol_against_local_server() -> DeviceResult<()> {
+    let (port, player) = spawn_server().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    let spec = format!("N1:ws://127.0.0.1:{}/lobby", port);
+    manager.open_device(&spec, OPEN_MODE_READ_WRITE, 0).await?;
+    let mut device = manager.get_network_device(0).await.expect("unit 1 should be open");
+
+    // Headers are accepted until the deferred handshake happens
+    let ws = device.protocol_handler().as_any_mut().downcast_mut::<WebSocketProtocol>().unwrap();
+    ws.set_header("X-Player", "ATARI")?;
+    assert_eq!(ws.status().await?, ConnectionStatus::Connecting);
+
+    // The greeting only arrives after the client has answered the server's ping
+    let mut buf = [0u8; 64];
+    let len = read_message(&mut device, &mut buf).await?;
+    assert_eq!(&buf[..len], b"hello ATARI");
+    assert_eq!(player.lock().unwrap().as_deref(), Some("ATARI"));
+
+    // Two writes come back as two separate messages
+    device.write_bytes(b"one").await?;
+    device.write_bytes(b"two").await?;
+    let len = read_message(&mut device, &mut buf).await?;
+    assert_eq!(&buf[..len], b"one");
+    let len = read_message(&mut device, &mut buf).await?;
+    assert_eq!(&buf[..len], b"two");
+
+    // Binary frames round-trip unchanged
+    let ws = device.protocol_handler().as_any_mut().downcast_mut::<WebSocketProtocol>().unwrap();
+    ws.set_message_type(WebSocketMessageType::Binary);
+    device.write_bytes(&[0x9b, 0x00, 0xff]).await?;
+    let len = read_message(&mut device, &mut buf).await?;
+    assert_eq!(&buf[..len], &[0x9b, 0x00, 0xff]);
+
+    // A close from the server is reported with its close code
+    let ws = device.protocol_handler().as_any_mut().downcast_mut::<WebSocketProtocol>().unwrap();
+    ws.set_message_type(WebSocketMessageType::Text);
+    device.write_bytes(b"quit").await?;
+    let mut status = ConnectionStatus::Connected;
+    for _ in 0..200 {
+        status = device.protocol_handler().status().await?;
+        if status != ConnectionStatus::Connected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(status, ConnectionStatus::Error(DeviceError::ConnectionClosed(4000)));
+
+    drop(device);
+    manager.close_device(0).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_websocket_raises_events() -> DeviceResult<()> {
+    let (port, _) = spawn_server().await;
+    let mut manager = NetworkManagerImpl::with_registry(create_protocol_registry());
+
+    manager.open_device(&format!("N2:ws://127.0.0.1:{}/lobby", port), OPEN_MODE_READ_WRITE, 0).await?;
+    let mut device = manager.get_network_device(1).await.expect("unit 2 should be open");
+    let ws = device.protocol_handler().as_any_mut().downcast_mut::<WebSocketProtocol>().unwrap();
+    ws.connect().await?;
+
+    // The greeting raises DataAvailable without anyone calling available()
+    let event = next_event(&manager).await;
+    assert_eq!(event, NetworkEvent { device_id: 1, kind: NetworkEventKind::DataAvailable });
+    let mut buf = [0u8; 64];
+    drop(device);
+    let mut device = manager.get_network_device(1).await.unwrap();
+    let len = device.read_bytes(&mut buf).await?;
+    assert_eq!(&buf[..len], b"hello ");
+
+    device.write_bytes(b"quit").await?;
+    let event = next_event(&manager).await;
+    assert_eq!(event, NetworkEvent { device_id: 1, kind: NetworkEventKind::ConnectionClosed });
+
+    drop(device);
+    manager.close_device(1).await?;
+    assert_eq!(manager.events().poll(), None);
+    Ok(())
+}